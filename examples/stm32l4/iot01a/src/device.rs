@@ -5,7 +5,7 @@ use drogue_device::{
         button::{Button, ButtonEvent},
         led::{Blinker, SimpleLED},
         sensor::hts221::Hts221,
-        timer::Timer,
+        timer::{Timer, TimerActor as McuTimerActor},
     },
     hal::timer::stm32l4xx::Timer as McuTimer,
     prelude::*,
@@ -41,12 +41,14 @@ type I2cSda = PB11<Alternate<AF4, Output<OpenDrain>>>;
 type I2cPeriph = HalI2c<I2C2, (I2cScl, I2cSda)>;
 type I2cPackage = I2c<I2cPeriph>;
 
-type Blinker1Actor = Blinker<Ld1Actor, McuTimer<TIM15>>;
-type Blinker2Actor = Blinker<Ld2Actor, McuTimer<TIM15>>;
+type McuClock = Address<McuTimerActor<McuTimer<TIM15>>>;
+
+type Blinker1Actor = Blinker<Ld1Actor, McuClock>;
+type Blinker2Actor = Blinker<Ld2Actor, McuClock>;
 
 type TimerActor = Timer<McuTimer<TIM15>>;
 
-type Hts221Package = Hts221<MyDevice, PD15<Input<PullDown>>, I2cPeriph>;
+type Hts221Package = Hts221<MyDevice, PD15<Input<PullDown>>, I2cPeriph, McuClock>;
 
 pub struct MyDevice {
     pub memory: ActorContext<Memory>,
@@ -84,6 +86,7 @@ impl Device for MyDevice {
         blinker2_addr.bind(ld2_addr);
 
         hts221_addr.bind(i2c_addr);
+        hts221_addr.bind(timer_addr);
 
         let button_addr = self.button.mount(supervisor);
         button_addr.bind(bus_address);
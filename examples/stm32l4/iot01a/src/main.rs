@@ -34,7 +34,8 @@ use drogue_device::{
         timer::{
             stm32l4xx::Timer as McuTimer
         },
-        Active
+        Active,
+        Edge as ButtonEdge,
     },
 
     prelude::*,
@@ -97,7 +98,7 @@ fn main() -> ! {
     button.enable_interrupt(&mut device.EXTI);
     button.trigger_on_edge(&mut device.EXTI, Edge::RISING_FALLING);
 
-    let button = Button::new(button, Active::Low);
+    let button = Button::new(button, Active::Low, ButtonEdge::Both);
 
     // == i2c
 
@@ -0,0 +1,390 @@
+//! Derive macros for drogue-device actors and devices.
+//!
+//! These are re-exported by `drogue-device` when its `derive` feature is enabled, and
+//! should be used through it rather than depended upon directly.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Field, Fields, GenericArgument, Ident, Path,
+    PathArguments, Result, Token, Type,
+};
+
+/// Lifecycle events which may be delegated to a function through `#[actor(...)]`.
+const LIFECYCLE: &[&str] = &[
+    "on_initialize",
+    "on_start",
+    "on_sleep",
+    "on_hibernate",
+    "on_stop",
+];
+
+/// Derive `Actor`, along with a `Bind` implementation for each dependency.
+///
+/// * A field marked `#[address]`, of type `Option<Address<Self>>`, receives the actor's own
+///   address when it is mounted.
+/// * Each field marked `#[bind]`, of type `Option<Address<A>>`, receives the address of the
+///   `A` actor bound into this one, through a generated `Bind<A>` implementation.
+/// * Lifecycle events may be handled by naming a function taking the actor and returning its
+///   `Completion`, such as `#[actor(on_start = Self::start)]`. Others complete immediately.
+///
+/// ```
+/// use drogue_device::prelude::*;
+///
+/// struct Led;
+///
+/// impl Actor for Led {}
+///
+/// #[derive(Actor)]
+/// #[actor(on_start = Self::start)]
+/// struct Blinker {
+///     #[address]
+///     address: Option<Address<Self>>,
+///     #[bind]
+///     led: Option<Address<Led>>,
+/// }
+///
+/// impl Blinker {
+///     fn start(self) -> Completion<Self> {
+///         Completion::immediate(self)
+///     }
+/// }
+/// ```
+///
+/// Only structs with named fields may derive `Actor`:
+///
+/// ```compile_fail
+/// use drogue_device::prelude::*;
+///
+/// #[derive(Actor)]
+/// struct Tuple(Option<Address<Self>>);
+/// ```
+///
+/// Bound fields must be addresses held in an `Option`:
+///
+/// ```compile_fail
+/// use drogue_device::prelude::*;
+///
+/// struct Led;
+///
+/// impl Actor for Led {}
+///
+/// #[derive(Actor)]
+/// struct Blinker {
+///     #[bind]
+///     led: Address<Led>,
+/// }
+/// ```
+///
+/// An actor has a single address:
+///
+/// ```compile_fail
+/// use drogue_device::prelude::*;
+///
+/// #[derive(Actor)]
+/// struct Blinker {
+///     #[address]
+///     first: Option<Address<Self>>,
+///     #[address]
+///     second: Option<Address<Self>>,
+/// }
+/// ```
+///
+/// Only lifecycle events may be delegated:
+///
+/// ```compile_fail
+/// use drogue_device::prelude::*;
+///
+/// #[derive(Actor)]
+/// #[actor(on_mount = Self::mount)]
+/// struct Blinker {}
+/// ```
+#[proc_macro_derive(Actor, attributes(actor, address, bind))]
+pub fn derive_actor(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let fields = named_fields(&input, "Actor")?;
+
+    let mut address: Option<&Ident> = None;
+    let mut binds = Vec::new();
+    for field in fields {
+        for attr in &field.attrs {
+            if attr.path().is_ident("address") {
+                attr.meta.require_path_only()?;
+                if address.is_some() {
+                    return Err(Error::new(
+                        attr.span(),
+                        "only one field may be marked `#[address]`",
+                    ));
+                }
+                address = field.ident.as_ref();
+            } else if attr.path().is_ident("bind") {
+                attr.meta.require_path_only()?;
+                binds.push((field.ident.as_ref(), bound_actor(&field.ty)?));
+            }
+        }
+    }
+
+    let mut hooks = Vec::new();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("actor")) {
+        attr.parse_nested_meta(|meta| {
+            let event = meta
+                .path
+                .get_ident()
+                .filter(|event| LIFECYCLE.iter().any(|e| event == e))
+                .cloned()
+                .ok_or_else(|| meta.error("expected a lifecycle event, such as `on_start`"))?;
+            let handler: Path = meta.value()?.parse()?;
+            hooks.push((event, handler));
+            Ok(())
+        })?;
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let on_mount = address.map(|field| {
+        quote! {
+            fn on_mount(&mut self, address: ::drogue_device::address::Address<Self>)
+            where
+                Self: Sized,
+            {
+                self.#field.replace(address);
+            }
+        }
+    });
+
+    let hooks = hooks.iter().map(|(event, handler)| {
+        quote! {
+            fn #event(self) -> ::drogue_device::handler::Completion<Self>
+            where
+                Self: 'static,
+            {
+                #handler(self)
+            }
+        }
+    });
+
+    let binds = binds.iter().map(|(field, actor)| {
+        quote! {
+            impl #impl_generics ::drogue_device::bind::Bind<#actor> for #name #ty_generics #where_clause {
+                fn on_bind(&mut self, address: ::drogue_device::address::Address<#actor>) {
+                    self.#field.replace(address);
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::drogue_device::actor::Actor for #name #ty_generics #where_clause {
+            #on_mount
+            #(#hooks)*
+        }
+
+        #(#binds)*
+    })
+}
+
+/// Derive an `EventHandler` for each event type a device routes to its actors.
+///
+/// Each field marked `#[route(...)]`, such as an `ActorContext` or `InterruptContext`, is
+/// notified of every event of the types listed, as published to the device's bus. An
+/// event routed to several fields is cloned for all but the last, and dropped with a
+/// warning by any whose actor has no room for it. Event types not routed are left to be
+/// handled by hand.
+///
+/// ```
+/// use drogue_device::domain::temperature::Celsius;
+/// use drogue_device::driver::button::ButtonEvent;
+/// use drogue_device::driver::sensor::hts221::SensorAcquisition;
+/// use drogue_device::prelude::*;
+///
+/// struct Lights;
+///
+/// impl Actor for Lights {}
+///
+/// impl NotifyHandler<ButtonEvent> for Lights {
+///     fn on_notify(self, _: ButtonEvent) -> Completion<Self> {
+///         Completion::immediate(self)
+///     }
+/// }
+///
+/// struct Climate;
+///
+/// impl Actor for Climate {}
+///
+/// impl NotifyHandler<SensorAcquisition<Celsius>> for Climate {
+///     fn on_notify(self, _: SensorAcquisition<Celsius>) -> Completion<Self> {
+///         Completion::immediate(self)
+///     }
+/// }
+///
+/// #[derive(EventRouter)]
+/// struct MyDevice {
+///     #[route(ButtonEvent)]
+///     lights: ActorContext<Lights>,
+///     #[route(SensorAcquisition<Celsius>)]
+///     climate: ActorContext<Climate>,
+/// }
+///
+/// impl Device for MyDevice {
+///     fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+/// }
+///
+/// fn routes<E, D: EventHandler<E>>() {}
+///
+/// routes::<ButtonEvent, MyDevice>();
+/// routes::<SensorAcquisition<Celsius>, MyDevice>();
+/// ```
+///
+/// Events may only be routed to actors handling them:
+///
+/// ```compile_fail
+/// use drogue_device::driver::button::ButtonEvent;
+/// use drogue_device::prelude::*;
+///
+/// struct Lights;
+///
+/// impl Actor for Lights {}
+///
+/// #[derive(EventRouter)]
+/// struct MyDevice {
+///     #[route(ButtonEvent)]
+///     lights: ActorContext<Lights>,
+/// }
+/// ```
+///
+/// At least one event type must be routed:
+///
+/// ```compile_fail
+/// use drogue_device::prelude::*;
+///
+/// struct Lights;
+///
+/// impl Actor for Lights {}
+///
+/// #[derive(EventRouter)]
+/// struct MyDevice {
+///     #[route()]
+///     lights: ActorContext<Lights>,
+/// }
+/// ```
+#[proc_macro_derive(EventRouter, attributes(route))]
+pub fn derive_event_router(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_router(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_router(input: DeriveInput) -> Result<TokenStream2> {
+    let fields = named_fields(&input, "EventRouter")?;
+
+    // each event type, with the fields it is routed to, in the order first routed
+    let mut routes: Vec<(Type, Vec<&Ident>)> = Vec::new();
+    for field in fields {
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("route")) {
+            let events =
+                attr.parse_args_with(Punctuated::<Type, Token![,]>::parse_terminated)?;
+            if events.is_empty() {
+                return Err(Error::new(
+                    attr.span(),
+                    "expected the event types to route, such as `#[route(ButtonEvent)]`",
+                ));
+            }
+            let ident = field.ident.as_ref().unwrap();
+            for event in events {
+                let key = event.to_token_stream().to_string();
+                match routes
+                    .iter_mut()
+                    .find(|(routed, _)| routed.to_token_stream().to_string() == key)
+                {
+                    Some((_, routed_to)) => routed_to.push(ident),
+                    None => routes.push((event, vec![ident])),
+                }
+            }
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let handlers = routes.iter().map(|(event, fields)| {
+        let (last, others) = fields.split_last().unwrap();
+        quote! {
+            impl #impl_generics ::drogue_device::handler::EventHandler<#event> for #name #ty_generics #where_clause {
+                fn on_event(&'static self, event: #event) {
+                    #(::drogue_device::device::Route::route(&self.#others, ::core::clone::Clone::clone(&event));)*
+                    ::drogue_device::device::Route::route(&self.#last, event);
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        #(#handlers)*
+    })
+}
+
+/// The fields of a struct with named fields, deriving `derive`.
+fn named_fields<'i>(
+    input: &'i DeriveInput,
+    derive: &str,
+) -> Result<&'i Punctuated<Field, Token![,]>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(Error::new(
+                input.ident.span(),
+                format!(
+                    "`{}` may only be derived for structs with named fields",
+                    derive
+                ),
+            )),
+        },
+        _ => Err(Error::new(
+            input.ident.span(),
+            format!("`{}` may only be derived for structs", derive),
+        )),
+    }
+}
+
+/// The `A` of a field of type `Option<Address<A>>`.
+fn bound_actor(ty: &Type) -> Result<&Type> {
+    single_argument(ty, "Option")
+        .and_then(|address| single_argument(address, "Address"))
+        .ok_or_else(|| {
+            Error::new(
+                ty.span(),
+                "`#[bind]` fields must be of type `Option<Address<...>>`",
+            )
+        })
+}
+
+/// The type argument of `ty`, if it is the single-argument generic type named `name`.
+fn single_argument<'t>(ty: &'t Type, name: &str) -> Option<&'t Type> {
+    let segment = match ty {
+        Type::Path(ty) if ty.qself.is_none() => ty.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != name {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
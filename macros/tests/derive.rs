@@ -0,0 +1,128 @@
+use drogue_device::device::Route;
+use drogue_device::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+
+struct Led;
+
+impl Actor for Led {}
+
+struct Timer<T>(PhantomData<T>);
+
+impl<T> Actor for Timer<T> {}
+
+thread_local! {
+    static STARTED: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Actor)]
+#[actor(on_start = Self::start)]
+struct Blinker<T: 'static> {
+    #[address]
+    address: Option<Address<Self>>,
+    #[bind]
+    led: Option<Address<Led>>,
+    #[bind]
+    timer: Option<Address<Timer<T>>>,
+}
+
+impl<T> Blinker<T> {
+    fn new() -> Self {
+        Self {
+            address: None,
+            led: None,
+            timer: None,
+        }
+    }
+
+    fn start(self) -> Completion<Self> {
+        STARTED.with(|started| started.set(true));
+        Completion::immediate(self)
+    }
+}
+
+#[derive(Actor)]
+struct Unbound {
+    blinks: u32,
+}
+
+fn leak<A: Actor>(actor: A) -> &'static ActorContext<A> {
+    Box::leak(Box::new(ActorContext::new(actor)))
+}
+
+#[test]
+fn test_bind() {
+    let mut blinker = Blinker::<u32>::new();
+
+    blinker.on_bind(leak(Led).address());
+    assert!(blinker.led.is_some());
+    assert!(blinker.timer.is_none());
+
+    blinker.on_bind(leak(Timer::<u32>(PhantomData)).address());
+    assert!(blinker.timer.is_some());
+    assert!(blinker.address.is_none());
+}
+
+#[test]
+fn test_mount_and_lifecycle() {
+    let mut blinker = Blinker::<u32>::new();
+
+    blinker.on_mount(leak(Blinker::new()).address());
+    assert!(blinker.address.is_some());
+    assert!(blinker.led.is_none());
+
+    assert!(!STARTED.with(Cell::get));
+    let _ = blinker.on_start();
+    assert!(STARTED.with(Cell::get));
+}
+
+#[test]
+fn test_defaults() {
+    let mut unbound = Unbound { blinks: 0 };
+    unbound.on_mount(leak(Unbound { blinks: 1 }).address());
+    assert_eq!(unbound.blinks, 0);
+    let _ = unbound.on_stop();
+}
+
+/// Stands in for an actor, recording the events routed to it in place of its queue.
+#[derive(Default)]
+struct Recorder(RefCell<Vec<String>>);
+
+impl<E: std::fmt::Debug> Route<E> for Recorder {
+    fn route(&'static self, event: E) {
+        self.0.borrow_mut().push(format!("{:?}", event));
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Press;
+
+#[derive(Clone, Debug)]
+struct Reading;
+
+#[derive(Default, EventRouter)]
+struct Panel {
+    #[route(Press)]
+    lights: Recorder,
+    #[route(Press, Reading)]
+    display: Recorder,
+    #[route(Reading)]
+    climate: Recorder,
+}
+
+impl Device for Panel {
+    fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+}
+
+#[test]
+fn test_route() {
+    let panel: &'static Panel = Box::leak(Box::default());
+    panel.on_event(Press);
+    panel.on_event(Reading);
+    panel.on_event(Press);
+
+    // each field receives the events routed to it, in order, and no others
+    assert_eq!(*panel.lights.0.borrow(), ["Press", "Press"]);
+    assert_eq!(*panel.display.0.borrow(), ["Press", "Reading", "Press"]);
+    assert_eq!(*panel.climate.0.borrow(), ["Reading"]);
+}
@@ -0,0 +1,148 @@
+//! Declarative state machines for actors.
+//!
+//! An actor implementing `StateMachine` stores its current state, and describes
+//! its behaviour as transitions between states in response to events. Events are
+//! fired at the actor through `Address::fire(...)`, and the actor reacts to entering
+//! each new state through `on_enter(...)`.
+
+use crate::actor::Actor;
+use crate::address::Address;
+use crate::handler::{Completion, NotifyHandler};
+
+/// Trait for actors driven by a state machine.
+pub trait StateMachine: Actor {
+    /// The states of the machine.
+    type State: Copy + PartialEq;
+
+    /// The events moving the machine between states.
+    type Event: 'static;
+
+    /// The state following `state` when `event` occurs.
+    ///
+    /// Events that do not apply to a state should leave it unchanged.
+    fn transition(state: Self::State, event: Self::Event) -> Self::State;
+
+    /// The state stored by the actor.
+    fn state(&mut self) -> &mut Self::State;
+
+    /// Called after the machine has moved into a different state.
+    ///
+    /// The default implementation does nothing.
+    fn on_enter(self, state: Self::State) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        Completion::immediate(self)
+    }
+}
+
+/// Message carrying an event to a `StateMachine`.
+pub struct Event<E>(pub E);
+
+impl<A> NotifyHandler<Event<A::Event>> for A
+where
+    A: StateMachine + 'static,
+{
+    fn on_notify(mut self, message: Event<A::Event>) -> Completion<Self> {
+        let state = self.state();
+        let next = A::transition(*state, message.0);
+        if next == *state {
+            return Completion::immediate(self);
+        }
+        *state = next;
+        self.on_enter(next)
+    }
+}
+
+impl<A> Address<A>
+where
+    A: StateMachine + 'static,
+{
+    /// Fire an event at the state machine behind this address.
+    pub fn fire(&self, event: A::Event) {
+        self.notify(Event(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::cell::Cell;
+    use std::boxed::Box;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Phase {
+        Idle,
+        Pressed,
+        Held,
+    }
+
+    enum Input {
+        Down,
+        Up,
+        Elapsed,
+    }
+
+    struct LongPress {
+        state: Phase,
+        entered: &'static Cell<u8>,
+    }
+
+    impl Actor for LongPress {}
+
+    impl StateMachine for LongPress {
+        type State = Phase;
+        type Event = Input;
+
+        fn transition(state: Phase, event: Input) -> Phase {
+            match (state, event) {
+                (Phase::Idle, Input::Down) => Phase::Pressed,
+                (Phase::Pressed, Input::Elapsed) => Phase::Held,
+                (_, Input::Up) => Phase::Idle,
+                (state, _) => state,
+            }
+        }
+
+        fn state(&mut self) -> &mut Phase {
+            &mut self.state
+        }
+
+        fn on_enter(self, _: Phase) -> Completion<Self> {
+            self.entered.set(self.entered.get() + 1);
+            Completion::immediate(self)
+        }
+    }
+
+    fn fire(machine: LongPress, event: Input) -> LongPress {
+        match machine.on_notify(Event(event)) {
+            Completion::Immediate(machine) => machine,
+            Completion::Defer(_) => panic!("deferred"),
+        }
+    }
+
+    #[test]
+    fn test_transitions() {
+        let entered = Box::leak(Box::new(Cell::new(0)));
+        let mut machine = LongPress {
+            state: Phase::Idle,
+            entered,
+        };
+
+        let sequence = [
+            (Input::Elapsed, Phase::Idle, 0),
+            (Input::Down, Phase::Pressed, 1),
+            (Input::Down, Phase::Pressed, 1),
+            (Input::Elapsed, Phase::Held, 2),
+            (Input::Up, Phase::Idle, 3),
+            (Input::Down, Phase::Pressed, 4),
+            (Input::Up, Phase::Idle, 5),
+        ];
+        for (event, state, count) in sequence {
+            machine = fire(machine, event);
+            assert_eq!(machine.state, state);
+            assert_eq!(entered.get(), count);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 //! Actor-related types and traits.
 
 use crate::address::Address;
-use crate::handler::{Completion, NotifyHandler, RequestHandler, Response};
+use crate::handler::{Completion, NotifyHandler, RequestHandler, Response, StreamHandler};
 use core::future::Future;
 use core::pin::Pin;
 use core::task::{Context, Poll, Waker};
@@ -9,14 +9,22 @@ use core::task::{Context, Poll, Waker};
 use crate::alloc::{alloc, Box, Rc};
 use crate::bind::Bind;
 use crate::device::Lifecycle;
+use crate::error::DeviceError;
 use crate::prelude::Interrupt;
 use crate::supervisor::{actor_executor::ActorState, Supervisor};
-use core::cell::{RefCell, UnsafeCell};
+use core::cell::{Cell, RefCell, UnsafeCell};
 use core::mem::transmute;
 use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use heapless::spsc::{Consumer, Producer};
 use heapless::{consts::*, spsc::Queue};
 use crate::supervisor::actor_executor::ActiveActor;
+use crate::synchronization::channel::Sender;
+
+pub mod fsm;
+
+/// Derive `Actor`, capturing the actor's own address and generating `Bind` implementations.
+#[cfg(feature = "derive")]
+pub use drogue_device_macros::Actor;
 
 pub trait Configurable {
     type Configuration;
@@ -70,7 +78,7 @@ pub trait Actor: Sized {
         Completion::immediate(self)
     }
 
-    /// Lifecycle event of *stop*. *Unused currently*.
+    /// Lifecycle event of *stop*, dispatched when the system is shut down.
     fn on_stop(self) -> Completion<Self>
     where
         Self: 'static,
@@ -81,17 +89,26 @@ pub trait Actor: Sized {
 
 /// Global methods for acquiring the current actor's infomation.
 pub struct ActorInfo {
-    pub(crate) name: Option<&'static str>,
+    pub(crate) name: Cell<Option<&'static str>>,
+    #[cfg(feature = "trace")]
+    pub(crate) type_name: Cell<Option<&'static str>>,
 }
 
+// Only the supervisor sets the current actor, while polling it outside of any interrupt.
+unsafe impl Sync for ActorInfo {}
+
 impl ActorInfo {
     /// Retrieve the current actor's name, if set, else probably just `"<unnamed>"`
     pub fn name() -> &'static str {
-        unsafe { CURRENT.name.unwrap_or("<unnamed>") }
+        CURRENT.name.get().unwrap_or("<unnamed>")
     }
 }
 
-pub(crate) static mut CURRENT: ActorInfo = ActorInfo { name: None };
+pub(crate) static CURRENT: ActorInfo = ActorInfo {
+    name: Cell::new(None),
+    #[cfg(feature = "trace")]
+    type_name: Cell::new(None),
+};
 
 type ItemsProducer<A> = RefCell<Option<Producer<'static, Box<dyn ActorFuture<A>>, U16>>>;
 type ItemsConsumer<A> = RefCell<Option<Consumer<'static, Box<dyn ActorFuture<A>>, U16>>>;
@@ -108,6 +125,10 @@ pub struct ActorContext<A: Actor + 'static> {
     //pub(crate) items: FutureQueue<A>,
     pub(crate) state_flag_handle: RefCell<Option<*const ()>>,
     pub(crate) in_flight: AtomicBool,
+    mounted: AtomicBool,
+    stopped: AtomicBool,
+    /// Whether a stop has been dispatched, and its `on_stop` not yet completed.
+    stopping: AtomicBool,
     name: Option<&'static str>,
 }
 
@@ -125,6 +146,9 @@ impl<A: Actor + 'static> ActorContext<A> {
             items_consumer: RefCell::new(None),
             state_flag_handle: RefCell::new(None),
             in_flight: AtomicBool::new(false),
+            mounted: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            stopping: AtomicBool::new(false),
             name: None,
         }
     }
@@ -140,6 +164,16 @@ impl<A: Actor + 'static> ActorContext<A> {
         self.name.unwrap_or("<unnamed>")
     }
 
+    /// Whether the actor has been stopped, and not started again since.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+
+    /// Whether the actor has been told to stop, but its `on_stop` is yet to complete.
+    pub(crate) fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Acquire)
+    }
+
     fn take_actor(&self) -> Option<A> {
         self.actor.borrow_mut().take()
     }
@@ -161,10 +195,18 @@ impl<A: Actor + 'static> ActorContext<A> {
     }
 
     /// Mount the context and its actor into the system.
+    ///
+    /// # Panics
+    ///
+    /// If the context has already been mounted, as the supervisor would otherwise poll
+    /// the actor twice over.
     pub fn mount(&'static self, supervisor: &mut Supervisor) -> Address<A> {
+        if self.mounted.swap(true, Ordering::AcqRel) {
+            panic!("[{}] mounted twice", self.name());
+        }
         let addr = Address::new(self);
         let (actor_index, state_flag_handle) = supervisor.activate_actor(self);
-        log::trace!("[{}] == {:x}", self.name(), state_flag_handle as u32);
+        trace!("[{}] == {:x}", self.name(), state_flag_handle as u32);
         self.state_flag_handle
             .borrow_mut()
             .replace(state_flag_handle);
@@ -179,7 +221,10 @@ impl<A: Actor + 'static> ActorContext<A> {
 
     /// Dispatch a lifecycle event.
     pub(crate) fn lifecycle(&'static self, event: Lifecycle) {
-        log::trace!("[{}].lifecycle(...)", self.name());
+        trace!("[{}].lifecycle(...)", self.name());
+        if let Lifecycle::Stop = event {
+            self.stopping.store(true, Ordering::Release);
+        }
         let lifecycle = alloc(OnLifecycle::new(self, event)).unwrap();
         let lifecycle: Box<dyn ActorFuture<A>> = Box::new(lifecycle);
         cortex_m::interrupt::free(|cs| {
@@ -192,12 +237,10 @@ impl<A: Actor + 'static> ActorContext<A> {
             //self.items.enqueue(lifecycle)
         });
 
-        let flag_ptr = self.state_flag_handle.borrow_mut().unwrap() as *const AtomicU8;
-        unsafe {
-            (*flag_ptr).store(ActorState::READY.into(), Ordering::Release);
-        }
+        self.set_ready();
 
-        self.do_poll( self.state_flag_handle.borrow().unwrap() );
+        let state_flag_handle = self.state_flag_handle.borrow().unwrap();
+        self.do_poll(state_flag_handle);
     }
 
     /// Dispatch a bind injection.
@@ -206,18 +249,75 @@ impl<A: Actor + 'static> ActorContext<A> {
         A: Bind<OA>,
         OA: 'static,
     {
-        log::trace!("[{}].bind(...)", self.name());
+        trace!("[{}].bind(...)", self.name());
         self.actor.borrow_mut().as_mut().unwrap().on_bind(address);
     }
 
+    /// Whether the actor is at rest, rather than taken out to handle a message.
+    pub(crate) fn is_at_rest(&self) -> bool {
+        matches!(self.actor.try_borrow(), Ok(actor) if actor.is_some())
+    }
+
+    /// Directly access the actor while it is at rest, such as while mounting.
+    pub(crate) fn with_actor<R, F: FnOnce(&mut A) -> R>(&'static self, f: F) -> R {
+        f(self.actor.borrow_mut().as_mut().unwrap())
+    }
+
     /// Dispatch a notification.
     pub(crate) fn notify<M>(&'static self, message: M)
     where
         A: NotifyHandler<M>,
         M: 'static,
     {
-        log::trace!("[{}].notify(...)", self.name());
-        let notify = alloc(OnNotify::new(self, message)).unwrap();
+        self.try_notify(message)
+            .unwrap_or_else(|error| panic!("[{}] notify failed: {:?}", self.name(), error));
+    }
+
+    /// Dispatch a notification, dropping it if the actor's queue is full
+    /// (`ResourceExhausted`) or the async pool is (`AllocFailed`).
+    pub(crate) fn try_notify<M>(&'static self, message: M) -> Result<(), DeviceError>
+    where
+        A: NotifyHandler<M>,
+        M: 'static,
+    {
+        trace!("[{}].notify(...)", self.name());
+        #[cfg(feature = "trace")]
+        crate::trace::send::<A, M>(crate::trace::Kind::Notify);
+        self.enqueue(message, A::on_notify)
+    }
+
+    /// The number of messages queued for the actor, not counting one in flight.
+    pub(crate) fn pending_len(&'static self) -> usize {
+        cortex_m::interrupt::free(|cs| self.pending())
+    }
+
+    pub(crate) fn pending(&self) -> usize {
+        // only the indices are read, which the producer and consumer update atomically
+        unsafe { (*self.items.get()).len() }
+    }
+
+    /// Dispatch a message whose items are streamed through the sender.
+    pub(crate) fn stream<M>(&'static self, message: M, sender: Sender<A::Item>)
+    where
+        A: StreamHandler<M>,
+        M: 'static,
+    {
+        trace!("[{}].stream(...)", self.name());
+        #[cfg(feature = "trace")]
+        crate::trace::send::<A, M>(crate::trace::Kind::Stream);
+        self.enqueue((message, sender), |actor, (message, sender)| {
+            actor.on_stream(message, sender)
+        })
+        .unwrap_or_else(|error| panic!("[{}] stream failed: {:?}", self.name(), error));
+    }
+
+    fn enqueue<M: 'static>(
+        &'static self,
+        message: M,
+        dispatch: fn(A, M) -> Completion<A>,
+    ) -> Result<(), DeviceError> {
+        let notify =
+            alloc(OnNotify::new(self, message, dispatch)).ok_or(DeviceError::AllocFailed)?;
         let notify: Box<dyn ActorFuture<A>> = Box::new(notify);
         cortex_m::interrupt::free(|cs| {
             self.items_producer
@@ -225,13 +325,25 @@ impl<A: Actor + 'static> ActorContext<A> {
                 .as_mut()
                 .unwrap()
                 .enqueue(notify)
-                .unwrap_or_else(|_| panic!("too many messages"));
+                .map_err(|_| DeviceError::ResourceExhausted)
             //self.items.enqueue(notify);
-        });
+        })?;
 
-        let flag_ptr = self.state_flag_handle.borrow_mut().unwrap() as *const AtomicU8;
-        unsafe {
-            (*flag_ptr).store(ActorState::READY.into(), Ordering::Release);
+        self.set_ready();
+        Ok(())
+    }
+
+    /// Mark the actor ready for the supervisor to poll on its next pass.
+    ///
+    /// The handle is only ever borrowed shared here, and copied out at once, so an
+    /// interrupt handler queueing a message can mark the actor ready even while it is
+    /// being polled.
+    fn set_ready(&self) {
+        let state_flag_handle = *self.state_flag_handle.borrow();
+        if let Some(flag) = state_flag_handle {
+            unsafe {
+                (*(flag as *const AtomicU8)).store(ActorState::READY.into(), Ordering::Release);
+            }
         }
     }
 
@@ -241,6 +353,8 @@ impl<A: Actor + 'static> ActorContext<A> {
         A: RequestHandler<M>,
         M: 'static,
     {
+        #[cfg(feature = "trace")]
+        crate::trace::send::<A, M>(crate::trace::Kind::Request);
         let signal = Rc::new(CompletionHandle::new());
         let sender = CompletionSender::new(signal.clone());
         let receiver = CompletionReceiver::new(signal);
@@ -248,21 +362,16 @@ impl<A: Actor + 'static> ActorContext<A> {
             alloc(OnRequest::new(self, message, sender)).unwrap();
         let response = RequestResponseFuture::new(receiver);
 
-        unsafe {
-            let request: Box<dyn ActorFuture<A>> = Box::new(request);
-            cortex_m::interrupt::free(|cs| {
-                self.items_producer
-                    .borrow_mut()
-                    .as_mut()
-                    .unwrap()
-                    .enqueue(request)
-                    .unwrap_or_else(|_| panic!("message queue full"));
-            });
-
-            //let flag_ptr = (&*self.state_flag_handle.get()).unwrap() as *const AtomicU8;
-            let flag_ptr = self.state_flag_handle.borrow_mut().unwrap() as *const AtomicU8;
-            (*flag_ptr).store(ActorState::READY.into(), Ordering::Release);
-        }
+        let request: Box<dyn ActorFuture<A>> = Box::new(request);
+        cortex_m::interrupt::free(|cs| {
+            self.items_producer
+                .borrow_mut()
+                .as_mut()
+                .unwrap()
+                .enqueue(request)
+                .unwrap_or_else(|_| panic!("message queue full"));
+        });
+        self.set_ready();
 
         response.await
     }
@@ -275,6 +384,8 @@ impl<A: Actor + 'static> ActorContext<A> {
     where
         A: RequestHandler<M>,
     {
+        #[cfg(feature = "trace")]
+        crate::trace::send::<A, M>(crate::trace::Kind::Request);
         let signal = Rc::new(CompletionHandle::new());
         let sender = CompletionSender::new(signal.clone());
         let receiver = CompletionReceiver::new(signal);
@@ -293,11 +404,8 @@ impl<A: Actor + 'static> ActorContext<A> {
                     .enqueue(request)
                     .unwrap_or_else(|_| panic!("message queue full"));
             });
-
-            //let flag_ptr = (&*self.state_flag_handle.get()).unwrap() as *const AtomicU8;
-            let flag_ptr = self.state_flag_handle.borrow_mut().unwrap() as *const AtomicU8;
-            (*flag_ptr).store(ActorState::READY.into(), Ordering::Release);
         }
+        self.set_ready();
 
         response.await
     }
@@ -342,18 +450,38 @@ impl<A: Actor> Future for OnLifecycle<A> {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        log::trace!("[{}] Lifecycle.poll()", self.actor.name());
+        let result = self.dispatch(cx);
+        if result.is_ready() {
+            if let Lifecycle::Stop = self.event {
+                self.actor.stopping.store(false, Ordering::Release);
+            }
+        }
+        result
+    }
+}
+
+impl<A: Actor> OnLifecycle<A> {
+    /// Dispatch the event to the actor, then drive any future it deferred to.
+    fn dispatch(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        trace!("[{}] Lifecycle.poll()", self.actor.name());
         if !self.dispatched {
             let actor = self.actor.take_actor().expect("actor is missing");
-            log::trace!(
+            trace!(
                 "[{}] Lifecycle.poll() - dispatch on_lifecycle {:?}",
                 self.actor.name(),
                 self.event
             );
             let completion = match self.event {
                 Lifecycle::Initialize => actor.on_initialize(),
-                Lifecycle::Start => actor.on_start(),
-                Lifecycle::Stop => actor.on_stop(),
+                Lifecycle::Start => {
+                    self.actor.stopped.store(false, Ordering::Release);
+                    actor.on_start()
+                }
+                Lifecycle::Stop => {
+                    // stopping from the moment it is dispatched, however long it takes
+                    self.actor.stopped.store(true, Ordering::Release);
+                    actor.on_stop()
+                }
                 Lifecycle::Sleep => actor.on_sleep(),
                 Lifecycle::Hibernate => actor.on_hibernate(),
             };
@@ -361,7 +489,7 @@ impl<A: Actor> Future for OnLifecycle<A> {
             match completion {
                 Completion::Immediate(actor) => {
                     self.actor.replace_actor(actor);
-                    log::trace!(
+                    trace!(
                         "[{}] Lifecycle.poll() - immediate: Ready",
                         self.actor.name()
                     );
@@ -373,25 +501,25 @@ impl<A: Actor> Future for OnLifecycle<A> {
             }
         }
 
-        log::trace!("[{}] Lifecycle.poll() - check defer", self.actor.name());
+        trace!("[{}] Lifecycle.poll() - check defer", self.actor.name());
         if let Some(Completion::Defer(ref mut fut)) = &mut self.defer {
             let fut = Pin::new(fut);
             let result = fut.poll(cx);
             match result {
                 Poll::Ready(actor) => {
-                    log::trace!("[{}] Lifecycle.poll() - defer: Ready", self.actor.name());
+                    trace!("[{}] Lifecycle.poll() - defer: Ready", self.actor.name());
                     self.actor.replace_actor(actor);
                     //self.sender.send(response);
                     self.defer.take();
                     Poll::Ready(())
                 }
                 Poll::Pending => {
-                    log::trace!("[{}] Lifecycle.poll() - defer: Pending", self.actor.name());
+                    trace!("[{}] Lifecycle.poll() - defer: Pending", self.actor.name());
                     Poll::Pending
                 }
             }
         } else {
-            log::trace!(
+            trace!(
                 "[{}] Lifecycle.poll() - ERROR - no defer?",
                 self.actor.name()
             );
@@ -401,52 +529,49 @@ impl<A: Actor> Future for OnLifecycle<A> {
     }
 }
 
-struct OnNotify<A: Actor, M>
-where
-    A: NotifyHandler<M> + 'static,
-{
+struct OnNotify<A: Actor + 'static, M> {
     actor: &'static ActorContext<A>,
     message: Option<M>,
+    dispatch: fn(A, M) -> Completion<A>,
     defer: Option<Completion<A>>,
 }
 
-impl<A: Actor, M> OnNotify<A, M>
-where
-    A: NotifyHandler<M>,
-{
-    pub fn new(actor: &'static ActorContext<A>, message: M) -> Self {
+impl<A: Actor, M> OnNotify<A, M> {
+    pub fn new(
+        actor: &'static ActorContext<A>,
+        message: M,
+        dispatch: fn(A, M) -> Completion<A>,
+    ) -> Self {
         Self {
             actor,
             message: Some(message),
+            dispatch,
             defer: None,
         }
     }
 }
 
-impl<A: Actor + NotifyHandler<M>, M> ActorFuture<A> for OnNotify<A, M> {}
+impl<A: Actor, M> ActorFuture<A> for OnNotify<A, M> {}
 
-impl<A, M> Unpin for OnNotify<A, M> where A: NotifyHandler<M> + Actor {}
+impl<A: Actor, M> Unpin for OnNotify<A, M> {}
 
-impl<A: Actor, M> Future for OnNotify<A, M>
-where
-    A: NotifyHandler<M>,
-{
+impl<A: Actor, M> Future for OnNotify<A, M> {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        log::trace!("[{}] Notify.poll()", self.actor.name());
+        trace!("[{}] Notify.poll()", self.actor.name());
         if self.message.is_some() {
             let actor = self.actor.take_actor().expect("actor is missing");
-            log::trace!(
+            trace!(
                 "[{}] Notify.poll() - dispatch on_notification",
                 self.actor.name()
             );
-            let completion = actor.on_notify(self.as_mut().message.take().unwrap());
+            let completion = (self.dispatch)(actor, self.as_mut().message.take().unwrap());
 
             match completion {
                 Completion::Immediate(actor) => {
                     self.actor.replace_actor(actor);
-                    log::trace!("[{}] Notify.poll() - immediate: Ready", self.actor.name());
+                    trace!("[{}] Notify.poll() - immediate: Ready", self.actor.name());
                     return Poll::Ready(());
                 }
                 Completion::Defer(_) => {
@@ -456,7 +581,7 @@ where
             /*
             if matches!(completion, Completion::Immediate(actor)) {
                 self.actor.replace_actor(actor);
-                log::trace!("[{}] Notify.poll() - immediate: Ready", self.actor.name());
+                trace!("[{}] Notify.poll() - immediate: Ready", self.actor.name());
                 return Poll::Ready(());
             }
             self.defer.replace(completion);
@@ -464,25 +589,25 @@ where
              */
         }
 
-        log::trace!("[{}] Notify.poll() - check defer", self.actor.name());
+        trace!("[{}] Notify.poll() - check defer", self.actor.name());
         if let Some(Completion::Defer(ref mut fut)) = &mut self.defer {
             let fut = Pin::new(fut);
             let result = fut.poll(cx);
             match result {
                 Poll::Ready(actor) => {
-                    log::trace!("[{}] Notify.poll() - defer: Ready", self.actor.name());
+                    trace!("[{}] Notify.poll() - defer: Ready", self.actor.name());
                     self.actor.replace_actor(actor);
                     //self.sender.send(response);
                     self.defer.take();
                     Poll::Ready(())
                 }
                 Poll::Pending => {
-                    log::trace!("[{}] Notify.poll() - defer: Pending", self.actor.name());
+                    trace!("[{}] Notify.poll() - defer: Pending", self.actor.name());
                     Poll::Pending
                 }
             }
         } else {
-            log::trace!("[{}] Notify.poll() - ERROR - no defer?", self.actor.name());
+            trace!("[{}] Notify.poll() - ERROR - no defer?", self.actor.name());
             // should not actually get here ever
             Poll::Ready(())
         }
@@ -530,7 +655,7 @@ where
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        log::trace!("[{}] Request.poll()", self.actor.name());
+        trace!("[{}] Request.poll()", self.actor.name());
         if self.message.is_some() {
             let actor = self.actor.take_actor().expect("actor is missing");
             let response = actor.on_request(self.as_mut().message.take().unwrap());
@@ -699,3 +824,126 @@ impl<T: 'static> CompletionReceiver<T> {
         self.handle.poll(cx)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::testing::poll;
+    use std::boxed::Box;
+
+    /// Queue `message` for the mounted `context` as `notify` would, such as from an
+    /// interrupt handler, but without the heap and critical section missing under test.
+    pub(crate) fn enqueue<A, M>(context: &'static ActorContext<A>, message: M)
+    where
+        A: NotifyHandler<M>,
+        M: 'static,
+    {
+        let notify = Box::leak(Box::new(OnNotify::new(context, message, A::on_notify)));
+        let notify: crate::alloc::Box<dyn ActorFuture<A>> = crate::alloc::Box::new(notify);
+        let mut producer = context.items_producer.borrow_mut();
+        assert!(producer.as_mut().unwrap().enqueue(notify).is_ok());
+        drop(producer);
+        context.set_ready();
+    }
+
+    /// Queue the request `message` for the mounted `context` as `request` would, returning
+    /// the future of its response, but without the heap and critical section missing under
+    /// test.
+    pub(crate) fn request<A, M>(
+        context: &'static ActorContext<A>,
+        message: M,
+    ) -> impl Future<Output = <A as RequestHandler<M>>::Response>
+    where
+        A: RequestHandler<M>,
+        M: 'static,
+    {
+        let signal = Rc::leak(CompletionHandle::new());
+        let sender = CompletionSender::new(signal.clone());
+        let receiver = CompletionReceiver::new(signal);
+        let request = Box::leak(Box::new(OnRequest::new(context, message, sender)));
+        let request: crate::alloc::Box<dyn ActorFuture<A>> = crate::alloc::Box::new(request);
+        let mut producer = context.items_producer.borrow_mut();
+        assert!(producer.as_mut().unwrap().enqueue(request).is_ok());
+        drop(producer);
+        context.set_ready();
+        RequestResponseFuture::new(receiver)
+    }
+
+    struct Idle;
+
+    impl Actor for Idle {}
+
+    #[test]
+    #[should_panic(expected = "[idle] mounted twice")]
+    fn test_mount_twice() {
+        let supervisor = Box::leak(Box::new(Supervisor::new()));
+        let context: &'static ActorContext<Idle> =
+            Box::leak(Box::new(ActorContext::new(Idle).with_name("idle")));
+        context.mount(supervisor);
+        context.mount(supervisor);
+    }
+
+    struct Counter(u32);
+
+    impl Actor for Counter {}
+
+    impl NotifyHandler<u32> for Counter {
+        fn on_notify(mut self, message: u32) -> Completion<Self> {
+            self.0 += message;
+            Completion::immediate(self)
+        }
+    }
+
+    #[test]
+    fn test_pending() {
+        let supervisor = Box::leak(Box::new(Supervisor::new()));
+        let context: &'static ActorContext<Counter> =
+            Box::leak(Box::new(ActorContext::new(Counter(0))));
+        assert_eq!(context.pending(), 0);
+        context.mount(supervisor);
+
+        for n in 1..=3 {
+            enqueue(context, n);
+        }
+        assert_eq!(context.pending(), 3);
+
+        let flag = context.state_flag_handle.borrow().unwrap();
+        let _ = context.do_poll(flag);
+        assert_eq!(context.pending(), 0);
+        assert_eq!(context.with_actor(|counter| counter.0), 6);
+    }
+
+    #[test]
+    fn test_try_notify_without_heap() {
+        let supervisor = Box::leak(Box::new(Supervisor::new()));
+        let context: &'static ActorContext<Counter> =
+            Box::leak(Box::new(ActorContext::new(Counter(0))));
+        context.mount(supervisor);
+
+        // no heap is initialized under test
+        assert_eq!(context.try_notify(1u32), Err(DeviceError::AllocFailed));
+        assert_eq!(context.pending(), 0);
+    }
+
+    #[test]
+    fn test_weak_address() {
+        let context: &'static ActorContext<Idle> =
+            Box::leak(Box::new(ActorContext::new(Idle)));
+        let weak = context.address().downgrade();
+        assert!(weak.upgrade() == Some(context.address()));
+
+        // dispatched as the supervisor would, without its heap and critical section
+        let lifecycle = |event| {
+            let mut lifecycle = OnLifecycle::new(context, event);
+            assert!(poll(Pin::new(&mut lifecycle)).is_ready());
+        };
+        lifecycle(Lifecycle::Stop);
+        assert!(weak.upgrade().is_none());
+
+        // started again, the actor can be reached again
+        lifecycle(Lifecycle::Start);
+        assert!(weak.upgrade() == Some(context.address()));
+    }
+}
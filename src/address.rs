@@ -2,14 +2,24 @@
 
 use crate::actor::{Actor, ActorContext};
 use crate::bind::Bind;
-use crate::handler::{NotifyHandler, RequestHandler};
+use crate::error::DeviceError;
+use crate::handler::{NotifyHandler, RequestHandler, StreamHandler};
+use crate::synchronization::channel::{channel, Receiver};
 
 /// A handle to another actor for dispatching notifications and requests.
 ///
+/// Messages are sent in one of two ways:
+///
+/// * `notify(...)` is fire-and-forget: the message is queued for the actor and the
+///   call returns at once, without waiting for the message to be handled or giving
+///   any result back.
+/// * `request(...)` awaits the response: the returned future resolves once the actor
+///   has handled the message, to the response it gave.
+///
 /// Individual actor implementations may augment the `Address` object
 /// when appropriate bounds are met to provide method-like invocations
-/// of either non-blocking synchronous `notify(...)` type behaviour or
-/// asynchronous `request(...)` type behaviour.
+/// over either, such as `address.stats().await` in place of
+/// `address.request(Stats).await`.
 pub struct Address<A: Actor + 'static> {
     actor: &'static ActorContext<A>,
 }
@@ -27,11 +37,28 @@ impl<A: Actor> Clone for Address<A> {
     }
 }
 
+/// Addresses are equal when they refer to the same actor.
+///
+/// Addresses of different actors are unequal even when the actors are of the same type.
+impl<A: Actor> PartialEq for Address<A> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self.actor, other.actor)
+    }
+}
+
+impl<A: Actor> Eq for Address<A> {}
+
 impl<A: Actor + 'static> Address<A> {
     pub(crate) fn new(actor: &'static ActorContext<A>) -> Self {
         Self { actor }
     }
 
+    /// A weak reference to the actor behind this address, which can no longer be
+    /// upgraded into an address once the actor is stopped.
+    pub fn downgrade(&self) -> WeakAddress<A> {
+        WeakAddress { actor: self.actor }
+    }
+
     /// Bind or inject another address into the actor behind this address.
     ///
     /// To accept bound addresses, the target must implement `Bind<...>`
@@ -44,10 +71,28 @@ impl<A: Actor + 'static> Address<A> {
         self.actor.bind(address);
     }
 
-    /// Send a non-blocking notification to the actor behind this address.
+    /// The context of the actor behind this address.
+    pub(crate) fn context(&self) -> &'static ActorContext<A> {
+        self.actor
+    }
+
+    /// Directly access the actor behind this address while it is at rest, such as while mounting.
+    pub(crate) fn with_actor<R, F: FnOnce(&mut A) -> R>(&self, f: F) -> R {
+        self.actor.with_actor(f)
+    }
+
+    /// Send a non-blocking, fire-and-forget notification to the actor behind this
+    /// address.
+    ///
+    /// The message is queued and handled once the actor is next polled; nothing is
+    /// returned, so use `request(...)` where the outcome matters.
     ///
-    /// To accept the message, the target must implement `NotificationHandler<...>`
+    /// To accept the message, the target must implement `NotifyHandler<...>`
     /// for the appropriate type of message being sent.
+    ///
+    /// # Panics
+    ///
+    /// If the actor already has too many pending messages.
     pub fn notify<M>(&self, message: M)
     where
         A: NotifyHandler<M>,
@@ -56,10 +101,50 @@ impl<A: Actor + 'static> Address<A> {
         self.actor.notify(message)
     }
 
-    /// Perform an _async_ request to the actor behind this address.
+    /// Send a non-blocking notification to the actor behind this address, dropping
+    /// it and returning `Err` if the actor already has too many pending messages, or
+    /// there is no room to allocate it.
+    pub(crate) fn try_notify<M>(&self, message: M) -> Result<(), DeviceError>
+    where
+        A: NotifyHandler<M>,
+        M: 'static,
+    {
+        self.actor.try_notify(message)
+    }
+
+    /// The number of messages queued for the actor behind this address and not yet
+    /// being handled, such as to find which actor is falling behind.
+    pub fn pending_len(&self) -> usize {
+        self.actor.pending_len()
+    }
+
+    /// Ask the actor behind this address for a stream of items, returning the
+    /// receiving end of the stream.
+    ///
+    /// To stream items, the target must implement `StreamHandler<...>` for the
+    /// appropriate type of message. The stream ends once the actor has completed
+    /// the message; dropping the receiver early makes further items fail to send.
+    pub fn stream<M>(&self, message: M) -> Receiver<<A as StreamHandler<M>>::Item>
+    where
+        A: StreamHandler<M>,
+        M: 'static,
+    {
+        let (sender, receiver) = channel();
+        self.actor.stream(message, sender);
+        receiver
+    }
+
+    /// Perform an _async_ request to the actor behind this address, resolving to
+    /// its response.
+    ///
+    /// The message is queued as the returned future is first polled, and the future
+    /// completes once the actor has handled it, whether immediately or deferred.
     ///
     /// To accept the request and provide a response, the target must implement
     /// `RequestHandler<...>` for the appropriate type of message.
+    ///
+    /// The message is moved into the request, and so copied on its way to the actor.
+    /// For large messages, such as buffers of a payload, prefer `request_ref(...)`.
     pub async fn request<M>(&self, message: M) -> <A as RequestHandler<M>>::Response
     where
         A: RequestHandler<M> + 'static,
@@ -68,6 +153,29 @@ impl<A: Actor + 'static> Address<A> {
         self.actor.request(message).await
     }
 
+    /// Perform an _async_ request to the actor behind this address, lending it
+    /// `message` rather than moving it, resolving to its response.
+    ///
+    /// Only a reference is copied into the request, so a large message is neither
+    /// copied onto the stack of the returned future nor into the heap, which matters on
+    /// devices with little of either. The handler borrows the message, so it cannot
+    /// keep it, nor any part of it without cloning; small messages, and those the actor
+    /// keeps, are better moved with `request(...)`.
+    ///
+    /// To accept the request, the target must implement `RequestHandler<&M>` for any
+    /// lifetime of the reference.
+    ///
+    /// # Safety
+    /// The future *must* be fully `.await`'d before allowing the `message` argument to fall out of scope.
+    /// The request is queued on the actor as the future is first polled, so dropping the
+    /// future early, such as by a timeout, leaves the actor to read the message after it is gone.
+    pub async unsafe fn request_ref<'m, M>(&self, message: &'m M) -> <A as RequestHandler<&'m M>>::Response
+    where
+        A: RequestHandler<&'m M> + 'static,
+    {
+        self.request_unchecked(message).await
+    }
+
     /// Perform an unsafe _async_ request to the actor behind this address.
     ///
     /// To accept the request and provide a response, the target must implement
@@ -86,3 +194,153 @@ impl<A: Actor + 'static> Address<A> {
         self.actor.request_unchecked(message).await
     }
 }
+
+/// A reference to an actor not implying that it is alive, obtained by
+/// `Address::downgrade`.
+///
+/// Actors holding addresses of each other, or of themselves, keep them for as long as
+/// they live. Holding a `WeakAddress` instead makes the dependency explicit: before each
+/// use, `upgrade()` checks the actor has not been stopped since.
+pub struct WeakAddress<A: Actor + 'static> {
+    actor: &'static ActorContext<A>,
+}
+
+impl<A: Actor> Copy for WeakAddress<A> {}
+
+impl<A: Actor> Clone for WeakAddress<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A: Actor + 'static> WeakAddress<A> {
+    /// The address of the actor, or `None` if it has been stopped.
+    pub fn upgrade(&self) -> Option<Address<A>> {
+        if self.actor.is_stopped() {
+            None
+        } else {
+            Some(Address::new(self.actor))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::actor::tests::{enqueue, request};
+    use crate::handler::{Completion, Response};
+    use crate::supervisor::{actor_executor::ActiveActor, Supervisor};
+    use crate::testing::poll;
+    use core::future::Future;
+    use core::task::Poll;
+    use std::boxed::Box;
+
+    struct Dummy {
+        pings: u32,
+    }
+
+    impl Actor for Dummy {}
+
+    struct Ping;
+
+    impl NotifyHandler<Ping> for Dummy {
+        fn on_notify(mut self, _: Ping) -> Completion<Self> {
+            self.pings += 1;
+            Completion::immediate(self)
+        }
+    }
+
+    impl RequestHandler<Ping> for Dummy {
+        type Response = u32;
+
+        fn on_request(mut self, _: Ping) -> Response<Self, u32> {
+            self.pings += 1;
+            let pings = self.pings;
+            Response::immediate(self, pings)
+        }
+    }
+
+    fn context() -> &'static ActorContext<Dummy> {
+        Box::leak(Box::new(ActorContext::new(Dummy { pings: 0 })))
+    }
+
+    #[test]
+    fn test_identity() {
+        let a = Address::new(context());
+        let b = Address::new(context());
+
+        #[allow(clippy::clone_on_copy)]
+        let copy = a.clone();
+        assert!(a == copy);
+        assert!(a != b);
+        assert!(b == Address::new(b.actor));
+    }
+
+    fn responds_with<R, F: Future<Output = R>>(_: F) {}
+
+    #[test]
+    fn test_messaging() {
+        let supervisor = Box::leak(Box::new(Supervisor::new()));
+        let address = Address::new(context());
+        address.context().mount(supervisor);
+        let flag = address.context().state_flag_handle.borrow().unwrap();
+
+        // queued as `notify` and `request` would, without the heap of a target
+        enqueue(address.context(), Ping);
+        let mut response = Box::pin(request(address.context(), Ping));
+        assert_eq!(address.context().pending(), 2);
+        assert!(poll(response.as_mut()).is_pending());
+
+        let _ = address.context().do_poll(flag);
+        assert_eq!(address.with_actor(|dummy| dummy.pings), 2);
+        assert_eq!(poll(response.as_mut()), Poll::Ready(2));
+    }
+
+    /// A message too large to copy around freely.
+    struct Payload([u8; 512]);
+
+    struct Checksum;
+
+    impl Actor for Checksum {}
+
+    impl<'m> RequestHandler<&'m Payload> for Checksum {
+        type Response = u32;
+
+        fn on_request(self, payload: &'m Payload) -> Response<Self, u32> {
+            let sum = payload.0.iter().map(|byte| *byte as u32).sum();
+            Response::immediate(self, sum)
+        }
+    }
+
+    impl RequestHandler<Payload> for Checksum {
+        type Response = u32;
+
+        fn on_request(self, payload: Payload) -> Response<Self, u32> {
+            self.on_request(&payload)
+        }
+    }
+
+    #[test]
+    fn test_request_ref() {
+        let mut payload = Payload([0; 512]);
+        for (i, byte) in payload.0.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        // the handler sees the data lent to it
+        match Checksum.on_request(&payload) {
+            Response::Immediate(_, sum) => assert_eq!(sum, 2 * (0..=255).sum::<u32>()),
+            _ => panic!("deferred"),
+        }
+
+        // lending keeps the payload out of the request, where moving it does not
+        let address = Address::new(Box::leak(Box::new(ActorContext::new(Checksum))));
+        let lent = unsafe { address.request_ref(&payload) };
+        assert!(core::mem::size_of_val(&lent) < 512);
+        responds_with::<u32, _>(lent);
+        let moved = address.request(Payload([0; 512]));
+        assert!(core::mem::size_of_val(&moved) >= 512);
+    }
+}
@@ -15,6 +15,7 @@ pub struct Heap {
     bottom: usize,
     size: usize,
     used: usize,
+    high_water: usize,
     holes: HoleList,
 }
 
@@ -25,6 +26,7 @@ impl Heap {
             bottom: 0,
             size: 0,
             used: 0,
+            high_water: 0,
             holes: HoleList::empty(),
         }
     }
@@ -41,6 +43,7 @@ impl Heap {
                     bottom: heap_bottom,
                     size: heap_size,
                     used: 0,
+                    high_water: 0,
                     holes: HoleList::new(heap_bottom, heap_size),
                 }
             }
@@ -68,6 +71,7 @@ impl Heap {
         let res = self.holes.allocate_first_fit(aligned_layout);
         if res.is_ok() {
             self.used += aligned_layout.size();
+            self.high_water = self.high_water.max(self.used);
         }
         res
     }
@@ -105,6 +109,11 @@ impl Heap {
         self.used
     }
 
+    /// Returns the most of the heap ever used at once
+    pub fn high_water(&self) -> usize {
+        self.high_water
+    }
+
     /// Returns the size of the free part of the heap
     pub fn free(&self) -> usize {
         self.size - self.used
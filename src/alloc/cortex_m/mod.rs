@@ -81,6 +81,21 @@ impl CortexMHeap {
         cortex_m::interrupt::free(|cs| self.heap.borrow(cs).borrow_mut().used())
     }
 
+    /// Returns the start address of the heap.
+    pub fn bottom(&self) -> usize {
+        cortex_m::interrupt::free(|cs| self.heap.borrow(cs).borrow().bottom())
+    }
+
+    /// Returns the size of the heap in bytes.
+    pub fn size(&self) -> usize {
+        cortex_m::interrupt::free(|cs| self.heap.borrow(cs).borrow().size())
+    }
+
+    /// Returns the most bytes ever in use at once.
+    pub fn high_water(&self) -> usize {
+        cortex_m::interrupt::free(|cs| self.heap.borrow(cs).borrow().high_water())
+    }
+
     /// Returns an estimate of the amount of bytes available.
     pub fn free(&self) -> usize {
         cortex_m::interrupt::free(|cs| self.heap.borrow(cs).borrow_mut().free())
@@ -92,17 +107,17 @@ impl CortexMHeap {
             mem::align_of::<(Layout, T)>(),
         )
         .unwrap();
-        log::trace!("[ALLOC] asking for {} aligned {}", layout.size(), layout.align());
+        trace!("[ALLOC] asking for {} aligned {}", layout.size(), layout.align());
         unsafe {
             let mut allocation = self.alloc(layout);
             if allocation.is_null() {
-                log::warn!("[ALLOC] allocation failed: requested={}; free={}", layout.size(), self.free() );
+                warn!("[ALLOC] allocation failed: requested={}; free={}", layout.size(), self.free() );
                 None
             } else {
                 //let mut allocation = &mut *(allocation as *mut MaybeUninit<T>);
                 //allocation.as_mut_ptr().write( (layout, val ));
                 //Some(&mut *allocation.as_mut_ptr())
-                log::trace!("[ALLOC] {:x} allocate {} || {} free", allocation as u32, layout.size(), self.free() );
+                trace!("[ALLOC] {:x} allocate {} || {} free", allocation as u32, layout.size(), self.free() );
                 (allocation as *mut Layout).write(layout);
                 allocation = (allocation as *mut Layout).add(1) as *mut u8;
                 (allocation as *mut T).write(val);
@@ -137,7 +152,7 @@ impl CortexMHeap {
     pub unsafe fn dealloc_object(&self, ptr: *mut u8) {
         let head_ptr = (ptr as *mut Layout).sub(1);
         let layout = head_ptr.read();
-        log::trace!(
+        trace!(
             "[ALLOC] {:x} deallocate {} || {} free",
             head_ptr as u32,
             layout.size(),
@@ -5,7 +5,7 @@ use core::future::Future;
 use core::ops::Deref;
 use core::ops::DerefMut;
 use core::pin::Pin;
-use core::ptr::drop_in_place;
+use core::ptr::{addr_of, drop_in_place};
 use core::task::{Context, Poll};
 
 pub mod cortex_m;
@@ -23,8 +23,10 @@ macro_rules! init_heap {
     };
 }
 
+/// Allocate `val` from the heap, returning `None` if it has no room, or if no heap has
+/// been initialized.
 pub fn alloc<'o, T: 'o>(val: T) -> Option<&'o mut T> {
-    unsafe { HEAP.as_mut().unwrap().alloc_init(val) }
+    unsafe { HEAP.as_mut()?.alloc_init(val) }
 }
 
 #[repr(transparent)]
@@ -68,9 +70,10 @@ impl<T: ?Sized> Drop for Box<T> {
     fn drop(&mut self) {
         unsafe {
             drop_in_place(self.pointer.get());
-            HEAP.as_ref()
-                .unwrap()
-                .dealloc_object(*self.pointer.get() as *mut u8);
+            // without a heap, the value cannot have been allocated from one
+            if let Some(heap) = &*addr_of!(HEAP) {
+                heap.dealloc_object(*self.pointer.get() as *mut u8);
+            }
         }
     }
 }
@@ -88,7 +91,7 @@ struct RcBox<T: ?Sized> {
 
 impl<T> RcBox<T> {
     pub fn new(value: T) -> Self {
-        Self { count: 0, value }
+        Self { count: 1, value }
     }
 }
 
@@ -106,6 +109,18 @@ impl<'m, T: 'm> Rc<T> {
     }
 }
 
+#[cfg(test)]
+impl<T> Rc<T> {
+    /// Share `val` from a leaked allocation, as no heap is initialized under test.
+    pub(crate) fn leak(val: T) -> Self {
+        extern crate std;
+        let rc_box = std::boxed::Box::leak(std::boxed::Box::new(RcBox::new(val)));
+        Self {
+            pointer: UnsafeCell::new(rc_box),
+        }
+    }
+}
+
 impl<T> Clone for Rc<T> {
     fn clone(&self) -> Self {
         unsafe {
@@ -131,9 +146,10 @@ impl<T> Drop for Rc<T> {
         unsafe {
             (**self.pointer.get()).count -= 1;
             if (**self.pointer.get()).count == 0 {
-                HEAP.as_ref()
-                    .unwrap()
-                    .dealloc_object(*self.pointer.get() as *mut u8);
+                // without a heap, the value cannot have been allocated from one
+                if let Some(heap) = &*addr_of!(HEAP) {
+                    heap.dealloc_object(*self.pointer.get() as *mut u8);
+                }
             }
         }
     }
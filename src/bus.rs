@@ -2,6 +2,13 @@
 
 use crate::prelude::*;
 use crate::prelude::device::DeviceContext;
+use crate::alloc::{alloc, Box};
+use core::any::TypeId;
+use core::cell::{Cell, RefCell, UnsafeCell};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use heapless::{consts::*, Vec};
 
 /// The shared device-level event-bus actor.
 ///
@@ -9,38 +16,547 @@ use crate::prelude::device::DeviceContext;
 /// of a system using the `EventHandler<...>` trait, which is to be implemented
 /// for each expected type of event.
 ///
+/// Actors may additionally subscribe to events of a given type, optionally
+/// only those matching a predicate, using `subscribe(...)` and
+/// `subscribe_filtered(...)` while the device is mounting. Each published event
+/// is cloned to every matching subscriber, in the order they subscribed, before
+/// the `Device` handles it. A subscriber whose queue of pending messages is
+/// full misses the event, without affecting the other subscribers or the
+/// `Device`.
+///
+/// An actor may instead await the next event of a type inline, such as from a
+/// deferred response, using `wait_for()` or `wait_for_filtered(...)`.
+///
 /// An `EventBus` may not be directly instantiated, but is created prior to the
 /// activation of any other actor within the system and may be bound into other
 /// actors that wish to `publish` events.
+///
+/// # Interrupts
+///
+/// Events may be published from interrupt handlers, such as that of a `Button`.
+/// Publishing only queues the event, within a critical section, and marks the bus
+/// ready: the event is dispatched to subscribers and the `Device` on the supervisor's
+/// next pass, never within the interrupt. An interrupt handler is so kept short, and
+/// never reenters a subscriber or the `Device` while they are handling another event.
 pub struct EventBus<D: Device + 'static> {
     device: &'static DeviceContext<D>,
+    subscriptions: Subscriptions,
 }
 
 impl<D: Device> EventBus<D> {
     pub(crate) fn new(device: &'static DeviceContext<D>) -> Self {
         Self {
             device,
+            subscriptions: Subscriptions::new(),
         }
     }
 }
 
 impl<D: Device> Actor for EventBus<D> {}
 
-impl<D: Device, M> NotifyHandler<M> for EventBus<D>
+impl<D: Device, M: 'static> NotifyHandler<M> for EventBus<D>
 where
     D: EventHandler<M> + 'static,
 {
-    fn on_notify(self, message: M) -> Completion<Self> {
+    fn on_notify(mut self, message: M) -> Completion<Self> {
+        self.subscriptions.dispatch(&message);
         self.device.on_event( message );
         Completion::immediate(self)
     }
 }
 
 impl<D: Device> Address<EventBus<D>> {
+    /// Queue `message` for dispatch on the supervisor's next pass. Safe to call from an
+    /// interrupt handler.
+    ///
+    /// Should the bus have too many events pending, or the heap be exhausted, the event
+    /// is dropped with a warning, rather than panicking within an interrupt.
     pub fn publish<E: 'static>(&self, message: E)
     where
         D: EventHandler<E> + 'static,
     {
-        self.notify(message)
+        if let Err(error) = self.try_notify(message) {
+            warn!("[event-bus] dropping event: {:?}", error);
+        }
+    }
+
+    /// Deliver a copy of each published `E` to the actor at `address`.
+    ///
+    /// Subscriptions may only be made while the device is mounting, and at most
+    /// 16 may be made in total.
+    pub fn subscribe<E, A>(&self, address: Address<A>)
+    where
+        E: Clone + 'static,
+        A: Actor + NotifyHandler<E> + 'static,
+    {
+        let subscriber: &'static _ = alloc(address).unwrap();
+        self.with_actor(|bus| bus.subscriptions.subscribe::<E, _>(subscriber))
+            .unwrap_or_else(|_| panic!("too many subscriptions"));
+    }
+
+    /// Deliver a copy of each published `E` for which `filter` returns `true`
+    /// to the actor at `address`.
+    ///
+    /// Subscriptions may only be made while the device is mounting, and at most
+    /// 16 may be made in total.
+    pub fn subscribe_filtered<E, A, F>(&self, address: Address<A>, filter: F)
+    where
+        E: Clone + 'static,
+        A: Actor + NotifyHandler<E> + 'static,
+        F: Fn(&E) -> bool + 'static,
+    {
+        let subscriber: &'static _ = alloc(Filtered::new(address, filter)).unwrap();
+        self.with_actor(|bus| bus.subscriptions.subscribe::<E, _>(subscriber))
+            .unwrap_or_else(|_| panic!("too many subscriptions"));
+    }
+
+    /// Await the next `E` published after this call.
+    ///
+    /// The future is resolved by a one-shot subscription, counting towards the 16
+    /// allowed, and removed once the event has been received, or once the future is
+    /// dropped and the next event of any type is published.
+    ///
+    /// The subscription is made on the bus directly, so the bus must be at rest: this
+    /// may be called from the handlers of actors, but not while the bus is dispatching
+    /// an event, such as from the `Device`'s `EventHandler`, a `Subscriber`, or an
+    /// interrupt preempting the bus.
+    ///
+    /// # Panics
+    ///
+    /// If 16 subscriptions are already in place, the heap is exhausted, or the bus is
+    /// dispatching an event.
+    pub fn wait_for<E>(&self) -> WaitFor<E, fn(&E) -> bool>
+    where
+        E: Clone + 'static,
+    {
+        self.wait_for_filtered(|_| true)
+    }
+
+    /// Await the next `E` published after this call for which `filter` returns `true`.
+    ///
+    /// As with `wait_for()`, the bus must not be dispatching an event.
+    ///
+    /// # Panics
+    ///
+    /// If 16 subscriptions are already in place, the heap is exhausted, or the bus is
+    /// dispatching an event.
+    pub fn wait_for_filtered<E, F>(&self, filter: F) -> WaitFor<E, F>
+    where
+        E: Clone + 'static,
+        F: Fn(&E) -> bool + 'static,
+    {
+        assert!(
+            self.context().is_at_rest(),
+            "[event-bus] wait_for(...) while the bus is dispatching an event"
+        );
+        let waiter: &'static _ = alloc(Waiter::new(filter)).unwrap();
+        self.with_actor(|bus| bus.subscriptions.subscribe::<E, _>(waiter))
+            .unwrap_or_else(|_| panic!("too many subscriptions"));
+        WaitFor::new(waiter)
+    }
+}
+
+/// A recipient of events dispatched by the event-bus.
+pub trait Subscriber<E> {
+    fn on_event(&self, event: &E);
+
+    /// Whether the subscriber wants no more events, to be unsubscribed.
+    fn is_done(&self) -> bool {
+        false
+    }
+
+    /// Called once the subscriber has been unsubscribed.
+    fn on_unsubscribe(&self) {}
+}
+
+impl<E, A> Subscriber<E> for Address<A>
+where
+    E: Clone + 'static,
+    A: Actor + NotifyHandler<E> + 'static,
+{
+    fn on_event(&self, event: &E) {
+        if self.try_notify(event.clone()).is_err() {
+            warn!("[event-bus] subscriber busy, dropping event");
+        }
+    }
+}
+
+/// A subscriber only receiving the events matching a predicate.
+pub struct Filtered<S, F> {
+    subscriber: S,
+    filter: F,
+}
+
+impl<S, F> Filtered<S, F> {
+    pub fn new(subscriber: S, filter: F) -> Self {
+        Self { subscriber, filter }
+    }
+}
+
+impl<E, S, F> Subscriber<E> for Filtered<S, F>
+where
+    S: Subscriber<E>,
+    F: Fn(&E) -> bool,
+{
+    fn on_event(&self, event: &E) {
+        if (self.filter)(event) {
+            self.subscriber.on_event(event);
+        }
+    }
+}
+
+/// A one-shot subscriber, shared by the bus and a `WaitFor` future.
+struct Waiter<E, F> {
+    filter: F,
+    event: RefCell<Option<E>>,
+    waker: RefCell<Option<Waker>>,
+    resolved: Cell<bool>,
+    cancelled: Cell<bool>,
+    /// Set by the first of the bus and the future to let go of the waiter, for the
+    /// second to free it.
+    released: Cell<bool>,
+}
+
+impl<E, F> Waiter<E, F> {
+    fn new(filter: F) -> Self {
+        Self {
+            filter,
+            event: RefCell::new(None),
+            waker: RefCell::new(None),
+            resolved: Cell::new(false),
+            cancelled: Cell::new(false),
+            released: Cell::new(false),
+        }
+    }
+
+    fn release(&self) {
+        if self.released.replace(true) {
+            // both the bus and the future have let go of the waiter, allocated by
+            // `wait_for_filtered(...)`
+            drop(Box {
+                pointer: UnsafeCell::new(self as *const Self as *mut Self),
+            });
+        }
+    }
+}
+
+impl<E, F> Subscriber<E> for Waiter<E, F>
+where
+    E: Clone,
+    F: Fn(&E) -> bool,
+{
+    fn on_event(&self, event: &E) {
+        if self.is_done() || !(self.filter)(event) {
+            return;
+        }
+        self.event.borrow_mut().replace(event.clone());
+        self.resolved.set(true);
+        if let Some(waker) = self.waker.borrow_mut().take() {
+            waker.wake();
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.resolved.get() || self.cancelled.get()
+    }
+
+    fn on_unsubscribe(&self) {
+        self.release();
+    }
+}
+
+/// The future of the next matching event, from `wait_for()` or
+/// `wait_for_filtered(...)`.
+pub struct WaitFor<E: 'static, F: 'static> {
+    waiter: &'static Waiter<E, F>,
+}
+
+impl<E, F> WaitFor<E, F> {
+    fn new(waiter: &'static Waiter<E, F>) -> Self {
+        Self { waiter }
+    }
+}
+
+impl<E, F> Future for WaitFor<E, F> {
+    type Output = E;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.waiter.event.borrow_mut().take() {
+            Some(event) => Poll::Ready(event),
+            None => {
+                self.waiter.waker.borrow_mut().replace(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<E, F> Drop for WaitFor<E, F> {
+    fn drop(&mut self) {
+        self.waiter.cancelled.set(true);
+        self.waiter.release();
+    }
+}
+
+/// A type-erased subscription to events of a single type.
+struct Subscription {
+    event: TypeId,
+    subscriber: *const (),
+    deliver: fn(*const (), *const ()),
+    is_done: fn(*const ()) -> bool,
+    unsubscribe: fn(*const ()),
+}
+
+impl Subscription {
+    fn new<E: 'static, S: Subscriber<E>>(subscriber: &'static S) -> Self {
+        Self {
+            event: TypeId::of::<E>(),
+            subscriber: subscriber as *const S as *const (),
+            deliver: Self::deliver::<E, S>,
+            is_done: Self::is_done::<E, S>,
+            unsubscribe: Self::unsubscribe::<E, S>,
+        }
+    }
+
+    fn deliver<E, S: Subscriber<E>>(subscriber: *const (), event: *const ()) {
+        // # Safety
+        // Only called through `Subscriptions::dispatch`, with pointers to the types this
+        // function was instantiated with, as guaranteed by the `TypeId` check.
+        unsafe { (*(subscriber as *const S)).on_event(&*(event as *const E)) }
+    }
+
+    fn is_done<E, S: Subscriber<E>>(subscriber: *const ()) -> bool {
+        // # Safety
+        // The subscriber was erased from an `S` by `new`.
+        unsafe { (*(subscriber as *const S)).is_done() }
+    }
+
+    fn unsubscribe<E, S: Subscriber<E>>(subscriber: *const ()) {
+        // # Safety
+        // The subscriber was erased from an `S` by `new`.
+        unsafe { (*(subscriber as *const S)).on_unsubscribe() }
+    }
+}
+
+/// Subscriptions to events of any type, dispatched in the order they were made.
+pub(crate) struct Subscriptions {
+    subscriptions: Vec<Subscription, U16>,
+}
+
+impl Subscriptions {
+    pub(crate) fn new() -> Self {
+        Self {
+            subscriptions: Vec::new(),
+        }
+    }
+
+    pub(crate) fn subscribe<E: 'static, S: Subscriber<E>>(
+        &mut self,
+        subscriber: &'static S,
+    ) -> Result<(), ()> {
+        self.subscriptions
+            .push(Subscription::new::<E, S>(subscriber))
+            .map_err(|_| ())
+    }
+
+    /// Deliver `event` to each subscriber of its type, then unsubscribe those done,
+    /// keeping the rest in order.
+    pub(crate) fn dispatch<E: 'static>(&mut self, event: &E) {
+        let event_type = TypeId::of::<E>();
+        for subscription in self.subscriptions.iter() {
+            if subscription.event == event_type {
+                (subscription.deliver)(subscription.subscriber, event as *const E as *const ());
+            }
+        }
+
+        let mut kept = 0;
+        for index in 0..self.subscriptions.len() {
+            let subscription = &self.subscriptions[index];
+            if (subscription.is_done)(subscription.subscriber) {
+                (subscription.unsubscribe)(subscription.subscriber);
+            } else {
+                self.subscriptions.swap(kept, index);
+                kept += 1;
+            }
+        }
+        while self.subscriptions.len() > kept {
+            self.subscriptions.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::button::ButtonEvent;
+    use crate::supervisor::actor_executor::ActiveActor;
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use std::boxed::Box;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: RefCell<Vec<ButtonEvent, U8>>,
+    }
+
+    impl Subscriber<ButtonEvent> for &Recorder {
+        fn on_event(&self, event: &ButtonEvent) {
+            self.events.borrow_mut().push(*event).ok();
+        }
+    }
+
+    struct MockDevice {
+        events: &'static Recorder,
+    }
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    impl EventHandler<ButtonEvent> for MockDevice {
+        fn on_event(&'static self, event: ButtonEvent) {
+            self.events.events.borrow_mut().push(event).ok();
+        }
+    }
+
+    #[test]
+    fn test_publish_from_interrupt() {
+        let events: &'static Recorder = Box::leak(Box::new(Recorder::default()));
+        let device: &'static DeviceContext<MockDevice> =
+            Box::leak(Box::new(DeviceContext::new(MockDevice { events })));
+        let supervisor = Box::leak(Box::new(Supervisor::new()));
+        let bus = Box::leak(Box::new(ActorContext::new(EventBus::new(device))));
+        bus.mount(supervisor);
+
+        // queued by the interrupt, without dispatching there
+        crate::actor::tests::enqueue(bus, ButtonEvent::Pressed);
+        assert_eq!(bus.pending(), 1);
+        assert!(events.events.borrow().is_empty());
+
+        // the supervisor's next pass dispatches it
+        let state_flag_handle = bus.state_flag_handle.borrow().unwrap();
+        let _ = ActiveActor::do_poll(bus, state_flag_handle);
+        assert_eq!(bus.pending(), 0);
+        let events = events.events.borrow();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], ButtonEvent::Pressed));
+    }
+
+    #[test]
+    #[should_panic(expected = "while the bus is dispatching")]
+    fn test_wait_for_while_dispatching() {
+        let events: &'static Recorder = Box::leak(Box::new(Recorder::default()));
+        let device: &'static DeviceContext<MockDevice> =
+            Box::leak(Box::new(DeviceContext::new(MockDevice { events })));
+        let bus = Box::leak(Box::new(ActorContext::new(EventBus::new(device))));
+
+        // as from the device's handler, with the bus taken out to dispatch the event
+        let dispatching = bus.actor.borrow_mut().take();
+        assert!(dispatching.is_some());
+        drop(bus.address().wait_for::<ButtonEvent>());
+    }
+
+    #[test]
+    fn test_fan_out() {
+        let first: &'static Recorder = Box::leak(Box::new(Recorder::default()));
+        let second: &'static Recorder = Box::leak(Box::new(Recorder::default()));
+
+        let mut subscriptions = Subscriptions::new();
+        subscriptions
+            .subscribe::<ButtonEvent, _>(Box::leak(Box::new(first)))
+            .unwrap();
+        subscriptions
+            .subscribe::<ButtonEvent, _>(Box::leak(Box::new(second)))
+            .unwrap();
+
+        subscriptions.dispatch(&ButtonEvent::Released);
+
+        for recorder in &[first, second] {
+            let events = recorder.events.borrow();
+            assert_eq!(events.len(), 1);
+            assert!(matches!(events[0], ButtonEvent::Released));
+        }
+    }
+
+    #[test]
+    fn test_filtered_subscription() {
+        let recorder: &'static Recorder = Box::leak(Box::new(Recorder::default()));
+        let filtered = Box::leak(Box::new(Filtered::new(recorder, |event: &ButtonEvent| {
+            matches!(event, ButtonEvent::Pressed)
+        })));
+
+        let mut subscriptions = Subscriptions::new();
+        subscriptions.subscribe::<ButtonEvent, _>(filtered).unwrap();
+
+        subscriptions.dispatch(&ButtonEvent::Pressed);
+        subscriptions.dispatch(&ButtonEvent::Released);
+        subscriptions.dispatch(&42u32);
+        subscriptions.dispatch(&ButtonEvent::Pressed);
+
+        let events = recorder.events.borrow();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| matches!(e, ButtonEvent::Pressed)));
+    }
+
+    #[derive(Default)]
+    struct Woken(AtomicBool);
+
+    impl Wake for Woken {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_wait_for() {
+        let waiter = Box::leak(Box::new(Waiter::new(|event: &ButtonEvent| {
+            matches!(event, ButtonEvent::Released)
+        })));
+        let mut subscriptions = Subscriptions::new();
+        subscriptions.subscribe::<ButtonEvent, _>(waiter).unwrap();
+
+        let woken = Arc::new(Woken::default());
+        let waker = Waker::from(woken.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut wait_for = WaitFor::new(waiter);
+        assert!(Pin::new(&mut wait_for).poll(&mut cx).is_pending());
+
+        // neither another type nor a filtered event resolves it
+        subscriptions.dispatch(&42u32);
+        subscriptions.dispatch(&ButtonEvent::Pressed);
+        assert!(!woken.0.load(Ordering::SeqCst));
+        assert_eq!(subscriptions.subscriptions.len(), 1);
+
+        subscriptions.dispatch(&ButtonEvent::Released);
+        assert!(woken.0.load(Ordering::SeqCst));
+        assert!(subscriptions.subscriptions.is_empty());
+        assert!(matches!(
+            Pin::new(&mut wait_for).poll(&mut cx),
+            Poll::Ready(ButtonEvent::Released)
+        ));
+    }
+
+    #[test]
+    fn test_wait_for_dropped() {
+        let recorder: &'static Recorder = Box::leak(Box::new(Recorder::default()));
+        let waiter = Box::leak(Box::new(Waiter::new(|_: &ButtonEvent| true)));
+        let mut subscriptions = Subscriptions::new();
+        subscriptions
+            .subscribe::<ButtonEvent, _>(Box::leak(Box::new(recorder)))
+            .unwrap();
+        subscriptions.subscribe::<ButtonEvent, _>(waiter).unwrap();
+        subscriptions
+            .subscribe::<ButtonEvent, _>(Box::leak(Box::new(recorder)))
+            .unwrap();
+
+        // dropped unresolved, and unsubscribed on the next event, the rest kept in order
+        drop(WaitFor::new(waiter));
+        subscriptions.dispatch(&42u32);
+        assert_eq!(subscriptions.subscriptions.len(), 2);
+        subscriptions.dispatch(&ButtonEvent::Pressed);
+        assert_eq!(recorder.events.borrow().len(), 2);
     }
 }
@@ -1,17 +1,24 @@
 //! Types and traits related to the root-level device and system-wide lifecycle events.
 
 use core::cell::{RefCell, UnsafeCell};
+use core::sync::atomic::AtomicBool;
 
 use crate::actor::ActorContext;
 use crate::prelude::{Address, EventBus, EventHandler};
+#[cfg(feature = "derive")]
+use crate::prelude::{Actor, Interrupt, InterruptContext, NotifyHandler};
 use crate::supervisor::Supervisor;
 
+#[cfg(feature = "derive")]
+pub use drogue_device_macros::EventRouter;
+
 /// System-wide lifecycle events.
 ///
 /// See also `NotificationHandler<...>`.  Each actor within the system is
 /// required to implement `NotificationHandler<Lifecycle>` but may opt to
 /// ignore any or all of the events.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub(crate) enum Lifecycle {
     /// Called after mounting but prior to starting the async executor.
     Initialize,
@@ -46,6 +53,42 @@ pub trait Device {
         Self: Sized;
 }
 
+/// A field an `EventRouter` routes events of type `E` to.
+///
+/// Events are dropped with a warning rather than panicking when the actor has no room
+/// for them, as the bus does for its subscribers.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub trait Route<E> {
+    fn route(&'static self, event: E);
+}
+
+#[cfg(feature = "derive")]
+impl<A, E> Route<E> for ActorContext<A>
+where
+    A: Actor + NotifyHandler<E> + 'static,
+    E: 'static,
+{
+    fn route(&'static self, event: E) {
+        if self.try_notify(event).is_err() {
+            warn!("[{}] busy, dropping routed event", self.name());
+        }
+    }
+}
+
+#[cfg(feature = "derive")]
+impl<I, E> Route<E> for InterruptContext<I>
+where
+    I: Interrupt + NotifyHandler<E> + 'static,
+    E: 'static,
+{
+    fn route(&'static self, event: E) {
+        if self.address().try_notify(event).is_err() {
+            warn!("[event-router] interrupt busy, dropping routed event");
+        }
+    }
+}
+
 #[doc(hidden)]
 pub struct DeviceContext<D: Device + 'static> {
     device: D,
@@ -64,6 +107,18 @@ impl<D: Device> DeviceContext<D> {
     }
 
     pub fn mount(&'static self) -> ! {
+        self.mount_device();
+        self.supervisor.borrow().run_forever()
+    }
+
+    /// Mount the device as `mount()` does, but once `shutdown` is found set at the end
+    /// of a pass, stop every actor and return when their `on_stop` handlers complete.
+    pub fn mount_until_shutdown(&'static self, shutdown: &AtomicBool) {
+        self.mount_device();
+        self.supervisor.borrow().run_until_shutdown(shutdown)
+    }
+
+    fn mount_device(&'static self) {
         let bus = ActorContext::new(EventBus::new(self)).with_name("event-bus");
         unsafe {
             // # Safety
@@ -74,7 +129,6 @@ impl<D: Device> DeviceContext<D> {
 
             let bus_address = bus.address();
             self.device.mount(bus_address, &mut *self.supervisor.borrow_mut());
-            (&*self.supervisor.borrow()).run_forever()
         }
     }
 
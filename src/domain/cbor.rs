@@ -0,0 +1,200 @@
+//! Minimal CBOR (RFC 8949) encoding, for shipping readings over links where JSON is
+//! too verbose.
+//!
+//! Only the items telemetry needs are supported: maps of known length, text strings,
+//! unsigned integers and single-precision floats. Items are written directly into a
+//! caller-provided buffer, and non-finite floats are encoded as such.
+
+/// Error returned when an encoding does not fit in the buffer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    BufferTooSmall,
+}
+
+const UNSIGNED: u8 = 0;
+const TEXT: u8 = 3;
+const MAP: u8 = 5;
+const FLOAT32: u8 = (7 << 5) | 26;
+
+/// Writes CBOR items one after the other into a buffer.
+pub struct Encoder<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+}
+
+impl<'b> Encoder<'b> {
+    pub fn new(buf: &'b mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// The number of bytes written so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Start a map of `entries` key/value pairs, each written as two items following it.
+    pub fn map(&mut self, entries: u64) -> Result<&mut Self, Error> {
+        self.head(MAP, entries)
+    }
+
+    pub fn text(&mut self, text: &str) -> Result<&mut Self, Error> {
+        self.head(TEXT, text.len() as u64)?;
+        self.write(text.as_bytes())
+    }
+
+    pub fn unsigned(&mut self, value: u64) -> Result<&mut Self, Error> {
+        self.head(UNSIGNED, value)
+    }
+
+    pub fn f32(&mut self, value: f32) -> Result<&mut Self, Error> {
+        self.write(&[FLOAT32])?;
+        self.write(&value.to_bits().to_be_bytes())
+    }
+
+    /// Write the initial bytes of an item, in the shortest form holding `value`.
+    fn head(&mut self, major: u8, value: u64) -> Result<&mut Self, Error> {
+        let major = major << 5;
+        if value < 24 {
+            self.write(&[major | value as u8])
+        } else if value <= u8::MAX as u64 {
+            self.write(&[major | 24, value as u8])
+        } else if value <= u16::MAX as u64 {
+            self.write(&[major | 25])?;
+            self.write(&(value as u16).to_be_bytes())
+        } else if value <= u32::MAX as u64 {
+            self.write(&[major | 26])?;
+            self.write(&(value as u32).to_be_bytes())
+        } else {
+            self.write(&[major | 27])?;
+            self.write(&value.to_be_bytes())
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<&mut Self, Error> {
+        let end = self.len + bytes.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(Error::BufferTooSmall)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Reads back the items written by an `Encoder`, panicking on anything else.
+    pub(crate) struct Decoder<'b> {
+        buf: &'b [u8],
+    }
+
+    impl<'b> Decoder<'b> {
+        pub(crate) fn new(buf: &'b [u8]) -> Self {
+            Self { buf }
+        }
+
+        pub(crate) fn is_empty(&self) -> bool {
+            self.buf.is_empty()
+        }
+
+        pub(crate) fn map(&mut self) -> u64 {
+            self.head(MAP)
+        }
+
+        pub(crate) fn text(&mut self) -> &'b str {
+            let len = self.head(TEXT) as usize;
+            core::str::from_utf8(self.take(len)).unwrap()
+        }
+
+        pub(crate) fn unsigned(&mut self) -> u64 {
+            self.head(UNSIGNED)
+        }
+
+        pub(crate) fn f32(&mut self) -> f32 {
+            assert_eq!(self.take(1), [FLOAT32]);
+            let mut bits = [0; 4];
+            bits.copy_from_slice(self.take(4));
+            f32::from_bits(u32::from_be_bytes(bits))
+        }
+
+        fn head(&mut self, major: u8) -> u64 {
+            let initial = self.take(1)[0];
+            assert_eq!(initial >> 5, major);
+            let len = match initial & 0x1f {
+                info if info < 24 => return info as u64,
+                24 => 1,
+                25 => 2,
+                26 => 4,
+                27 => 8,
+                info => panic!("unsupported additional information {}", info),
+            };
+            self.take(len)
+                .iter()
+                .fold(0, |value, byte| (value << 8) | *byte as u64)
+        }
+
+        fn take(&mut self, len: usize) -> &'b [u8] {
+            let (taken, rest) = self.buf.split_at(len);
+            self.buf = rest;
+            taken
+        }
+    }
+
+    #[test]
+    fn test_heads() {
+        let mut buf = [0; 32];
+        let mut encoder = Encoder::new(&mut buf);
+        encoder
+            .unsigned(23)
+            .and_then(|e| e.unsigned(24))
+            .and_then(|e| e.unsigned(1000))
+            .and_then(|e| e.unsigned(100_000))
+            .and_then(|e| e.unsigned(u64::MAX))
+            .unwrap();
+        let len = encoder.len();
+        assert_eq!(
+            buf[..len],
+            [
+                0x17, 0x18, 0x18, 0x19, 0x03, 0xe8, 0x1a, 0x00, 0x01, 0x86, 0xa0, 0x1b, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut buf = [0; 32];
+        let mut encoder = Encoder::new(&mut buf);
+        encoder
+            .map(2)
+            .and_then(|e| e.text("a"))
+            .and_then(|e| e.f32(-0.5))
+            .and_then(|e| e.text("bb"))
+            .and_then(|e| e.f32(f32::INFINITY))
+            .unwrap();
+        let len = encoder.len();
+
+        let mut decoder = Decoder::new(&buf[..len]);
+        assert_eq!(decoder.map(), 2);
+        assert_eq!(decoder.text(), "a");
+        assert_eq!(decoder.f32(), -0.5);
+        assert_eq!(decoder.text(), "bb");
+        assert_eq!(decoder.f32(), f32::INFINITY);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_buffer_too_small() {
+        let mut buf = [0; 4];
+        let mut encoder = Encoder::new(&mut buf);
+        encoder.text("abc").unwrap();
+        assert_eq!(encoder.f32(1.0).err(), Some(Error::BufferTooSmall));
+        assert_eq!(encoder.len(), 4);
+    }
+}
@@ -0,0 +1,187 @@
+//! Calendar date and time of day.
+
+/// A date and time of day in UTC, to the second, from 1970 onwards.
+///
+/// A `DateTime` is always a valid date, and orders chronologically.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+impl DateTime {
+    /// Create a date and time from its components, with `month` and `day` counting from
+    /// 1, returning `None` if it is not a valid date and time from 1970 onwards.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        let valid = year >= 1970
+            && (1..=12).contains(&month)
+            && day >= 1
+            && day <= days_in_month(year, month)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        if valid {
+            Some(Self {
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The date and time `seconds` after the start of 1970, returning `None` beyond the
+    /// year 65535.
+    pub fn from_unix_seconds(seconds: u64) -> Option<Self> {
+        let days = seconds / SECONDS_PER_DAY;
+        let time = seconds % SECONDS_PER_DAY;
+
+        // Days to civil date, after Howard Hinnant's algorithm, with eras of 400 years
+        // beginning on March 1st 0000.
+        let days = days + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        if year > u16::MAX as u64 {
+            return None;
+        }
+        Some(Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        })
+    }
+
+    /// Seconds since the start of 1970.
+    pub fn to_unix_seconds(&self) -> u64 {
+        let year = self.year as u64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year / 400;
+        let year_of_era = year % 400;
+        let shifted_month = (self.month as u64 + 9) % 12;
+        let day_of_year = (153 * shifted_month + 2) / 5 + self.day as u64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+
+        days * SECONDS_PER_DAY
+            + self.hour as u64 * 3600
+            + self.minute as u64 * 60
+            + self.second as u64
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month, from 1 to 12.
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, from 1.
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation() {
+        assert!(DateTime::new(2021, 2, 28, 23, 59, 59).is_some());
+        assert!(DateTime::new(2021, 2, 29, 0, 0, 0).is_none());
+        assert!(DateTime::new(2020, 2, 29, 0, 0, 0).is_some());
+        assert!(DateTime::new(1900, 2, 29, 0, 0, 0).is_none());
+        assert!(DateTime::new(2000, 2, 29, 0, 0, 0).is_some());
+        assert!(DateTime::new(2021, 4, 31, 0, 0, 0).is_none());
+        assert!(DateTime::new(2021, 13, 1, 0, 0, 0).is_none());
+        assert!(DateTime::new(2021, 1, 0, 0, 0, 0).is_none());
+        assert!(DateTime::new(2021, 1, 1, 24, 0, 0).is_none());
+        assert!(DateTime::new(1969, 12, 31, 23, 59, 59).is_none());
+    }
+
+    #[test]
+    fn test_unix_seconds() {
+        let epoch = DateTime::new(1970, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(epoch.to_unix_seconds(), 0);
+        assert_eq!(DateTime::from_unix_seconds(0), Some(epoch));
+
+        let leap_day = DateTime::new(2020, 2, 29, 12, 34, 56).unwrap();
+        assert_eq!(leap_day.to_unix_seconds(), 1_582_979_696);
+        assert_eq!(DateTime::from_unix_seconds(1_582_979_696), Some(leap_day));
+
+        let end_of_year = DateTime::new(2021, 12, 31, 23, 59, 59).unwrap();
+        assert_eq!(
+            DateTime::from_unix_seconds(end_of_year.to_unix_seconds() + 1),
+            DateTime::new(2022, 1, 1, 0, 0, 0)
+        );
+
+        let last = DateTime::new(u16::MAX, 12, 31, 23, 59, 59).unwrap();
+        assert_eq!(
+            DateTime::from_unix_seconds(last.to_unix_seconds()),
+            Some(last)
+        );
+        assert_eq!(
+            DateTime::from_unix_seconds(last.to_unix_seconds() + 1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        let earlier = DateTime::new(2021, 3, 14, 23, 59, 59).unwrap();
+        let later = DateTime::new(2021, 3, 15, 0, 0, 0).unwrap();
+        assert!(earlier < later);
+        assert!(DateTime::new(2020, 12, 31, 0, 0, 0).unwrap() < earlier);
+    }
+}
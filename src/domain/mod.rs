@@ -1,4 +1,8 @@
 //! General domain types and traits.
 
+pub mod cbor;
+pub mod datetime;
+pub mod pressure;
+pub mod telemetry;
 pub mod temperature;
 pub mod time;
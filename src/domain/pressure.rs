@@ -0,0 +1,40 @@
+//! Types related to pressure.
+
+use core::fmt::{Debug, Display, Formatter};
+
+/// An absolute pressure.
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub struct Pressure {
+    hectopascals: f32,
+}
+
+impl Pressure {
+    pub fn from_hectopascals(hectopascals: f32) -> Self {
+        Self { hectopascals }
+    }
+
+    pub fn from_pascals(pascals: f32) -> Self {
+        Self::from_hectopascals(pascals / 100.0)
+    }
+
+    pub fn hectopascals(&self) -> f32 {
+        self.hectopascals
+    }
+
+    pub fn pascals(&self) -> f32 {
+        self.hectopascals * 100.0
+    }
+}
+
+impl Debug for Pressure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}hPa", self.hectopascals)
+    }
+}
+
+impl Display for Pressure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.hectopascals, f)?;
+        f.write_str("hPa")
+    }
+}
@@ -0,0 +1,172 @@
+//! Telemetry envelopes encoded as JSON or CBOR for shipping readings off-device.
+//!
+//! JSON encoding writes directly into a `heapless::String` through `core::fmt`, with
+//! fields always emitted in declaration order. Non-finite values are encoded as `null`.
+//!
+//! CBOR encoding, for bandwidth-constrained links, writes a map with the same keys into
+//! a caller-provided buffer, with readings as single-precision floats.
+
+use crate::domain::cbor::{self, Encoder};
+use crate::domain::temperature::{Celsius, Temperature};
+use crate::domain::time::duration::Milliseconds;
+use crate::domain::time::{Clock, ConversionError, Instant};
+use core::convert::TryFrom;
+use core::fmt::{self, Write};
+use heapless::{ArrayLength, String};
+
+/// A temperature and humidity reading stamped with the device uptime.
+///
+/// Encodes as `{"ts":<uptime ms>,"temp_c":<°C>,"humidity":<%RH>}`.
+#[derive(Copy, Clone, Debug)]
+pub struct Telemetry {
+    pub ts: Milliseconds,
+    pub temperature: Temperature<Celsius>,
+    pub relative_humidity: f32,
+}
+
+impl Telemetry {
+    pub fn new(
+        ts: Milliseconds,
+        temperature: Temperature<Celsius>,
+        relative_humidity: f32,
+    ) -> Self {
+        Self {
+            ts,
+            temperature,
+            relative_humidity,
+        }
+    }
+
+    /// Stamp a reading with the uptime of `clock`, as given by an `Instant` read from it.
+    pub fn at<C: Clock>(
+        now: Instant<C>,
+        temperature: Temperature<Celsius>,
+        relative_humidity: f32,
+    ) -> Result<Self, ConversionError>
+    where
+        u32: TryFrom<C::T>,
+    {
+        Ok(Self::new(
+            Milliseconds::try_from(now.duration_since_epoch())?,
+            temperature,
+            relative_humidity,
+        ))
+    }
+
+    /// Encode as JSON, failing if it does not fit in `N` bytes.
+    pub fn to_json<N: ArrayLength<u8>>(&self) -> Result<String<N>, fmt::Error> {
+        let mut json = String::new();
+        write!(json, "{{\"ts\":{},", self.ts.0)?;
+        write_reading(&mut json, self.temperature, self.relative_humidity)?;
+        Ok(json)
+    }
+
+    /// Encode as CBOR into `buf`, returning the number of bytes written.
+    pub fn to_cbor(&self, buf: &mut [u8]) -> Result<usize, cbor::Error> {
+        let mut encoder = Encoder::new(buf);
+        encoder.map(3)?.text("ts")?.unsigned(self.ts.0 as u64)?;
+        encode_reading(&mut encoder, self.temperature, self.relative_humidity)?;
+        Ok(encoder.len())
+    }
+}
+
+/// Encode the `temp_c` and `humidity` entries shared by the CBOR encodings of a reading.
+pub(crate) fn encode_reading(
+    encoder: &mut Encoder,
+    temperature: Temperature<Celsius>,
+    relative_humidity: f32,
+) -> Result<(), cbor::Error> {
+    encoder
+        .text("temp_c")?
+        .f32(temperature.value())?
+        .text("humidity")?
+        .f32(relative_humidity)?;
+    Ok(())
+}
+
+/// Write the `"temp_c":..,"humidity":..}` tail shared by the encodings of a reading.
+pub(crate) fn write_reading<W: Write>(
+    w: &mut W,
+    temperature: Temperature<Celsius>,
+    relative_humidity: f32,
+) -> fmt::Result {
+    w.write_str("\"temp_c\":")?;
+    write_number(w, temperature.value())?;
+    w.write_str(",\"humidity\":")?;
+    write_number(w, relative_humidity)?;
+    w.write_char('}')
+}
+
+fn write_number<W: Write>(w: &mut W, value: f32) -> fmt::Result {
+    if value.is_finite() {
+        write!(w, "{}", value)
+    } else {
+        w.write_str("null")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::cbor::tests::Decoder;
+    use heapless::consts::*;
+
+    #[test]
+    fn test_to_json() {
+        let telemetry = Telemetry::new(Milliseconds(12345u32), 21.5.into(), 40.25);
+        assert_eq!(
+            telemetry.to_json::<U64>().unwrap().as_str(),
+            r#"{"ts":12345,"temp_c":21.5,"humidity":40.25}"#
+        );
+    }
+
+    #[test]
+    fn test_to_json_negative_and_whole() {
+        let telemetry = Telemetry::new(Milliseconds(0u32), (-7.0).into(), 100.0);
+        assert_eq!(
+            telemetry.to_json::<U64>().unwrap().as_str(),
+            r#"{"ts":0,"temp_c":-7,"humidity":100}"#
+        );
+    }
+
+    #[test]
+    fn test_to_json_non_finite() {
+        let telemetry = Telemetry::new(Milliseconds(1u32), f32::NAN.into(), f32::INFINITY);
+        assert_eq!(
+            telemetry.to_json::<U64>().unwrap().as_str(),
+            r#"{"ts":1,"temp_c":null,"humidity":null}"#
+        );
+    }
+
+    #[test]
+    fn test_to_json_too_long() {
+        let telemetry = Telemetry::new(Milliseconds(12345u32), 21.5.into(), 40.25);
+        assert_eq!(telemetry.to_json::<U16>(), Err(fmt::Error));
+    }
+
+    #[test]
+    fn test_to_cbor() {
+        let telemetry = Telemetry::new(Milliseconds(12345u32), (-7.25).into(), f32::NAN);
+        let mut buf = [0; 64];
+        let len = telemetry.to_cbor(&mut buf).unwrap();
+        assert_eq!(len, 33);
+        assert!(len < telemetry.to_json::<U64>().unwrap().len());
+
+        let mut decoder = Decoder::new(&buf[..len]);
+        assert_eq!(decoder.map(), 3);
+        assert_eq!(decoder.text(), "ts");
+        assert_eq!(decoder.unsigned(), 12345);
+        assert_eq!(decoder.text(), "temp_c");
+        assert_eq!(decoder.f32(), -7.25);
+        assert_eq!(decoder.text(), "humidity");
+        assert!(decoder.f32().is_nan());
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_to_cbor_too_long() {
+        let telemetry = Telemetry::new(Milliseconds(12345u32), 21.5.into(), 40.25);
+        let mut buf = [0; 32];
+        assert_eq!(telemetry.to_cbor(&mut buf), Err(cbor::Error::BufferTooSmall));
+    }
+}
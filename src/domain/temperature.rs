@@ -1,5 +1,6 @@
 //! Types and traits related to temperature.
 
+use core::cmp::Ordering;
 use core::fmt::{Debug, Display, Formatter};
 use core::marker::PhantomData;
 use core::ops::{Add, Div, Sub};
@@ -71,6 +72,19 @@ impl<S:TemperatureScale> Debug for Temperature<S> {
 
 impl<S: TemperatureScale> Copy for Temperature<S> {}
 
+impl<S: TemperatureScale> PartialEq for Temperature<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+/// Temperatures in the same scale are ordered by value, such as for threshold checks.
+impl<S: TemperatureScale> PartialOrd for Temperature<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
 impl<S: TemperatureScale> Temperature<S> {
     pub fn new(value: f32) -> Self {
         Self {
@@ -78,6 +92,11 @@ impl<S: TemperatureScale> Temperature<S> {
             _marker: PhantomData::default(),
         }
     }
+
+    /// The numeric value of this temperature, in its scale.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
 }
 
 impl Temperature<Celsius> {
@@ -98,10 +117,27 @@ impl Into<Temperature<Celsius>> for f32 {
     }
 }
 
+/// The difference between two temperatures.
 impl<S: TemperatureScale> Sub for Temperature<S> {
-    type Output = Self;
+    type Output = TemperatureDelta<S>;
 
     fn sub(self, rhs: Self) -> Self::Output {
+        TemperatureDelta::new(self.value - rhs.value)
+    }
+}
+
+impl<S: TemperatureScale> Add<TemperatureDelta<S>> for Temperature<S> {
+    type Output = Self;
+
+    fn add(self, rhs: TemperatureDelta<S>) -> Self::Output {
+        Self::new(self.value + rhs.value)
+    }
+}
+
+impl<S: TemperatureScale> Sub<TemperatureDelta<S>> for Temperature<S> {
+    type Output = Self;
+
+    fn sub(self, rhs: TemperatureDelta<S>) -> Self::Output {
         Self::new(self.value - rhs.value)
     }
 }
@@ -130,9 +166,132 @@ impl<S: TemperatureScale> Div<f32> for Temperature<S> {
     }
 }
 
+/// Formats the value followed by its scale, such as `21.5°C`.
+///
+/// The precision of the value is taken from the format string, so
+/// `{:.1}` formats `-3.26°C` as `-3.3°C`.
 impl<S: TemperatureScale> Display for Temperature<S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         Display::fmt(&self.value, f)?;
         write!(f, "°{}", S::LETTER)
     }
 }
+
+/// A difference between two temperatures in the same scale.
+///
+/// Unlike a `Temperature`, a delta does not denote a point on its scale, and so
+/// is not converted between scales by offsetting it.
+pub struct TemperatureDelta<S: TemperatureScale> {
+    value: f32,
+    _marker: PhantomData<S>,
+}
+
+impl<S: TemperatureScale> TemperatureDelta<S> {
+    pub fn new(value: f32) -> Self {
+        Self {
+            value,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The numeric value of this delta, in its scale.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// The magnitude of this delta, regardless of its direction.
+    pub fn abs(self) -> Self {
+        Self::new(if self.value < 0.0 {
+            -self.value
+        } else {
+            self.value
+        })
+    }
+}
+
+impl<S: TemperatureScale> Clone for TemperatureDelta<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: TemperatureScale> Copy for TemperatureDelta<S> {}
+
+impl<S: TemperatureScale> PartialEq for TemperatureDelta<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<S: TemperatureScale> PartialOrd for TemperatureDelta<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+/// Divides the value of a delta, such as to compute degrees per sensor count.
+impl<S: TemperatureScale> Div<f32> for TemperatureDelta<S> {
+    type Output = f32;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        self.value / rhs
+    }
+}
+
+impl<S: TemperatureScale> Debug for TemperatureDelta<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Δ{}°{}", &self.value, S::LETTER)
+    }
+}
+
+/// Formats the value followed by its scale, taking the precision from the format string.
+impl<S: TemperatureScale> Display for TemperatureDelta<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.value, f)?;
+        write!(f, "°{}", S::LETTER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+    use heapless::{consts::*, String};
+
+    fn celsius(value: f32) -> Temperature<Celsius> {
+        Temperature::new(value)
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(celsius(-1.5) < celsius(0.0));
+        assert!(celsius(30.0) >= celsius(30.0));
+        assert!(celsius(30.5) > celsius(30.0));
+        assert_eq!(celsius(f32::NAN).partial_cmp(&celsius(0.0)), None);
+    }
+
+    #[test]
+    fn test_delta() {
+        let delta = celsius(18.5) - celsius(21.0);
+        assert_eq!(delta.value(), -2.5);
+        assert_eq!(delta.abs().value(), 2.5);
+        assert!(delta.abs() > TemperatureDelta::new(2.0));
+        assert!(celsius(21.0) + delta == celsius(18.5));
+        assert!(celsius(21.0) - delta == celsius(23.5));
+    }
+
+    #[test]
+    fn test_display_negative() {
+        let mut s: String<U16> = String::new();
+        write!(s, "{}", celsius(-7.0)).unwrap();
+        assert_eq!(s.as_str(), "-7°C");
+
+        let mut s: String<U16> = String::new();
+        write!(s, "{:.1}", celsius(-3.26)).unwrap();
+        assert_eq!(s.as_str(), "-3.3°C");
+
+        let mut s: String<U16> = String::new();
+        write!(s, "{:.2}", celsius(0.5) - celsius(2.0)).unwrap();
+        assert_eq!(s.as_str(), "-1.50°C");
+    }
+}
@@ -40,12 +40,16 @@ pub use units::*;
 ///
 /// # Formatting
 ///
-/// Just forwards the underlying integer to [`core::fmt::Display::fmt()`]
+/// Writes the duration in the largest unit it amounts to at least one of, from hours
+/// (`h`), minutes (`min`) and seconds (`s`) down to milliseconds (`ms`), microseconds (`us`)
+/// and nanoseconds (`ns`), with up to three decimals, truncated
 ///
 /// ```rust
 /// use drogue_device::domain::time::duration::*;
 ///
-/// assert_eq!(format!("{}", Seconds(123_u32)), "123");
+/// assert_eq!(format!("{}", Seconds(123_u32)), "2.05min");
+/// assert_eq!(format!("{}", Milliseconds(1_500_u32)), "1.5s");
+/// assert_eq!(format!("{}", Microseconds(250_u32)), "250us");
 /// ```
 ///
 /// # Getting H:M:S.MS... Components
@@ -76,17 +80,32 @@ pub use units::*;
 /// assert_eq!(seconds.integer(), &23_u32);
 /// ```
 ///
-/// Others require the use of `TryFrom`/`TryInto`:
+/// Converting a `u32` duration to a finer unit saturates at `u32::MAX`:
 ///
 /// ```rust
 /// use drogue_device::domain::time::duration::*;
-/// use std::convert::{TryInto, TryFrom};
 ///
-/// let millis = Milliseconds::<u32>::try_from(23_u32.seconds()).unwrap();
+/// let millis = Milliseconds::<u32>::from(23_u32.seconds());
 /// assert_eq!(millis.integer(), &23_000_u32);
 ///
-/// let millis: Milliseconds<u32> = 23_u32.seconds().try_into().unwrap();
+/// let millis: Milliseconds<u32> = 23_u32.seconds().into();
 /// assert_eq!(millis.integer(), &23_000_u32);
+///
+/// let millis: Milliseconds<u32> = Minutes(71_583_u32).into();
+/// assert_eq!(millis, Milliseconds(u32::MAX));
+/// ```
+///
+/// Others require the use of `TryFrom`/`TryInto`:
+///
+/// ```rust
+/// use drogue_device::domain::time::duration::*;
+/// use std::convert::{TryInto, TryFrom};
+///
+/// let millis = Milliseconds::<u64>::try_from(23_u64.seconds()).unwrap();
+/// assert_eq!(millis.integer(), &23_000_u64);
+///
+/// let millis: Result<Milliseconds<u32>, _> = 23_u64.seconds().try_into();
+/// assert_eq!(millis, Ok(Milliseconds(23_000_u32)));
 /// ```
 ///
 /// # Converting to `core` types
@@ -251,14 +270,15 @@ pub use units::*;
 ///     Milliseconds(1_001_u32));
 /// ```
 ///
-/// ## Panics
+/// ## Saturation
 ///
-/// The same reason the integer operation would panic. Namely, if the result overflows the type.
+/// Rather than overflowing, results saturate at the largest value of the type, and at zero.
 ///
-/// ```rust,should_panic
+/// ```rust
 /// use drogue_device::domain::time::duration::*;
 ///
-/// let _ = Seconds(u32::MAX) + Seconds(1_u32);
+/// assert_eq!(Seconds(u32::MAX) + Seconds(1_u32), Seconds(u32::MAX));
+/// assert_eq!(Seconds(1_u32) - Seconds(2_u32), Seconds(0_u32));
 /// ```
 ///
 /// # Mul/Div
@@ -480,6 +500,35 @@ pub mod units {
     #[doc(hidden)]
     pub use Extensions as _;
 
+    /// Write `nanos` in the largest unit it amounts to at least one of, with up to three
+    /// decimals.
+    fn fmt_nanos(nanos: u128, f: &mut Formatter<'_>) -> fmt::Result {
+        const UNITS: [(u128, &str); 6] = [
+            (3_600_000_000_000, "h"),
+            (60_000_000_000, "min"),
+            (1_000_000_000, "s"),
+            (1_000_000, "ms"),
+            (1_000, "us"),
+            (1, "ns"),
+        ];
+        let (scale, unit) = UNITS
+            .iter()
+            .copied()
+            .find(|(scale, _)| nanos >= *scale)
+            .unwrap_or((1_000_000_000, "s"));
+        write!(f, "{}", nanos / scale)?;
+        let mut decimals = (nanos % scale) * 1_000 / scale;
+        if decimals > 0 {
+            let mut digits = 3;
+            while decimals.is_multiple_of(10) {
+                decimals /= 10;
+                digits -= 1;
+            }
+            write!(f, ".{:0digits$}", decimals, digits = digits)?;
+        }
+        f.write_str(unit)
+    }
+
     macro_rules! impl_duration {
         ( $name:ident, ($numer:expr, $denom:expr) ) => {
             /// A duration unit type
@@ -513,7 +562,16 @@ pub mod units {
             impl<T: TimeInt> fmt::Display for $name<T> {
                 /// See [Formatting](trait.Duration.html#formatting)
                 fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-                    fmt::Display::fmt(&self.0, f)
+                    let value: u64 = self.0.into();
+                    let nanos = value as u128 * $numer * 1_000_000_000 / $denom;
+                    fmt_nanos(nanos, f)
+                }
+            }
+
+            #[cfg(feature = "defmt")]
+            impl<T: TimeInt> defmt::Format for $name<T> {
+                fn format(&self, f: defmt::Formatter) {
+                    defmt::write!(f, "{}", defmt::Display2Format(self))
                 }
             }
 
@@ -526,7 +584,7 @@ pub mod units {
 
                 /// See [Add/Sub](trait.Duration.html#addsub)
                 fn add(self, rhs: Rhs) -> Self::Output {
-                    <Self as FixedPoint>::add(self, rhs)
+                    <Self as FixedPoint>::saturating_add(self, rhs)
                 }
             }
 
@@ -539,7 +597,7 @@ pub mod units {
 
                 /// See [Add/Sub](trait.Duration.html#addsub)
                 fn sub(self, rhs: Rhs) -> Self::Output {
-                    <Self as FixedPoint>::sub(self, rhs)
+                    <Self as FixedPoint>::saturating_sub(self, rhs)
                 }
             }
 
@@ -690,6 +748,60 @@ pub mod units {
     impl_duration![Microseconds, (1, 1_000_000), from_micros, as_micros];
     impl_duration![Nanoseconds, (1, 1_000_000_000), from_nanos, as_nanos];
 
+    impl Milliseconds<u32> {
+        /// `secs` seconds, saturating at `u32::MAX` milliseconds.
+        ///
+        /// ```rust
+        /// use drogue_device::domain::time::duration::*;
+        ///
+        /// const TIMEOUT: Milliseconds = Milliseconds::from_secs(5);
+        /// assert_eq!(TIMEOUT, Milliseconds(5_000_u32));
+        /// ```
+        pub const fn from_secs(secs: u32) -> Self {
+            Self(secs.saturating_mul(1_000))
+        }
+
+        /// `minutes` minutes, saturating at `u32::MAX` milliseconds.
+        pub const fn from_minutes(minutes: u32) -> Self {
+            Self(minutes.saturating_mul(60_000))
+        }
+
+        /// `millis` milliseconds, such as parsed from user input.
+        ///
+        /// # Errors
+        ///
+        /// [`ConversionError::Overflow`] : `millis` exceeds `u32::MAX`.
+        ///
+        /// ```rust
+        /// use drogue_device::domain::time::{duration::*, ConversionError};
+        ///
+        /// assert_eq!(Milliseconds::try_from_u64(200), Ok(Milliseconds(200_u32)));
+        /// assert_eq!(
+        ///     Milliseconds::try_from_u64(1 << 32),
+        ///     Err(ConversionError::Overflow)
+        /// );
+        /// ```
+        pub fn try_from_u64(millis: u64) -> Result<Self, ConversionError> {
+            u32::try_from(millis)
+                .map(Self)
+                .map_err(|_| ConversionError::Overflow)
+        }
+
+        /// `millis` milliseconds, such as parsed from user input.
+        ///
+        /// # Errors
+        ///
+        /// [`ConversionError::NegDuration`] : `millis` is negative.
+        ///
+        /// [`ConversionError::Overflow`] : `millis` exceeds `u32::MAX`.
+        pub fn try_from_i64(millis: i64) -> Result<Self, ConversionError> {
+            if millis < 0 {
+                return Err(ConversionError::NegDuration);
+            }
+            Self::try_from_u64(millis as u64)
+        }
+    }
+
     macro_rules! impl_partial_eq {
         ($name:ident) => {
             impl<T: TimeInt, RhsInt: TimeInt> cmp::PartialEq<$name<RhsInt>> for $name<T>
@@ -948,15 +1060,25 @@ pub mod units {
                     }
                 }
 
-                impl<T: TimeInt> TryFrom<$big<T>> for $small<T>
+                impl From<$big<u32>> for $small<u32>
+                {
+                    /// See [Converting between `Duration`s](trait.Duration.html#converting-between-durations)
+                    ///
+                    /// Saturates at `u32::MAX` if the duration does not fit.
+                    fn from(big: $big<u32>) -> Self {
+                        fixed_point::FixedPoint::from_ticks(*big.integer(), $big::<u32>::SCALING_FACTOR).unwrap_or(Self(u32::MAX))
+                    }
+                }
+
+                impl TryFrom<$big<u64>> for $small<u64>
                 {
                     type Error = ConversionError;
 
                     /// See [Converting between `Duration`s](trait.Duration.html#converting-between-durations)
-                    fn try_from(big: $big<T>) -> Result<Self, Self::Error> {
+                    fn try_from(big: $big<u64>) -> Result<Self, Self::Error> {
                         fixed_point::FixedPoint::from_ticks(
                             *big.integer(),
-                            $big::<T>::SCALING_FACTOR,
+                            $big::<u64>::SCALING_FACTOR,
                         )
                     }
                 }
@@ -1035,4 +1157,135 @@ pub mod units {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use core::convert::TryFrom;
+
+    #[test]
+    fn test_display() {
+        extern crate std;
+        use std::format;
+
+        assert_eq!(format!("{}", Milliseconds(0_u32)), "0s");
+        assert_eq!(format!("{}", Nanoseconds(7_u32)), "7ns");
+        assert_eq!(format!("{}", Nanoseconds(1_500_u32)), "1.5us");
+        assert_eq!(format!("{}", Microseconds(250_u32)), "250us");
+        assert_eq!(format!("{}", Microseconds(999_999_u32)), "999.999ms");
+        assert_eq!(format!("{}", Milliseconds(500_u32)), "500ms");
+        assert_eq!(format!("{}", Milliseconds(1_500_u32)), "1.5s");
+        assert_eq!(format!("{}", Milliseconds(1_234_567_u32)), "20.576min");
+        assert_eq!(format!("{}", Seconds(60_u32)), "1min");
+        assert_eq!(format!("{}", Minutes(150_u32)), "2.5h");
+        assert_eq!(format!("{}", Hours(u64::MAX)), "18446744073709551615h");
+    }
+
+    #[test]
+    fn test_into_milliseconds() {
+        let millis: Milliseconds = Seconds(5_u32).into();
+        assert_eq!(millis, Milliseconds(5_000_u32));
+        let millis: Milliseconds = Minutes(2_u32).into();
+        assert_eq!(millis, Milliseconds(120_000_u32));
+        let millis: Milliseconds = Hours(1_u32).into();
+        assert_eq!(millis, Milliseconds(3_600_000_u32));
+    }
+
+    #[test]
+    fn test_into_milliseconds_saturates() {
+        // 71_582 minutes is the most that fits in u32 milliseconds
+        let millis: Milliseconds = Minutes(71_582_u32).into();
+        assert_eq!(millis, Milliseconds(4_294_920_000_u32));
+        let millis: Milliseconds = Minutes(71_583_u32).into();
+        assert_eq!(millis, Milliseconds(u32::MAX));
+        let millis: Milliseconds = Seconds(u32::MAX).into();
+        assert_eq!(millis, Milliseconds(u32::MAX));
+    }
+
+    #[test]
+    fn test_u64_conversion_fails_on_overflow() {
+        assert!(Milliseconds::<u64>::try_from(Hours(u64::MAX)).is_err());
+        assert!(Milliseconds::<u32>::try_from(Minutes(71_583_u64)).is_err());
+    }
+
+    #[test]
+    fn test_add_saturates() {
+        assert_eq!(
+            Milliseconds(u32::MAX - 1) + Milliseconds(1_u32),
+            Milliseconds(u32::MAX)
+        );
+        assert_eq!(
+            Milliseconds(u32::MAX) + Milliseconds(1_u32),
+            Milliseconds(u32::MAX)
+        );
+        assert_eq!(Seconds(u32::MAX) + Minutes(1_u32), Seconds(u32::MAX));
+        assert_eq!(Minutes(1_u32) + Seconds(u32::MAX), Minutes(1 + u32::MAX / 60));
+        assert_eq!(Minutes(u32::MAX) + Seconds(60_u32), Minutes(u32::MAX));
+    }
+
+    #[test]
+    fn test_sub_saturates() {
+        assert_eq!(Milliseconds(5_u32) - Milliseconds(5_u32), Milliseconds(0_u32));
+        assert_eq!(Milliseconds(5_u32) - Milliseconds(6_u32), Milliseconds(0_u32));
+        assert_eq!(Milliseconds(0_u32) - Seconds(1_u32), Milliseconds(0_u32));
+        assert_eq!(Milliseconds(1_500_u32) - Seconds(1_u32), Milliseconds(500_u32));
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(Milliseconds(250_u32) * 4, Milliseconds(1_000_u32));
+        assert_eq!(Milliseconds(1_000_u32) / 3, Milliseconds(333_u32));
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(Milliseconds(1_u32) < Milliseconds(2_u32));
+        assert!(Milliseconds(0_u32) <= Milliseconds(0_u32));
+        assert!(Seconds(1_u32) > Milliseconds(999_u32));
+        assert_eq!(
+            Milliseconds(7_u32).min(Milliseconds(3_u32)),
+            Milliseconds(3_u32)
+        );
+        assert_eq!(
+            Milliseconds(7_u32).max(Milliseconds(u32::MAX)),
+            Milliseconds(u32::MAX)
+        );
+    }
+
+    #[test]
+    fn test_const_constructors() {
+        const SECS: Milliseconds = Milliseconds::from_secs(5);
+        const MINUTES: Milliseconds = Milliseconds::from_minutes(3);
+        assert_eq!(SECS, Milliseconds(5_000_u32));
+        assert_eq!(MINUTES, Milliseconds(180_000_u32));
+        assert_eq!(Milliseconds::from_secs(u32::MAX), Milliseconds(u32::MAX));
+        assert_eq!(Milliseconds::from_minutes(71_583), Milliseconds(u32::MAX));
+    }
+
+    #[test]
+    fn test_checked_constructors() {
+        assert_eq!(Milliseconds::try_from_u64(0), Ok(Milliseconds(0_u32)));
+        assert_eq!(Milliseconds::try_from_u64(1_500), Ok(Milliseconds(1_500_u32)));
+        assert_eq!(
+            Milliseconds::try_from_u64(u32::MAX as u64),
+            Ok(Milliseconds(u32::MAX))
+        );
+        assert_eq!(
+            Milliseconds::try_from_u64(u32::MAX as u64 + 1),
+            Err(ConversionError::Overflow)
+        );
+        assert_eq!(
+            Milliseconds::try_from_u64(u64::MAX),
+            Err(ConversionError::Overflow)
+        );
+
+        assert_eq!(
+            Milliseconds::try_from_i64(u32::MAX as i64),
+            Ok(Milliseconds(u32::MAX))
+        );
+        assert_eq!(
+            Milliseconds::try_from_i64(u32::MAX as i64 + 1),
+            Err(ConversionError::Overflow)
+        );
+        assert_eq!(
+            Milliseconds::try_from_i64(-1),
+            Err(ConversionError::NegDuration)
+        );
+    }
 }
@@ -1,7 +1,7 @@
 //! Fixed-point values
 use crate::domain::time::{fraction::Fraction, time_int::TimeInt, ConversionError};
 use core::{convert::TryFrom, mem::size_of, prelude::v1::*};
-use num::{Bounded, CheckedDiv, CheckedMul};
+use num::{Bounded, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub};
 
 /// Fixed-point value type
 ///
@@ -163,6 +163,34 @@ pub trait FixedPoint: Sized + Copy {
         Self::new(*self.integer() - *Self::try_from(rhs).ok().unwrap().integer())
     }
 
+    /// Addition saturating at the maximum integer value
+    #[doc(hidden)]
+    fn saturating_add<Rhs: FixedPoint>(self, rhs: Rhs) -> Self
+    where
+        Self: TryFrom<Rhs>,
+    {
+        Self::new(
+            Self::try_from(rhs)
+                .ok()
+                .and_then(|rhs| self.integer().checked_add(rhs.integer()))
+                .unwrap_or_else(Self::max_value),
+        )
+    }
+
+    /// Subtraction saturating at zero
+    #[doc(hidden)]
+    fn saturating_sub<Rhs: FixedPoint>(self, rhs: Rhs) -> Self
+    where
+        Self: TryFrom<Rhs>,
+    {
+        Self::new(
+            Self::try_from(rhs)
+                .ok()
+                .and_then(|rhs| self.integer().checked_sub(rhs.integer()))
+                .unwrap_or_else(Self::min_value),
+        )
+    }
+
     /// Panicky multiplication
     #[doc(hidden)]
     fn mul(self, rhs: Self::T) -> Self {
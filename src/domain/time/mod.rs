@@ -283,6 +283,7 @@ mod instant;
 pub mod rate;
 mod time_int;
 mod timer;
+pub mod uptime;
 
 pub use clock::Clock;
 pub use instant::Instant;
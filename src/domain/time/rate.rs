@@ -375,6 +375,7 @@ impl<T: TimeInt> Rate for Generic<T> {}
 pub mod units {
     use super::*;
     use crate::domain::time::{
+        duration::{Microseconds, Milliseconds},
         fixed_point::{self, FixedPoint},
         fraction::Fraction,
         time_int::TimeInt,
@@ -513,6 +514,39 @@ pub mod units {
     impl_rate![Centihertz, (1, 100), "Hertz / 100"];
     impl_rate![Millihertz, (1, 1_000), "Hertz / 1000"];
     impl_rate![Microhertz, (1, 1_000_000), "Hertz / 1,000,000"];
+
+    impl Hertz<u32> {
+        /// The period of one cycle, rounded to the nearest millisecond.
+        ///
+        /// Rates above 1 kHz, which would round to less than a millisecond, give a period of
+        /// `Milliseconds(1)`. `Hertz(0)` has no period and gives `Milliseconds(u32::MAX)`.
+        ///
+        /// ```rust
+        /// use drogue_device::domain::time::{duration::*, rate::*};
+        ///
+        /// assert_eq!(Hertz(2_u32).period(), Milliseconds(500_u32));
+        /// assert_eq!(Hertz(3_u32).period(), Milliseconds(333_u32));
+        /// ```
+        pub fn period(self) -> Milliseconds<u32> {
+            Milliseconds(Self::divide(1_000, self.0))
+        }
+
+        /// The period of one cycle, rounded to the nearest microsecond.
+        ///
+        /// As with [`period`](Hertz::period), the result is at least `Microseconds(1)`, and
+        /// `Hertz(0)` gives `Microseconds(u32::MAX)`.
+        pub fn period_micros(self) -> Microseconds<u32> {
+            Microseconds(Self::divide(1_000_000, self.0))
+        }
+
+        fn divide(units_per_second: u32, hertz: u32) -> u32 {
+            if hertz == 0 {
+                return u32::MAX;
+            }
+            let rounded = (units_per_second as u64 + hertz as u64 / 2) / hertz as u64;
+            rounded.max(1) as u32
+        }
+    }
     impl_rate![
         MebibytesPerSecond,
         (1_048_576 * 8, 1),
@@ -879,4 +913,39 @@ pub mod units {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::domain::time::duration::*;
+
+    #[test]
+    fn test_period() {
+        assert_eq!(Hertz(1_u32).period(), Milliseconds(1_000_u32));
+        assert_eq!(Hertz(10_u32).period(), Milliseconds(100_u32));
+        assert_eq!(Hertz(100_u32).period(), Milliseconds(10_u32));
+        assert_eq!(Hertz(1_000_u32).period(), Milliseconds(1_u32));
+    }
+
+    #[test]
+    fn test_period_rounding() {
+        // 142.857ms rounds up, 333.333ms rounds down
+        assert_eq!(Hertz(7_u32).period(), Milliseconds(143_u32));
+        assert_eq!(Hertz(3_u32).period(), Milliseconds(333_u32));
+        // never rounds down to zero
+        assert_eq!(Hertz(5_000_u32).period(), Milliseconds(1_u32));
+    }
+
+    #[test]
+    fn test_period_micros() {
+        assert_eq!(Hertz(1_u32).period_micros(), Microseconds(1_000_000_u32));
+        assert_eq!(Hertz(10_u32).period_micros(), Microseconds(100_000_u32));
+        assert_eq!(Hertz(100_u32).period_micros(), Microseconds(10_000_u32));
+        assert_eq!(Hertz(3_u32).period_micros(), Microseconds(333_333_u32));
+        assert_eq!(Hertz(3_000_000_u32).period_micros(), Microseconds(1_u32));
+    }
+
+    #[test]
+    fn test_zero_hertz() {
+        assert_eq!(Hertz(0_u32).period(), Milliseconds(u32::MAX));
+        assert_eq!(Hertz(0_u32).period_micros(), Microseconds(u32::MAX));
+    }
+}
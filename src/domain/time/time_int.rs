@@ -14,6 +14,7 @@ pub trait TimeInt:
     + num::CheckedMul
     + num::CheckedDiv
     + From<u32>
+    + Into<u64>
     + ops::Mul<Fraction, Output = Self>
     + ops::Div<Fraction, Output = Self>
     + fmt::Display
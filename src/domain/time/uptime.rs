@@ -0,0 +1,157 @@
+//! Instants of the uptime counted by a driver clock
+//!
+//! The clocks of drivers, such as the address of a `TimerActor`, count the time since
+//! they started in [`Milliseconds`], wrapping after about 49.7 days. An [`Instant`] is
+//! a reading of that count, to which durations may be added and from which others may
+//! be subtracted:
+//!
+//! ```rust
+//! use drogue_device::domain::time::duration::Milliseconds;
+//! use drogue_device::domain::time::uptime::Instant;
+//!
+//! let started = Instant::from_uptime(Milliseconds(1_000u32));
+//! let deadline = started + Milliseconds(250u32);
+//! assert!(deadline > started);
+//! assert_eq!(deadline - started, Milliseconds(250u32));
+//! ```
+//!
+//! Arithmetic wraps with the count, so instants compare and subtract correctly across
+//! the wraparound, as long as they are less than half the range, about 24.8 days,
+//! apart.
+//!
+//! This is not the [`Instant`](crate::domain::time::Instant) of the `time` module, which
+//! counts the ticks of a hardware `domain::time::Clock` in its own units. An uptime
+//! `Instant` is always in milliseconds, as read from `driver::timer::Clock::instant`.
+
+use crate::domain::time::duration::Milliseconds;
+use core::cmp::Ordering;
+use core::ops;
+
+/// An instant of the uptime counted by a driver clock
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Instant(u32);
+
+impl Instant {
+    /// The instant `uptime` after the clock started
+    pub fn from_uptime(uptime: Milliseconds) -> Self {
+        Self(uptime.0)
+    }
+
+    /// The count of the clock at this instant
+    pub fn uptime(&self) -> Milliseconds {
+        Milliseconds(self.0)
+    }
+
+    /// The time from `earlier` to this instant, or `None` if `earlier` is later
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Milliseconds> {
+        if *self >= earlier {
+            Some(Milliseconds(self.0.wrapping_sub(earlier.0)))
+        } else {
+            None
+        }
+    }
+}
+
+impl From<Milliseconds> for Instant {
+    fn from(uptime: Milliseconds) -> Self {
+        Self::from_uptime(uptime)
+    }
+}
+
+impl PartialOrd for Instant {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Instant {
+    /// Instants are ordered by the wrapping difference between them, so an instant
+    /// just past the wraparound is later than one just before it.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.wrapping_sub(other.0) as i32).cmp(&0)
+    }
+}
+
+impl ops::Add<Milliseconds> for Instant {
+    type Output = Self;
+
+    /// The instant `duration` later, wrapping with the count
+    fn add(self, duration: Milliseconds) -> Self {
+        Self(self.0.wrapping_add(duration.0))
+    }
+}
+
+impl ops::AddAssign<Milliseconds> for Instant {
+    fn add_assign(&mut self, duration: Milliseconds) {
+        *self = *self + duration;
+    }
+}
+
+impl ops::Sub<Milliseconds> for Instant {
+    type Output = Self;
+
+    /// The instant `duration` earlier, wrapping with the count
+    fn sub(self, duration: Milliseconds) -> Self {
+        Self(self.0.wrapping_sub(duration.0))
+    }
+}
+
+impl ops::Sub<Instant> for Instant {
+    type Output = Milliseconds;
+
+    /// The time from `earlier` to this instant, or zero if `earlier` is later
+    fn sub(self, earlier: Instant) -> Milliseconds {
+        self.checked_duration_since(earlier)
+            .unwrap_or(Milliseconds(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ms: u32) -> Instant {
+        Instant::from_uptime(Milliseconds(ms))
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut instant = at(1_000) + Milliseconds(500u32);
+        assert_eq!(instant, at(1_500));
+        assert_eq!(instant - Milliseconds(1_500u32), at(0));
+        assert_eq!(instant - at(400), Milliseconds(1_100u32));
+        assert_eq!(at(400).checked_duration_since(instant), None);
+        assert_eq!(at(400) - instant, Milliseconds(0u32));
+
+        instant += Milliseconds(1u32);
+        assert_eq!(instant.uptime(), Milliseconds(1_501u32));
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(at(5) > at(3));
+        assert!(at(3) < at(5));
+        assert!(at(5) >= at(5));
+        assert_eq!(at(5).cmp(&at(5)), Ordering::Equal);
+
+        let mut instants = [at(30), at(10), at(20)];
+        instants.sort();
+        assert_eq!(instants, [at(10), at(20), at(30)]);
+    }
+
+    #[test]
+    fn test_wraparound() {
+        let before = at(u32::MAX - 99);
+        let after = before + Milliseconds(200u32);
+        assert_eq!(after, at(100));
+        assert!(after > before);
+        assert_eq!(after - before, Milliseconds(200u32));
+        assert_eq!(before - after, Milliseconds(0u32));
+        assert_eq!(after - Milliseconds(200u32), before);
+
+        // the latest instant still later than another is just under half the range on
+        let half = 1u32 << 31;
+        assert!(at(0) + Milliseconds(half - 1) > at(0));
+        assert!(at(0) + Milliseconds(half) < at(0));
+    }
+}
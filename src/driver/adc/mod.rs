@@ -0,0 +1,310 @@
+//! Analog-to-digital conversion of a single channel.
+//!
+//! The `Adc` actor owns an `embedded-hal` one-shot ADC together with the pin of the
+//! channel it samples. Readings are taken on request with `read_once()`, and, when
+//! given a sampling period, on a timer, each being published to the event-bus as an
+//! `AnalogReading`.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::TimerActor;
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+use core::marker::PhantomData;
+use embedded_hal::adc::{Channel, OneShot};
+
+/// A single conversion, along with its value in millivolts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AnalogReading {
+    pub channel: u8,
+    pub raw: u16,
+    pub millivolts: u32,
+}
+
+/// Converts raw samples to millivolts.
+#[derive(Copy, Clone, Debug)]
+pub struct Reference {
+    millivolts: u32,
+    full_scale: u32,
+}
+
+impl Reference {
+    /// A reference of `millivolts` for an ADC with `bits` of resolution, such that the
+    /// largest raw value converts to the full reference voltage.
+    pub fn new(millivolts: u32, bits: u8) -> Self {
+        let bits = bits.clamp(1, 16);
+        Self {
+            millivolts,
+            full_scale: (1 << bits) - 1,
+        }
+    }
+
+    pub fn to_millivolts(&self, raw: u16) -> u32 {
+        let raw = (raw as u32).min(self.full_scale) as u64;
+        (raw * self.millivolts as u64 / self.full_scale as u64) as u32
+    }
+}
+
+pub struct Adc<D, T, ADC, A, P>
+where
+    D: Device + EventHandler<AnalogReading> + 'static,
+    T: HalTimer + 'static,
+    ADC: 'static,
+    A: OneShot<ADC, u16, P> + 'static,
+    P: Channel<ADC, ID = u8> + 'static,
+{
+    adc: A,
+    pin: P,
+    reference: Reference,
+    period: Option<Milliseconds>,
+    bus: Option<Address<EventBus<D>>>,
+    timer: Option<Address<TimerActor<T>>>,
+    address: Option<Address<Self>>,
+    _adc: PhantomData<ADC>,
+}
+
+impl<D, T, ADC, A, P> Adc<D, T, ADC, A, P>
+where
+    D: Device + EventHandler<AnalogReading>,
+    T: HalTimer,
+    A: OneShot<ADC, u16, P>,
+    P: Channel<ADC, ID = u8>,
+{
+    /// Sample `pin` with `adc`, converting samples to millivolts against `reference`.
+    pub fn new(adc: A, pin: P, reference: Reference) -> Self {
+        Self {
+            adc,
+            pin,
+            reference,
+            period: None,
+            bus: None,
+            timer: None,
+            address: None,
+            _adc: PhantomData,
+        }
+    }
+
+    /// Also sample every `period` once started, which requires binding a timer.
+    pub fn sample_every(mut self, period: Milliseconds) -> Self {
+        self.period.replace(period);
+        self
+    }
+
+    /// Take one reading, waiting for the conversion to complete.
+    pub fn read(&mut self) -> Result<AnalogReading, A::Error> {
+        loop {
+            match self.adc.read(&mut self.pin) {
+                Ok(raw) => {
+                    return Ok(AnalogReading {
+                        channel: P::channel(),
+                        raw,
+                        millivolts: self.reference.to_millivolts(raw),
+                    })
+                }
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+    }
+
+    fn schedule_sample(&self) {
+        if let (Some(period), Some(timer), Some(address)) = (self.period, self.timer, self.address)
+        {
+            timer.schedule(period, Sample, address);
+        }
+    }
+}
+
+impl<D, T, ADC, A, P> Actor for Adc<D, T, ADC, A, P>
+where
+    D: Device + EventHandler<AnalogReading>,
+    T: HalTimer,
+    A: OneShot<ADC, u16, P>,
+    P: Channel<ADC, ID = u8>,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_start(self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.schedule_sample();
+        Completion::immediate(self)
+    }
+}
+
+impl<D, T, ADC, A, P> Bind<EventBus<D>> for Adc<D, T, ADC, A, P>
+where
+    D: Device + EventHandler<AnalogReading>,
+    T: HalTimer,
+    A: OneShot<ADC, u16, P>,
+    P: Channel<ADC, ID = u8>,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, T, ADC, A, P> Bind<TimerActor<T>> for Adc<D, T, ADC, A, P>
+where
+    D: Device + EventHandler<AnalogReading>,
+    T: HalTimer,
+    A: OneShot<ADC, u16, P>,
+    P: Channel<ADC, ID = u8>,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.timer.replace(address);
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Sample;
+
+pub struct ReadOnce;
+
+impl<D, T, ADC, A, P> NotifyHandler<Sample> for Adc<D, T, ADC, A, P>
+where
+    D: Device + EventHandler<AnalogReading>,
+    T: HalTimer,
+    A: OneShot<ADC, u16, P>,
+    P: Channel<ADC, ID = u8>,
+{
+    fn on_notify(mut self, _: Sample) -> Completion<Self> {
+        match self.read() {
+            Ok(reading) => {
+                if let Some(bus) = self.bus {
+                    bus.publish(reading);
+                }
+            }
+            Err(_) => warn!("[adc] conversion failed"),
+        }
+        self.schedule_sample();
+        Completion::immediate(self)
+    }
+}
+
+impl<D, T, ADC, A, P> RequestHandler<ReadOnce> for Adc<D, T, ADC, A, P>
+where
+    D: Device + EventHandler<AnalogReading>,
+    T: HalTimer,
+    A: OneShot<ADC, u16, P>,
+    P: Channel<ADC, ID = u8>,
+{
+    type Response = Result<AnalogReading, A::Error>;
+
+    fn on_request(mut self, _: ReadOnce) -> Response<Self, Self::Response> {
+        let result = self.read();
+        Response::immediate(self, result)
+    }
+}
+
+impl<D, T, ADC, A, P> Address<Adc<D, T, ADC, A, P>>
+where
+    D: Device + EventHandler<AnalogReading>,
+    T: HalTimer,
+    A: OneShot<ADC, u16, P>,
+    P: Channel<ADC, ID = u8>,
+{
+    /// Take one reading now, without publishing it.
+    pub async fn read_once(&self) -> Result<AnalogReading, A::Error> {
+        self.request(ReadOnce).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockAdc {
+        pending: u8,
+        value: u16,
+        fail: bool,
+    }
+
+    struct MockPin;
+
+    impl Channel<MockAdc> for MockPin {
+        type ID = u8;
+
+        fn channel() -> u8 {
+            3
+        }
+    }
+
+    impl OneShot<MockAdc, u16, MockPin> for MockAdc {
+        type Error = ();
+
+        fn read(&mut self, _: &mut MockPin) -> nb::Result<u16, ()> {
+            if self.pending > 0 {
+                self.pending -= 1;
+                Err(nb::Error::WouldBlock)
+            } else if self.fail {
+                Err(nb::Error::Other(()))
+            } else {
+                Ok(self.value)
+            }
+        }
+    }
+
+    struct MockTimer;
+
+    impl HalTimer for MockTimer {
+        fn start(&mut self, _: Milliseconds) {}
+
+        fn clear_update_interrupt_flag(&mut self) {}
+    }
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    impl EventHandler<AnalogReading> for MockDevice {}
+
+    type TestAdc = Adc<MockDevice, MockTimer, MockAdc, MockAdc, MockPin>;
+
+    fn adc(value: u16, fail: bool) -> TestAdc {
+        let adc = MockAdc {
+            pending: 2,
+            value,
+            fail,
+        };
+        Adc::new(adc, MockPin, Reference::new(3300, 12))
+    }
+
+    #[test]
+    fn test_read() {
+        let mut adc = adc(2048, false);
+        assert_eq!(
+            adc.read(),
+            Ok(AnalogReading {
+                channel: 3,
+                raw: 2048,
+                millivolts: 1650,
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_error() {
+        let mut adc = adc(0, true);
+        assert_eq!(adc.read(), Err(()));
+    }
+
+    #[test]
+    fn test_reference() {
+        let reference = Reference::new(3300, 12);
+        assert_eq!(reference.to_millivolts(0), 0);
+        assert_eq!(reference.to_millivolts(4095), 3300);
+        assert_eq!(reference.to_millivolts(u16::MAX), 3300);
+
+        let reference = Reference::new(5000, 10);
+        assert_eq!(reference.to_millivolts(511), 2497);
+    }
+}
@@ -1,76 +1,88 @@
-use crate::bind::Bind;
+use crate::driver::input::digital::DigitalInput;
 use crate::hal::gpio::exti_pin::ExtiPin;
-use crate::hal::Active;
-use crate::handler::EventHandler;
+use crate::hal::{Active, Edge};
 use crate::prelude::*;
 use embedded_hal::digital::v2::InputPin;
 
-#[derive(Copy, Clone)]
+pub use crate::driver::input::digital::{InputStats as ButtonStats, Stats};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ButtonEvent {
     Pressed,
     Released,
 }
 
-pub struct Button<D: Device + 'static, PIN> {
-    pin: PIN,
-    active: Active,
-    bus: Option<Address<EventBus<D>>>,
-}
-
-impl<D, PIN> Actor for Button<D, PIN>
-where
-    D: Device,
-    PIN: InputPin + ExtiPin,
-{
-}
-
-impl<D, PIN> Bind<EventBus<D>> for Button<D, PIN>
-where
-    D: Device,
-{
-    fn on_bind(&mut self, address: Address<EventBus<D>>) {
-        self.bus.replace(address);
-    }
-}
+/// A button, publishing `ButtonEvent::Pressed` as it is pressed and
+/// `ButtonEvent::Released` as it is released.
+pub type Button<D, PIN> = DigitalInput<D, PIN, ButtonEvent>;
 
 impl<D, PIN> Button<D, PIN>
 where
     D: Device,
     PIN: InputPin + ExtiPin,
 {
-    pub fn new(pin: PIN, active: Active) -> Self {
-        Self {
+    /// Create a button wired `active` high or low, publishing an event for each `edge` of
+    /// the pin.
+    ///
+    /// Edges are filtered in software, so the pin's interrupt should also be configured
+    /// to trigger on `edge` alone, sparing the interrupts that would be ignored.
+    pub fn new(pin: PIN, active: Active, edge: Edge) -> Self {
+        Self::with_events(
             pin,
             active,
-            bus: None,
-        }
+            edge,
+            ButtonEvent::Pressed,
+            ButtonEvent::Released,
+        )
     }
 }
 
-impl<D, PIN> Interrupt for Button<D, PIN>
-where
-    D: Device + EventHandler<ButtonEvent> + 'static,
-    PIN: InputPin + ExtiPin,
-{
-    fn on_interrupt(&mut self) {
-        if self.pin.check_interrupt() {
-            match self.active {
-                Active::High => {
-                    if self.pin.is_high().ok().unwrap() {
-                        self.bus.unwrap().publish(ButtonEvent::Pressed);
-                    } else {
-                        self.bus.unwrap().publish(ButtonEvent::Released);
-                    }
-                }
-                Active::Low => {
-                    if self.pin.is_low().ok().unwrap() {
-                        self.bus.unwrap().publish(ButtonEvent::Pressed);
-                    } else {
-                        self.bus.unwrap().publish(ButtonEvent::Released);
-                    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::input::digital::asserted;
+
+    fn events(active: Active, edge: Edge) -> [Option<ButtonEvent>; 4] {
+        let mut events = [None; 4];
+        for (i, high) in [true, false, true, false].iter().enumerate() {
+            events[i] = asserted(&active, edge, *high).map(|pressed| {
+                if pressed {
+                    ButtonEvent::Pressed
+                } else {
+                    ButtonEvent::Released
                 }
-            }
-            self.pin.clear_interrupt_pending_bit();
+            });
         }
+        events
+    }
+
+    #[test]
+    fn test_active_high() {
+        use ButtonEvent::*;
+        assert_eq!(
+            events(Active::High, Edge::Both),
+            [Some(Pressed), Some(Released), Some(Pressed), Some(Released)]
+        );
+        assert_eq!(
+            events(Active::High, Edge::Rising),
+            [Some(Pressed), None, Some(Pressed), None]
+        );
+        assert_eq!(
+            events(Active::High, Edge::Falling),
+            [None, Some(Released), None, Some(Released)]
+        );
+    }
+
+    #[test]
+    fn test_active_low() {
+        use ButtonEvent::*;
+        assert_eq!(
+            events(Active::Low, Edge::Both),
+            [Some(Released), Some(Pressed), Some(Released), Some(Pressed)]
+        );
+        assert_eq!(
+            events(Active::Low, Edge::Falling),
+            [None, Some(Pressed), None, Some(Pressed)]
+        );
     }
 }
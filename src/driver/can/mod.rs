@@ -0,0 +1,333 @@
+//! CAN bus controller.
+//!
+//! The `Can` actor wraps an `embedded-hal` CAN peripheral. Frames, with standard or
+//! extended identifiers, are sent with `send(frame)`, and each frame received is
+//! published to the event-bus as a `CanRx` from the RX interrupt. Received frames may be
+//! narrowed with acceptance filters, applied in software so that they work the same
+//! across peripherals; the peripheral's own filters should be left accepting every frame.
+
+use crate::bind::Bind;
+use crate::prelude::*;
+use embedded_hal::can::nb::Can as HalCan;
+use embedded_hal::can::{Frame, Id};
+use heapless::{consts::*, Vec};
+
+/// A frame received on the bus.
+#[derive(Clone, Debug)]
+pub struct CanRx<F>(pub F);
+
+/// Accepts the frames whose identifier matches `id` in every bit set in `mask`.
+#[derive(Copy, Clone, Debug)]
+pub struct Filter {
+    id: Id,
+    mask: u32,
+}
+
+impl Filter {
+    /// Accept the frames whose identifier, of the same kind as `id`, matches it in every
+    /// bit set in `mask`.
+    pub fn new(id: impl Into<Id>, mask: u32) -> Self {
+        Self {
+            id: id.into(),
+            mask,
+        }
+    }
+
+    /// Accept only the frames with identifier `id`.
+    pub fn exact(id: impl Into<Id>) -> Self {
+        Self::new(id, u32::MAX)
+    }
+
+    pub fn accepts(&self, id: Id) -> bool {
+        match (self.id, id) {
+            (Id::Standard(filter), Id::Standard(id)) => {
+                (filter.as_raw() as u32 ^ id.as_raw() as u32) & self.mask == 0
+            }
+            (Id::Extended(filter), Id::Extended(id)) => {
+                (filter.as_raw() ^ id.as_raw()) & self.mask == 0
+            }
+            _ => false,
+        }
+    }
+}
+
+pub struct Can<D, C>
+where
+    D: Device + EventHandler<CanRx<C::Frame>> + 'static,
+    C: HalCan + 'static,
+{
+    can: C,
+    filters: Vec<Filter, U8>,
+    bus: Option<Address<EventBus<D>>>,
+}
+
+impl<D, C> Can<D, C>
+where
+    D: Device + EventHandler<CanRx<C::Frame>>,
+    C: HalCan,
+{
+    /// Drive `can`, accepting every frame received.
+    pub fn new(can: C) -> Self {
+        Self {
+            can,
+            filters: Vec::new(),
+            bus: None,
+        }
+    }
+
+    /// Only accept received frames matching at least one of up to 8 filters.
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filters
+            .push(filter)
+            .unwrap_or_else(|_| panic!("too many filters"));
+        self
+    }
+
+    fn accepts(&self, id: Id) -> bool {
+        self.filters.is_empty() || self.filters.iter().any(|f| f.accepts(id))
+    }
+
+    /// Transmit `frame`, waiting for a transmit buffer.
+    ///
+    /// A lower-priority pending frame displaced by `frame` is transmitted again after it.
+    fn send(&mut self, frame: C::Frame) -> Result<(), C::Error> {
+        let mut frame = frame;
+        loop {
+            match self.can.transmit(&frame) {
+                Ok(None) => return Ok(()),
+                Ok(Some(displaced)) => frame = displaced,
+                Err(nb::Error::WouldBlock) => continue,
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+    }
+
+    /// Drain the receive buffer, passing each accepted frame to `emit`.
+    fn receive(&mut self, mut emit: impl FnMut(C::Frame)) {
+        loop {
+            match self.can.receive() {
+                Ok(frame) => {
+                    if self.accepts(frame.id()) {
+                        emit(frame);
+                    }
+                }
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(_)) => {
+                    warn!("[can] receive error");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<D, C> Actor for Can<D, C>
+where
+    D: Device + EventHandler<CanRx<C::Frame>>,
+    C: HalCan,
+{
+}
+
+impl<D, C> Bind<EventBus<D>> for Can<D, C>
+where
+    D: Device + EventHandler<CanRx<C::Frame>>,
+    C: HalCan,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, C> Interrupt for Can<D, C>
+where
+    D: Device + EventHandler<CanRx<C::Frame>>,
+    C: HalCan,
+{
+    fn on_interrupt(&mut self) {
+        let bus = self.bus;
+        self.receive(|frame| {
+            if let Some(bus) = bus {
+                bus.publish(CanRx(frame));
+            }
+        });
+    }
+}
+
+pub struct Send<F>(pub F);
+
+impl<D, C> RequestHandler<Send<C::Frame>> for Can<D, C>
+where
+    D: Device + EventHandler<CanRx<C::Frame>>,
+    C: HalCan,
+{
+    type Response = Result<(), C::Error>;
+
+    fn on_request(mut self, message: Send<C::Frame>) -> Response<Self, Self::Response> {
+        let result = self.send(message.0);
+        Response::immediate(self, result)
+    }
+}
+
+impl<D, C> Address<Can<D, C>>
+where
+    D: Device + EventHandler<CanRx<C::Frame>>,
+    C: HalCan,
+{
+    /// Transmit `frame`, built with `embedded_hal::can::Frame::new(id, data)`.
+    pub async fn send(&self, frame: C::Frame) -> Result<(), C::Error> {
+        self.request(Send(frame)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal::can::{Error, ErrorKind, ExtendedId, StandardId};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct MockFrame {
+        id: Id,
+        data: Vec<u8, U8>,
+    }
+
+    impl Frame for MockFrame {
+        fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+            Some(Self {
+                id: id.into(),
+                data: Vec::from_slice(data).ok()?,
+            })
+        }
+
+        fn new_remote(_: impl Into<Id>, _: usize) -> Option<Self> {
+            None
+        }
+
+        fn is_extended(&self) -> bool {
+            matches!(self.id, Id::Extended(_))
+        }
+
+        fn is_remote_frame(&self) -> bool {
+            false
+        }
+
+        fn id(&self) -> Id {
+            self.id
+        }
+
+        fn dlc(&self) -> usize {
+            self.data.len()
+        }
+
+        fn data(&self) -> &[u8] {
+            &self.data
+        }
+    }
+
+    #[derive(Debug)]
+    struct MockError;
+
+    impl Error for MockError {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    /// A controller sending frames as soon as they are transmitted, and receiving `rx` in order.
+    #[derive(Default)]
+    struct MockCan {
+        transmitted: Vec<MockFrame, U8>,
+        rx: Vec<MockFrame, U8>,
+        received: usize,
+    }
+
+    impl HalCan for MockCan {
+        type Frame = MockFrame;
+        type Error = MockError;
+
+        fn transmit(&mut self, frame: &MockFrame) -> nb::Result<Option<MockFrame>, MockError> {
+            self.transmitted
+                .push(frame.clone())
+                .map_err(|_| nb::Error::Other(MockError))?;
+            Ok(None)
+        }
+
+        fn receive(&mut self) -> nb::Result<MockFrame, MockError> {
+            let frame = self.rx.get(self.received).cloned();
+            self.received += 1;
+            frame.ok_or(nb::Error::WouldBlock)
+        }
+    }
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    impl EventHandler<CanRx<MockFrame>> for MockDevice {}
+
+    fn standard(id: u16, data: &[u8]) -> MockFrame {
+        MockFrame::new(StandardId::new(id).unwrap(), data).unwrap()
+    }
+
+    fn extended(id: u32, data: &[u8]) -> MockFrame {
+        MockFrame::new(ExtendedId::new(id).unwrap(), data).unwrap()
+    }
+
+    #[test]
+    fn test_send() {
+        let mut can: Can<MockDevice, _> = Can::new(MockCan::default());
+        can.send(standard(0x123, &[1, 2, 3])).unwrap();
+        can.send(extended(0x1ABC_DEF0, &[0xFF; 8])).unwrap();
+
+        let transmitted = &can.can.transmitted;
+        assert_eq!(transmitted.len(), 2);
+        assert!(transmitted[0].is_standard());
+        assert_eq!(
+            transmitted[0].id(),
+            Id::Standard(StandardId::new(0x123).unwrap())
+        );
+        assert_eq!(transmitted[0].data(), &[1, 2, 3]);
+        assert!(transmitted[1].is_extended());
+        assert_eq!(transmitted[1].dlc(), 8);
+    }
+
+    #[test]
+    fn test_receive() {
+        let mut can: Can<MockDevice, _> = Can::new(MockCan::default());
+        can.can.rx.push(standard(0x100, &[1])).unwrap();
+        can.can.rx.push(extended(0x100, &[2])).unwrap();
+
+        let mut received: Vec<MockFrame, U8> = Vec::new();
+        can.receive(|frame| received.push(frame).unwrap());
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], standard(0x100, &[1]));
+        assert_eq!(received[1], extended(0x100, &[2]));
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut can: Can<MockDevice, _> = Can::new(MockCan::default())
+            .with_filter(Filter::new(StandardId::new(0x120).unwrap(), 0x7F0))
+            .with_filter(Filter::exact(ExtendedId::new(0x1ABC_DEF0).unwrap()));
+        for frame in [
+            standard(0x123, &[]),
+            standard(0x130, &[]),
+            extended(0x123, &[]),
+            extended(0x1ABC_DEF0, &[]),
+            extended(0x1ABC_DEF1, &[]),
+        ] {
+            can.can.rx.push(frame).unwrap();
+        }
+
+        let mut received: Vec<Id, U8> = Vec::new();
+        can.receive(|frame| received.push(frame.id()).unwrap());
+        assert_eq!(
+            &received[..],
+            &[
+                Id::Standard(StandardId::new(0x123).unwrap()),
+                Id::Extended(ExtendedId::new(0x1ABC_DEF0).unwrap()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! A line-oriented command console over a serial line.
+//!
+//! The `Console` actor reads lines with `read_line()`, splits each into whitespace-separated
+//! words, and runs the registered `Command` named by the first word with the rest as its
+//! arguments. Whatever the command writes to its `Response` is written back, followed by
+//! `\r\n`, so `blink 200` runs the `blink` command with the argument `200`.
+//!
+//! Unknown commands are answered with the names of those known, and commands rejecting
+//! their arguments with their usage. The console holds the serial line's lock while
+//! waiting for each line, so it should not be shared with other writers.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::uart::serial::{LineError, SerialPeripheral};
+use crate::prelude::*;
+use crate::synchronization::MutexActor;
+use core::fmt::Write as _;
+use embedded_hal::serial::{Read, Write};
+use heapless::{consts::*, String, Vec};
+
+/// The text written back in response to a command line.
+pub type Response = String<U128>;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CommandError {
+    /// The arguments were missing, surplus or malformed.
+    InvalidArguments,
+    /// An argument was well-formed, but outside the range accepted.
+    OutOfRange,
+}
+
+/// Parse an argument of whole milliseconds, such as a blink delay.
+///
+/// Values not fitting into `Milliseconds` are rejected as `OutOfRange` rather than wrapped.
+pub fn parse_milliseconds(arg: &str) -> Result<Milliseconds, CommandError> {
+    let millis: u64 = arg.parse().map_err(|_| CommandError::InvalidArguments)?;
+    Milliseconds::try_from_u64(millis).map_err(|_| CommandError::OutOfRange)
+}
+
+/// A command run from the console.
+pub trait Command {
+    /// The name by which the command is run.
+    fn name(&self) -> &str;
+
+    /// The arguments accepted, such as `<on|off>`, shown when they are rejected.
+    fn usage(&self) -> &str;
+
+    /// Run the command with `args`, writing any response to `response`.
+    fn run(&self, args: &[&str], response: &mut Response) -> Result<(), CommandError>;
+}
+
+/// The commands of a console, by name.
+pub struct Commands {
+    commands: Vec<&'static dyn Command, U16>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Register `command`, of up to 16.
+    pub fn register(&mut self, command: &'static dyn Command) {
+        self.commands
+            .push(command)
+            .unwrap_or_else(|_| panic!("too many commands"));
+    }
+
+    /// Run the command on `line`, of up to 8 words, returning the response to write back.
+    pub fn dispatch(&self, line: &str) -> Response {
+        let mut response = Response::new();
+        let mut words: Vec<&str, U8> = Vec::new();
+        let mut too_many = false;
+        for word in line.split_whitespace() {
+            too_many |= words.push(word).is_err();
+        }
+        let (name, args) = match words.split_first() {
+            Some(words) => words,
+            None => return response,
+        };
+
+        let command = match self.commands.iter().find(|c| c.name() == *name) {
+            Some(command) => command,
+            None => {
+                write!(response, "unknown command: {}; try", name).ok();
+                for command in self.commands.iter() {
+                    write!(response, " {}", command.name()).ok();
+                }
+                return response;
+            }
+        };
+
+        let result = if too_many {
+            Err(CommandError::InvalidArguments)
+        } else {
+            command.run(args, &mut response)
+        };
+        if let Err(error) = result {
+            response = Response::new();
+            if error == CommandError::OutOfRange {
+                response.push_str("argument out of range; ").ok();
+            }
+            write!(response, "usage: {} {}", command.name(), command.usage()).ok();
+        }
+        response
+    }
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Console<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    commands: Commands,
+    serial: Option<Address<MutexActor<SerialPeripheral<S>>>>,
+    address: Option<Address<Self>>,
+}
+
+impl<S> Console<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    pub fn new(commands: Commands) -> Self {
+        Self {
+            commands,
+            serial: None,
+            address: None,
+        }
+    }
+
+    fn read_next_line(&self) {
+        if let Some(address) = self.address {
+            address.notify(ReadLine);
+        }
+    }
+}
+
+impl<S> Actor for Console<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_start(self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.read_next_line();
+        Completion::immediate(self)
+    }
+}
+
+impl<S> Bind<MutexActor<SerialPeripheral<S>>> for Console<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    fn on_bind(&mut self, address: Address<MutexActor<SerialPeripheral<S>>>) {
+        self.serial.replace(address);
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ReadLine;
+
+impl<S> NotifyHandler<ReadLine> for Console<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    fn on_notify(self, _: ReadLine) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(serial) = self.serial {
+                let mut serial = serial.lock().await;
+                let response = match serial.read_line().await {
+                    Ok(line) => self.commands.dispatch(&line),
+                    Err(LineError::TooLong) => Response::from("line too long"),
+                    Err(LineError::InvalidUtf8) => Response::from("invalid UTF-8"),
+                };
+                if !response.is_empty() {
+                    let written = serial.write_all(response.as_bytes()).await;
+                    if written.and(serial.write_all(b"\r\n").await).is_err() {
+                        warn!("[console] failed to write response");
+                    }
+                }
+            }
+            self.read_next_line();
+            self
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    struct Led {
+        on: Cell<bool>,
+    }
+
+    impl Command for Led {
+        fn name(&self) -> &str {
+            "led"
+        }
+
+        fn usage(&self) -> &str {
+            "<on|off>"
+        }
+
+        fn run(&self, args: &[&str], _: &mut Response) -> Result<(), CommandError> {
+            match args {
+                ["on"] => self.on.set(true),
+                ["off"] => self.on.set(false),
+                _ => return Err(CommandError::InvalidArguments),
+            }
+            Ok(())
+        }
+    }
+
+    struct Blink {
+        period: Cell<Milliseconds>,
+    }
+
+    impl Command for Blink {
+        fn name(&self) -> &str {
+            "blink"
+        }
+
+        fn usage(&self) -> &str {
+            "<milliseconds>"
+        }
+
+        fn run(&self, args: &[&str], response: &mut Response) -> Result<(), CommandError> {
+            let period = match args {
+                [period] => parse_milliseconds(period)?,
+                _ => return Err(CommandError::InvalidArguments),
+            };
+            self.period.set(period);
+            write!(response, "blinking every {}ms", period.0).ok();
+            Ok(())
+        }
+    }
+
+    fn commands(led: &'static Led, blink: &'static Blink) -> Commands {
+        let mut commands = Commands::new();
+        commands.register(led);
+        commands.register(blink);
+        commands
+    }
+
+    fn leak<T>(value: T) -> &'static T {
+        extern crate std;
+        std::boxed::Box::leak(std::boxed::Box::new(value))
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let led = leak(Led {
+            on: Cell::new(false),
+        });
+        let blink = leak(Blink {
+            period: Cell::new(Milliseconds(0u32)),
+        });
+        let commands = commands(led, blink);
+
+        assert_eq!(commands.dispatch("led on"), "");
+        assert!(led.on.get());
+        assert_eq!(commands.dispatch("  led   off "), "");
+        assert!(!led.on.get());
+        assert_eq!(commands.dispatch("blink 200"), "blinking every 200ms");
+        assert_eq!(blink.period.get(), Milliseconds(200u32));
+        assert_eq!(commands.dispatch("blink 4294967295"), "blinking every 4294967295ms");
+        assert_eq!(blink.period.get(), Milliseconds(u32::MAX));
+        assert_eq!(commands.dispatch(""), "");
+    }
+
+    #[test]
+    fn test_errors() {
+        let led = leak(Led {
+            on: Cell::new(false),
+        });
+        let blink = leak(Blink {
+            period: Cell::new(Milliseconds(0u32)),
+        });
+        let commands = commands(led, blink);
+
+        assert_eq!(
+            commands.dispatch("temp"),
+            "unknown command: temp; try led blink"
+        );
+        assert_eq!(commands.dispatch("led dim"), "usage: led <on|off>");
+        assert_eq!(
+            commands.dispatch("blink soon"),
+            "usage: blink <milliseconds>"
+        );
+        assert_eq!(commands.dispatch("blink"), "usage: blink <milliseconds>");
+        assert_eq!(
+            commands.dispatch("blink 1 2 3 4 5 6 7 8"),
+            "usage: blink <milliseconds>"
+        );
+        assert_eq!(
+            commands.dispatch("blink 4294967296"),
+            "argument out of range; usage: blink <milliseconds>"
+        );
+        assert_eq!(commands.dispatch("blink -1"), "usage: blink <milliseconds>");
+        assert_eq!(blink.period.get(), Milliseconds(0u32));
+    }
+}
@@ -0,0 +1,82 @@
+//! Cyclic redundancy checks.
+//!
+//! `crc32()` and `crc16_ccitt()` compute CRCs in software, bit by bit, trading speed for
+//! not needing a lookup table in flash. Code validating data should be generic over
+//! `hal::crc::Crc`, so that a hardware CRC peripheral can be used where available, and
+//! `SoftwareCrc` otherwise.
+
+use crate::hal::crc::Crc;
+
+/// The reflected CRC-32 polynomial.
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+const CRC16_CCITT_POLYNOMIAL: u16 = 0x1021;
+
+/// The CRC-32 (as used by Ethernet and zlib) of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (CRC32_POLYNOMIAL & mask);
+        }
+    }
+    !crc
+}
+
+/// The CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xFFFF) of `data`.
+pub fn crc16_ccitt(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFF;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            let mask = (crc >> 15).wrapping_neg();
+            crc = (crc << 1) ^ (CRC16_CCITT_POLYNOMIAL & mask);
+        }
+    }
+    crc
+}
+
+/// Computes CRCs in software, for chips without a CRC peripheral.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SoftwareCrc;
+
+impl Crc for SoftwareCrc {
+    fn crc32(&mut self, data: &[u8]) -> u32 {
+        crc32(data)
+    }
+
+    fn crc16_ccitt(&mut self, data: &[u8]) -> u16 {
+        crc16_ccitt(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"a"), 0xE8B7_BE43);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414F_A339
+        );
+    }
+
+    #[test]
+    fn test_crc16_ccitt() {
+        assert_eq!(crc16_ccitt(b""), 0xFFFF);
+        assert_eq!(crc16_ccitt(b"123456789"), 0x29B1);
+        assert_eq!(crc16_ccitt(b"A"), 0xB915);
+    }
+
+    #[test]
+    fn test_software_crc() {
+        let mut crc = SoftwareCrc;
+        assert_eq!(crc.crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc.crc16_ccitt(b"123456789"), 0x29B1);
+    }
+}
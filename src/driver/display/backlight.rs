@@ -0,0 +1,201 @@
+//! Display backlight following the ambient light.
+//!
+//! The `Backlight` actor drives the PWM channel of a backlight from the readings of
+//! an ambient light sensor on an ADC. A device forwards the `AnalogReading`s it is
+//! published to the backlight, which smooths them, maps the light level onto a duty
+//! cycle along its curve, and clamps the duty cycle between a minimum and a maximum.
+
+use crate::driver::adc::AnalogReading;
+use crate::prelude::*;
+use embedded_hal::PwmPin;
+
+/// How the duty cycle follows the light level.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Curve {
+    /// Brighter as the reading rises, for sensors whose voltage rises with the light.
+    Proportional,
+    /// Dimmer as the reading rises, for sensors whose voltage falls with the light.
+    Inverse,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BacklightConfig {
+    /// The reading in darkness, in millivolts.
+    pub dark: u32,
+    /// The reading in full light, in millivolts.
+    pub bright: u32,
+    pub curve: Curve,
+    /// The lowest duty cycle, as a fraction of the maximum duty.
+    pub min: f32,
+    /// The highest duty cycle, as a fraction of the maximum duty.
+    pub max: f32,
+    /// The weight of each new reading against those before, from 0 (excluded) for the
+    /// steadiest light to 1 for following every reading.
+    pub smoothing: f32,
+}
+
+impl BacklightConfig {
+    /// The duty cycle for a light reading of `millivolts`, as a fraction of the maximum duty.
+    fn duty(&self, millivolts: f32) -> f32 {
+        let (dark, bright) = (self.dark as f32, self.bright as f32);
+        let level = if bright == dark {
+            1.0
+        } else {
+            ((millivolts - dark) / (bright - dark)).clamp(0.0, 1.0)
+        };
+        let level = match self.curve {
+            Curve::Proportional => level,
+            Curve::Inverse => 1.0 - level,
+        };
+        (self.min + level * (self.max - self.min)).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for BacklightConfig {
+    /// Follows a sensor reading 0mV in darkness and 3300mV in full light, never turning
+    /// the backlight fully off, and smoothing readings over about 4 samples.
+    fn default() -> Self {
+        Self {
+            dark: 0,
+            bright: 3300,
+            curve: Curve::Proportional,
+            min: 0.1,
+            max: 1.0,
+            smoothing: 0.25,
+        }
+    }
+}
+
+pub struct Backlight<P>
+where
+    P: PwmPin<Duty = u16> + 'static,
+{
+    pwm: P,
+    config: BacklightConfig,
+    smoothed: Option<f32>,
+}
+
+impl<P> Backlight<P>
+where
+    P: PwmPin<Duty = u16>,
+{
+    pub fn new(pwm: P, config: BacklightConfig) -> Self {
+        Self {
+            pwm,
+            config,
+            smoothed: None,
+        }
+    }
+
+    /// Fold a light reading into the smoothed level, and set the duty cycle for it.
+    fn follow(&mut self, millivolts: u32) {
+        let millivolts = millivolts as f32;
+        let smoothed = match self.smoothed {
+            Some(smoothed) => smoothed + self.config.smoothing * (millivolts - smoothed),
+            None => millivolts,
+        };
+        self.smoothed.replace(smoothed);
+
+        let duty = self.config.duty(smoothed) * self.pwm.get_max_duty() as f32;
+        // rounded to the nearest step
+        self.pwm.set_duty((duty + 0.5) as u16);
+    }
+}
+
+impl<P> Actor for Backlight<P>
+where
+    P: PwmPin<Duty = u16>,
+{
+    fn on_initialize(mut self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.pwm.enable();
+        Completion::immediate(self)
+    }
+}
+
+impl<P> NotifyHandler<AnalogReading> for Backlight<P>
+where
+    P: PwmPin<Duty = u16>,
+{
+    fn on_notify(mut self, reading: AnalogReading) -> Completion<Self> {
+        self.follow(reading.millivolts);
+        Completion::immediate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockPwm {
+        duty: u16,
+    }
+
+    impl PwmPin for MockPwm {
+        type Duty = u16;
+
+        fn disable(&mut self) {}
+
+        fn enable(&mut self) {}
+
+        fn get_duty(&self) -> u16 {
+            self.duty
+        }
+
+        fn get_max_duty(&self) -> u16 {
+            1000
+        }
+
+        fn set_duty(&mut self, duty: u16) {
+            self.duty = duty;
+        }
+    }
+
+    fn backlight(curve: Curve, smoothing: f32) -> Backlight<MockPwm> {
+        let config = BacklightConfig {
+            dark: 500,
+            bright: 2500,
+            curve,
+            min: 0.2,
+            max: 0.9,
+            smoothing,
+        };
+        Backlight::new(MockPwm { duty: 0 }, config)
+    }
+
+    fn duty(backlight: &mut Backlight<MockPwm>, millivolts: u32) -> u16 {
+        backlight.follow(millivolts);
+        backlight.pwm.get_duty()
+    }
+
+    #[test]
+    fn test_curve() {
+        let mut proportional = backlight(Curve::Proportional, 1.0);
+        assert_eq!(duty(&mut proportional, 0), 200);
+        assert_eq!(duty(&mut proportional, 500), 200);
+        assert_eq!(duty(&mut proportional, 1500), 550);
+        assert_eq!(duty(&mut proportional, 2500), 900);
+        assert_eq!(duty(&mut proportional, 3300), 900);
+
+        let mut inverse = backlight(Curve::Inverse, 1.0);
+        assert_eq!(duty(&mut inverse, 0), 900);
+        assert_eq!(duty(&mut inverse, 1000), 725);
+        assert_eq!(duty(&mut inverse, 3300), 200);
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let mut backlight = backlight(Curve::Proportional, 0.5);
+        assert_eq!(duty(&mut backlight, 500), 200);
+
+        // a jump to full light is approached halfway with each reading
+        assert_eq!(duty(&mut backlight, 2500), 550);
+        assert_eq!(duty(&mut backlight, 2500), 725);
+        assert_eq!(duty(&mut backlight, 2500), 813);
+
+        // and a single dark reading only dips it
+        assert_eq!(duty(&mut backlight, 500), 506);
+    }
+}
@@ -0,0 +1,415 @@
+//! HD44780 character LCD, driven in 4-bit mode over GPIOs.
+//!
+//! The display is wired with its RS, E and D4-D7 lines to output pins, and R/W tied low,
+//! so the busy flag cannot be read and every instruction is instead given time to
+//! complete. The waits of a millisecond or more, during initialization and to clear the
+//! display, are awaited on a timer. Shorter ones, the enable pulse and the ~40µs taken
+//! by most instructions, are too short for it and use a blocking `DelayUs`.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::TimerActor;
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+use core::future::Future;
+use embedded_hal::blocking::delay::DelayUs;
+use embedded_hal::digital::v2::OutputPin;
+use heapless::{consts::*, String};
+
+const CLEAR: u8 = 0x01;
+/// Entry mode set: move the cursor right after each character.
+const ENTRY_MODE: u8 = 0x06;
+/// Display control: display on, cursor off, blink off.
+const DISPLAY_ON: u8 = 0x0C;
+/// Function set: 4-bit interface, two lines, 5x8 font.
+const FUNCTION_SET: u8 = 0x28;
+const SET_DDRAM_ADDRESS: u8 = 0x80;
+
+/// DDRAM address of the start of each row, for displays of up to 4 rows of 20.
+const ROW_OFFSETS: [u8; 4] = [0x00, 0x40, 0x14, 0x54];
+
+/// Time taken by most instructions, in microseconds.
+const INSTRUCTION_US: u16 = 50;
+
+/// The pins of a display.
+pub struct Pins<P, DL>
+where
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    rs: P,
+    en: P,
+    data: [P; 4],
+    delay: DL,
+}
+
+impl<P, DL> Pins<P, DL>
+where
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    /// The register select, enable and `[D4, D5, D6, D7]` data pins, with `delay` timing
+    /// their pulses.
+    pub fn new(rs: P, en: P, data: [P; 4], delay: DL) -> Self {
+        Self {
+            rs,
+            en,
+            data,
+            delay,
+        }
+    }
+
+    /// Latch the low four bits of `nibble`, as data if `rs` or otherwise as instruction.
+    fn write_nibble(&mut self, rs: bool, nibble: u8) -> Result<(), P::Error> {
+        set(&mut self.rs, rs)?;
+        for (i, pin) in self.data.iter_mut().enumerate() {
+            set(pin, nibble & (1 << i) != 0)?;
+        }
+        self.en.set_high()?;
+        self.delay.delay_us(1);
+        self.en.set_low()?;
+        self.delay.delay_us(1);
+        Ok(())
+    }
+
+    /// Write `byte`, high nibble first, allowing it the time of most instructions.
+    fn write_byte(&mut self, rs: bool, byte: u8) -> Result<(), P::Error> {
+        self.write_nibble(rs, byte >> 4)?;
+        self.write_nibble(rs, byte & 0x0F)?;
+        self.delay.delay_us(INSTRUCTION_US);
+        Ok(())
+    }
+}
+
+fn set<P: OutputPin>(pin: &mut P, high: bool) -> Result<(), P::Error> {
+    if high {
+        pin.set_high()
+    } else {
+        pin.set_low()
+    }
+}
+
+/// Initialize the display into 4-bit mode, switched on and cleared, using `wait` for the
+/// waits of a millisecond or more.
+pub async fn init<P, DL, W, F>(pins: &mut Pins<P, DL>, mut wait: W) -> Result<(), P::Error>
+where
+    P: OutputPin,
+    DL: DelayUs<u16>,
+    W: FnMut(Milliseconds) -> F,
+    F: Future<Output = ()>,
+{
+    // Whatever mode the display is in, three function sets in 8-bit mode bring it to
+    // 8-bit mode, from which it is switched to 4-bit mode.
+    wait(Milliseconds(50)).await;
+    pins.write_nibble(false, 0x3)?;
+    wait(Milliseconds(5)).await;
+    pins.write_nibble(false, 0x3)?;
+    wait(Milliseconds(1)).await;
+    pins.write_nibble(false, 0x3)?;
+    pins.delay.delay_us(INSTRUCTION_US);
+    pins.write_nibble(false, 0x2)?;
+    pins.delay.delay_us(INSTRUCTION_US);
+
+    pins.write_byte(false, FUNCTION_SET)?;
+    pins.write_byte(false, DISPLAY_ON)?;
+    clear(pins, wait).await?;
+    pins.write_byte(false, ENTRY_MODE)
+}
+
+/// Clear the display and return the cursor to the start of the first row.
+pub async fn clear<P, DL, W, F>(pins: &mut Pins<P, DL>, mut wait: W) -> Result<(), P::Error>
+where
+    P: OutputPin,
+    DL: DelayUs<u16>,
+    W: FnMut(Milliseconds) -> F,
+    F: Future<Output = ()>,
+{
+    pins.write_byte(false, CLEAR)?;
+    wait(Milliseconds(2)).await;
+    Ok(())
+}
+
+/// Move the cursor to `col` of `row`, counting from 0.
+pub fn set_cursor<P, DL>(pins: &mut Pins<P, DL>, row: u8, col: u8) -> Result<(), P::Error>
+where
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    let offset = ROW_OFFSETS[row as usize % ROW_OFFSETS.len()];
+    pins.write_byte(false, SET_DDRAM_ADDRESS | (offset + col))
+}
+
+/// Write `text` at the cursor, with characters outside of ASCII written as `?`.
+pub fn write_str<P, DL>(pins: &mut Pins<P, DL>, text: &str) -> Result<(), P::Error>
+where
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    for c in text.chars() {
+        let byte = if c.is_ascii() { c as u8 } else { b'?' };
+        pins.write_byte(true, byte)?;
+    }
+    Ok(())
+}
+
+pub struct Hd44780<T, P, DL>
+where
+    T: HalTimer + 'static,
+    P: OutputPin + 'static,
+    DL: DelayUs<u16> + 'static,
+{
+    pins: Pins<P, DL>,
+    timer: Option<Address<TimerActor<T>>>,
+}
+
+impl<T, P, DL> Hd44780<T, P, DL>
+where
+    T: HalTimer,
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    pub fn new(pins: Pins<P, DL>) -> Self {
+        Self { pins, timer: None }
+    }
+}
+
+impl<T, P, DL> Actor for Hd44780<T, P, DL>
+where
+    T: HalTimer,
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    fn on_initialize(mut self) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(timer) = self.timer {
+                let wait = move |duration| async move { timer.delay(duration).await };
+                if init(&mut self.pins, wait).await.is_err() {
+                    error!("[hd44780] unable to initialize display");
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<T, P, DL> Bind<TimerActor<T>> for Hd44780<T, P, DL>
+where
+    T: HalTimer,
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.timer.replace(address);
+    }
+}
+
+pub struct Clear;
+
+pub struct SetCursor {
+    pub row: u8,
+    pub col: u8,
+}
+
+pub struct WriteStr(pub String<U32>);
+
+impl<T, P, DL> NotifyHandler<Clear> for Hd44780<T, P, DL>
+where
+    T: HalTimer,
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    fn on_notify(mut self, _: Clear) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(timer) = self.timer {
+                let wait = move |duration| async move { timer.delay(duration).await };
+                if clear(&mut self.pins, wait).await.is_err() {
+                    warn!("[hd44780] unable to clear display");
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<T, P, DL> NotifyHandler<SetCursor> for Hd44780<T, P, DL>
+where
+    T: HalTimer,
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    fn on_notify(mut self, message: SetCursor) -> Completion<Self> {
+        if set_cursor(&mut self.pins, message.row, message.col).is_err() {
+            warn!("[hd44780] unable to set cursor");
+        }
+        Completion::immediate(self)
+    }
+}
+
+impl<T, P, DL> NotifyHandler<WriteStr> for Hd44780<T, P, DL>
+where
+    T: HalTimer,
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    fn on_notify(mut self, message: WriteStr) -> Completion<Self> {
+        if write_str(&mut self.pins, &message.0).is_err() {
+            warn!("[hd44780] unable to write");
+        }
+        Completion::immediate(self)
+    }
+}
+
+impl<T, P, DL> Address<Hd44780<T, P, DL>>
+where
+    T: HalTimer,
+    P: OutputPin,
+    DL: DelayUs<u16>,
+{
+    /// Clear the display and return the cursor to the start of the first row.
+    pub fn clear(&self) {
+        self.notify(Clear)
+    }
+
+    /// Move the cursor to `col` of `row`, counting from 0.
+    pub fn set_cursor(&self, row: u8, col: u8) {
+        self.notify(SetCursor { row, col })
+    }
+
+    /// Write `text` at the cursor.
+    ///
+    /// At most 32 bytes of `text` are written, more than fit across a row.
+    pub fn write_str(&self, text: &str) {
+        let mut message = WriteStr(String::new());
+        for c in text.chars() {
+            if message.0.push(c).is_err() {
+                break;
+            }
+        }
+        self.notify(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::block_on;
+    use core::cell::{Cell, RefCell};
+    use core::convert::Infallible;
+    use heapless::Vec;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Event {
+        /// A nibble latched, as data (`true`) or instruction.
+        Nibble(bool, u8),
+        Wait(u32),
+    }
+
+    use Event::*;
+
+    const RS: usize = 4;
+    const EN: usize = 5;
+
+    #[derive(Default)]
+    struct Wiring {
+        levels: Cell<[bool; 6]>,
+        events: RefCell<Vec<Event, U64>>,
+    }
+
+    impl Wiring {
+        fn nibble(&self) -> u8 {
+            let levels = self.levels.get();
+            (0..4).fold(0, |nibble, i| nibble | (levels[i] as u8) << i)
+        }
+    }
+
+    struct MockPin<'a>(usize, &'a Wiring);
+
+    impl MockPin<'_> {
+        fn set(&self, high: bool) {
+            let mut levels = self.1.levels.get();
+            if self.0 == EN && levels[EN] && !high {
+                let event = Nibble(levels[RS], self.1.nibble());
+                self.1.events.borrow_mut().push(event).unwrap();
+            }
+            levels[self.0] = high;
+            self.1.levels.set(levels);
+        }
+    }
+
+    impl OutputPin for MockPin<'_> {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.set(true);
+            Ok(())
+        }
+    }
+
+    struct NoDelay;
+
+    impl DelayUs<u16> for NoDelay {
+        fn delay_us(&mut self, _: u16) {}
+    }
+
+    fn pins(wiring: &Wiring) -> Pins<MockPin<'_>, NoDelay> {
+        Pins::new(
+            MockPin(RS, wiring),
+            MockPin(EN, wiring),
+            [
+                MockPin(0, wiring),
+                MockPin(1, wiring),
+                MockPin(2, wiring),
+                MockPin(3, wiring),
+            ],
+            NoDelay,
+        )
+    }
+
+    #[test]
+    fn test_init() {
+        let wiring = Wiring::default();
+        let mut pins = pins(&wiring);
+        let wait = |duration: Milliseconds| {
+            wiring.events.borrow_mut().push(Wait(duration.0)).unwrap();
+            async {}
+        };
+        block_on(init(&mut pins, wait)).unwrap();
+
+        #[rustfmt::skip]
+        let expected = [
+            Wait(50), Nibble(false, 0x3),
+            Wait(5), Nibble(false, 0x3),
+            Wait(1), Nibble(false, 0x3),
+            Nibble(false, 0x2),
+            // function set, display on, clear, entry mode
+            Nibble(false, 0x2), Nibble(false, 0x8),
+            Nibble(false, 0x0), Nibble(false, 0xC),
+            Nibble(false, 0x0), Nibble(false, 0x1), Wait(2),
+            Nibble(false, 0x0), Nibble(false, 0x6),
+        ];
+        assert_eq!(&wiring.events.borrow()[..], &expected[..]);
+    }
+
+    #[test]
+    fn test_write() {
+        let wiring = Wiring::default();
+        let mut pins = pins(&wiring);
+        set_cursor(&mut pins, 1, 3).unwrap();
+        write_str(&mut pins, "Hi").unwrap();
+
+        #[rustfmt::skip]
+        let expected = [
+            // DDRAM address 0x43
+            Nibble(false, 0xC), Nibble(false, 0x3),
+            // 'H' and 'i'
+            Nibble(true, 0x4), Nibble(true, 0x8),
+            Nibble(true, 0x6), Nibble(true, 0x9),
+        ];
+        assert_eq!(&wiring.events.borrow()[..], &expected[..]);
+    }
+}
@@ -0,0 +1,10 @@
+//! Display drivers.
+
+pub mod backlight;
+pub mod font;
+pub mod hd44780;
+pub mod ssd1306;
+
+pub use backlight::{Backlight, BacklightConfig};
+pub use hd44780::Hd44780;
+pub use ssd1306::{Ssd1306, Ssd1306Spi};
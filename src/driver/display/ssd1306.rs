@@ -0,0 +1,727 @@
+//! SSD1306 128x64 monochrome OLED panel over I2C, or 4-wire SPI.
+//!
+//! Drawing operations update a framebuffer held by the actor; nothing reaches the
+//! panel until `flush()` is requested.
+//!
+//! The `Ssd1306` actor drives the panel over I2C, and `Ssd1306Spi` over SPI, sharing a
+//! `SpiPeripheral` through its mutex. Over SPI, `flush_spi()` writes the whole frame at
+//! once, which a `SpiPeripheral` copies through its DMA buffers `CHUNK_LEN` bytes at a
+//! time.
+
+use crate::bind::Bind;
+use crate::driver::display::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::driver::i2c::{I2cBus, I2cPeripheral};
+use crate::driver::spi::{SpiBus, SpiPeripheral};
+use crate::hal::i2c::I2cAddress;
+use crate::hal::spi::SpiDma;
+use crate::prelude::*;
+use crate::synchronization::MutexActor;
+use embedded_hal::blocking::i2c::Write;
+use embedded_hal::digital::v2::OutputPin;
+use heapless::{consts::*, String};
+
+#[cfg(feature = "embedded-graphics")]
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+
+/// Default I2C address of the panel (`0x3D` when `SA0` is pulled high).
+pub const ADDR: u8 = 0x3C;
+
+pub const WIDTH: usize = 128;
+pub const HEIGHT: usize = 64;
+
+/// Control byte preceding a stream of commands.
+const COMMAND: u8 = 0x00;
+/// Control byte preceding a stream of GDDRAM data.
+const DATA: u8 = 0x40;
+
+/// Number of GDDRAM bytes sent per I2C write.
+const CHUNK: usize = 32;
+
+/// Power-on configuration for a 128x64 panel using the internal charge pump.
+#[rustfmt::skip]
+pub const INIT: [u8; 26] = [
+    COMMAND,
+    0xAE,       // display off
+    0xD5, 0x80, // clock divide ratio / oscillator frequency
+    0xA8, 0x3F, // multiplex ratio: 64
+    0xD3, 0x00, // display offset: 0
+    0x40,       // start line: 0
+    0x8D, 0x14, // charge pump: enabled
+    0x20, 0x00, // memory addressing mode: horizontal
+    0xA1,       // segment remap: column 127 is SEG0
+    0xC8,       // COM scan direction: remapped
+    0xDA, 0x12, // COM pins: alternative, no remap
+    0x81, 0xCF, // contrast
+    0xD9, 0xF1, // pre-charge period
+    0xDB, 0x40, // VCOMH deselect level
+    0xA4,       // resume to GDDRAM content
+    0xA6,       // normal, non-inverted
+    0xAF,       // display on
+];
+
+/// Sets the GDDRAM window to the whole panel, ahead of a full-frame write.
+#[rustfmt::skip]
+const WINDOW: [u8; 7] = [
+    COMMAND,
+    0x21, 0, (WIDTH - 1) as u8,      // column address
+    0x22, 0, (HEIGHT / 8 - 1) as u8, // page address
+];
+
+/// A frame in the panel's GDDRAM layout: 8 horizontal pages of 8 pixel rows,
+/// each byte a column of a page with its top row in bit 0.
+pub struct Framebuffer {
+    buffer: [u8; WIDTH * HEIGHT / 8],
+}
+
+impl Framebuffer {
+    pub fn new() -> Self {
+        Self {
+            buffer: [0; WIDTH * HEIGHT / 8],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for b in self.buffer.iter_mut() {
+            *b = 0;
+        }
+    }
+
+    /// Turn the pixel at `(x, y)` on or off; pixels outside the panel are ignored.
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        if x >= WIDTH || y >= HEIGHT {
+            return;
+        }
+        let byte = &mut self.buffer[x + (y / 8) * WIDTH];
+        if on {
+            *byte |= 1 << (y % 8);
+        } else {
+            *byte &= !(1 << (y % 8));
+        }
+    }
+
+    pub fn is_set(&self, x: usize, y: usize) -> bool {
+        x < WIDTH && y < HEIGHT && self.buffer[x + (y / 8) * WIDTH] & (1 << (y % 8)) != 0
+    }
+
+    /// Draw `text` with its top-left corner at `(x, y)`, clipped to the panel.
+    ///
+    /// Each character occupies a 6x8 cell, including a blank column and row, which is
+    /// drawn in full so text may be redrawn in place.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str) {
+        for (i, c) in text.chars().enumerate() {
+            let left = x + i * (GLYPH_WIDTH + 1);
+            if left >= WIDTH {
+                break;
+            }
+            let glyph = font::glyph(c);
+            for column in 0..=GLYPH_WIDTH {
+                let bits = glyph.get(column).copied().unwrap_or(0);
+                for row in 0..=GLYPH_HEIGHT {
+                    self.set_pixel(left + column, y + row, bits & (1 << row) != 0);
+                }
+            }
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl DrawTarget for Framebuffer {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if point.x >= 0 && point.y >= 0 {
+                self.set_pixel(point.x as usize, point.y as usize, color.is_on());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+/// Send the power-on configuration to the panel at `address`.
+pub async fn init<B: I2cBus>(bus: &mut B, address: I2cAddress) -> Result<(), B::Error> {
+    bus.write(address, &INIT).await
+}
+
+/// Write the whole of `frame` into the GDDRAM of the panel at `address`.
+pub async fn flush<B: I2cBus>(
+    bus: &mut B,
+    address: I2cAddress,
+    frame: &Framebuffer,
+) -> Result<(), B::Error> {
+    bus.write(address, &WINDOW).await?;
+    let mut buf = [0; CHUNK + 1];
+    buf[0] = DATA;
+    for chunk in frame.as_bytes().chunks(CHUNK) {
+        buf[1..=chunk.len()].copy_from_slice(chunk);
+        bus.write(address, &buf[..=chunk.len()]).await?;
+    }
+    Ok(())
+}
+
+/// Error from a transfer over SPI, or from driving the data/command pin.
+#[derive(Debug, PartialEq)]
+pub enum SpiError<B, P> {
+    Bus(B),
+    Pin(P),
+}
+
+impl<B, P> From<SpiError<B, P>> for DeviceError {
+    fn from(_: SpiError<B, P>) -> Self {
+        DeviceError::BusError
+    }
+}
+
+/// Send the power-on configuration to the panel over SPI, with `dc` as its data/command pin.
+pub async fn init_spi<B: SpiBus, DC: OutputPin>(
+    bus: &mut B,
+    dc: &mut DC,
+) -> Result<(), SpiError<B::Error, DC::Error>> {
+    dc.set_low().map_err(SpiError::Pin)?;
+    bus.write(&INIT[1..]).await.map_err(SpiError::Bus)
+}
+
+/// Write the whole of `frame` into the GDDRAM of the panel over SPI, with `dc` as
+/// its data/command pin.
+pub async fn flush_spi<B: SpiBus, DC: OutputPin>(
+    bus: &mut B,
+    dc: &mut DC,
+    frame: &Framebuffer,
+) -> Result<(), SpiError<B::Error, DC::Error>> {
+    dc.set_low().map_err(SpiError::Pin)?;
+    bus.write(&WINDOW[1..]).await.map_err(SpiError::Bus)?;
+    dc.set_high().map_err(SpiError::Pin)?;
+    bus.write(frame.as_bytes()).await.map_err(SpiError::Bus)
+}
+
+pub struct Ssd1306<I>
+where
+    I: Write + 'static,
+{
+    address: I2cAddress,
+    i2c: Option<Address<I2cPeripheral<I>>>,
+    frame: Framebuffer,
+}
+
+impl<I> Ssd1306<I>
+where
+    I: Write,
+{
+    pub fn new() -> Self {
+        Self::with_address(I2cAddress::new(ADDR))
+    }
+
+    pub fn with_address(address: I2cAddress) -> Self {
+        Self {
+            address,
+            i2c: None,
+            frame: Framebuffer::new(),
+        }
+    }
+}
+
+impl<I> Default for Ssd1306<I>
+where
+    I: Write,
+{
+    fn default() -> Self {
+        Ssd1306::new()
+    }
+}
+
+impl<I> Actor for Ssd1306<I>
+where
+    I: Write,
+{
+    fn on_initialize(self) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(mut i2c) = self.i2c {
+                if init(&mut i2c, self.address).await.is_err() {
+                    error!("[ssd1306] unable to initialize panel");
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<I> Bind<I2cPeripheral<I>> for Ssd1306<I>
+where
+    I: Write,
+{
+    fn on_bind(&mut self, address: Address<I2cPeripheral<I>>) {
+        self.i2c.replace(address);
+    }
+}
+
+pub struct Clear;
+
+pub struct SetPixel {
+    pub x: usize,
+    pub y: usize,
+    pub on: bool,
+}
+
+pub struct DrawText {
+    pub x: usize,
+    pub y: usize,
+    pub text: String<U32>,
+}
+
+impl DrawText {
+    /// Draw as much of `text` as fits in the message, whole characters at a time.
+    fn new(x: usize, y: usize, text: &str) -> Self {
+        let mut message = DrawText {
+            x,
+            y,
+            text: String::new(),
+        };
+        for c in text.chars() {
+            if message.text.push(c).is_err() {
+                break;
+            }
+        }
+        message
+    }
+}
+
+pub struct Flush;
+
+impl<I> NotifyHandler<Clear> for Ssd1306<I>
+where
+    I: Write,
+{
+    fn on_notify(mut self, _: Clear) -> Completion<Self> {
+        self.frame.clear();
+        Completion::immediate(self)
+    }
+}
+
+impl<I> NotifyHandler<SetPixel> for Ssd1306<I>
+where
+    I: Write,
+{
+    fn on_notify(mut self, message: SetPixel) -> Completion<Self> {
+        self.frame.set_pixel(message.x, message.y, message.on);
+        Completion::immediate(self)
+    }
+}
+
+impl<I> NotifyHandler<DrawText> for Ssd1306<I>
+where
+    I: Write,
+{
+    fn on_notify(mut self, message: DrawText) -> Completion<Self> {
+        self.frame.draw_text(message.x, message.y, &message.text);
+        Completion::immediate(self)
+    }
+}
+
+impl<I> NotifyHandler<Framebuffer> for Ssd1306<I>
+where
+    I: Write,
+{
+    fn on_notify(mut self, frame: Framebuffer) -> Completion<Self> {
+        self.frame = frame;
+        Completion::immediate(self)
+    }
+}
+
+impl<I> RequestHandler<Flush> for Ssd1306<I>
+where
+    I: Write,
+{
+    type Response = Result<(), DeviceError>;
+
+    fn on_request(self, _: Flush) -> Response<Self, Self::Response> {
+        Response::defer(async move {
+            let result = match self.i2c {
+                Some(mut i2c) => flush(&mut i2c, self.address, &self.frame).await,
+                None => Ok(()),
+            };
+            (self, result)
+        })
+    }
+}
+
+impl<I> Address<Ssd1306<I>>
+where
+    I: Write,
+{
+    /// Clear the framebuffer.
+    pub fn clear(&self) {
+        self.notify(Clear)
+    }
+
+    /// Turn the pixel at `(x, y)` on or off in the framebuffer.
+    pub fn set_pixel(&self, x: usize, y: usize, on: bool) {
+        self.notify(SetPixel { x, y, on })
+    }
+
+    /// Draw `text` into the framebuffer with its top-left corner at `(x, y)`.
+    ///
+    /// At most 32 bytes of `text` are drawn, more than fit across the panel.
+    pub fn draw_text(&self, x: usize, y: usize, text: &str) {
+        self.notify(DrawText::new(x, y, text))
+    }
+
+    /// Replace the framebuffer with `frame`, e.g. one drawn with `embedded-graphics`.
+    pub fn apply(&self, frame: Framebuffer) {
+        self.notify(frame)
+    }
+
+    /// Write the framebuffer to the panel.
+    pub async fn flush(&self) -> Result<(), DeviceError> {
+        self.request(Flush).await
+    }
+}
+
+/// The panel over 4-wire SPI, with `DC` as its data/command pin.
+///
+/// The chip select of the panel is expected to be driven by the peripheral for the
+/// duration of each transfer.
+pub struct Ssd1306Spi<S, DC>
+where
+    S: SpiDma + 'static,
+    DC: OutputPin + 'static,
+{
+    spi: Option<Address<MutexActor<SpiPeripheral<S>>>>,
+    dc: DC,
+    frame: Framebuffer,
+}
+
+impl<S, DC> Ssd1306Spi<S, DC>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    pub fn new(dc: DC) -> Self {
+        Self {
+            spi: None,
+            dc,
+            frame: Framebuffer::new(),
+        }
+    }
+}
+
+impl<S, DC> Actor for Ssd1306Spi<S, DC>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    fn on_initialize(mut self) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(spi) = self.spi {
+                let mut spi = spi.lock().await;
+                if init_spi(&mut *spi, &mut self.dc).await.is_err() {
+                    error!("[ssd1306] unable to initialize panel");
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<S, DC> Bind<MutexActor<SpiPeripheral<S>>> for Ssd1306Spi<S, DC>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    fn on_bind(&mut self, address: Address<MutexActor<SpiPeripheral<S>>>) {
+        self.spi.replace(address);
+    }
+}
+
+impl<S, DC> NotifyHandler<Clear> for Ssd1306Spi<S, DC>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    fn on_notify(mut self, _: Clear) -> Completion<Self> {
+        self.frame.clear();
+        Completion::immediate(self)
+    }
+}
+
+impl<S, DC> NotifyHandler<SetPixel> for Ssd1306Spi<S, DC>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    fn on_notify(mut self, message: SetPixel) -> Completion<Self> {
+        self.frame.set_pixel(message.x, message.y, message.on);
+        Completion::immediate(self)
+    }
+}
+
+impl<S, DC> NotifyHandler<DrawText> for Ssd1306Spi<S, DC>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    fn on_notify(mut self, message: DrawText) -> Completion<Self> {
+        self.frame.draw_text(message.x, message.y, &message.text);
+        Completion::immediate(self)
+    }
+}
+
+impl<S, DC> NotifyHandler<Framebuffer> for Ssd1306Spi<S, DC>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    fn on_notify(mut self, frame: Framebuffer) -> Completion<Self> {
+        self.frame = frame;
+        Completion::immediate(self)
+    }
+}
+
+impl<S, DC> RequestHandler<Flush> for Ssd1306Spi<S, DC>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    type Response = Result<(), DeviceError>;
+
+    fn on_request(mut self, _: Flush) -> Response<Self, Self::Response> {
+        Response::defer(async move {
+            let result = match self.spi {
+                Some(spi) => {
+                    let mut spi = spi.lock().await;
+                    flush_spi(&mut *spi, &mut self.dc, &self.frame)
+                        .await
+                        .map_err(DeviceError::from)
+                }
+                None => Ok(()),
+            };
+            (self, result)
+        })
+    }
+}
+
+impl<S, DC> Address<Ssd1306Spi<S, DC>>
+where
+    S: SpiDma,
+    DC: OutputPin,
+{
+    /// Clear the framebuffer.
+    pub fn clear(&self) {
+        self.notify(Clear)
+    }
+
+    /// Turn the pixel at `(x, y)` on or off in the framebuffer.
+    pub fn set_pixel(&self, x: usize, y: usize, on: bool) {
+        self.notify(SetPixel { x, y, on })
+    }
+
+    /// Draw `text` into the framebuffer with its top-left corner at `(x, y)`.
+    ///
+    /// At most 32 bytes of `text` are drawn, more than fit across the panel.
+    pub fn draw_text(&self, x: usize, y: usize, text: &str) {
+        self.notify(DrawText::new(x, y, text))
+    }
+
+    /// Replace the framebuffer with `frame`, e.g. one drawn with `embedded-graphics`.
+    pub fn apply(&self, frame: Framebuffer) {
+        self.notify(frame)
+    }
+
+    /// Write the framebuffer to the panel.
+    pub async fn flush(&self) -> Result<(), DeviceError> {
+        self.request(Flush).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::block_on;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+    use heapless::Vec;
+
+    type Transfer = Vec<u8, U40>;
+
+    #[derive(Default)]
+    struct MockI2c {
+        writes: Vec<(I2cAddress, Transfer), U64>,
+    }
+
+    impl I2cBus for MockI2c {
+        type Error = ();
+
+        async fn write(&mut self, address: I2cAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+            self.writes
+                .push((address, Transfer::from_slice(bytes)?))
+                .map_err(|_| ())
+        }
+    }
+
+    /// Records each SPI write with the level of the data/command pin during it.
+    struct MockSpi<'p> {
+        dc: &'p Cell<bool>,
+        writes: Vec<(bool, Vec<u8, U1024>), U4>,
+    }
+
+    impl SpiBus for MockSpi<'_> {
+        type Error = ();
+
+        async fn transfer(&mut self, tx: &[u8], rx: &mut [u8]) -> Result<(), Self::Error> {
+            assert!(rx.is_empty());
+            self.writes
+                .push((self.dc.get(), Vec::from_slice(tx)?))
+                .map_err(|_| ())
+        }
+    }
+
+    struct MockDc<'p>(&'p Cell<bool>);
+
+    impl OutputPin for MockDc<'_> {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.0.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.0.set(true);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        let mut i2c = MockI2c::default();
+        block_on(init(&mut i2c, I2cAddress::new(ADDR))).unwrap();
+        assert_eq!(i2c.writes.len(), 1);
+        assert_eq!(i2c.writes[0].0, I2cAddress::new(0x3C));
+        assert_eq!(
+            &i2c.writes[0].1[..],
+            &[
+                0x00, 0xAE, 0xD5, 0x80, 0xA8, 0x3F, 0xD3, 0x00, 0x40, 0x8D, 0x14, 0x20, 0x00, 0xA1,
+                0xC8, 0xDA, 0x12, 0x81, 0xCF, 0xD9, 0xF1, 0xDB, 0x40, 0xA4, 0xA6, 0xAF
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_flush() {
+        let mut frame = Framebuffer::new();
+        frame.set_pixel(0, 0, true);
+        frame.set_pixel(1, 9, true);
+        frame.set_pixel(127, 63, true);
+        frame.set_pixel(128, 0, true);
+
+        let mut i2c = MockI2c::default();
+        block_on(flush(&mut i2c, I2cAddress::new(ADDR), &frame)).unwrap();
+
+        assert_eq!(i2c.writes.len(), 1 + 1024 / 32);
+        assert_eq!(&i2c.writes[0].1[..], &[0x00, 0x21, 0, 127, 0x22, 0, 7][..]);
+        for (i, (_, transfer)) in i2c.writes[1..].iter().enumerate() {
+            assert_eq!(transfer.len(), 33);
+            assert_eq!(transfer[0], 0x40);
+            for (j, byte) in transfer[1..].iter().enumerate() {
+                let expected = match i * 32 + j {
+                    0 => 0x01,
+                    129 => 0x02,
+                    1023 => 0x80,
+                    _ => 0x00,
+                };
+                assert_eq!(*byte, expected, "GDDRAM byte {}", i * 32 + j);
+            }
+        }
+    }
+
+    #[test]
+    fn test_flush_spi() {
+        let mut frame = Framebuffer::new();
+        frame.set_pixel(0, 0, true);
+        frame.set_pixel(127, 63, true);
+
+        let level = Cell::new(true);
+        let mut spi = MockSpi {
+            dc: &level,
+            writes: Vec::new(),
+        };
+        block_on(init_spi(&mut spi, &mut MockDc(&level))).unwrap();
+        block_on(flush_spi(&mut spi, &mut MockDc(&level), &frame)).unwrap();
+
+        assert_eq!(spi.writes.len(), 3);
+        assert!(!spi.writes[0].0);
+        assert_eq!(&spi.writes[0].1[..], &INIT[1..]);
+        assert!(!spi.writes[1].0);
+        assert_eq!(&spi.writes[1].1[..], &[0x21, 0, 127, 0x22, 0, 7][..]);
+        assert!(spi.writes[2].0);
+        assert_eq!(&spi.writes[2].1[..], frame.as_bytes());
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let mut frame = Framebuffer::new();
+        frame.draw_text(1, 8, "A!");
+        assert_eq!(
+            &frame.as_bytes()[128..142],
+            &[0, 0x7E, 0x11, 0x11, 0x11, 0x7E, 0, 0, 0, 0x5F, 0, 0, 0, 0][..]
+        );
+
+        frame.set_pixel(0, 8, true);
+        frame.draw_text(0, 8, " ");
+        assert!(!frame.is_set(0, 8));
+
+        frame.clear();
+        frame.draw_text(124, 60, "A");
+        assert!(frame.is_set(124, 61));
+        assert!(!frame.is_set(124, 60));
+    }
+
+    #[cfg(feature = "embedded-graphics")]
+    #[test]
+    fn test_draw_target() {
+        use embedded_graphics::prelude::*;
+        use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+        let mut frame = Framebuffer::new();
+        Rectangle::new(Point::new(2, 6), Size::new(3, 4))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut frame)
+            .unwrap();
+        Rectangle::new(Point::new(126, 62), Size::new(4, 4))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut frame)
+            .unwrap();
+
+        let bytes = frame.as_bytes();
+        // rows 6..=7 in page 0, rows 8..=9 in page 1
+        assert_eq!(&bytes[..6], &[0, 0, 0xC0, 0xC0, 0xC0, 0][..]);
+        assert_eq!(&bytes[128..134], &[0, 0, 0x03, 0x03, 0x03, 0][..]);
+        assert_eq!(&bytes[1024 - 3..], &[0, 0xC0, 0xC0][..]);
+        assert_eq!(bytes.iter().filter(|b| **b != 0).count(), 8);
+    }
+}
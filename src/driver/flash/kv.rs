@@ -0,0 +1,588 @@
+//! A small append-only key/value store on top of two flash sectors.
+//!
+//! # On-flash format
+//!
+//! The store occupies two adjacent sectors, only one of which is active at a time.
+//! The active sector starts with a header, padded to the flash write size:
+//!
+//! | bytes  | content                    |
+//! |--------|----------------------------|
+//! | `0..4` | magic `b"DKV1"`            |
+//! | `4`    | generation (wrapping `u8`) |
+//!
+//! and is followed by a log of records, each also padded to the write size:
+//!
+//! | bytes  | content                                                   |
+//! |--------|-----------------------------------------------------------|
+//! | `0`    | key length (`0xFF` marks the end of the log)              |
+//! | `1`    | value length                                              |
+//! | `2..4` | Fletcher-16 checksum of bytes `0..2`, key and value (LE)  |
+//! | `4..`  | key, followed by value                                    |
+//!
+//! The newest record for a key wins.
+//!
+//! # Power loss
+//!
+//! A record with a bad checksum was interrupted while being written. It, and anything
+//! following it, is ignored when the store is opened, and the next `set(...)` compacts
+//! the store first so the garbage gets erased.
+//!
+//! Compaction copies the live records into the spare sector, writes the spare's header
+//! (with the next generation) last, and only then erases the old sector. If power is lost
+//! part-way, the spare either has no valid header, and the old sector remains active, or it
+//! carries the newer generation and is picked on the next boot.
+
+use crate::hal::flash::Flash;
+use crate::prelude::*;
+use heapless::{consts::*, Vec};
+
+const MAGIC: [u8; 4] = *b"DKV1";
+const ERASED: u8 = 0xFF;
+const SECTOR_HEADER_SIZE: usize = 5;
+const RECORD_HEADER_SIZE: usize = 4;
+const MAX_WRITE_SIZE: usize = 8;
+const MAX_RECORD_SIZE: usize = align(
+    RECORD_HEADER_SIZE + MAX_KEY_LEN + MAX_VALUE_LEN,
+    MAX_WRITE_SIZE,
+);
+
+/// Maximum length of a key, in bytes.
+pub const MAX_KEY_LEN: usize = 32;
+
+/// Maximum length of a value, in bytes.
+pub const MAX_VALUE_LEN: usize = 128;
+
+pub type Key = Vec<u8, U32>;
+pub type Value = Vec<u8, U128>;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Error<E> {
+    Flash(E),
+    KeyTooLong,
+    ValueTooLong,
+    /// The live records do not fit in a sector, even after compaction.
+    Full,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::Flash(e)
+    }
+}
+
+const fn align(len: usize, to: usize) -> usize {
+    len.div_ceil(to) * to
+}
+
+fn checksum(data: &[u8]) -> u16 {
+    let mut a: u16 = 0;
+    let mut b: u16 = 0;
+    for byte in data {
+        a = (a + *byte as u16) % 255;
+        b = (b + a) % 255;
+    }
+    (b << 8) | a
+}
+
+struct Record {
+    bytes: [u8; MAX_RECORD_SIZE],
+    key_len: usize,
+    value_len: usize,
+}
+
+impl Record {
+    fn new(key: &[u8], value: &[u8]) -> Self {
+        let mut bytes = [ERASED; MAX_RECORD_SIZE];
+        bytes[0] = key.len() as u8;
+        bytes[1] = value.len() as u8;
+        let body = RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + key.len();
+        bytes[body].copy_from_slice(key);
+        let body = RECORD_HEADER_SIZE + key.len()..RECORD_HEADER_SIZE + key.len() + value.len();
+        bytes[body].copy_from_slice(value);
+
+        let mut record = Self {
+            bytes,
+            key_len: key.len(),
+            value_len: value.len(),
+        };
+        let sum = record.checksum();
+        record.bytes[2..4].copy_from_slice(&sum.to_le_bytes());
+        record
+    }
+
+    fn len(&self) -> usize {
+        RECORD_HEADER_SIZE + self.key_len + self.value_len
+    }
+
+    fn size(&self, write_size: usize) -> usize {
+        align(self.len(), write_size)
+    }
+
+    fn key(&self) -> &[u8] {
+        &self.bytes[RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + self.key_len]
+    }
+
+    fn value(&self) -> &[u8] {
+        &self.bytes[RECORD_HEADER_SIZE + self.key_len..self.len()]
+    }
+
+    fn checksum(&self) -> u16 {
+        let mut sum = [0; MAX_KEY_LEN + MAX_VALUE_LEN + 2];
+        sum[..2].copy_from_slice(&self.bytes[..2]);
+        sum[2..2 + self.key_len + self.value_len]
+            .copy_from_slice(&self.bytes[RECORD_HEADER_SIZE..self.len()]);
+        checksum(&sum[..2 + self.key_len + self.value_len])
+    }
+
+    fn is_valid(&self) -> bool {
+        u16::from_le_bytes([self.bytes[2], self.bytes[3]]) == self.checksum()
+    }
+}
+
+enum Scan {
+    Record(Record),
+    End,
+    Corrupt,
+}
+
+/// An append-only key/value store, compacting when its sector fills.
+///
+/// May be used directly, or mounted as an actor and driven through
+/// `Address<KvStore<F>>::get(...)` and `set(...)`.
+pub struct KvStore<F: Flash> {
+    flash: F,
+    first_sector: usize,
+    active: usize,
+    generation: u8,
+    head: usize,
+    dirty: bool,
+    opened: bool,
+}
+
+impl<F: Flash> KvStore<F> {
+    /// Create a store occupying `first_sector` and the sector following it.
+    pub fn new(flash: F, first_sector: usize) -> Self {
+        assert!(flash.write_size() <= MAX_WRITE_SIZE);
+        Self {
+            flash,
+            first_sector,
+            active: 0,
+            generation: 0,
+            head: 0,
+            dirty: false,
+            opened: false,
+        }
+    }
+
+    /// Locate the active sector and the end of its log.
+    ///
+    /// Called implicitly by `get(...)` and `set(...)` if required.
+    pub fn open(&mut self) -> Result<(), Error<F::Error>> {
+        let first = self.read_generation(0)?;
+        let second = self.read_generation(1)?;
+        let (active, generation) = match (first, second) {
+            (Some(a), Some(b)) if (b.wrapping_sub(a) as i8) > 0 => (1, b),
+            (Some(a), _) => (0, a),
+            (None, Some(b)) => (1, b),
+            (None, None) => {
+                self.format(0, 0)?;
+                (0, 0)
+            }
+        };
+        self.active = active;
+        self.generation = generation;
+
+        let mut offset = self.header_size();
+        self.dirty = loop {
+            match self.read_record(active, offset)? {
+                Scan::Record(record) => offset += record.size(self.flash.write_size()),
+                Scan::End => break false,
+                Scan::Corrupt => {
+                    warn!("[kv] ignoring truncated record at {}", offset);
+                    break true;
+                }
+            }
+        };
+        self.head = offset;
+        self.opened = true;
+        Ok(())
+    }
+
+    /// Retrieve the current value for `key`, if any.
+    pub fn get(&mut self, key: &[u8]) -> Result<Option<Value>, Error<F::Error>> {
+        self.ensure_open()?;
+        let mut found = None;
+        let mut offset = self.header_size();
+        while let Scan::Record(record) = self.read_record(self.active, offset)? {
+            if record.key() == key {
+                found.replace(Value::from_slice(record.value()).unwrap());
+            }
+            offset += record.size(self.flash.write_size());
+        }
+        Ok(found)
+    }
+
+    /// Store `value` for `key`, replacing any previous value.
+    pub fn set(&mut self, key: &[u8], value: &[u8]) -> Result<(), Error<F::Error>> {
+        if key.is_empty() || key.len() > MAX_KEY_LEN {
+            return Err(Error::KeyTooLong);
+        }
+        if value.len() > MAX_VALUE_LEN {
+            return Err(Error::ValueTooLong);
+        }
+        self.ensure_open()?;
+
+        let record = Record::new(key, value);
+        let size = record.size(self.flash.write_size());
+        if self.dirty || self.head + size > self.flash.sector_size() {
+            self.compact()?;
+            if self.head + size > self.flash.sector_size() {
+                return Err(Error::Full);
+            }
+        }
+
+        let offset = self.sector_offset(self.active) + self.head;
+        if let Err(error) = self.flash.write(offset, &record.bytes[..size]) {
+            // part of the record may have been written, so compact it away before the
+            // next record is appended after it
+            self.dirty = true;
+            return Err(error.into());
+        }
+        self.head += size;
+        Ok(())
+    }
+
+    /// Copy the live records into the spare sector and make it the active one.
+    pub fn compact(&mut self) -> Result<(), Error<F::Error>> {
+        self.ensure_open()?;
+        let write_size = self.flash.write_size();
+        let spare = 1 - self.active;
+        self.flash.erase(self.first_sector + spare)?;
+
+        let mut head = self.header_size();
+        let mut offset = self.header_size();
+        while let Scan::Record(record) = self.read_record(self.active, offset)? {
+            offset += record.size(write_size);
+            if !self.is_superseded(record.key(), offset)? {
+                let size = record.size(write_size);
+                self.flash
+                    .write(self.sector_offset(spare) + head, &record.bytes[..size])?;
+                head += size;
+            }
+        }
+
+        let generation = self.generation.wrapping_add(1);
+        self.write_header(spare, generation)?;
+        self.flash.erase(self.first_sector + self.active)?;
+
+        self.active = spare;
+        self.generation = generation;
+        self.head = head;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn ensure_open(&mut self) -> Result<(), Error<F::Error>> {
+        if !self.opened {
+            self.open()?;
+        }
+        Ok(())
+    }
+
+    fn is_superseded(&self, key: &[u8], mut offset: usize) -> Result<bool, Error<F::Error>> {
+        while let Scan::Record(record) = self.read_record(self.active, offset)? {
+            if record.key() == key {
+                return Ok(true);
+            }
+            offset += record.size(self.flash.write_size());
+        }
+        Ok(false)
+    }
+
+    fn sector_offset(&self, sector: usize) -> usize {
+        (self.first_sector + sector) * self.flash.sector_size()
+    }
+
+    fn header_size(&self) -> usize {
+        align(SECTOR_HEADER_SIZE, self.flash.write_size())
+    }
+
+    fn read_generation(&self, sector: usize) -> Result<Option<u8>, Error<F::Error>> {
+        let mut header = [0; SECTOR_HEADER_SIZE];
+        self.flash.read(self.sector_offset(sector), &mut header)?;
+        if header[..4] == MAGIC {
+            Ok(Some(header[4]))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn write_header(&mut self, sector: usize, generation: u8) -> Result<(), Error<F::Error>> {
+        let mut header = [ERASED; align(SECTOR_HEADER_SIZE, MAX_WRITE_SIZE)];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4] = generation;
+        let size = self.header_size();
+        self.flash
+            .write(self.sector_offset(sector), &header[..size])?;
+        Ok(())
+    }
+
+    fn format(&mut self, sector: usize, generation: u8) -> Result<(), Error<F::Error>> {
+        self.flash.erase(self.first_sector + sector)?;
+        self.write_header(sector, generation)
+    }
+
+    fn read_record(&self, sector: usize, offset: usize) -> Result<Scan, Error<F::Error>> {
+        let sector_size = self.flash.sector_size();
+        if offset + RECORD_HEADER_SIZE > sector_size {
+            return Ok(Scan::End);
+        }
+
+        let mut bytes = [ERASED; MAX_RECORD_SIZE];
+        let base = self.sector_offset(sector) + offset;
+        self.flash.read(base, &mut bytes[..RECORD_HEADER_SIZE])?;
+        if bytes[0] == ERASED {
+            return Ok(Scan::End);
+        }
+
+        let key_len = bytes[0] as usize;
+        let value_len = bytes[1] as usize;
+        if key_len == 0 || key_len > MAX_KEY_LEN || value_len > MAX_VALUE_LEN {
+            return Ok(Scan::Corrupt);
+        }
+        let len = RECORD_HEADER_SIZE + key_len + value_len;
+        if offset + len > sector_size {
+            return Ok(Scan::Corrupt);
+        }
+        self.flash
+            .read(base + RECORD_HEADER_SIZE, &mut bytes[RECORD_HEADER_SIZE..len])?;
+
+        let record = Record {
+            bytes,
+            key_len,
+            value_len,
+        };
+        if record.is_valid() {
+            Ok(Scan::Record(record))
+        } else {
+            Ok(Scan::Corrupt)
+        }
+    }
+}
+
+impl<F> Actor for KvStore<F>
+where
+    F: Flash + 'static,
+{
+    fn on_initialize(mut self) -> Completion<Self> {
+        if self.open().is_err() {
+            error!("[{}] unable to open key/value store", ActorInfo::name());
+        }
+        Completion::immediate(self)
+    }
+}
+
+/// Request the current value of a key.
+pub struct Get(pub Key);
+
+/// Request a key be set to a value.
+pub struct Set(pub Key, pub Value);
+
+impl<F> RequestHandler<Get> for KvStore<F>
+where
+    F: Flash + 'static,
+    F::Error: 'static,
+{
+    type Response = Result<Option<Value>, Error<F::Error>>;
+
+    fn on_request(mut self, message: Get) -> Response<Self, Self::Response> {
+        let result = self.get(&message.0);
+        Response::immediate(self, result)
+    }
+}
+
+impl<F> RequestHandler<Set> for KvStore<F>
+where
+    F: Flash + 'static,
+    F::Error: 'static,
+{
+    type Response = Result<(), Error<F::Error>>;
+
+    fn on_request(mut self, message: Set) -> Response<Self, Self::Response> {
+        let result = self.set(&message.0, &message.1);
+        Response::immediate(self, result)
+    }
+}
+
+impl<F> Address<KvStore<F>>
+where
+    F: Flash + 'static,
+    F::Error: 'static,
+{
+    pub async fn get(&self, key: &[u8]) -> Result<Option<Value>, Error<F::Error>> {
+        let key = Key::from_slice(key).map_err(|_| Error::KeyTooLong)?;
+        self.request(Get(key)).await
+    }
+
+    pub async fn set(&self, key: &[u8], value: &[u8]) -> Result<(), Error<F::Error>> {
+        let key = Key::from_slice(key).map_err(|_| Error::KeyTooLong)?;
+        let value = Value::from_slice(value).map_err(|_| Error::ValueTooLong)?;
+        self.request(Set(key, value)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTOR_SIZE: usize = 256;
+
+    struct MockFlash {
+        memory: [u8; SECTOR_SIZE * 2],
+        // Number of bytes that may still be programmed before "power loss".
+        budget: Option<usize>,
+    }
+
+    impl MockFlash {
+        fn new() -> Self {
+            Self {
+                memory: [ERASED; SECTOR_SIZE * 2],
+                budget: None,
+            }
+        }
+    }
+
+    impl Flash for MockFlash {
+        type Error = ();
+
+        fn sector_size(&self) -> usize {
+            SECTOR_SIZE
+        }
+
+        fn write_size(&self) -> usize {
+            4
+        }
+
+        fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), Self::Error> {
+            buf.copy_from_slice(&self.memory[offset..offset + buf.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Self::Error> {
+            assert_eq!(offset % 4, 0);
+            assert_eq!(data.len() % 4, 0);
+            for (i, byte) in data.iter().enumerate() {
+                if let Some(budget) = self.budget.as_mut() {
+                    if *budget == 0 {
+                        return Err(());
+                    }
+                    *budget -= 1;
+                }
+                self.memory[offset + i] &= *byte;
+            }
+            Ok(())
+        }
+
+        fn erase(&mut self, sector: usize) -> Result<(), Self::Error> {
+            for byte in self.memory[sector * SECTOR_SIZE..(sector + 1) * SECTOR_SIZE].iter_mut() {
+                *byte = ERASED;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_set_get() {
+        let mut store = KvStore::new(MockFlash::new(), 0);
+        assert_eq!(store.get(b"missing"), Ok(None));
+        store.set(b"ssid", b"drogue").unwrap();
+        store.set(b"pass", b"secret").unwrap();
+        assert_eq!(store.get(b"ssid").unwrap().unwrap(), b"drogue");
+        assert_eq!(store.get(b"pass").unwrap().unwrap(), b"secret");
+    }
+
+    #[test]
+    fn test_overwrite() {
+        let mut store = KvStore::new(MockFlash::new(), 0);
+        store.set(b"count", b"1").unwrap();
+        store.set(b"count", b"22").unwrap();
+        assert_eq!(store.get(b"count").unwrap().unwrap(), b"22");
+
+        let mut store = KvStore::new(store.flash, 0);
+        assert_eq!(store.get(b"count").unwrap().unwrap(), b"22");
+    }
+
+    #[test]
+    fn test_compaction() {
+        let mut store = KvStore::new(MockFlash::new(), 0);
+        store.set(b"fixed", b"value").unwrap();
+        for i in 0..100u8 {
+            store.set(b"counter", &[i; 8]).unwrap();
+        }
+        assert!(store.generation > 0);
+        assert_eq!(store.get(b"fixed").unwrap().unwrap(), b"value");
+        assert_eq!(store.get(b"counter").unwrap().unwrap(), &[99; 8]);
+
+        let mut store = KvStore::new(store.flash, 0);
+        assert_eq!(store.get(b"fixed").unwrap().unwrap(), b"value");
+        assert_eq!(store.get(b"counter").unwrap().unwrap(), &[99; 8]);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut store = KvStore::new(MockFlash::new(), 0);
+        let value = [0; MAX_VALUE_LEN];
+        store.set(b"a", &value).unwrap();
+        assert_eq!(store.set(b"b", &value), Err(Error::Full));
+        assert_eq!(store.get(b"a").unwrap().unwrap(), &value[..]);
+    }
+
+    #[test]
+    fn test_recover_truncated_record() {
+        let mut store = KvStore::new(MockFlash::new(), 0);
+        store.set(b"kept", b"yes").unwrap();
+
+        store.flash.budget.replace(6);
+        assert_eq!(store.set(b"lost", b"partially written"), Err(Error::Flash(())));
+        store.flash.budget.take();
+        assert!(store.dirty);
+
+        // the same store compacts the partial record away before writing the next
+        store.set(b"retried", b"ok").unwrap();
+        assert!(!store.dirty);
+        assert_eq!(store.get(b"retried").unwrap().unwrap(), b"ok");
+        assert_eq!(store.get(b"kept").unwrap().unwrap(), b"yes");
+
+        store.flash.budget.replace(6);
+        assert!(store.set(b"lost", b"partially written").is_err());
+        store.flash.budget.take();
+
+        let mut store = KvStore::new(store.flash, 0);
+        assert_eq!(store.get(b"lost"), Ok(None));
+        assert_eq!(store.get(b"kept").unwrap().unwrap(), b"yes");
+        assert!(store.dirty);
+
+        store.set(b"next", b"ok").unwrap();
+        assert!(!store.dirty);
+
+        let mut store = KvStore::new(store.flash, 0);
+        assert_eq!(store.get(b"kept").unwrap().unwrap(), b"yes");
+        assert_eq!(store.get(b"next").unwrap().unwrap(), b"ok");
+        assert_eq!(store.get(b"lost"), Ok(None));
+    }
+
+    #[test]
+    fn test_recover_interrupted_compaction() {
+        let mut store = KvStore::new(MockFlash::new(), 0);
+        store.set(b"key", b"value").unwrap();
+
+        // Power is lost before the spare sector's header is written.
+        store.flash.budget.replace(8);
+        assert!(store.compact().is_err());
+        store.flash.budget.take();
+
+        let mut store = KvStore::new(store.flash, 0);
+        assert_eq!(store.get(b"key").unwrap().unwrap(), b"value");
+        assert_eq!(store.active, 0);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod kv;
+
+pub use kv::KvStore;
@@ -0,0 +1,238 @@
+//! General-purpose inputs, polled on a timer.
+//!
+//! For inputs that cannot raise an interrupt, the `InputPin` actor samples a pin every
+//! poll interval and publishes a `PinChanged` event each time its level changes. A
+//! change is only accepted after the pin reads the new level for `debounce` consecutive
+//! samples, so contact bounce shorter than `debounce` intervals is ignored.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::TimerActor;
+use crate::hal::timer::Timer as HalTimer;
+use crate::handler::EventHandler;
+use crate::prelude::*;
+use embedded_hal::digital::v2::InputPin as HalInputPin;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Level {
+    Low,
+    High,
+}
+
+/// The debounced level of a pin changed to `.0`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PinChanged(pub Level);
+
+/// A pin together with its debounced level.
+pub struct DebouncedPin<P>
+where
+    P: HalInputPin,
+{
+    pin: P,
+    level: Option<Level>,
+    count: u8,
+    debounce: u8,
+}
+
+impl<P> DebouncedPin<P>
+where
+    P: HalInputPin,
+{
+    /// Track `pin`, changing level after `debounce` consecutive differing samples.
+    pub fn new(pin: P, debounce: u8) -> Self {
+        Self {
+            pin,
+            level: None,
+            count: 0,
+            debounce: debounce.max(1),
+        }
+    }
+
+    /// Sample the pin once, returning its new level if it changed.
+    ///
+    /// The first sample sets the level without counting as a change. A failed read
+    /// is ignored.
+    pub fn sample(&mut self) -> Option<Level> {
+        let sampled = match self.pin.is_high() {
+            Ok(true) => Level::High,
+            Ok(false) => Level::Low,
+            Err(_) => return None,
+        };
+        let level = match self.level {
+            None => {
+                self.level.replace(sampled);
+                return None;
+            }
+            Some(level) => level,
+        };
+        if sampled == level {
+            self.count = 0;
+            return None;
+        }
+        self.count += 1;
+        if self.count < self.debounce {
+            return None;
+        }
+        self.level.replace(sampled);
+        self.count = 0;
+        Some(sampled)
+    }
+
+    /// The debounced level, once sampled.
+    pub fn level(&self) -> Option<Level> {
+        self.level
+    }
+}
+
+/// An input publishing a `PinChanged` event for each debounced change of level.
+pub struct InputPin<D, T, P>
+where
+    D: Device + EventHandler<PinChanged> + 'static,
+    T: HalTimer + 'static,
+    P: HalInputPin + 'static,
+{
+    pin: DebouncedPin<P>,
+    interval: Milliseconds,
+    bus: Option<Address<EventBus<D>>>,
+    timer: Option<Address<TimerActor<T>>>,
+    address: Option<Address<Self>>,
+}
+
+impl<D, T, P> InputPin<D, T, P>
+where
+    D: Device + EventHandler<PinChanged>,
+    T: HalTimer,
+    P: HalInputPin,
+{
+    /// Create an input sampling `pin` every `interval`, changing level after `debounce`
+    /// consecutive samples reading the same way.
+    pub fn new(pin: P, interval: Milliseconds, debounce: u8) -> Self {
+        Self {
+            pin: DebouncedPin::new(pin, debounce),
+            interval,
+            bus: None,
+            timer: None,
+            address: None,
+        }
+    }
+
+    fn schedule_poll(&self) {
+        if let (Some(timer), Some(address)) = (self.timer, self.address) {
+            timer.schedule(self.interval, Poll, address);
+        }
+    }
+}
+
+impl<D, T, P> Actor for InputPin<D, T, P>
+where
+    D: Device + EventHandler<PinChanged>,
+    T: HalTimer,
+    P: HalInputPin,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_start(mut self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.pin.sample();
+        self.schedule_poll();
+        Completion::immediate(self)
+    }
+}
+
+impl<D, T, P> Bind<EventBus<D>> for InputPin<D, T, P>
+where
+    D: Device + EventHandler<PinChanged>,
+    T: HalTimer,
+    P: HalInputPin,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, T, P> Bind<TimerActor<T>> for InputPin<D, T, P>
+where
+    D: Device + EventHandler<PinChanged>,
+    T: HalTimer,
+    P: HalInputPin,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.timer.replace(address);
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Poll;
+
+impl<D, T, P> NotifyHandler<Poll> for InputPin<D, T, P>
+where
+    D: Device + EventHandler<PinChanged>,
+    T: HalTimer,
+    P: HalInputPin,
+{
+    fn on_notify(mut self, _: Poll) -> Completion<Self> {
+        if let Some(level) = self.pin.sample() {
+            if let Some(bus) = self.bus {
+                bus.publish(PinChanged(level));
+            }
+        }
+        self.schedule_poll();
+        Completion::immediate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    struct MockPin<'a>(&'a Cell<bool>);
+
+    impl HalInputPin for MockPin<'_> {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(self.0.get())
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(!self.0.get())
+        }
+    }
+
+    #[test]
+    fn test_bouncy_transitions() {
+        let high = Cell::new(false);
+        let mut pin = DebouncedPin::new(MockPin(&high), 3);
+        assert_eq!(pin.sample(), None);
+        assert_eq!(pin.level(), Some(Level::Low));
+
+        let mut changes = [None; 16];
+        for (i, reading) in [
+            // rising, bouncing twice
+            true, false, true, true, false, true, true, true, true,
+            // falling, bouncing once
+            false, false, true, false, false, false, false,
+        ]
+        .iter()
+        .enumerate()
+        {
+            high.set(*reading);
+            changes[i] = pin.sample();
+        }
+
+        let mut expected = [None; 16];
+        expected[7] = Some(Level::High);
+        expected[14] = Some(Level::Low);
+        assert_eq!(changes, expected);
+        assert_eq!(pin.level(), Some(Level::Low));
+    }
+}
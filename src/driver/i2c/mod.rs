@@ -1,18 +1,102 @@
+pub mod mux;
+
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::Clock;
+use crate::error::DeviceError;
 use crate::prelude::*;
+use core::any::Any;
+use core::cell::RefCell;
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Poll;
 use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
-use crate::hal::i2c::I2cAddress;
+use heapless::{consts::*, Vec};
+use crate::hal::i2c::{I2cAddress, I2cTransfer};
+use crate::util::task::yield_now;
+
+/// Async write access to an I2C bus, allowing device drivers to be
+/// exercised against a mock bus.
+#[allow(async_fn_in_trait)]
+pub trait I2cBus {
+    type Error;
+
+    /// Write `bytes` to the device at `address`.
+    async fn write(&mut self, address: I2cAddress, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Async register access on an I2C bus, writing then reading in a single transaction.
+#[allow(async_fn_in_trait)]
+pub trait I2cReadBus: I2cBus {
+    /// Write `bytes` to the device at `address`, then read into `buffer`.
+    async fn write_read(
+        &mut self,
+        address: I2cAddress,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error>;
+}
+
+/// The bus of an `I2c` package, shared by its actor and any `SharedI2cBus` handles.
+///
+/// Each transfer takes the bus for its duration. Transfers are blocking, so they can
+/// only contend when one is started from an interrupt handler while another is under
+/// way, and the later one then fails with `DeviceError::Busy` rather than deadlocking.
+pub struct Shared<I> {
+    i2c: RefCell<I>,
+    locked: AtomicBool,
+}
+
+impl<I> Shared<I> {
+    fn new(i2c: I) -> Self {
+        Self {
+            i2c: RefCell::new(i2c),
+            locked: AtomicBool::new(false),
+        }
+    }
+
+    /// Run `transfer` with the bus taken, reporting any error of the HAL as a `BusError`,
+    /// but passing on a `DeviceError`, such as of a bus behind a multiplexer.
+    fn transfer<E, F>(&self, transfer: F) -> Result<(), DeviceError>
+    where
+        E: 'static,
+        F: FnOnce(&mut I) -> Result<(), E>,
+    {
+        if self.locked.swap(true, Ordering::Acquire) {
+            return Err(DeviceError::Busy);
+        }
+        let result = transfer(&mut self.i2c.borrow_mut());
+        self.locked.store(false, Ordering::Release);
+        result.map_err(|error| {
+            (&error as &dyn Any)
+                .downcast_ref::<DeviceError>()
+                .copied()
+                .unwrap_or(DeviceError::BusError)
+        })
+    }
+}
 
 pub struct I2c<I>
 where
     I: 'static,
 {
     peripheral: ActorContext<I2cPeripheral<I>>,
+    shared: Shared<I>,
 }
 
 impl<I> I2c<I> {
     pub fn new(i2c: I) -> Self {
         Self {
-            peripheral: ActorContext::new(I2cPeripheral::new(i2c)),
+            peripheral: ActorContext::new(I2cPeripheral::new()),
+            shared: Shared::new(i2c),
+        }
+    }
+
+    /// A blocking `embedded-hal` handle on the bus, for drivers written against the
+    /// `embedded-hal` traits rather than as actors.
+    pub fn bus(&'static self) -> SharedI2cBus<I> {
+        SharedI2cBus {
+            shared: &self.shared,
         }
     }
 }
@@ -26,17 +110,40 @@ where
         bus_address: Address<EventBus<D>>,
         supervisor: &mut Supervisor,
     ) -> Address<I2cPeripheral<I>> {
-        self.peripheral.mount(supervisor)
+        let address = self.peripheral.mount(supervisor);
+        self.peripheral.configure(&self.shared);
+        address
     }
 }
 
-pub struct I2cPeripheral<I> {
-    i2c: I,
+/// Actor performing transfers on the bus of an `I2c` package, reporting any error of
+/// the HAL as a `DeviceError::BusError`.
+pub struct I2cPeripheral<I: 'static> {
+    shared: Option<&'static Shared<I>>,
 }
 
 impl<I> I2cPeripheral<I> {
-    fn new(i2c: I) -> Self {
-        Self { i2c }
+    fn new() -> Self {
+        Self { shared: None }
+    }
+
+    fn transfer<E, F>(&self, transfer: F) -> Result<(), DeviceError>
+    where
+        E: 'static,
+        F: FnOnce(&mut I) -> Result<(), E>,
+    {
+        match self.shared {
+            Some(shared) => shared.transfer(transfer),
+            None => Err(DeviceError::NotBound),
+        }
+    }
+}
+
+impl<I> Configurable for I2cPeripheral<I> {
+    type Configuration = Shared<I>;
+
+    fn configure(&mut self, config: &'static Shared<I>) {
+        self.shared.replace(config);
     }
 }
 
@@ -51,10 +158,10 @@ impl<'b, I> RequestHandler<I2cRead<'b>> for I2cPeripheral<I>
 where
     I: Read + 'static,
 {
-    type Response = Result<(), I::Error>;
+    type Response = Result<(), DeviceError>;
 
-    fn on_request(mut self, message: I2cRead<'b>) -> Response<Self, Self::Response> {
-        let result = self.i2c.read(message.address.into(), message.buffer);
+    fn on_request(self, message: I2cRead<'b>) -> Response<Self, Self::Response> {
+        let result = self.transfer(|i2c| i2c.read(message.address.into(), message.buffer));
         Response::immediate(self, result)
     }
 }
@@ -68,10 +175,88 @@ impl<'b, I> RequestHandler<I2cWrite<'b>> for I2cPeripheral<I>
 where
     I: Write + 'static,
 {
-    type Response = Result<(), I::Error>;
+    type Response = Result<(), DeviceError>;
+
+    fn on_request(self, message: I2cWrite<'b>) -> Response<Self, Self::Response> {
+        let result = self.transfer(|i2c| i2c.write(message.address.into(), message.buffer));
+        Response::immediate(self, result)
+    }
+}
+
+/// The bytes of a transaction through `I2cBus` on the address of an `I2cPeripheral`,
+/// copied into the request.
+pub type TransferBytes = Vec<u8, U64>;
+
+/// Request to write bytes owned by the request, so that a request dropped before it is
+/// handled leaves the actor nothing borrowed.
+pub struct I2cWriteOwned {
+    address: I2cAddress,
+    bytes: TransferBytes,
+}
+
+impl I2cWriteOwned {
+    /// Copy `bytes` into a request, failing with `ResourceExhausted` if more than a
+    /// `TransferBytes` holds.
+    fn new(address: I2cAddress, bytes: &[u8]) -> Result<Self, DeviceError> {
+        let bytes =
+            TransferBytes::from_slice(bytes).map_err(|_| DeviceError::ResourceExhausted)?;
+        Ok(Self { address, bytes })
+    }
+}
+
+impl<I> RequestHandler<I2cWriteOwned> for I2cPeripheral<I>
+where
+    I: Write + 'static,
+{
+    type Response = Result<(), DeviceError>;
+
+    fn on_request(self, message: I2cWriteOwned) -> Response<Self, Self::Response> {
+        let result = self.transfer(|i2c| i2c.write(message.address.into(), &message.bytes));
+        Response::immediate(self, result)
+    }
+}
+
+/// Request to write bytes owned by the request, then read `read_len` bytes into the
+/// response, so that a request dropped before it is handled leaves the actor nothing
+/// borrowed to write into.
+pub struct I2cWriteReadOwned {
+    address: I2cAddress,
+    bytes: TransferBytes,
+    read_len: usize,
+}
+
+impl I2cWriteReadOwned {
+    /// Copy `bytes` into a request, failing with `ResourceExhausted` if more than a
+    /// `TransferBytes` holds, or more than it holds is to be read.
+    fn new(address: I2cAddress, bytes: &[u8], read_len: usize) -> Result<Self, DeviceError> {
+        let bytes =
+            TransferBytes::from_slice(bytes).map_err(|_| DeviceError::ResourceExhausted)?;
+        if read_len > bytes.capacity() {
+            return Err(DeviceError::ResourceExhausted);
+        }
+        Ok(Self {
+            address,
+            bytes,
+            read_len,
+        })
+    }
+}
+
+impl<I> RequestHandler<I2cWriteReadOwned> for I2cPeripheral<I>
+where
+    I: WriteRead + 'static,
+{
+    /// The bytes read.
+    type Response = Result<TransferBytes, DeviceError>;
 
-    fn on_request(mut self, message: I2cWrite<'b>) -> Response<Self, Self::Response> {
-        let result = self.i2c.write(message.address.into(), message.buffer);
+    fn on_request(self, message: I2cWriteReadOwned) -> Response<Self, Self::Response> {
+        let mut read = TransferBytes::new();
+        let result = match read.resize_default(message.read_len) {
+            Ok(()) => self
+                .transfer(|i2c| i2c.write_read(message.address.into(), &message.bytes, &mut read))
+                .map(|_| read),
+            Err(_) => Err(DeviceError::ResourceExhausted),
+        };
         Response::immediate(self, result)
     }
 }
@@ -86,16 +271,84 @@ impl<'b, I> RequestHandler<I2cWriteRead<'b>> for I2cPeripheral<I>
 where
     I: WriteRead + 'static,
 {
-    type Response = Result<(), I::Error>;
+    type Response = Result<(), DeviceError>;
 
-    fn on_request(mut self, message: I2cWriteRead<'b>) -> Response<Self, Self::Response> {
+    fn on_request(self, message: I2cWriteRead<'b>) -> Response<Self, Self::Response> {
         let result = self
-            .i2c
-            .write_read(message.address.into(), message.bytes, message.buffer);
+            .transfer(|i2c| i2c.write_read(message.address.into(), message.bytes, message.buffer));
+        Response::immediate(self, result)
+    }
+}
+
+/// The bytes written to or read from a register by an `I2cCommand`.
+pub type RegisterBytes = Vec<u8, U32>;
+
+/// Request for register-level access to a device on the bus, owning its bytes so that
+/// it can be sent like any other message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum I2cCommand {
+    /// Write `write` to the registers of the device at `addr` from `reg` on.
+    Write {
+        addr: I2cAddress,
+        reg: u8,
+        write: RegisterBytes,
+    },
+    /// Write `write` to the device at `addr` following the register address `reg`, then
+    /// read `read_len` bytes from it in the same transaction, after a repeated start.
+    /// With a `read_len` of zero, this is a `Write`.
+    WriteRead {
+        addr: I2cAddress,
+        reg: u8,
+        write: RegisterBytes,
+        read_len: usize,
+    },
+}
+
+impl<I> RequestHandler<I2cCommand> for I2cPeripheral<I>
+where
+    I: Write + WriteRead + 'static,
+{
+    /// The bytes read, none for a `Write`. A command of more bytes than a
+    /// `RegisterBytes` holds fails with `ResourceExhausted`.
+    type Response = Result<RegisterBytes, DeviceError>;
+
+    fn on_request(self, command: I2cCommand) -> Response<Self, Self::Response> {
+        let result = self.command(command);
         Response::immediate(self, result)
     }
 }
 
+impl<I> I2cPeripheral<I>
+where
+    I: Write + WriteRead,
+{
+    fn command(&self, command: I2cCommand) -> Result<RegisterBytes, DeviceError> {
+        let (addr, reg, write, read_len) = match command {
+            I2cCommand::Write { addr, reg, write } => (addr, reg, write, 0),
+            I2cCommand::WriteRead {
+                addr,
+                reg,
+                write,
+                read_len,
+            } => (addr, reg, write, read_len),
+        };
+        let mut bytes: Vec<u8, U33> = Vec::new();
+        bytes.push(reg).ok();
+        bytes
+            .extend_from_slice(&write)
+            .map_err(|_| DeviceError::ResourceExhausted)?;
+        let mut read = RegisterBytes::new();
+        read.resize_default(read_len)
+            .map_err(|_| DeviceError::ResourceExhausted)?;
+        if read_len == 0 {
+            self.transfer(|i2c| i2c.write(addr.into(), &bytes))?;
+        } else {
+            self.transfer(|i2c| i2c.write_read(addr.into(), &bytes, &mut read))?;
+        }
+        Ok(read)
+    }
+}
+
 impl<I> Address<I2cPeripheral<I>>
     where
         I: Read,
@@ -106,7 +359,7 @@ impl<I> Address<I2cPeripheral<I>>
         &self,
         address: I2cAddress,
         buffer: &mut [u8],
-    ) -> Result<(), I::Error> {
+    ) -> Result<(), DeviceError> {
         self.request_unchecked(I2cRead {
             address,
             buffer,
@@ -124,7 +377,7 @@ impl<I> Address<I2cPeripheral<I>>
         &self,
         address: I2cAddress,
         buffer: &[u8],
-    ) -> Result<(), I::Error> {
+    ) -> Result<(), DeviceError> {
         self.request_unchecked(I2cWrite {
             address,
             buffer,
@@ -143,7 +396,7 @@ where
         address: I2cAddress,
         bytes: &'b [u8],
         buffer: &'b mut [u8],
-    ) -> Result<(), I::Error> {
+    ) -> Result<(), DeviceError> {
         self.request_unchecked(I2cWriteRead {
             address,
             bytes,
@@ -151,3 +404,614 @@ where
         }).await
     }
 }
+
+impl<I> Address<I2cPeripheral<I>>
+where
+    I: Write + WriteRead + 'static,
+{
+    /// Access the registers of a device on the bus, resolving to the bytes read.
+    pub async fn command(&self, command: I2cCommand) -> Result<RegisterBytes, DeviceError> {
+        self.request(command).await
+    }
+}
+
+/// The bytes are copied into the request, as the future may be dropped before the actor
+/// handles it, so a write of more than a `TransferBytes` holds fails with
+/// `ResourceExhausted`.
+impl<I> I2cBus for Address<I2cPeripheral<I>>
+where
+    I: Write + 'static,
+{
+    type Error = DeviceError;
+
+    async fn write(&mut self, address: I2cAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.request(I2cWriteOwned::new(address, bytes)?).await
+    }
+}
+
+/// As with writes, the bytes are copied into the request, and those read are copied out
+/// of the response into `buffer`, so each of `bytes` and `buffer` is limited to what a
+/// `TransferBytes` holds.
+impl<I> I2cReadBus for Address<I2cPeripheral<I>>
+where
+    I: Write + WriteRead + 'static,
+{
+    async fn write_read(
+        &mut self,
+        address: I2cAddress,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let read = self
+            .request(I2cWriteReadOwned::new(address, bytes, buffer.len())?)
+            .await?;
+        buffer.copy_from_slice(&read);
+        Ok(())
+    }
+}
+
+/// Blocking `embedded-hal` access to the bus of an `I2c` package, obtained by
+/// `I2c::bus()`, so drivers from the wider ecosystem can share the bus with actors.
+///
+/// Each operation takes the bus for the duration of the transfer, and fails with
+/// `DeviceError::Busy` if it is already taken, such as from an interrupt handler.
+///
+/// # Blocking
+///
+/// Operations block the caller until the transfer completes. Used from within an
+/// actor's handler or future, that stalls the executor, and every other actor with it,
+/// for as long as the transfer takes, so keep to short transfers, or prefer the
+/// async requests on the address of the `I2cPeripheral`.
+pub struct SharedI2cBus<I: 'static> {
+    shared: &'static Shared<I>,
+}
+
+impl<I> Copy for SharedI2cBus<I> {}
+
+impl<I> Clone for SharedI2cBus<I> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I: Read> Read for SharedI2cBus<I> {
+    type Error = DeviceError;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), DeviceError> {
+        self.shared.transfer(|i2c| i2c.read(address, buffer))
+    }
+}
+
+impl<I: Write> Write for SharedI2cBus<I> {
+    type Error = DeviceError;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), DeviceError> {
+        self.shared.transfer(|i2c| i2c.write(address, bytes))
+    }
+}
+
+impl<I: WriteRead> WriteRead for SharedI2cBus<I> {
+    type Error = DeviceError;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), DeviceError> {
+        self.shared
+            .transfer(|i2c| i2c.write_read(address, bytes, buffer))
+    }
+}
+
+/// The error of a transaction on an `I2cController`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum I2cTimeout<E> {
+    /// The bus failed the transaction.
+    Bus(E),
+    /// The transaction was abandoned for taking longer than the timeout, and the bus
+    /// cleared.
+    TimedOut,
+}
+
+/// Actor performing transfers on an I2C peripheral which runs them in the background,
+/// abandoning any that takes longer than a timeout, such as while a misbehaving device
+/// stretches SCL forever, then clearing the bus for the next.
+///
+/// Transfers are polled, yielding to the other actors between polls, and timed by a
+/// `Clock` such as the address of a `TimerActor`. A transfer timed out fails with
+/// `DeviceError::Timeout`, and any error of the HAL is reported as a
+/// `DeviceError::BusError`.
+pub struct I2cController<T, C>
+where
+    T: I2cTransfer + 'static,
+    C: Clock,
+{
+    i2c: T,
+    clock: C,
+    timeout: Milliseconds,
+}
+
+impl<T, C> I2cController<T, C>
+where
+    T: I2cTransfer,
+    C: Clock,
+{
+    pub fn new<DUR: Into<Milliseconds>>(i2c: T, clock: C, timeout: DUR) -> Self {
+        Self {
+            i2c,
+            clock,
+            timeout: timeout.into(),
+        }
+    }
+
+    /// Write `bytes` to the device at `address`, then read into `buffer` unless it is
+    /// empty, clearing the bus if the transfer times out.
+    async fn transfer(
+        &mut self,
+        address: I2cAddress,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), I2cTimeout<T::Error>> {
+        self.i2c
+            .start_transfer(address, bytes, buffer.len())
+            .map_err(I2cTimeout::Bus)?;
+        let i2c = &mut self.i2c;
+        let finished = async move {
+            loop {
+                match i2c.finish_transfer(buffer) {
+                    Ok(()) => return Ok(()),
+                    Err(nb::Error::WouldBlock) => yield_now().await,
+                    Err(nb::Error::Other(error)) => return Err(error),
+                }
+            }
+        };
+        match within(self.clock, self.timeout, finished).await {
+            Some(result) => result.map_err(I2cTimeout::Bus),
+            None => {
+                warn!("I2C transaction timed out, clearing the bus");
+                self.i2c.bus_clear();
+                Err(I2cTimeout::TimedOut)
+            }
+        }
+    }
+}
+
+impl<T, C> Actor for I2cController<T, C>
+where
+    T: I2cTransfer,
+    C: Clock,
+{
+}
+
+impl<T, C> RequestHandler<I2cWriteOwned> for I2cController<T, C>
+where
+    T: I2cTransfer,
+    C: Clock,
+{
+    type Response = Result<(), DeviceError>;
+
+    fn on_request(mut self, message: I2cWriteOwned) -> Response<Self, Self::Response> {
+        Response::defer(async move {
+            let result = self.transfer(message.address, &message.bytes, &mut []).await;
+            (self, result.map_err(DeviceError::from))
+        })
+    }
+}
+
+impl<T, C> RequestHandler<I2cWriteReadOwned> for I2cController<T, C>
+where
+    T: I2cTransfer,
+    C: Clock,
+{
+    /// The bytes read.
+    type Response = Result<TransferBytes, DeviceError>;
+
+    fn on_request(mut self, message: I2cWriteReadOwned) -> Response<Self, Self::Response> {
+        Response::defer(async move {
+            let mut read = TransferBytes::new();
+            let result = match read.resize_default(message.read_len) {
+                Ok(()) => self
+                    .transfer(message.address, &message.bytes, &mut read)
+                    .await
+                    .map(|_| read)
+                    .map_err(DeviceError::from),
+                Err(_) => Err(DeviceError::ResourceExhausted),
+            };
+            (self, result)
+        })
+    }
+}
+
+/// As on the address of an `I2cPeripheral`, the bytes are copied into the request.
+impl<T, C> I2cBus for Address<I2cController<T, C>>
+where
+    T: I2cTransfer,
+    C: Clock,
+{
+    type Error = DeviceError;
+
+    async fn write(&mut self, address: I2cAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.request(I2cWriteOwned::new(address, bytes)?).await
+    }
+}
+
+/// As on the address of an `I2cPeripheral`, the bytes are copied into the request, and
+/// those read out of the response into `buffer`.
+impl<T, C> I2cReadBus for Address<I2cController<T, C>>
+where
+    T: I2cTransfer,
+    C: Clock,
+{
+    async fn write_read(
+        &mut self,
+        address: I2cAddress,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let read = self
+            .request(I2cWriteReadOwned::new(address, bytes, buffer.len())?)
+            .await?;
+        buffer.copy_from_slice(&read);
+        Ok(())
+    }
+}
+
+/// Wait on `transaction` for up to `timeout`, returning `None` if it expires first.
+async fn within<C, F>(clock: C, timeout: Milliseconds, transaction: F) -> Option<F::Output>
+where
+    C: Clock,
+    F: Future,
+{
+    let mut transaction = pin!(transaction);
+    let mut expired = pin!(clock.delay(timeout));
+    poll_fn(|cx| {
+        if let Poll::Ready(result) = transaction.as_mut().poll(cx) {
+            Poll::Ready(Some(result))
+        } else if expired.as_mut().poll(cx).is_ready() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::timer::MockClock;
+    use crate::testing::{block_on, block_on_advancing as run};
+    use std::boxed::Box;
+
+    /// Completes writes after a poll, and stretches SCL forever on reads.
+    #[derive(Default)]
+    struct MockI2c {
+        written: usize,
+        reading: bool,
+        polled: bool,
+        cleared: usize,
+    }
+
+    impl I2cTransfer for MockI2c {
+        type Error = ();
+
+        fn start_transfer(
+            &mut self,
+            _: I2cAddress,
+            bytes: &[u8],
+            read_len: usize,
+        ) -> Result<(), ()> {
+            if bytes.is_empty() {
+                return Err(());
+            }
+            self.written += bytes.len();
+            self.reading = read_len > 0;
+            self.polled = false;
+            Ok(())
+        }
+
+        fn finish_transfer(&mut self, _: &mut [u8]) -> nb::Result<(), ()> {
+            if self.reading || !core::mem::replace(&mut self.polled, true) {
+                Err(nb::Error::WouldBlock)
+            } else {
+                Ok(())
+            }
+        }
+
+        fn bus_clear(&mut self) {
+            self.reading = false;
+            self.cleared += 1;
+        }
+    }
+
+    /// Fails every transfer.
+    struct Nack;
+
+    impl Write for Nack {
+        type Error = ();
+
+        fn write(&mut self, _: u8, _: &[u8]) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    impl WriteRead for Nack {
+        type Error = ();
+
+        fn write_read(&mut self, _: u8, _: &[u8], _: &mut [u8]) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    /// A device of four registers, the first written selecting the register.
+    #[derive(Default)]
+    struct Registers([u8; 4]);
+
+    impl Write for Registers {
+        type Error = ();
+
+        fn write(&mut self, _: u8, bytes: &[u8]) -> Result<(), ()> {
+            match bytes {
+                [register, value] => *self.0.get_mut(*register as usize).ok_or(())? = *value,
+                _ => return Err(()),
+            }
+            Ok(())
+        }
+    }
+
+    impl WriteRead for Registers {
+        type Error = ();
+
+        fn write_read(&mut self, _: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            buffer[0] = *self.0.get(bytes[0] as usize).ok_or(())?;
+            Ok(())
+        }
+    }
+
+    /// Records each transaction, answering reads from the register addressed on.
+    #[derive(Default)]
+    struct Recorder {
+        transactions: std::vec::Vec<(u8, std::vec::Vec<u8>, usize)>,
+    }
+
+    impl Write for Recorder {
+        type Error = ();
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+            self.transactions.push((address, bytes.to_vec(), 0));
+            Ok(())
+        }
+    }
+
+    impl WriteRead for Recorder {
+        type Error = ();
+
+        fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            self.transactions
+                .push((address, bytes.to_vec(), buffer.len()));
+            for (offset, byte) in buffer.iter_mut().enumerate() {
+                *byte = bytes[0] + offset as u8;
+            }
+            Ok(())
+        }
+    }
+
+    /// A driver written against `embedded-hal`, as found in the wider ecosystem.
+    fn set_and_check<B, E>(i2c: &mut B, register: u8, value: u8) -> Result<bool, E>
+    where
+        B: Write<Error = E> + WriteRead<Error = E>,
+    {
+        i2c.write(0x5f, &[register, value])?;
+        let mut read = [0];
+        i2c.write_read(0x5f, &[register], &mut read)?;
+        Ok(read[0] == value)
+    }
+
+    #[test]
+    fn test_timeout() {
+        let clock: &'static MockClock<()> = Box::leak(Box::new(MockClock::new()));
+        let mut controller =
+            I2cController::new(MockI2c::default(), clock, Milliseconds(25u32));
+        let address = I2cAddress::new(0x5f);
+
+        assert_eq!(run(clock, controller.transfer(address, &[1, 2], &mut [])), Ok(()));
+        let result = run(clock, controller.transfer(address, &[], &mut []));
+        assert_eq!(result, Err(I2cTimeout::Bus(())));
+        assert_eq!(controller.i2c.written, 2);
+        assert_eq!(controller.i2c.cleared, 0);
+        let now = clock.now();
+        assert!(now < Milliseconds(25u32));
+
+        let mut buffer = [0; 2];
+        let result = run(clock, controller.transfer(address, &[0x28], &mut buffer));
+        assert_eq!(result, Err(I2cTimeout::TimedOut));
+        assert_eq!(clock.now(), Milliseconds(now.0 + 25));
+        assert_eq!(controller.i2c.cleared, 1);
+
+        // the cleared bus carries on
+        assert_eq!(run(clock, controller.transfer(address, &[3], &mut [])), Ok(()));
+        assert_eq!(controller.i2c.written, 4);
+        assert_eq!(controller.i2c.cleared, 1);
+    }
+
+    #[test]
+    fn test_bus_write() {
+        let shared: &'static Shared<Recorder> =
+            Box::leak(Box::new(Shared::new(Recorder::default())));
+        let mut peripheral = I2cPeripheral::new();
+        peripheral.configure(shared);
+        let address = I2cAddress::new(0x3c);
+
+        // the actor writes the bytes copied into the request
+        let bytes = TransferBytes::from_slice(&[0x40, 1, 2]).unwrap();
+        match peripheral.on_request(I2cWriteOwned { address, bytes }) {
+            Response::Immediate(_, result) => assert_eq!(result, Ok(())),
+            _ => panic!("deferred"),
+        }
+        assert_eq!(
+            shared.i2c.borrow().transactions,
+            [(0x3c, std::vec![0x40, 1, 2], 0)]
+        );
+
+        // more than a request holds is refused before reaching the actor
+        let context: &'static ActorContext<I2cPeripheral<Recorder>> =
+            Box::leak(Box::new(ActorContext::new(I2cPeripheral::new())));
+        let mut bus = Address::new(context);
+        let result = block_on(I2cBus::write(&mut bus, address, &[0; 65]));
+        assert_eq!(result, Err(DeviceError::ResourceExhausted));
+        assert_eq!(context.pending(), 0);
+    }
+
+    #[test]
+    fn test_bus_write_read() {
+        let shared: &'static Shared<Recorder> =
+            Box::leak(Box::new(Shared::new(Recorder::default())));
+        let mut peripheral = I2cPeripheral::new();
+        peripheral.configure(shared);
+        let address = I2cAddress::new(0x5f);
+
+        // the bytes read are handed back in the response
+        let bytes = TransferBytes::from_slice(&[0x28]).unwrap();
+        let peripheral = match peripheral.on_request(I2cWriteReadOwned {
+            address,
+            bytes: bytes.clone(),
+            read_len: 3,
+        }) {
+            Response::Immediate(peripheral, result) => {
+                assert_eq!(&result.unwrap()[..], [0x28, 0x29, 0x2a]);
+                peripheral
+            }
+            _ => panic!("deferred"),
+        };
+        match peripheral.on_request(I2cWriteReadOwned {
+            address,
+            bytes,
+            read_len: 65,
+        }) {
+            Response::Immediate(_, result) => {
+                assert_eq!(result, Err(DeviceError::ResourceExhausted))
+            }
+            _ => panic!("deferred"),
+        }
+        assert_eq!(
+            shared.i2c.borrow().transactions,
+            [(0x5f, std::vec![0x28], 3)]
+        );
+
+        // a buffer larger than a response holds is refused before reaching the actor
+        let context: &'static ActorContext<I2cPeripheral<Recorder>> =
+            Box::leak(Box::new(ActorContext::new(I2cPeripheral::new())));
+        let mut bus = Address::new(context);
+        let mut buffer = [0; 65];
+        let write_read = I2cReadBus::write_read(&mut bus, address, &[0x28], &mut buffer);
+        let result = block_on(write_read);
+        assert_eq!(result, Err(DeviceError::ResourceExhausted));
+        assert_eq!(context.pending(), 0);
+    }
+
+    #[test]
+    fn test_device_error() {
+        let address = I2cAddress::new(0x5f);
+        let mut peripheral = match I2cPeripheral::<Nack>::new().on_request(I2cWrite {
+            address,
+            buffer: &[0x20, 0x80],
+        }) {
+            Response::Immediate(peripheral, result) => {
+                assert_eq!(result, Err(DeviceError::NotBound));
+                peripheral
+            }
+            _ => panic!("deferred"),
+        };
+        peripheral.configure(Box::leak(Box::new(Shared::new(Nack))));
+        let peripheral = match peripheral.on_request(I2cWrite {
+            address,
+            buffer: &[0x20, 0x80],
+        }) {
+            Response::Immediate(peripheral, result) => {
+                assert_eq!(result, Err(DeviceError::BusError));
+                peripheral
+            }
+            _ => panic!("deferred"),
+        };
+        let mut buffer = [0; 1];
+        match peripheral.on_request(I2cWriteRead {
+            address,
+            bytes: &[0x0f],
+            buffer: &mut buffer,
+        }) {
+            Response::Immediate(_, result) => assert_eq!(result, Err(DeviceError::BusError)),
+            _ => panic!("deferred"),
+        }
+
+        // as reported by an `I2cController`
+        assert_eq!(
+            DeviceError::from(I2cTimeout::Bus(())),
+            DeviceError::BusError
+        );
+        assert_eq!(
+            DeviceError::from(I2cTimeout::<()>::TimedOut),
+            DeviceError::Timeout
+        );
+    }
+
+    #[test]
+    fn test_shared_bus() {
+        let i2c: &'static I2c<Registers> = Box::leak(Box::new(I2c::new(Registers::default())));
+        let mut bus = i2c.bus();
+        assert_eq!(set_and_check(&mut bus, 2, 0x80), Ok(true));
+        assert_eq!(i2c.shared.i2c.borrow().0, [0, 0, 0x80, 0]);
+
+        // errors of the device are those of the bus
+        assert_eq!(set_and_check(&mut bus, 7, 0x80), Err(DeviceError::BusError));
+
+        // the bus taken by a transfer under way
+        i2c.shared.locked.store(true, Ordering::Release);
+        assert_eq!(bus.write(0x5f, &[1, 0x40]), Err(DeviceError::Busy));
+        i2c.shared.locked.store(false, Ordering::Release);
+        assert_eq!(set_and_check(&mut i2c.bus(), 1, 0x40), Ok(true));
+    }
+
+    #[test]
+    fn test_command() {
+        let shared: &'static Shared<Recorder> =
+            Box::leak(Box::new(Shared::new(Recorder::default())));
+        let mut peripheral = I2cPeripheral::new();
+        peripheral.configure(shared);
+        let addr = I2cAddress::new(0x5f);
+        let bytes = |bytes: &[u8]| RegisterBytes::from_slice(bytes).unwrap();
+
+        let read = peripheral.command(I2cCommand::WriteRead {
+            addr,
+            reg: 0x28,
+            write: bytes(&[0x01]),
+            read_len: 3,
+        });
+        assert_eq!(read, Ok(bytes(&[0x28, 0x29, 0x2a])));
+        let written = peripheral.command(I2cCommand::Write {
+            addr,
+            reg: 0x20,
+            write: bytes(&[0x80, 0x01]),
+        });
+        assert_eq!(written, Ok(bytes(&[])));
+        assert_eq!(
+            shared.i2c.borrow().transactions,
+            [
+                (0x5f, std::vec![0x28, 0x01], 3),
+                (0x5f, std::vec![0x20, 0x80, 0x01], 0)
+            ]
+        );
+
+        // more than a response holds is refused before reaching the bus
+        let read = peripheral.command(I2cCommand::WriteRead {
+            addr,
+            reg: 0x28,
+            write: bytes(&[]),
+            read_len: 33,
+        });
+        assert_eq!(read, Err(DeviceError::ResourceExhausted));
+        assert_eq!(shared.i2c.borrow().transactions.len(), 2);
+    }
+}
@@ -0,0 +1,179 @@
+//! The TCA9548A I2C multiplexer, fanning one bus out to eight channels.
+//!
+//! Devices sharing an address, such as several sensors of the same kind, can sit on the
+//! same bus behind a multiplexer, each on its own channel. Every channel of a
+//! `Tca9548a` is an `I2c` package of its own, mounted like the bus itself, so sensor
+//! drivers bind to the `I2cPeripheral` of a channel just as to that of a bus.
+//!
+//! Each transfer on a channel selects it on the multiplexer first, with the underlying
+//! bus taken across both, so transfers on different channels never interleave.
+
+use crate::driver::i2c::{I2c, Shared};
+use crate::error::DeviceError;
+use crate::hal::i2c::I2cAddress;
+use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
+
+/// The number of channels of a TCA9548A.
+pub const CHANNELS: usize = 8;
+
+/// The address of a TCA9548A with its address pins tied low.
+pub const ADDR: u8 = 0x70;
+
+/// One channel of a `Tca9548a`, as a blocking bus selecting the channel before each
+/// transfer on the underlying bus.
+pub struct Channel<I: 'static> {
+    bus: &'static Shared<I>,
+    mux: I2cAddress,
+    channel: u8,
+}
+
+impl<I> Channel<I>
+where
+    I: Write,
+{
+    /// Run `transfer` on the underlying bus once the channel is selected, the bus taken
+    /// across both.
+    fn transfer<F>(&self, transfer: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&mut I) -> Result<(), ()>,
+    {
+        self.bus.transfer(|i2c| {
+            i2c.write(self.mux.into(), &[1 << self.channel])
+                .map_err(|_| ())?;
+            transfer(i2c)
+        })
+    }
+}
+
+impl<I: Read + Write> Read for Channel<I> {
+    type Error = DeviceError;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), DeviceError> {
+        self.transfer(|i2c| i2c.read(address, buffer).map_err(|_| ()))
+    }
+}
+
+impl<I: Write> Write for Channel<I> {
+    type Error = DeviceError;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), DeviceError> {
+        self.transfer(|i2c| i2c.write(address, bytes).map_err(|_| ()))
+    }
+}
+
+impl<I: WriteRead + Write> WriteRead for Channel<I> {
+    type Error = DeviceError;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), DeviceError> {
+        self.transfer(|i2c| i2c.write_read(address, bytes, buffer).map_err(|_| ()))
+    }
+}
+
+/// A TCA9548A multiplexer on the bus of an `I2c` package.
+pub struct Tca9548a<I: 'static> {
+    channels: [I2c<Channel<I>>; CHANNELS],
+}
+
+impl<I> Tca9548a<I> {
+    /// The multiplexer at `address` on the bus of `i2c`.
+    pub fn new(i2c: &'static I2c<I>, address: I2cAddress) -> Self {
+        Self {
+            channels: core::array::from_fn(|channel| {
+                I2c::new(Channel {
+                    bus: &i2c.shared,
+                    mux: address,
+                    channel: channel as u8,
+                })
+            }),
+        }
+    }
+
+    /// The package of the bus behind `channel`, to be mounted for the drivers of the
+    /// devices on it to bind to.
+    ///
+    /// # Panics
+    ///
+    /// If `channel` is not below `CHANNELS`.
+    pub fn channel(&self, channel: usize) -> &I2c<Channel<I>> {
+        &self.channels[channel]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::Ordering;
+    use std::boxed::Box;
+    use std::vec::Vec;
+
+    /// Records each transaction as its address, the bytes written, and the bytes read.
+    #[derive(Default)]
+    struct Recorder {
+        transactions: Vec<(u8, Vec<u8>, usize)>,
+    }
+
+    impl Read for Recorder {
+        type Error = ();
+
+        fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), ()> {
+            self.transactions.push((address, Vec::new(), buffer.len()));
+            Ok(())
+        }
+    }
+
+    impl Write for Recorder {
+        type Error = ();
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+            self.transactions.push((address, bytes.to_vec(), 0));
+            Ok(())
+        }
+    }
+
+    impl WriteRead for Recorder {
+        type Error = ();
+
+        fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            self.transactions
+                .push((address, bytes.to_vec(), buffer.len()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_select_channel() {
+        let i2c: &'static I2c<Recorder> = Box::leak(Box::new(I2c::new(Recorder::default())));
+        let mux: &'static Tca9548a<Recorder> =
+            Box::leak(Box::new(Tca9548a::new(i2c, I2cAddress::new(ADDR))));
+
+        let mut first = mux.channel(0).bus();
+        let mut fourth = mux.channel(3).bus();
+        fourth.write(0x5f, &[0x20, 0x80]).unwrap();
+        first.write_read(0x5f, &[0x28], &mut [0; 2]).unwrap();
+        fourth.read(0x5f, &mut [0; 1]).unwrap();
+        assert_eq!(
+            i2c.shared.i2c.borrow().transactions,
+            [
+                (ADDR, std::vec![0b1000], 0),
+                (0x5f, std::vec![0x20, 0x80], 0),
+                (ADDR, std::vec![0b0001], 0),
+                (0x5f, std::vec![0x28], 2),
+                (ADDR, std::vec![0b1000], 0),
+                (0x5f, std::vec![], 1),
+            ]
+        );
+
+        // neither selected nor forwarded while the bus is taken
+        i2c.shared.locked.store(true, Ordering::Release);
+        assert_eq!(fourth.write(0x5f, &[0x21]), Err(DeviceError::Busy));
+        i2c.shared.locked.store(false, Ordering::Release);
+        assert_eq!(i2c.shared.i2c.borrow().transactions.len(), 6);
+    }
+}
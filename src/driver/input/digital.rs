@@ -0,0 +1,279 @@
+//! Binary sensors on a GPIO interrupt line, such as buttons, reed switches, limit
+//! switches and motion sensors.
+//!
+//! A `DigitalInput` publishes an event of the application's choosing as its pin is
+//! asserted, and another as it is deasserted, so each kind of sensor shares the
+//! interrupt plumbing while keeping events of its own. A `Button` is a `DigitalInput`
+//! publishing `ButtonEvent`s.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::hal::gpio::exti_pin::ExtiPin;
+use crate::hal::{Active, Edge};
+use crate::handler::EventHandler;
+use crate::prelude::*;
+use embedded_hal::digital::v2::InputPin;
+
+/// The assertions of an input since start.
+///
+/// The fields keep the names they had as the statistics of a `Button`, where each
+/// assertion is a press.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct InputStats {
+    /// Counts the assertions, wrapping around.
+    pub presses: u32,
+    /// The uptime at the last assertion, if asserted and stamped.
+    pub last_press: Option<Milliseconds>,
+}
+
+pub struct DigitalInput<D: Device + 'static, PIN, E: Copy + 'static> {
+    pin: PIN,
+    active: Active,
+    edge: Edge,
+    asserted: E,
+    deasserted: E,
+    bus: Option<Address<EventBus<D>>>,
+    uptime: Option<fn() -> Milliseconds>,
+    stats: InputStats,
+}
+
+impl<D, PIN, E> Actor for DigitalInput<D, PIN, E>
+where
+    D: Device,
+    PIN: InputPin + ExtiPin,
+    E: Copy,
+{
+}
+
+impl<D, PIN, E> Bind<EventBus<D>> for DigitalInput<D, PIN, E>
+where
+    D: Device,
+    E: Copy,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, PIN, E> DigitalInput<D, PIN, E>
+where
+    D: Device,
+    PIN: InputPin + ExtiPin,
+    E: Copy,
+{
+    /// Create an input wired `active` high or low, publishing `asserted` or `deasserted`
+    /// for each `edge` of the pin.
+    ///
+    /// Edges are filtered in software, so the pin's interrupt should also be configured
+    /// to trigger on `edge` alone, sparing the interrupts that would be ignored.
+    pub fn with_events(pin: PIN, active: Active, edge: Edge, asserted: E, deasserted: E) -> Self {
+        Self {
+            pin,
+            active,
+            edge,
+            asserted,
+            deasserted,
+            bus: None,
+            uptime: None,
+            stats: InputStats::default(),
+        }
+    }
+
+    /// Stamp assertions with the uptime given by `uptime`, to report the last in
+    /// `InputStats`.
+    pub fn with_uptime(mut self, uptime: fn() -> Milliseconds) -> Self {
+        self.uptime.replace(uptime);
+        self
+    }
+}
+
+impl<D, PIN, E> DigitalInput<D, PIN, E>
+where
+    D: Device,
+    E: Copy,
+{
+    /// The event for the pin settling `high` (or low), counted into the statistics, if
+    /// the edge is reacted to.
+    fn on_edge(&mut self, high: bool) -> Option<E> {
+        let asserted = asserted(&self.active, self.edge, high)?;
+        if asserted {
+            self.stats.presses = self.stats.presses.wrapping_add(1);
+            self.stats.last_press = self.uptime.map(|uptime| uptime());
+            Some(self.asserted)
+        } else {
+            Some(self.deasserted)
+        }
+    }
+}
+
+/// Whether the pin settling `high` (or low) asserts the input, if `edge` is reacted to.
+pub(crate) fn asserted(active: &Active, edge: Edge, high: bool) -> Option<bool> {
+    let reacts = match edge {
+        Edge::Rising => high,
+        Edge::Falling => !high,
+        Edge::Both => true,
+    };
+    if !reacts {
+        return None;
+    }
+    Some(match active {
+        Active::High => high,
+        Active::Low => !high,
+    })
+}
+
+impl<D, PIN, E> Interrupt for DigitalInput<D, PIN, E>
+where
+    D: Device + EventHandler<E> + 'static,
+    PIN: InputPin + ExtiPin,
+    E: Copy,
+{
+    fn on_interrupt(&mut self) {
+        if self.pin.check_interrupt() {
+            let high = self.pin.is_high().ok().unwrap();
+            if let Some(event) = self.on_edge(high) {
+                match self.bus {
+                    Some(bus) => bus.publish(event),
+                    None => warn!("[digital] no event bus bound"),
+                }
+            }
+            self.pin.clear_interrupt_pending_bit();
+        }
+    }
+}
+
+/// Request for the `InputStats` of an input.
+#[derive(Copy, Clone, Debug)]
+pub struct Stats;
+
+impl<D, PIN, E> RequestHandler<Stats> for DigitalInput<D, PIN, E>
+where
+    D: Device + 'static,
+    PIN: InputPin + ExtiPin,
+    E: Copy,
+{
+    type Response = InputStats;
+
+    fn on_request(self, _: Stats) -> Response<Self, Self::Response> {
+        let stats = self.stats;
+        Response::immediate(self, stats)
+    }
+}
+
+impl<D, PIN, E> Address<DigitalInput<D, PIN, E>>
+where
+    D: Device + 'static,
+    PIN: InputPin + ExtiPin,
+    E: Copy,
+{
+    pub async fn stats(&self) -> InputStats {
+        self.request(Stats).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    struct MockPin;
+
+    impl InputPin for MockPin {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(true)
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(false)
+        }
+    }
+
+    impl ExtiPin for MockPin {
+        fn check_interrupt(&mut self) -> bool {
+            true
+        }
+
+        fn clear_interrupt_pending_bit(&mut self) {}
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Door {
+        Opened,
+        Closed,
+    }
+
+    type ReedSwitch = DigitalInput<MockDevice, MockPin, Door>;
+
+    fn edges(mut input: ReedSwitch) -> [Option<Door>; 4] {
+        let mut events = [None; 4];
+        for (i, high) in [true, false, true, false].iter().enumerate() {
+            events[i] = input.on_edge(*high);
+        }
+        events
+    }
+
+    #[test]
+    fn test_events() {
+        use Door::*;
+        let switch = ReedSwitch::with_events(MockPin, Active::High, Edge::Both, Opened, Closed);
+        assert_eq!(
+            edges(switch),
+            [Some(Opened), Some(Closed), Some(Opened), Some(Closed)]
+        );
+        let switch = ReedSwitch::with_events(MockPin, Active::Low, Edge::Rising, Closed, Opened);
+        assert_eq!(edges(switch), [Some(Opened), None, Some(Opened), None]);
+        let switch = ReedSwitch::with_events(MockPin, Active::Low, Edge::Falling, Closed, Opened);
+        assert_eq!(edges(switch), [None, Some(Closed), None, Some(Closed)]);
+    }
+
+    static UPTIME: AtomicU32 = AtomicU32::new(0);
+
+    fn uptime() -> Milliseconds {
+        Milliseconds(UPTIME.load(Ordering::SeqCst))
+    }
+
+    fn stats(input: ReedSwitch) -> (ReedSwitch, InputStats) {
+        match input.on_request(Stats) {
+            Response::Immediate(input, stats) => (input, stats),
+            _ => panic!("deferred"),
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let input = ReedSwitch::with_events(
+            MockPin,
+            Active::High,
+            Edge::Both,
+            Door::Opened,
+            Door::Closed,
+        )
+        .with_uptime(uptime);
+        let (mut input, initial) = stats(input);
+        assert_eq!(initial, InputStats::default());
+
+        let mut last = None;
+        for (presses, at) in [(1, 100), (2, 250), (3, 900)] {
+            UPTIME.store(at, Ordering::SeqCst);
+            assert_eq!(input.on_edge(true), Some(Door::Opened));
+            UPTIME.store(at + 50, Ordering::SeqCst);
+            assert_eq!(input.on_edge(false), Some(Door::Closed));
+
+            let (next, stats) = stats(input);
+            input = next;
+            assert_eq!(stats.presses, presses);
+            assert_eq!(stats.last_press, Some(Milliseconds(at)));
+            assert!(stats.last_press > last);
+            last = stats.last_press;
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Matrix keypad, scanned on a timer.
+//!
+//! Rows are outputs and columns are inputs with pull-ups. Each scan drives one row low
+//! at a time and reads a key as pressed when its column reads low. A key changes state
+//! only after reading the same way for `debounce` consecutive scans, and each key is
+//! tracked on its own so any number may be held at once. Holding three or more keys
+//! spanning two rows and two columns will also read the fourth corner as pressed unless
+//! the keypad has a diode per key.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::TimerActor;
+use crate::hal::timer::Timer as HalTimer;
+use crate::handler::EventHandler;
+use crate::prelude::*;
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum KeyEvent {
+    /// The key at `(row, column)` was pressed.
+    Down(usize, usize),
+    /// The key at `(row, column)` was released.
+    Up(usize, usize),
+}
+
+#[derive(Copy, Clone, Default)]
+struct Key {
+    pressed: bool,
+    count: u8,
+}
+
+/// Debounces the raw readings of an `R` by `C` key matrix.
+pub struct KeyState<const R: usize, const C: usize> {
+    keys: [[Key; C]; R],
+    debounce: u8,
+}
+
+impl<const R: usize, const C: usize> KeyState<R, C> {
+    /// Track keys that change state after `debounce` consecutive differing readings.
+    pub fn new(debounce: u8) -> Self {
+        Self {
+            keys: [[Key::default(); C]; R],
+            debounce: debounce.max(1),
+        }
+    }
+
+    /// Feed one raw reading of the key at `(row, col)`.
+    pub fn update(&mut self, row: usize, col: usize, pressed: bool) -> Option<KeyEvent> {
+        let key = &mut self.keys[row][col];
+        if pressed == key.pressed {
+            key.count = 0;
+            return None;
+        }
+        key.count += 1;
+        if key.count < self.debounce {
+            return None;
+        }
+        key.pressed = pressed;
+        key.count = 0;
+        Some(if pressed {
+            KeyEvent::Down(row, col)
+        } else {
+            KeyEvent::Up(row, col)
+        })
+    }
+
+    /// Whether the key at `(row, col)` is currently held, after debouncing.
+    pub fn is_pressed(&self, row: usize, col: usize) -> bool {
+        self.keys[row][col].pressed
+    }
+}
+
+/// The pins of a key matrix together with the state of its keys.
+pub struct Matrix<ROW, COL, const R: usize, const C: usize>
+where
+    ROW: OutputPin,
+    COL: InputPin,
+{
+    rows: [ROW; R],
+    cols: [COL; C],
+    state: KeyState<R, C>,
+}
+
+impl<ROW, COL, const R: usize, const C: usize> Matrix<ROW, COL, R, C>
+where
+    ROW: OutputPin,
+    COL: InputPin,
+{
+    pub fn new(rows: [ROW; R], cols: [COL; C], debounce: u8) -> Self {
+        let mut matrix = Self {
+            rows,
+            cols,
+            state: KeyState::new(debounce),
+        };
+        for row in matrix.rows.iter_mut() {
+            row.set_high().ok();
+        }
+        matrix
+    }
+
+    /// Read every key once, passing each debounced change to `emit`.
+    pub fn scan<F: FnMut(KeyEvent)>(&mut self, mut emit: F) {
+        for (r, row) in self.rows.iter_mut().enumerate() {
+            row.set_low().ok();
+            for (c, col) in self.cols.iter().enumerate() {
+                let pressed = col.is_low().unwrap_or(false);
+                if let Some(event) = self.state.update(r, c, pressed) {
+                    emit(event);
+                }
+            }
+            row.set_high().ok();
+        }
+    }
+
+    pub fn is_pressed(&self, row: usize, col: usize) -> bool {
+        self.state.is_pressed(row, col)
+    }
+}
+
+/// A keypad publishing a `KeyEvent` for each debounced key press and release.
+pub struct Keypad<D, T, ROW, COL, const R: usize, const C: usize>
+where
+    D: Device + EventHandler<KeyEvent> + 'static,
+    T: HalTimer + 'static,
+    ROW: OutputPin + 'static,
+    COL: InputPin + 'static,
+{
+    matrix: Matrix<ROW, COL, R, C>,
+    period: Milliseconds,
+    bus: Option<Address<EventBus<D>>>,
+    timer: Option<Address<TimerActor<T>>>,
+    address: Option<Address<Self>>,
+}
+
+impl<D, T, ROW, COL, const R: usize, const C: usize> Keypad<D, T, ROW, COL, R, C>
+where
+    D: Device + EventHandler<KeyEvent>,
+    T: HalTimer,
+    ROW: OutputPin,
+    COL: InputPin,
+{
+    /// Create a keypad scanned every `period`, with keys changing state after
+    /// `debounce` consecutive scans reading the same way.
+    pub fn new(rows: [ROW; R], cols: [COL; C], period: Milliseconds, debounce: u8) -> Self {
+        Self {
+            matrix: Matrix::new(rows, cols, debounce),
+            period,
+            bus: None,
+            timer: None,
+            address: None,
+        }
+    }
+
+    fn schedule_scan(&self) {
+        if let (Some(timer), Some(address)) = (self.timer, self.address) {
+            timer.schedule(self.period, Scan, address);
+        }
+    }
+}
+
+impl<D, T, ROW, COL, const R: usize, const C: usize> Actor for Keypad<D, T, ROW, COL, R, C>
+where
+    D: Device + EventHandler<KeyEvent>,
+    T: HalTimer,
+    ROW: OutputPin,
+    COL: InputPin,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_start(self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.schedule_scan();
+        Completion::immediate(self)
+    }
+}
+
+impl<D, T, ROW, COL, const R: usize, const C: usize> Bind<EventBus<D>>
+    for Keypad<D, T, ROW, COL, R, C>
+where
+    D: Device + EventHandler<KeyEvent>,
+    T: HalTimer,
+    ROW: OutputPin,
+    COL: InputPin,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, T, ROW, COL, const R: usize, const C: usize> Bind<TimerActor<T>>
+    for Keypad<D, T, ROW, COL, R, C>
+where
+    D: Device + EventHandler<KeyEvent>,
+    T: HalTimer,
+    ROW: OutputPin,
+    COL: InputPin,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.timer.replace(address);
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Scan;
+
+impl<D, T, ROW, COL, const R: usize, const C: usize> NotifyHandler<Scan>
+    for Keypad<D, T, ROW, COL, R, C>
+where
+    D: Device + EventHandler<KeyEvent>,
+    T: HalTimer,
+    ROW: OutputPin,
+    COL: InputPin,
+{
+    fn on_notify(mut self, _: Scan) -> Completion<Self> {
+        let bus = self.bus;
+        self.matrix.scan(|event| {
+            if let Some(bus) = bus {
+                bus.publish(event);
+            }
+        });
+        self.schedule_scan();
+        Completion::immediate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    #[derive(Default)]
+    struct Wiring {
+        pressed: Cell<[[bool; 2]; 2]>,
+        driven: Cell<Option<usize>>,
+    }
+
+    struct MockRow<'a>(usize, &'a Wiring);
+
+    impl OutputPin for MockRow<'_> {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.1.driven.set(Some(self.0));
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            if self.1.driven.get() == Some(self.0) {
+                self.1.driven.set(None);
+            }
+            Ok(())
+        }
+    }
+
+    struct MockCol<'a>(usize, &'a Wiring);
+
+    impl InputPin for MockCol<'_> {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            self.is_low().map(|low| !low)
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(match self.1.driven.get() {
+                Some(row) => self.1.pressed.get()[row][self.0],
+                None => false,
+            })
+        }
+    }
+
+    fn scan(matrix: &mut Matrix<MockRow, MockCol, 2, 2>) -> Option<KeyEvent> {
+        let mut events = None;
+        matrix.scan(|event| {
+            assert!(events.is_none(), "more than one event per scan");
+            events.replace(event);
+        });
+        events
+    }
+
+    #[test]
+    fn test_press_and_release() {
+        let wiring = Wiring::default();
+        let mut matrix = Matrix::new(
+            [MockRow(0, &wiring), MockRow(1, &wiring)],
+            [MockCol(0, &wiring), MockCol(1, &wiring)],
+            2,
+        );
+
+        wiring.pressed.set([[false, false], [false, true]]);
+        assert_eq!(scan(&mut matrix), None);
+        assert_eq!(scan(&mut matrix), Some(KeyEvent::Down(1, 1)));
+        assert_eq!(scan(&mut matrix), None);
+        assert!(matrix.is_pressed(1, 1));
+
+        wiring.pressed.set([[false, false], [false, false]]);
+        assert_eq!(scan(&mut matrix), None);
+        assert_eq!(scan(&mut matrix), Some(KeyEvent::Up(1, 1)));
+        assert_eq!(scan(&mut matrix), None);
+        assert!(!matrix.is_pressed(1, 1));
+    }
+
+    #[test]
+    fn test_bounce_ignored() {
+        let wiring = Wiring::default();
+        let mut matrix = Matrix::new(
+            [MockRow(0, &wiring), MockRow(1, &wiring)],
+            [MockCol(0, &wiring), MockCol(1, &wiring)],
+            2,
+        );
+
+        for _ in 0..3 {
+            wiring.pressed.set([[true, false], [false, false]]);
+            assert_eq!(scan(&mut matrix), None);
+            wiring.pressed.set([[false, false], [false, false]]);
+            assert_eq!(scan(&mut matrix), None);
+        }
+        assert!(!matrix.is_pressed(0, 0));
+    }
+
+    #[test]
+    fn test_multiple_held() {
+        let mut state = KeyState::<2, 2>::new(1);
+        assert_eq!(state.update(0, 0, true), Some(KeyEvent::Down(0, 0)));
+        assert_eq!(state.update(1, 1, true), Some(KeyEvent::Down(1, 1)));
+        assert_eq!(state.update(0, 0, true), None);
+        assert_eq!(state.update(1, 1, false), Some(KeyEvent::Up(1, 1)));
+        assert!(state.is_pressed(0, 0));
+    }
+}
@@ -0,0 +1,9 @@
+//! Human input devices, and binary sensors.
+
+pub mod digital;
+pub mod keypad;
+pub mod rotary;
+
+pub use digital::{DigitalInput, InputStats};
+pub use keypad::{KeyEvent, Keypad};
+pub use rotary::{EncoderEvent, RotaryEncoder};
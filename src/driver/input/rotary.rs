@@ -0,0 +1,384 @@
+//! Quadrature rotary encoder on two GPIO interrupt lines.
+//!
+//! Each line's pin raises an interrupt on both edges. Transitions are decoded with a
+//! full-step state machine that only reports a detent once the contacts have passed
+//! through every intermediate state and returned to rest, so contact bounce (a line
+//! toggling back and forth) never produces an event.
+
+use crate::bind::Bind;
+use crate::hal::gpio::exti_pin::ExtiPin;
+use crate::hal::Active;
+use crate::handler::EventHandler;
+use crate::prelude::*;
+use cortex_m::interrupt::Nr;
+use embedded_hal::digital::v2::InputPin;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EncoderEvent {
+    /// One detent clockwise: line B left rest before line A.
+    Clockwise,
+    /// One detent counter-clockwise: line A left rest before line B.
+    CounterClockwise,
+}
+
+const START: u8 = 0;
+const CW_FINAL: u8 = 1;
+const CW_BEGIN: u8 = 2;
+const CW_NEXT: u8 = 3;
+const CCW_BEGIN: u8 = 4;
+const CCW_FINAL: u8 = 5;
+const CCW_NEXT: u8 = 6;
+const EMIT_CW: u8 = 0x10;
+const EMIT_CCW: u8 = 0x20;
+
+/// Next state, indexed by current state and `(b << 1) | a`, where a line reads `1`
+/// when its contact is open (at rest).
+#[rustfmt::skip]
+const TRANSITIONS: [[u8; 4]; 7] = [
+    // START
+    [START, CW_BEGIN, CCW_BEGIN, START],
+    // CW_FINAL
+    [CW_NEXT, START, CW_FINAL, START | EMIT_CW],
+    // CW_BEGIN
+    [CW_NEXT, CW_BEGIN, START, START],
+    // CW_NEXT
+    [CW_NEXT, CW_BEGIN, CW_FINAL, START],
+    // CCW_BEGIN
+    [CCW_NEXT, START, CCW_BEGIN, START],
+    // CCW_FINAL
+    [CCW_NEXT, CCW_FINAL, START, START | EMIT_CCW],
+    // CCW_NEXT
+    [CCW_NEXT, CCW_FINAL, CCW_BEGIN, START],
+];
+
+/// Decodes line levels into detents and tracks the accumulated position.
+#[derive(Default)]
+pub struct QuadratureDecoder {
+    state: u8,
+    position: i32,
+}
+
+impl QuadratureDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the current levels of both lines, `true` meaning the contact is open.
+    pub fn update(&mut self, a: bool, b: bool) -> Option<EncoderEvent> {
+        let next = TRANSITIONS[self.state as usize][((b as usize) << 1) | a as usize];
+        self.state = next & 0x0F;
+        match next & 0xF0 {
+            EMIT_CW => {
+                self.position = self.position.wrapping_add(1);
+                Some(EncoderEvent::Clockwise)
+            }
+            EMIT_CCW => {
+                self.position = self.position.wrapping_sub(1);
+                Some(EncoderEvent::CounterClockwise)
+            }
+            _ => None,
+        }
+    }
+
+    /// Clockwise detents minus counter-clockwise detents seen so far.
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Line {
+    A,
+    B,
+}
+
+/// A level change on one of the encoder's lines, `open` being `true` at rest.
+#[derive(Copy, Clone, Debug)]
+pub struct Edge {
+    pub line: Line,
+    pub open: bool,
+}
+
+pub struct Position;
+
+/// A rotary encoder package, publishing an `EncoderEvent` per detent.
+///
+/// `active` describes the level a line reads while its contact is closed; typically
+/// `Active::Low` for contacts to ground with pull-ups.
+pub struct RotaryEncoder<D, A, B>
+where
+    D: Device + EventHandler<EncoderEvent> + 'static,
+    A: InputPin + ExtiPin + 'static,
+    B: InputPin + ExtiPin + 'static,
+{
+    encoder: ActorContext<Encoder<D>>,
+    a: InterruptContext<EncoderPin<D, A>>,
+    b: InterruptContext<EncoderPin<D, B>>,
+}
+
+impl<D, A, B> RotaryEncoder<D, A, B>
+where
+    D: Device + EventHandler<EncoderEvent>,
+    A: InputPin + ExtiPin,
+    B: InputPin + ExtiPin,
+{
+    pub fn new<IRQA: Nr, IRQB: Nr>(a: A, irq_a: IRQA, b: B, irq_b: IRQB, active: Active) -> Self {
+        let active_high = matches!(active, Active::High);
+        Self {
+            encoder: ActorContext::new(Encoder::new()),
+            a: InterruptContext::new(EncoderPin::new(a, Line::A, active_high), irq_a),
+            b: InterruptContext::new(EncoderPin::new(b, Line::B, active_high), irq_b),
+        }
+    }
+}
+
+impl<D, A, B> Package<D, Encoder<D>> for RotaryEncoder<D, A, B>
+where
+    D: Device + EventHandler<EncoderEvent>,
+    A: InputPin + ExtiPin,
+    B: InputPin + ExtiPin,
+{
+    fn mount(
+        &'static self,
+        bus_address: Address<EventBus<D>>,
+        supervisor: &mut Supervisor,
+    ) -> Address<Encoder<D>> {
+        let encoder = self.encoder.mount(supervisor);
+        encoder.bind(bus_address);
+        self.a.mount(supervisor).bind(encoder);
+        self.b.mount(supervisor).bind(encoder);
+        encoder
+    }
+}
+
+pub struct Encoder<D>
+where
+    D: Device + EventHandler<EncoderEvent> + 'static,
+{
+    decoder: QuadratureDecoder,
+    a: bool,
+    b: bool,
+    bus: Option<Address<EventBus<D>>>,
+}
+
+impl<D> Encoder<D>
+where
+    D: Device + EventHandler<EncoderEvent>,
+{
+    pub fn new() -> Self {
+        Self {
+            decoder: QuadratureDecoder::new(),
+            a: true,
+            b: true,
+            bus: None,
+        }
+    }
+}
+
+impl<D> Default for Encoder<D>
+where
+    D: Device + EventHandler<EncoderEvent>,
+{
+    fn default() -> Self {
+        Encoder::new()
+    }
+}
+
+impl<D> Actor for Encoder<D> where D: Device + EventHandler<EncoderEvent> {}
+
+impl<D> Bind<EventBus<D>> for Encoder<D>
+where
+    D: Device + EventHandler<EncoderEvent>,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D> NotifyHandler<Edge> for Encoder<D>
+where
+    D: Device + EventHandler<EncoderEvent>,
+{
+    fn on_notify(mut self, edge: Edge) -> Completion<Self> {
+        match edge.line {
+            Line::A => self.a = edge.open,
+            Line::B => self.b = edge.open,
+        }
+        if let Some(event) = self.decoder.update(self.a, self.b) {
+            if let Some(bus) = self.bus {
+                bus.publish(event);
+            }
+        }
+        Completion::immediate(self)
+    }
+}
+
+impl<D> RequestHandler<Position> for Encoder<D>
+where
+    D: Device + EventHandler<EncoderEvent>,
+{
+    type Response = i32;
+
+    fn on_request(self, _: Position) -> Response<Self, Self::Response> {
+        let position = self.decoder.position();
+        Response::immediate(self, position)
+    }
+}
+
+impl<D> Address<Encoder<D>>
+where
+    D: Device + EventHandler<EncoderEvent>,
+{
+    /// Clockwise detents minus counter-clockwise detents since startup.
+    pub async fn position(&self) -> i32 {
+        self.request(Position).await
+    }
+}
+
+pub struct EncoderPin<D, P>
+where
+    D: Device + EventHandler<EncoderEvent> + 'static,
+    P: InputPin + ExtiPin,
+{
+    pin: P,
+    line: Line,
+    active_high: bool,
+    encoder: Option<Address<Encoder<D>>>,
+}
+
+impl<D, P> EncoderPin<D, P>
+where
+    D: Device + EventHandler<EncoderEvent>,
+    P: InputPin + ExtiPin,
+{
+    fn new(pin: P, line: Line, active_high: bool) -> Self {
+        Self {
+            pin,
+            line,
+            active_high,
+            encoder: None,
+        }
+    }
+}
+
+impl<D, P> Actor for EncoderPin<D, P>
+where
+    D: Device + EventHandler<EncoderEvent>,
+    P: InputPin + ExtiPin,
+{
+}
+
+impl<D, P> Bind<Encoder<D>> for EncoderPin<D, P>
+where
+    D: Device + EventHandler<EncoderEvent>,
+    P: InputPin + ExtiPin,
+{
+    fn on_bind(&mut self, address: Address<Encoder<D>>) {
+        self.encoder.replace(address);
+    }
+}
+
+impl<D, P> Interrupt for EncoderPin<D, P>
+where
+    D: Device + EventHandler<EncoderEvent>,
+    P: InputPin + ExtiPin,
+{
+    fn on_interrupt(&mut self) {
+        if self.pin.check_interrupt() {
+            if let (Ok(high), Some(encoder)) = (self.pin.is_high(), self.encoder) {
+                encoder.notify(Edge {
+                    line: self.line,
+                    open: high != self.active_high,
+                });
+            }
+            self.pin.clear_interrupt_pending_bit();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `(a, b)` levels, returning the events emitted.
+    fn feed(
+        decoder: &mut QuadratureDecoder,
+        levels: &[(u8, u8)],
+    ) -> ([Option<EncoderEvent>; 16], usize) {
+        let mut events = [None; 16];
+        let mut count = 0;
+        for (a, b) in levels {
+            if let Some(event) = decoder.update(*a == 1, *b == 1) {
+                events[count] = Some(event);
+                count += 1;
+            }
+        }
+        (events, count)
+    }
+
+    #[test]
+    fn test_clockwise() {
+        let mut decoder = QuadratureDecoder::new();
+        let (events, count) = feed(
+            &mut decoder,
+            &[
+                (1, 0),
+                (0, 0),
+                (0, 1),
+                (1, 1),
+                (1, 0),
+                (0, 0),
+                (0, 1),
+                (1, 1),
+            ],
+        );
+        assert_eq!(count, 2);
+        assert_eq!(events[0], Some(EncoderEvent::Clockwise));
+        assert_eq!(events[1], Some(EncoderEvent::Clockwise));
+        assert_eq!(decoder.position(), 2);
+    }
+
+    #[test]
+    fn test_mixed_with_bounce() {
+        let mut decoder = QuadratureDecoder::new();
+        let (events, count) = feed(
+            &mut decoder,
+            &[
+                // counter-clockwise, with line A bouncing as it leaves rest
+                (0, 1),
+                (1, 1),
+                (0, 1),
+                (0, 0),
+                (1, 0),
+                (1, 1),
+                // a bounce on line B alone
+                (1, 0),
+                (1, 1),
+                // counter-clockwise, then clockwise with line A bouncing mid-step
+                (0, 1),
+                (0, 0),
+                (1, 0),
+                (1, 1),
+                (1, 0),
+                (0, 0),
+                (1, 0),
+                (0, 0),
+                (0, 1),
+                (1, 1),
+            ],
+        );
+        assert_eq!(count, 3);
+        assert_eq!(events[0], Some(EncoderEvent::CounterClockwise));
+        assert_eq!(events[1], Some(EncoderEvent::CounterClockwise));
+        assert_eq!(events[2], Some(EncoderEvent::Clockwise));
+        assert_eq!(decoder.position(), -1);
+    }
+
+    #[test]
+    fn test_abandoned_step() {
+        let mut decoder = QuadratureDecoder::new();
+        let (_, count) = feed(&mut decoder, &[(1, 0), (0, 0), (1, 0), (1, 1)]);
+        assert_eq!(count, 0);
+        assert_eq!(decoder.position(), 0);
+    }
+}
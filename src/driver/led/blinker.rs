@@ -1,60 +1,145 @@
 use crate::bind::Bind;
 use crate::domain::time::duration::Milliseconds;
+use crate::driver::console::{parse_milliseconds, Command, CommandError, Response};
 use crate::driver::led::simple::Switchable;
-use crate::driver::timer::TimerActor;
+use crate::driver::reconfigure::Reconfigurable;
+use crate::driver::timer::{Clock, TimerActor};
 use crate::hal::timer::Timer as HalTimer;
 use crate::prelude::*;
 
-pub struct Blinker<S, T>
+/// The blink pattern of a `Blinker`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BlinkerConfig {
+    /// How long the LED stays on.
+    pub on: Milliseconds,
+    /// How long the LED stays off.
+    pub off: Milliseconds,
+}
+
+impl BlinkerConfig {
+    /// Blink with the LED on and off for the same time.
+    pub fn even<DUR: Into<Milliseconds>>(delay: DUR) -> Self {
+        let delay = delay.into();
+        Self {
+            on: delay,
+            off: delay,
+        }
+    }
+}
+
+/// Actor blinking an LED, timed by a `Clock` such as the address of a `TimerActor`.
+pub struct Blinker<S, C>
 where
     S: Switchable + 'static,
-    T: HalTimer + 'static,
+    C: Clock,
 {
     led: Option<Address<S>>,
-    timer: Option<Address<TimerActor<T>>>,
-    delay: Milliseconds,
+    clock: Option<C>,
+    config: BlinkerConfig,
     address: Option<Address<Self>>,
+    /// The state the LED was last toggled into, once started.
+    state: Option<State>,
+    /// Counts the reconfigurations, to tell the toggle scheduled under each pattern.
+    generation: u8,
+    /// Whether to blink from start, or wait for a `Start`.
+    start_active: bool,
 }
 
-impl<S, T> Blinker<S, T>
+impl<S, C> Blinker<S, C>
 where
     S: Switchable,
-    T: HalTimer,
+    C: Clock,
 {
     pub fn new<DUR: Into<Milliseconds>>(delay: DUR) -> Self {
+        Self::with_config(BlinkerConfig::even(delay))
+    }
+
+    pub fn with_config(config: BlinkerConfig) -> Self {
         Self {
             led: None,
-            timer: None,
-            delay: delay.into(),
+            clock: None,
+            config,
             address: None,
+            state: None,
+            generation: 0,
+            start_active: true,
+        }
+    }
+
+    /// Create a blinker left off once started, until told to `Start`.
+    pub fn new_stopped<DUR: Into<Milliseconds>>(delay: DUR) -> Self {
+        Self {
+            start_active: false,
+            ..Self::new(delay)
+        }
+    }
+
+    /// Time the blinker by `clock` rather than by a bound timer.
+    pub fn with_clock(mut self, clock: C) -> Self {
+        self.clock.replace(clock);
+        self
+    }
+
+    /// Check a clock to time the blinker by is bound, as starting needs. An LED left
+    /// unbound is tolerated, toggling nothing.
+    fn check_bound(&self) -> Result<(), DeviceError> {
+        self.clock.map(|_| ()).ok_or(DeviceError::NotBound)
+    }
+
+    /// Start blinking with the LED off, unless no clock is bound to time it by.
+    fn begin(&mut self) {
+        if let Err(error) = self.check_bound() {
+            warn!("[blinker] not started, no timer bound: {:?}", error);
+            return;
+        }
+        self.state.replace(State::Off);
+        self.schedule(State::On);
+    }
+
+    /// Toggle the LED into `state` after it has stayed in the other for its duration.
+    fn schedule(&self, state: State) {
+        let toggle = Toggle {
+            state,
+            generation: self.generation,
+        };
+        if let (Some(clock), Some(address)) = (self.clock, self.address) {
+            clock.schedule(self.duration(state.other()), toggle, address);
+        }
+    }
+
+    /// How long the LED stays in `state` before toggling.
+    fn duration(&self, state: State) -> Milliseconds {
+        match state {
+            State::On => self.config.on,
+            State::Off => self.config.off,
         }
     }
 }
 
-impl<S, T> Bind<S> for Blinker<S, T>
+impl<S, C> Bind<S> for Blinker<S, C>
 where
     S: Switchable,
-    T: HalTimer,
+    C: Clock,
 {
     fn on_bind(&mut self, address: Address<S>) {
         self.led.replace(address);
     }
 }
 
-impl<S, T> Bind<TimerActor<T>> for Blinker<S, T>
+impl<S, T> Bind<TimerActor<T>> for Blinker<S, Address<TimerActor<T>>>
 where
     S: Switchable,
-    T: HalTimer,
+    T: HalTimer + 'static,
 {
     fn on_bind(&mut self, address: Address<TimerActor<T>>) {
-        self.timer.replace(address);
+        self.clock.replace(address);
     }
 }
 
-impl<S, T> Actor for Blinker<S, T>
+impl<S, C> Actor for Blinker<S, C>
 where
     S: Switchable,
-    T: HalTimer,
+    C: Clock,
 {
     fn on_mount(&mut self, address: Address<Self>)
     where
@@ -63,70 +148,369 @@ where
         self.address.replace(address);
     }
 
-    fn on_start(self) -> Completion<Self> {
-        self.timer.unwrap().schedule(
-            self.delay,
-            State::On,
-            self.address.unwrap(),
-        );
+    fn on_start(mut self) -> Completion<Self> {
+        if self.start_active {
+            self.begin();
+        }
         Completion::immediate(self)
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 enum State {
     On,
     Off,
 }
 
-impl<S, T> NotifyHandler<State> for Blinker<S, T>
+impl State {
+    fn other(self) -> Self {
+        match self {
+            State::On => State::Off,
+            State::Off => State::On,
+        }
+    }
+}
+
+/// Toggle of the LED into `state`, as scheduled under the pattern of `generation`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Toggle {
+    state: State,
+    generation: u8,
+}
+
+impl<S, C> NotifyHandler<Toggle> for Blinker<S, C>
 where
     S: Switchable,
-    T: HalTimer,
+    C: Clock,
 {
-    fn on_notify(self, message: State) -> Completion<Self> {
-        match message {
+    fn on_notify(mut self, message: Toggle) -> Completion<Self> {
+        // the toggle scheduled under a pattern since replaced is cancelled
+        if message.generation != self.generation {
+            return Completion::immediate(self);
+        }
+        self.state.replace(message.state);
+        match message.state {
             State::On => {
-                self.led.unwrap().turn_on();
-                self.timer.unwrap().schedule(
-                    self.delay,
-                    State::Off,
-                    self.address.unwrap(),
-                );
+                if let Some(led) = self.led {
+                    led.turn_on();
+                }
+                self.schedule(State::Off);
             }
             State::Off => {
-                self.led.unwrap().turn_off();
-                self.timer.unwrap().schedule(
-                    self.delay,
-                    State::On,
-                    self.address.unwrap(),
-                );
+                if let Some(led) = self.led {
+                    led.turn_off();
+                }
+                self.schedule(State::On);
             }
         }
         Completion::immediate(self)
     }
 }
 
+/// Start blinking a blinker created stopped. Ignored once blinking.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Start;
+
+impl<S, C> NotifyHandler<Start> for Blinker<S, C>
+where
+    S: Switchable,
+    C: Clock,
+{
+    fn on_notify(mut self, _: Start) -> Completion<Self> {
+        if self.state.is_none() {
+            self.begin();
+        }
+        Completion::immediate(self)
+    }
+}
+
 pub struct AdjustDelay(Milliseconds);
 
-impl<S, T> NotifyHandler<AdjustDelay> for Blinker<S, T>
+impl<S, C> NotifyHandler<AdjustDelay> for Blinker<S, C>
 where
     S: Switchable,
-    T: HalTimer,
+    C: Clock,
 {
     fn on_notify(mut self, message: AdjustDelay) -> Completion<Self> {
-        self.delay = message.0;
+        self.config = BlinkerConfig::even(message.0);
         Completion::immediate(self)
     }
 }
 
-impl<S, T> Address<Blinker<S, T>>
+/// The new pattern takes effect at once: the toggle pending under the old pattern is
+/// cancelled, and the LED next toggles once it has stayed in its state for the new
+/// duration, counted from the reconfiguration.
+impl<S, C> Reconfigurable for Blinker<S, C>
+where
+    S: Switchable,
+    C: Clock,
+{
+    type Config = BlinkerConfig;
+
+    fn validate(&self, config: &BlinkerConfig) -> bool {
+        config.on.0 > 0 && config.off.0 > 0
+    }
+
+    fn on_reconfigure(mut self, config: BlinkerConfig) -> Completion<Self> {
+        self.config = config;
+        self.generation = self.generation.wrapping_add(1);
+        if let Some(state) = self.state {
+            self.schedule(state.other());
+        }
+        Completion::immediate(self)
+    }
+}
+
+impl<S, C> Address<Blinker<S, C>>
 where
     Self: 'static,
     S: Switchable,
-    T: HalTimer,
+    C: Clock,
 {
     pub fn adjust_delay(&self, delay: Milliseconds) {
         self.notify(AdjustDelay(delay))
     }
+
+    /// Start blinking, if created stopped.
+    pub fn start(&self) {
+        self.notify(Start)
+    }
+}
+
+/// Console command reconfiguring a `Blinker`, run as `blink <on ms> [<off ms>]`.
+///
+/// Without an off time, the LED is on and off for the same time.
+pub struct BlinkCommand<S, C>
+where
+    S: Switchable + 'static,
+    C: Clock,
+{
+    blinker: Address<Blinker<S, C>>,
+}
+
+impl<S, C> BlinkCommand<S, C>
+where
+    S: Switchable,
+    C: Clock,
+{
+    pub fn new(blinker: Address<Blinker<S, C>>) -> Self {
+        Self { blinker }
+    }
+}
+
+/// Parse the pattern of the `blink` command, rejecting durations the blinker would.
+fn pattern(args: &[&str]) -> Result<BlinkerConfig, CommandError> {
+    let config = match args {
+        [on] => BlinkerConfig::even(parse_milliseconds(on)?),
+        [on, off] => BlinkerConfig {
+            on: parse_milliseconds(on)?,
+            off: parse_milliseconds(off)?,
+        },
+        _ => return Err(CommandError::InvalidArguments),
+    };
+    if config.on.0 == 0 || config.off.0 == 0 {
+        return Err(CommandError::OutOfRange);
+    }
+    Ok(config)
+}
+
+impl<S, C> Command for BlinkCommand<S, C>
+where
+    S: Switchable,
+    C: Clock,
+{
+    fn name(&self) -> &str {
+        "blink"
+    }
+
+    fn usage(&self) -> &str {
+        "<on ms> [<off ms>]"
+    }
+
+    fn run(&self, args: &[&str], _: &mut Response) -> Result<(), CommandError> {
+        self.blinker.reconfigure(pattern(args)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::led::simple::SimpleLED;
+    use crate::driver::reconfigure::Reconfigure;
+    use crate::driver::timer::MockClock;
+    use crate::hal::gpio::ActiveHigh;
+    use core::convert::Infallible;
+    use embedded_hal::digital::v2::OutputPin;
+    use std::boxed::Box;
+
+    struct NoPin;
+
+    impl OutputPin for NoPin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    type TestBlinker = Blinker<SimpleLED<NoPin, ActiveHigh>, &'static MockClock<Toggle>>;
+
+    fn notify<M>(blinker: TestBlinker, message: M) -> TestBlinker
+    where
+        TestBlinker: NotifyHandler<M>,
+    {
+        match blinker.on_notify(message) {
+            Completion::Immediate(blinker) => blinker,
+            Completion::Defer(_) => panic!("deferred"),
+        }
+    }
+
+    fn reconfigure(blinker: TestBlinker, on: u32, off: u32) -> TestBlinker {
+        let config = BlinkerConfig {
+            on: Milliseconds(on),
+            off: Milliseconds(off),
+        };
+        notify(blinker, Reconfigure(config))
+    }
+
+    #[test]
+    fn test_clock() {
+        let clock: &'static MockClock<Toggle> = Box::leak(Box::new(MockClock::new()));
+        let context = Box::leak(Box::new(ActorContext::new(TestBlinker::new(
+            Milliseconds(0u32),
+        ))));
+        let mut blinker = TestBlinker::with_config(BlinkerConfig {
+            on: Milliseconds(100u32),
+            off: Milliseconds(400u32),
+        })
+        .with_clock(clock);
+        blinker.on_mount(Address::new(context));
+        let mut blinker = match blinker.on_start() {
+            Completion::Immediate(blinker) => blinker,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+
+        // the LED starts off, and toggles once each of its durations has passed
+        assert!(clock.advance(Milliseconds(399u32)).is_empty());
+        for (by, state) in [(1, State::On), (100, State::Off), (400, State::On)] {
+            let due = clock.advance(Milliseconds(by));
+            assert_eq!(due, [toggle(state, 0)]);
+            blinker = notify(blinker, due[0]);
+        }
+        assert_eq!(clock.now(), Milliseconds(900u32));
+
+        // mid-cycle, a new pattern applies at once
+        blinker = reconfigure(blinker, 50, 200);
+        let due = clock.advance(Milliseconds(50u32));
+        assert_eq!(due, [toggle(State::Off, 1)]);
+        blinker = notify(blinker, due[0]);
+        assert_eq!(blinker.state, Some(State::Off));
+
+        // the toggle of the old pattern still fires, but is ignored
+        let due = clock.advance(Milliseconds(50u32));
+        assert_eq!(due, [toggle(State::Off, 0)]);
+        blinker = notify(blinker, due[0]);
+        assert_eq!(clock.pending(), 1);
+
+        // leaving only the new pattern's toggles
+        assert!(clock.advance(Milliseconds(149u32)).is_empty());
+        let due = clock.advance(Milliseconds(1u32));
+        assert_eq!(due, [toggle(State::On, 1)]);
+        blinker = notify(blinker, due[0]);
+        assert_eq!(blinker.state, Some(State::On));
+        assert_eq!(clock.advance(Milliseconds(50u32)), [toggle(State::Off, 1)]);
+        assert_eq!(clock.pending(), 0);
+    }
+
+    #[test]
+    fn test_unbound() {
+        let context = Box::leak(Box::new(ActorContext::new(TestBlinker::new(
+            Milliseconds(0u32),
+        ))));
+        let mut blinker = TestBlinker::new(Milliseconds(100u32));
+        blinker.on_mount(Address::new(context));
+        assert_eq!(blinker.check_bound(), Err(DeviceError::NotBound));
+
+        // left stopped rather than panicking
+        let blinker = match blinker.on_start() {
+            Completion::Immediate(blinker) => blinker,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(blinker.state, None);
+
+        // so reconfiguring schedules nothing
+        let blinker = reconfigure(blinker, 50, 50);
+        assert_eq!(blinker.state, None);
+    }
+
+    #[test]
+    fn test_start_stopped() {
+        let clock: &'static MockClock<Toggle> = Box::leak(Box::new(MockClock::new()));
+        let context = Box::leak(Box::new(ActorContext::new(TestBlinker::new(
+            Milliseconds(0u32),
+        ))));
+        let mut blinker = TestBlinker::new_stopped(Milliseconds(100u32)).with_clock(clock);
+        blinker.on_mount(Address::new(context));
+        let blinker = match blinker.on_start() {
+            Completion::Immediate(blinker) => blinker,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(blinker.state, None);
+        assert!(clock.advance(Milliseconds(1000u32)).is_empty());
+
+        // blinking from the start command on
+        let blinker = notify(blinker, Start);
+        assert_eq!(blinker.state, Some(State::Off));
+        assert!(clock.advance(Milliseconds(99u32)).is_empty());
+        let due = clock.advance(Milliseconds(1u32));
+        assert_eq!(due, [toggle(State::On, 0)]);
+        let blinker = notify(blinker, due[0]);
+
+        // a second start leaves the blinking as it is
+        let blinker = notify(blinker, Start);
+        assert_eq!(blinker.state, Some(State::On));
+        assert_eq!(clock.pending(), 1);
+    }
+
+    fn toggle(state: State, generation: u8) -> Toggle {
+        Toggle { state, generation }
+    }
+
+    #[test]
+    fn test_reconfigure() {
+        let blinker = TestBlinker::new(Milliseconds(500u32));
+        assert_eq!(blinker.duration(State::On), Milliseconds(500u32));
+
+        let blinker = reconfigure(blinker, 100, 900);
+        assert_eq!(blinker.duration(State::On), Milliseconds(100u32));
+        assert_eq!(blinker.duration(State::Off), Milliseconds(900u32));
+
+        // a pattern never turning the LED off is rejected as a whole
+        let blinker = reconfigure(blinker, 200, 0);
+        assert_eq!(blinker.duration(State::On), Milliseconds(100u32));
+        assert_eq!(blinker.duration(State::Off), Milliseconds(900u32));
+    }
+
+    #[test]
+    fn test_pattern() {
+        assert_eq!(pattern(&["250"]), Ok(BlinkerConfig::even(Milliseconds(250u32))));
+        assert_eq!(
+            pattern(&["100", "4294967295"]),
+            Ok(BlinkerConfig {
+                on: Milliseconds(100u32),
+                off: Milliseconds(u32::MAX),
+            })
+        );
+        assert_eq!(pattern(&["4294967296"]), Err(CommandError::OutOfRange));
+        assert_eq!(pattern(&["100", "0"]), Err(CommandError::OutOfRange));
+        assert_eq!(pattern(&["soon"]), Err(CommandError::InvalidArguments));
+        assert_eq!(pattern(&[]), Err(CommandError::InvalidArguments));
+    }
 }
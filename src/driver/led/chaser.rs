@@ -0,0 +1,279 @@
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::led::simple::Switchable;
+use crate::driver::reconfigure::Reconfigurable;
+use crate::driver::timer::{Clock, TimerActor};
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+use heapless::{ArrayLength, Vec};
+
+/// Actor lighting a row of LEDs one at a time, sweeping forward along the row then
+/// back, timed by a `Clock` such as the address of a `TimerActor`.
+///
+/// The LEDs are bound in the order of the row, up to `N` of them. A single LED is
+/// simply kept lit.
+pub struct Chaser<S, C, N>
+where
+    S: Switchable + 'static,
+    C: Clock,
+    N: ArrayLength<Address<S>>,
+{
+    leds: Vec<Address<S>, N>,
+    clock: Option<C>,
+    delay: Milliseconds,
+    address: Option<Address<Self>>,
+    /// The LED lit, once started.
+    lit: Option<usize>,
+    forward: bool,
+}
+
+impl<S, C, N> Chaser<S, C, N>
+where
+    S: Switchable,
+    C: Clock,
+    N: ArrayLength<Address<S>>,
+{
+    /// Create a chaser moving on to the next LED every `delay`.
+    pub fn new<DUR: Into<Milliseconds>>(delay: DUR) -> Self {
+        Self {
+            leds: Vec::new(),
+            clock: None,
+            delay: delay.into(),
+            address: None,
+            lit: None,
+            forward: true,
+        }
+    }
+
+    /// Time the chaser by `clock` rather than by a bound timer.
+    pub fn with_clock(mut self, clock: C) -> Self {
+        self.clock.replace(clock);
+        self
+    }
+
+    /// Move on to the next LED, returning the LED to turn off, if another, and the LED
+    /// to light.
+    fn advance(&mut self) -> (Option<usize>, usize) {
+        let last = self.leds.len().saturating_sub(1);
+        let next = match self.lit {
+            None => 0,
+            Some(_) if last == 0 => 0,
+            Some(lit) => {
+                if lit == last {
+                    self.forward = false;
+                } else if lit == 0 {
+                    self.forward = true;
+                }
+                if self.forward {
+                    lit + 1
+                } else {
+                    lit - 1
+                }
+            }
+        };
+        let previous = self.lit.replace(next);
+        (previous.filter(|previous| *previous != next), next)
+    }
+
+    fn schedule_step(&self) {
+        if let (Some(clock), Some(address)) = (self.clock, self.address) {
+            clock.schedule(self.delay, Step, address);
+        }
+    }
+}
+
+impl<S, C, N> Bind<S> for Chaser<S, C, N>
+where
+    S: Switchable,
+    C: Clock,
+    N: ArrayLength<Address<S>>,
+{
+    fn on_bind(&mut self, address: Address<S>) {
+        self.leds
+            .push(address)
+            .unwrap_or_else(|_| panic!("too many LEDs"));
+    }
+}
+
+impl<S, T, N> Bind<TimerActor<T>> for Chaser<S, Address<TimerActor<T>>, N>
+where
+    S: Switchable,
+    T: HalTimer + 'static,
+    N: ArrayLength<Address<S>>,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.clock.replace(address);
+    }
+}
+
+impl<S, C, N> Actor for Chaser<S, C, N>
+where
+    S: Switchable,
+    C: Clock,
+    N: ArrayLength<Address<S>>,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_start(self) -> Completion<Self> {
+        self.on_notify(Step)
+    }
+}
+
+/// Move on to the next LED.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Step;
+
+impl<S, C, N> NotifyHandler<Step> for Chaser<S, C, N>
+where
+    S: Switchable,
+    C: Clock,
+    N: ArrayLength<Address<S>>,
+{
+    fn on_notify(mut self, _: Step) -> Completion<Self> {
+        if self.leds.is_empty() {
+            return Completion::immediate(self);
+        }
+        let (off, on) = self.advance();
+        if let Some(off) = off {
+            self.leds[off].turn_off();
+        }
+        self.leds[on].turn_on();
+        self.schedule_step();
+        Completion::immediate(self)
+    }
+}
+
+/// A new delay applies from the step after the one pending.
+impl<S, C, N> Reconfigurable for Chaser<S, C, N>
+where
+    S: Switchable,
+    C: Clock,
+    N: ArrayLength<Address<S>>,
+{
+    type Config = Milliseconds;
+
+    fn validate(&self, delay: &Milliseconds) -> bool {
+        delay.0 > 0
+    }
+
+    fn on_reconfigure(mut self, delay: Milliseconds) -> Completion<Self> {
+        self.delay = delay;
+        Completion::immediate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::led::simple::SimpleLED;
+    use crate::driver::reconfigure::Reconfigure;
+    use crate::driver::timer::MockClock;
+    use crate::hal::gpio::ActiveHigh;
+    use core::convert::Infallible;
+    use embedded_hal::digital::v2::OutputPin;
+    use heapless::consts::*;
+    use std::boxed::Box;
+
+    struct NoPin;
+
+    impl OutputPin for NoPin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    type Led = SimpleLED<NoPin, ActiveHigh>;
+    type TestChaser = Chaser<Led, &'static MockClock<Step>, U4>;
+
+    fn chaser(leds: usize) -> TestChaser {
+        let mut chaser = TestChaser::new(Milliseconds(100u32));
+        for _ in 0..leds {
+            let led = Box::leak(Box::new(ActorContext::new(Led::new(
+                NoPin,
+                crate::hal::Active::High,
+            ))));
+            chaser.on_bind(Address::new(led));
+        }
+        chaser
+    }
+
+    /// The LEDs lit after each of `STEPS` steps, applying each step to the row.
+    fn sweep<const STEPS: usize>(mut chaser: TestChaser) -> [[bool; 3]; STEPS] {
+        let mut row = [false; 3];
+        let mut lit = [[false; 3]; STEPS];
+        for step in lit.iter_mut() {
+            let (off, on) = chaser.advance();
+            if let Some(off) = off {
+                assert!(row[off]);
+                row[off] = false;
+            }
+            row[on] = true;
+            *step = row;
+        }
+        lit
+    }
+
+    #[test]
+    fn test_sweep() {
+        let (x, o) = (true, false);
+        // forward, back, and forward again
+        assert_eq!(
+            sweep(chaser(3)),
+            [
+                [x, o, o],
+                [o, x, o],
+                [o, o, x],
+                [o, x, o],
+                [x, o, o],
+                [o, x, o],
+                [o, o, x],
+            ]
+        );
+        assert_eq!(
+            sweep(chaser(2)),
+            [[x, o, o], [o, x, o], [x, o, o], [o, x, o]]
+        );
+        // a single LED stays lit, never turned off
+        assert_eq!(sweep(chaser(1)), [[x, o, o], [x, o, o]]);
+    }
+
+    #[test]
+    fn test_reconfigure() {
+        let clock: &'static MockClock<Step> = Box::leak(Box::new(MockClock::new()));
+        let context = Box::leak(Box::new(ActorContext::new(TestChaser::new(Milliseconds(
+            0u32,
+        )))));
+        // without LEDs, nothing is scheduled
+        let mut chaser = TestChaser::new(Milliseconds(100u32)).with_clock(clock);
+        chaser.on_mount(Address::new(context));
+        let mut chaser = match chaser.on_start() {
+            Completion::Immediate(chaser) => chaser,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(clock.pending(), 0);
+
+        chaser = match chaser.on_notify(Reconfigure(Milliseconds(0u32))) {
+            Completion::Immediate(chaser) => chaser,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(chaser.delay, Milliseconds(100u32));
+        chaser = match chaser.on_notify(Reconfigure(Milliseconds(40u32))) {
+            Completion::Immediate(chaser) => chaser,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(chaser.delay, Milliseconds(40u32));
+    }
+}
@@ -1,33 +1,118 @@
 use crate::bind::Bind;
 use crate::domain::time::duration::Milliseconds;
-use crate::domain::time::rate::{Hertz, Rate};
+use crate::domain::time::rate::Hertz;
 
 use crate::driver::timer::TimerActor;
+use crate::hal::gpio::{ActiveHigh, ActiveLow, ActiveOutput};
 use crate::hal::timer::Timer as HalTimer;
 use crate::prelude::*;
+use core::marker::PhantomData;
 use embedded_hal::digital::v2::OutputPin;
 use heapless::{ArrayLength, Vec};
 
+#[cfg(feature = "embedded-graphics")]
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+
 // Led matrix driver supporting up to 32x32 led matrices.
-pub struct LEDMatrix<P, ROWS, COLS, T>
+//
+// Rows are driven active in turn, lighting the LEDs of the row whose columns are driven
+// active. By default rows are active high and columns active low, as on the micro:bit;
+// boards wired otherwise set `RA` and `CA`.
+//
+// The rows are scanned one per period of the refresh rate, so the whole matrix is
+// refreshed at the refresh rate divided by the rows. Created with a frame rate instead,
+// the whole matrix is refreshed at that rate, at least `MIN_FRAME_RATE` so as not to be
+// seen to flicker.
+//
+// Drawing changes the frame rendered straight away, so a frame drawn over several
+// commands may be shown part-drawn. Double-buffered, drawing goes to a back buffer
+// instead, shown whole once presented.
+pub struct LEDMatrix<P, ROWS, COLS, T, RA = ActiveHigh, CA = ActiveLow>
 where
     P: OutputPin + 'static,
     ROWS: ArrayLength<P> + 'static,
     COLS: ArrayLength<P> + 'static,
     T: HalTimer + 'static,
+    RA: ActiveOutput + 'static,
+    CA: ActiveOutput + 'static,
 {
     address: Option<Address<Self>>,
     pin_rows: Vec<P, ROWS>,
     pin_cols: Vec<P, COLS>,
     frame_buffer: Frame,
+    back_buffer: Option<Frame>,
     row_p: usize,
+    orientation: Orientation,
     timer: Option<Address<TimerActor<T>>>,
-    refresh_rate: Hertz,
+    row_period: Milliseconds,
+    _active: PhantomData<(RA, CA)>,
+}
+
+/// The lowest rate at which the whole matrix may be refreshed without being seen to
+/// flicker.
+pub const MIN_FRAME_RATE: Hertz = Hertz(50);
+
+/// A frame rate out of the range `row_period` accepts for the rows of a matrix.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameRateOutOfRange(pub Hertz);
+
+/// The time each of `rows` rows is lit for, in whole milliseconds, to refresh the whole
+/// matrix at least `frame_rate` times a second.
+///
+/// `None` if `frame_rate` is below `MIN_FRAME_RATE`, or too fast to scan `rows` rows
+/// with a timer counting milliseconds.
+pub fn row_period(frame_rate: Hertz, rows: usize) -> Option<Milliseconds> {
+    if frame_rate.0 < MIN_FRAME_RATE.0 {
+        return None;
+    }
+    let rows_per_second = frame_rate.0.checked_mul(rows as u32)?;
+    match 1_000u32.checked_div(rows_per_second)? {
+        0 => None,
+        ms => Some(Milliseconds(ms)),
+    }
+}
+
+/// How the frame is laid out on the matrix, to make up for how it is mounted.
+///
+/// Rotations are clockwise, and assume a square matrix: on others, the parts of the
+/// frame rotated outside of the matrix are not shown.
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Swap left and right.
+    MirrorHorizontal,
+    /// Swap top and bottom.
+    MirrorVertical,
+}
+
+impl Orientation {
+    /// The row and column of the frame shown at `row` and `col` of a matrix of `rows`
+    /// by `cols` LEDs.
+    fn source(&self, row: usize, col: usize, rows: usize, cols: usize) -> (usize, usize) {
+        match self {
+            Orientation::Normal => (row, col),
+            Orientation::Rotate90 => (cols - 1 - col, row),
+            Orientation::Rotate180 => (rows - 1 - row, cols - 1 - col),
+            Orientation::Rotate270 => (col, rows - 1 - row),
+            Orientation::MirrorHorizontal => (row, cols - 1 - col),
+            Orientation::MirrorVertical => (rows - 1 - row, col),
+        }
+    }
 }
 
 /**
  * A 32x32 bitmap that can be displayed on a LED matrix.
  */
+#[derive(Copy, Clone)]
 pub struct Frame {
     bitmap: [u32; 32],
 }
@@ -56,99 +141,234 @@ impl Frame {
     }
 }
 
-impl<P, ROWS, COLS, T> LEDMatrix<P, ROWS, COLS, T>
+impl Default for Frame {
+    fn default() -> Self {
+        Frame::new([0; 32])
+    }
+}
+
+/// Draws with `BinaryColor::On` lighting an LED, `x` counting columns and `y` rows.
+#[cfg(feature = "embedded-graphics")]
+impl DrawTarget for Frame {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if (0..32).contains(&point.x) && (0..32).contains(&point.y) {
+                let (row, col) = (point.y as usize, point.x as usize);
+                match color {
+                    BinaryColor::On => self.set(row, col),
+                    BinaryColor::Off => self.unset(row, col),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl OriginDimensions for Frame {
+    fn size(&self) -> Size {
+        Size::new(32, 32)
+    }
+}
+
+impl<P, ROWS, COLS, T, RA, CA> LEDMatrix<P, ROWS, COLS, T, RA, CA>
 where
     P: OutputPin,
     ROWS: ArrayLength<P>,
     COLS: ArrayLength<P>,
     T: HalTimer,
+    RA: ActiveOutput,
+    CA: ActiveOutput,
 {
+    /// Drive the matrix with `pin_rows` and `pin_cols`, rendering the next row
+    /// `refresh_rate` times a second.
     pub fn new(pin_rows: Vec<P, ROWS>, pin_cols: Vec<P, COLS>, refresh_rate: Hertz) -> Self {
+        Self::with_row_period(pin_rows, pin_cols, refresh_rate.period())
+    }
+
+    /// Drive the matrix with `pin_rows` and `pin_cols`, refreshing the whole matrix
+    /// `frame_rate` times a second, if in the range `row_period` accepts for the rows.
+    pub fn with_frame_rate(
+        pin_rows: Vec<P, ROWS>,
+        pin_cols: Vec<P, COLS>,
+        frame_rate: Hertz,
+    ) -> Result<Self, FrameRateOutOfRange> {
+        let row_period =
+            row_period(frame_rate, pin_rows.len()).ok_or(FrameRateOutOfRange(frame_rate))?;
+        Ok(Self::with_row_period(pin_rows, pin_cols, row_period))
+    }
+
+    fn with_row_period(
+        pin_rows: Vec<P, ROWS>,
+        pin_cols: Vec<P, COLS>,
+        row_period: Milliseconds,
+    ) -> Self {
         LEDMatrix {
             address: None,
             pin_rows,
             pin_cols,
             frame_buffer: Frame::new([0; 32]),
+            back_buffer: None,
             row_p: 0,
-            refresh_rate,
+            orientation: Orientation::Normal,
+            row_period,
             timer: None,
+            _active: PhantomData,
+        }
+    }
+
+    /// Draw into a back buffer, leaving the frame rendered unchanged until `present`.
+    pub fn with_double_buffer(mut self) -> Self {
+        self.back_buffer.replace(self.frame_buffer);
+        self
+    }
+
+    /// The frame drawn into: the back buffer if double-buffered, else the one rendered.
+    fn drawing(&mut self) -> &mut Frame {
+        match &mut self.back_buffer {
+            Some(back) => back,
+            None => &mut self.frame_buffer,
         }
     }
 
     pub fn clear(&mut self) {
-        self.frame_buffer.clear();
+        self.drawing().clear();
     }
 
     pub fn on(&mut self, x: usize, y: usize) {
-        self.frame_buffer.set(x, y);
+        self.drawing().set(x, y);
     }
 
     pub fn off(&mut self, x: usize, y: usize) {
-        self.frame_buffer.unset(x, y);
+        self.drawing().unset(x, y);
     }
 
     pub fn apply(&mut self, frame: Frame) {
-        self.frame_buffer = frame;
+        *self.drawing() = frame;
+    }
+
+    /// Render the back buffer from now on, if double-buffered. The back buffer keeps
+    /// the frame, for the next to be drawn over it.
+    pub fn present(&mut self) {
+        if let Some(back) = self.back_buffer {
+            self.frame_buffer = back;
+        }
+    }
+
+    /// Lay the frame out on the matrix in `orientation` from the next render on. Pixels
+    /// keep being set in the coordinates of the frame.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    /// Refresh the whole matrix `frame_rate` times a second from the next row on,
+    /// returning `false`, and leaving the rate unchanged, if it is out of the range
+    /// `row_period` accepts for the rows.
+    pub fn set_frame_rate(&mut self, frame_rate: Hertz) -> bool {
+        match row_period(frame_rate, self.pin_rows.len()) {
+            Some(row_period) => {
+                self.row_period = row_period;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the LED at `row` and `col` of the matrix is lit.
+    fn is_lit(&self, row: usize, col: usize) -> bool {
+        let (rows, cols) = (self.pin_rows.len(), self.pin_cols.len());
+        let (x, y) = self.orientation.source(row, col, rows, cols);
+        x < 32 && y < 32 && self.frame_buffer.is_set(x, y)
+    }
+
+    /// The frame as laid out on the matrix.
+    pub fn displayed(&self) -> Frame {
+        let mut frame = Frame::default();
+        for row in 0..self.pin_rows.len() {
+            for col in 0..self.pin_cols.len() {
+                if self.is_lit(row, col) {
+                    frame.set(row, col);
+                }
+            }
+        }
+        frame
     }
 
     pub fn render(&mut self) {
         for row in self.pin_rows.iter_mut() {
-            row.set_low().ok();
+            RA::set_inactive(row).ok();
         }
 
-        for (cid, col) in self.pin_cols.iter_mut().enumerate() {
-            if self.frame_buffer.is_set(self.row_p, cid) {
-                col.set_low().ok();
+        for cid in 0..self.pin_cols.len() {
+            let col = if self.is_lit(self.row_p, cid) {
+                CA::set_active(&mut self.pin_cols[cid])
             } else {
-                col.set_high().ok();
-            }
+                CA::set_inactive(&mut self.pin_cols[cid])
+            };
+            col.ok();
         }
-        self.pin_rows[self.row_p].set_high().ok();
+        RA::set_active(&mut self.pin_rows[self.row_p]).ok();
         self.row_p = (self.row_p + 1) % self.pin_rows.len();
     }
+
+    /// Render the next row once a row period has passed, if a timer is bound.
+    fn schedule_render(&self) {
+        if let (Some(timer), Some(address)) = (self.timer, self.address) {
+            timer.schedule(self.row_period, MatrixCommand::Render, address);
+        }
+    }
 }
 
-impl<P, ROWS, COLS, T> Bind<TimerActor<T>> for LEDMatrix<P, ROWS, COLS, T>
+impl<P, ROWS, COLS, T, RA, CA> Bind<TimerActor<T>> for LEDMatrix<P, ROWS, COLS, T, RA, CA>
 where
     P: OutputPin,
     ROWS: ArrayLength<P>,
     COLS: ArrayLength<P>,
     T: HalTimer,
+    RA: ActiveOutput,
+    CA: ActiveOutput,
 {
     fn on_bind(&mut self, address: Address<TimerActor<T>>) {
         self.timer.replace(address);
     }
 }
 
-impl<P, ROWS, COLS, T> Actor for LEDMatrix<P, ROWS, COLS, T>
+impl<P, ROWS, COLS, T, RA, CA> Actor for LEDMatrix<P, ROWS, COLS, T, RA, CA>
 where
     P: OutputPin,
     ROWS: ArrayLength<P>,
     COLS: ArrayLength<P>,
     T: HalTimer,
+    RA: ActiveOutput,
+    CA: ActiveOutput,
 {
     fn on_mount(&mut self, address: Address<Self>) {
         self.address.replace(address);
     }
 
     fn on_start(self) -> Completion<Self> {
-        if let Some(address) = self.address {
-            self.timer.unwrap().schedule(
-                self.refresh_rate.to_duration::<Milliseconds>().unwrap(),
-                MatrixCommand::Render,
-                address,
-            );
+        if self.timer.is_none() {
+            warn!("[matrix] not refreshing, no timer bound");
         }
+        self.schedule_render();
         Completion::immediate(self)
     }
 }
 
-impl<P, ROWS, COLS, T> NotifyHandler<MatrixCommand> for LEDMatrix<P, ROWS, COLS, T>
+impl<P, ROWS, COLS, T, RA, CA> NotifyHandler<MatrixCommand> for LEDMatrix<P, ROWS, COLS, T, RA, CA>
 where
     P: OutputPin,
     ROWS: ArrayLength<P>,
     COLS: ArrayLength<P>,
     T: HalTimer,
+    RA: ActiveOutput,
+    CA: ActiveOutput,
 {
     fn on_notify(mut self, command: MatrixCommand) -> Completion<Self> {
         match command {
@@ -164,27 +384,52 @@ where
             MatrixCommand::Clear => {
                 self.clear();
             }
+            MatrixCommand::SetOrientation(orientation) => {
+                self.set_orientation(orientation);
+            }
+            MatrixCommand::Present => {
+                self.present();
+            }
+            MatrixCommand::SetFrameRate(frame_rate) => {
+                if !self.set_frame_rate(frame_rate) {
+                    warn!("[matrix] frame rate {}Hz out of range; ignored", frame_rate.0);
+                }
+            }
             MatrixCommand::Render => {
                 self.render();
-                if let Some(address) = self.address {
-                    self.timer.unwrap().schedule(
-                        self.refresh_rate.to_duration::<Milliseconds>().unwrap(),
-                        MatrixCommand::Render,
-                        address,
-                    );
-                }
+                self.schedule_render();
             }
         }
         Completion::immediate(self)
     }
 }
 
+impl<P, ROWS, COLS, T, RA, CA> NotifyHandler<Frame> for LEDMatrix<P, ROWS, COLS, T, RA, CA>
+where
+    P: OutputPin,
+    ROWS: ArrayLength<P>,
+    COLS: ArrayLength<P>,
+    T: HalTimer,
+    RA: ActiveOutput,
+    CA: ActiveOutput,
+{
+    fn on_notify(mut self, frame: Frame) -> Completion<Self> {
+        self.apply(frame);
+        Completion::immediate(self)
+    }
+}
+
 #[derive(Debug, PartialEq, Copy, Clone, Eq)]
 pub enum MatrixCommand {
     On(usize, usize),
     Off(usize, usize),
     Clear,
     ApplyAscii(char),
+    SetOrientation(Orientation),
+    /// Show the frame drawn into the back buffer, if double-buffered.
+    Present,
+    /// Refresh the whole matrix at the given rate, if in the range `row_period` accepts.
+    SetFrameRate(Hertz),
     Render,
 }
 
@@ -420,6 +665,86 @@ impl ToFrame for char {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hal::timer::mock::MockTimer;
+    use core::convert::Infallible;
+    use heapless::consts::*;
+
+    #[derive(Default)]
+    struct MockPin {
+        high: bool,
+    }
+
+    impl OutputPin for MockPin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.high = false;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.high = true;
+            Ok(())
+        }
+    }
+
+    fn matrix<RA: ActiveOutput, CA: ActiveOutput>() -> LEDMatrix<MockPin, U3, U3, MockTimer, RA, CA>
+    {
+        let (rows, cols) = pins();
+        LEDMatrix::with_frame_rate(rows, cols, Hertz(100)).ok().unwrap()
+    }
+
+    fn pins() -> (Vec<MockPin, U3>, Vec<MockPin, U3>) {
+        let mut rows = Vec::new();
+        let mut cols = Vec::new();
+        for _ in 0..3 {
+            rows.push(MockPin::default()).ok().unwrap();
+            cols.push(MockPin::default()).ok().unwrap();
+        }
+        (rows, cols)
+    }
+
+    fn rows(frame: Frame) -> [u32; 3] {
+        [frame.bitmap[0], frame.bitmap[1], frame.bitmap[2]]
+    }
+
+    #[test]
+    fn test_orientation() {
+        // an L: the left column, and the bottom row
+        let mut matrix: LEDMatrix<MockPin, U3, U3, MockTimer> = matrix();
+        for (x, y) in [(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)] {
+            matrix = match matrix.on_notify(MatrixCommand::On(x, y)) {
+                Completion::Immediate(matrix) => matrix,
+                Completion::Defer(_) => panic!("deferred"),
+            };
+        }
+        assert_eq!(rows(matrix.displayed()), [0b001, 0b001, 0b111]);
+
+        matrix.set_orientation(Orientation::Rotate90);
+        assert_eq!(rows(matrix.displayed()), [0b111, 0b001, 0b001]);
+        matrix.set_orientation(Orientation::Rotate180);
+        assert_eq!(rows(matrix.displayed()), [0b111, 0b100, 0b100]);
+        matrix.set_orientation(Orientation::Rotate270);
+        assert_eq!(rows(matrix.displayed()), [0b100, 0b100, 0b111]);
+        matrix.set_orientation(Orientation::MirrorHorizontal);
+        assert_eq!(rows(matrix.displayed()), [0b100, 0b100, 0b111]);
+        matrix.set_orientation(Orientation::MirrorVertical);
+        assert_eq!(rows(matrix.displayed()), [0b111, 0b001, 0b001]);
+
+        // the first row rendered is the top of the rotated L, lit across
+        let command = MatrixCommand::SetOrientation(Orientation::Rotate90);
+        let mut matrix = match matrix.on_notify(command) {
+            Completion::Immediate(matrix) => matrix,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        matrix.render();
+        assert!(matrix.pin_rows[0].high);
+        assert!(matrix.pin_cols.iter().all(|col| !col.high));
+        matrix.render();
+        assert!(!matrix.pin_cols[0].high);
+        assert!(matrix.pin_cols[1].high);
+        assert!(matrix.pin_cols[2].high);
+    }
 
     #[test]
     fn test_frame() {
@@ -455,4 +780,132 @@ mod tests {
         assert!(frame.is_set(4, 3));
         assert!(!frame.is_set(4, 4));
     }
+
+    #[cfg(feature = "embedded-graphics")]
+    #[test]
+    fn test_draw_target() {
+        use embedded_graphics::prelude::*;
+        use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+        let mut frame = Frame::default();
+        Rectangle::new(Point::new(1, 2), Size::new(3, 2))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut frame)
+            .unwrap();
+        Rectangle::new(Point::new(30, 30), Size::new(4, 4))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut frame)
+            .unwrap();
+
+        assert_eq!(frame.bitmap[0], 0);
+        assert_eq!(frame.bitmap[1], 0);
+        assert_eq!(frame.bitmap[2], 0b1110);
+        assert_eq!(frame.bitmap[3], 0b1110);
+        assert_eq!(frame.bitmap[4], 0);
+        assert_eq!(frame.bitmap[30], 0b11 << 30);
+        assert_eq!(frame.bitmap[31], 0b11 << 30);
+    }
+
+    #[test]
+    fn test_polarity() {
+        // the top row lit on the left, the middle row dark
+        let mut high: LEDMatrix<MockPin, U3, U3, MockTimer> = matrix();
+        high.on(0, 0);
+        high.render();
+        assert_eq!(levels(&high.pin_rows), [true, false, false]);
+        assert_eq!(levels(&high.pin_cols), [false, true, true]);
+
+        let mut low: LEDMatrix<MockPin, U3, U3, MockTimer, ActiveLow, ActiveHigh> = matrix();
+        low.on(0, 0);
+        low.render();
+        // the same LEDs are lit, with every pin driven the other way
+        assert_eq!(levels(&low.pin_rows), [false, true, true]);
+        assert_eq!(levels(&low.pin_cols), [true, false, false]);
+
+        high.render();
+        low.render();
+        assert_eq!(levels(&high.pin_rows), [false, true, false]);
+        assert_eq!(levels(&high.pin_cols), [true, true, true]);
+        assert_eq!(levels(&low.pin_rows), [true, false, true]);
+        assert_eq!(levels(&low.pin_cols), [false, false, false]);
+    }
+
+    #[test]
+    fn test_row_period() {
+        // 5 rows, at 50Hz, are lit for 4ms each
+        assert_eq!(row_period(Hertz(50), 5), Some(Milliseconds(4u32)));
+        // rounded down to whole milliseconds, refreshing at 66Hz rather than 60Hz
+        assert_eq!(row_period(Hertz(60), 5), Some(Milliseconds(3u32)));
+        assert_eq!(row_period(Hertz(200), 5), Some(Milliseconds(1u32)));
+        assert_eq!(row_period(Hertz(100), 1), Some(Milliseconds(10u32)));
+        // flickering, or too fast for the timer
+        assert_eq!(row_period(Hertz(40), 5), None);
+        assert_eq!(row_period(Hertz(250), 5), None);
+        assert_eq!(row_period(Hertz(u32::MAX), 5), None);
+
+        let matrix: LEDMatrix<MockPin, U3, U3, MockTimer> = matrix();
+        assert_eq!(matrix.row_period, Milliseconds(3u32));
+        let matrix = match matrix.on_notify(MatrixCommand::SetFrameRate(Hertz(60))) {
+            Completion::Immediate(matrix) => matrix,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(matrix.row_period, Milliseconds(5u32));
+        let mut matrix = match matrix.on_notify(MatrixCommand::SetFrameRate(Hertz(30))) {
+            Completion::Immediate(matrix) => matrix,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(matrix.row_period, Milliseconds(5u32));
+        assert!(!matrix.set_frame_rate(Hertz(1_000)));
+
+        // out of range from the start
+        let (rows, cols) = pins();
+        let matrix: Result<LEDMatrix<MockPin, U3, U3, MockTimer>, _> =
+            LEDMatrix::with_frame_rate(rows, cols, Hertz(30));
+        assert_eq!(matrix.err(), Some(FrameRateOutOfRange(Hertz(30))));
+
+        // a row rendered at the refresh rate, however many rows there are
+        let (rows, cols) = pins();
+        let matrix: LEDMatrix<MockPin, U3, U3, MockTimer> = LEDMatrix::new(rows, cols, Hertz(200));
+        assert_eq!(matrix.row_period, Milliseconds(5u32));
+    }
+
+    fn levels(pins: &[MockPin]) -> [bool; 3] {
+        [pins[0].high, pins[1].high, pins[2].high]
+    }
+
+    #[test]
+    fn test_double_buffer() {
+        let mut double: LEDMatrix<MockPin, U3, U3, MockTimer> = matrix().with_double_buffer();
+        for command in [MatrixCommand::On(0, 0), MatrixCommand::On(1, 1)] {
+            double = match double.on_notify(command) {
+                Completion::Immediate(double) => double,
+                Completion::Defer(_) => panic!("deferred"),
+            };
+        }
+        // drawn, but not shown
+        assert_eq!(rows(double.back_buffer.unwrap()), [0b001, 0b010, 0b000]);
+        assert_eq!(rows(double.displayed()), [0, 0, 0]);
+        double.render();
+        assert!(double.pin_cols.iter().all(|col| col.high));
+
+        let mut double = match double.on_notify(MatrixCommand::Present) {
+            Completion::Immediate(double) => double,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(rows(double.displayed()), [0b001, 0b010, 0b000]);
+
+        // the next frame is drawn over the last
+        double.off(0, 0);
+        double.on(2, 2);
+        assert_eq!(rows(double.displayed()), [0b001, 0b010, 0b000]);
+        double.present();
+        assert_eq!(rows(double.displayed()), [0b000, 0b010, 0b100]);
+
+        // single-buffered, drawing shows straight away, and presenting does nothing
+        let mut single: LEDMatrix<MockPin, U3, U3, MockTimer> = matrix();
+        single.on(0, 0);
+        assert_eq!(rows(single.displayed()), [0b001, 0, 0]);
+        single.present();
+        assert_eq!(rows(single.displayed()), [0b001, 0, 0]);
+    }
 }
@@ -1,7 +1,11 @@
 pub mod blinker;
+pub mod chaser;
 pub mod matrix;
+pub mod neopixel;
 pub mod simple;
 
-pub use blinker::Blinker;
-pub use matrix::{LEDMatrix, MatrixCommand};
+pub use blinker::{BlinkCommand, Blinker, BlinkerConfig};
+pub use chaser::Chaser;
+pub use matrix::{FrameRateOutOfRange, LEDMatrix, MatrixCommand, MIN_FRAME_RATE};
+pub use neopixel::{NeoPixel, Rgb};
 pub use simple::SimpleLED;
@@ -0,0 +1,219 @@
+//! WS2812 ("NeoPixel") addressable RGB LED strips.
+//!
+//! Pixel colours are buffered by the actor, and nothing reaches the strip until `flush()`
+//! is requested.
+//!
+//! # Backend
+//!
+//! The WS2812 protocol encodes each bit as a 1.25µs pulse whose high time tells a 0 from
+//! a 1, and latches the frame once the line idles low for longer than the reset time.
+//! Rather than bit-banging a GPIO, the waveform is generated by an SPI peripheral, which
+//! must be clocked at 3.2MHz with only its MOSI line wired to the strip's data input.
+//! Each data bit is sent as four SPI bits, `1000` for a 0 and `1110` for a 1, giving
+//! high times of 312ns and 937ns.
+//!
+//! An interrupt stalling the SPI transfer mid-frame would latch a partial frame, so
+//! `flush()` writes the whole frame within a critical section. Interrupts are disabled
+//! for 30µs per pixel, plus the reset time of about 350µs: roughly 0.6ms for an
+//! 8-pixel stick, or 4.2ms for a 128-pixel strip.
+
+use crate::prelude::*;
+use embedded_hal::blocking::spi::Write;
+
+/// Number of SPI bytes encoding one pixel: 24 data bits of four SPI bits each.
+const SPI_BYTES_PER_PIXEL: usize = 12;
+
+/// Low SPI bytes holding the line idle long enough to latch the frame, even for
+/// newer parts requiring 280µs.
+const RESET_BYTES: usize = 140;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+/// Encode `pixel` as sent over SPI, in the strip's green-red-blue order.
+pub fn encode(pixel: Rgb) -> [u8; SPI_BYTES_PER_PIXEL] {
+    let mut encoded = [0; SPI_BYTES_PER_PIXEL];
+    for (i, byte) in [pixel.g, pixel.r, pixel.b].iter().enumerate() {
+        // each SPI byte carries two data bits, most significant first
+        for pair in 0..4 {
+            let high = byte >> (7 - pair * 2) & 1;
+            let low = byte >> (6 - pair * 2) & 1;
+            encoded[i * 4 + pair] = symbol(high) << 4 | symbol(low);
+        }
+    }
+    encoded
+}
+
+fn symbol(bit: u8) -> u8 {
+    if bit == 0 {
+        0b1000
+    } else {
+        0b1110
+    }
+}
+
+/// Write `pixels` to the strip, followed by the reset time latching them.
+pub fn write_frame<S: Write<u8>>(spi: &mut S, pixels: &[Rgb]) -> Result<(), S::Error> {
+    for pixel in pixels {
+        spi.write(&encode(*pixel))?;
+    }
+    spi.write(&[0; RESET_BYTES])
+}
+
+pub struct NeoPixel<P, const N: usize>
+where
+    P: Write<u8> + 'static,
+{
+    spi: P,
+    pixels: [Rgb; N],
+}
+
+impl<P, const N: usize> NeoPixel<P, N>
+where
+    P: Write<u8>,
+{
+    /// Drive a strip of `N` pixels with `spi`, clocked at 3.2MHz.
+    pub fn new(spi: P) -> Self {
+        Self {
+            spi,
+            pixels: [Rgb::default(); N],
+        }
+    }
+
+    /// Set the colour of pixel `i`, ignoring pixels beyond the strip.
+    pub fn set_pixel(&mut self, i: usize, rgb: Rgb) {
+        if let Some(pixel) = self.pixels.get_mut(i) {
+            *pixel = rgb;
+        }
+    }
+
+    /// Write the buffered pixels to the strip, with interrupts disabled.
+    pub fn flush(&mut self) -> Result<(), P::Error> {
+        let (spi, pixels) = (&mut self.spi, &self.pixels);
+        cortex_m::interrupt::free(|_| write_frame(spi, pixels))
+    }
+}
+
+impl<P, const N: usize> Actor for NeoPixel<P, N> where P: Write<u8> {}
+
+pub struct SetPixel {
+    pub i: usize,
+    pub rgb: Rgb,
+}
+
+pub struct Fill(pub Rgb);
+
+pub struct Flush;
+
+impl<P, const N: usize> NotifyHandler<SetPixel> for NeoPixel<P, N>
+where
+    P: Write<u8>,
+{
+    fn on_notify(mut self, message: SetPixel) -> Completion<Self> {
+        self.set_pixel(message.i, message.rgb);
+        Completion::immediate(self)
+    }
+}
+
+impl<P, const N: usize> NotifyHandler<Fill> for NeoPixel<P, N>
+where
+    P: Write<u8>,
+{
+    fn on_notify(mut self, message: Fill) -> Completion<Self> {
+        self.pixels = [message.0; N];
+        Completion::immediate(self)
+    }
+}
+
+impl<P, const N: usize> RequestHandler<Flush> for NeoPixel<P, N>
+where
+    P: Write<u8>,
+{
+    type Response = Result<(), P::Error>;
+
+    fn on_request(mut self, _: Flush) -> Response<Self, Self::Response> {
+        let result = NeoPixel::flush(&mut self);
+        Response::immediate(self, result)
+    }
+}
+
+impl<P, const N: usize> Address<NeoPixel<P, N>>
+where
+    P: Write<u8>,
+{
+    /// Set the colour of pixel `i` in the buffer.
+    pub fn set_pixel(&self, i: usize, rgb: Rgb) {
+        self.notify(SetPixel { i, rgb })
+    }
+
+    /// Set the colour of every pixel in the buffer.
+    pub fn fill(&self, rgb: Rgb) {
+        self.notify(Fill(rgb))
+    }
+
+    /// Write the buffered pixels to the strip.
+    pub async fn flush(&self) -> Result<(), P::Error> {
+        self.request(Flush).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::{consts::*, Vec};
+
+    #[derive(Default)]
+    struct MockSpi {
+        sent: Vec<u8, U256>,
+    }
+
+    impl Write<u8> for MockSpi {
+        type Error = ();
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            self.sent.extend_from_slice(words).map_err(|_| ())
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(Rgb::new(0, 0, 0)), [0x88; 12]);
+        // green first, each bit as a 1110 or 1000 nibble
+        assert_eq!(
+            encode(Rgb::new(0x00, 0xA5, 0xFF)),
+            [0xE8, 0xE8, 0x8E, 0x8E, 0x88, 0x88, 0x88, 0x88, 0xEE, 0xEE, 0xEE, 0xEE]
+        );
+    }
+
+    #[test]
+    fn test_two_pixel_frame() {
+        let mut strip = NeoPixel::<_, 2>::new(MockSpi::default());
+        strip.set_pixel(0, Rgb::new(0xFF, 0x00, 0x00));
+        strip.set_pixel(1, Rgb::new(0x00, 0x00, 0x01));
+        strip.set_pixel(2, Rgb::new(0xFF, 0xFF, 0xFF));
+
+        let mut spi = MockSpi::default();
+        write_frame(&mut spi, &strip.pixels).unwrap();
+
+        #[rustfmt::skip]
+        let expected: [u8; 24] = [
+            // pixel 0: G=0x00 R=0xFF B=0x00
+            0x88, 0x88, 0x88, 0x88, 0xEE, 0xEE, 0xEE, 0xEE, 0x88, 0x88, 0x88, 0x88,
+            // pixel 1: G=0x00 R=0x00 B=0x01
+            0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x8E,
+        ];
+        assert_eq!(spi.sent.len(), expected.len() + RESET_BYTES);
+        assert_eq!(&spi.sent[..24], &expected[..]);
+        assert!(spi.sent[24..].iter().all(|b| *b == 0));
+    }
+}
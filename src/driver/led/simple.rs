@@ -1,4 +1,4 @@
-use crate::hal::gpio::ActiveOutput;
+use crate::hal::gpio::{ActiveOutput, OutputMode};
 use crate::hal::Active;
 use crate::prelude::*;
 use core::marker::PhantomData;
@@ -8,17 +8,38 @@ pub struct On;
 
 pub struct Off;
 
+pub struct Toggle;
+
 pub trait Switchable: Actor + NotifyHandler<On> + NotifyHandler<Off> {
     fn turn_on(&mut self);
     fn turn_off(&mut self);
+
+    /// Whether the switch was last turned on, as cached by the implementation.
+    fn is_on(&self) -> bool;
+
+    /// Turn the switch off if on, and on otherwise, returning whether it is now on.
+    fn toggle(&mut self) -> bool {
+        if self.is_on() {
+            self.turn_off();
+        } else {
+            self.turn_on();
+        }
+        self.is_on()
+    }
 }
 
+/// An LED on a single output, lit while the output is active as `A`.
+///
+/// Driven open-drain, the output can only sink current, so the LED is lit by driving
+/// the pin low, and put out by releasing it, whatever `A`.
 pub struct SimpleLED<P, A>
 where
     P: OutputPin,
     A: ActiveOutput,
 {
     pin: P,
+    mode: OutputMode,
+    on: bool,
     _active: PhantomData<A>,
 }
 
@@ -30,9 +51,17 @@ where
     pub fn new(pin: P, active: Active) -> Self {
         Self {
             pin,
+            mode: OutputMode::PushPull,
+            on: false,
             _active: PhantomData,
         }
     }
+
+    /// Drive the LED in `mode` rather than push-pull.
+    pub fn with_output_mode(mut self, mode: OutputMode) -> Self {
+        self.mode = mode;
+        self
+    }
 }
 
 impl<P, A> Switchable for SimpleLED<P, A>
@@ -41,11 +70,23 @@ where
     A: ActiveOutput + 'static,
 {
     fn turn_on(&mut self) {
-        A::set_active(&mut self.pin).ok();
+        match self.mode {
+            OutputMode::PushPull => A::set_active(&mut self.pin).ok(),
+            OutputMode::OpenDrain => self.pin.set_low().ok(),
+        };
+        self.on = true;
     }
 
     fn turn_off(&mut self) {
-        A::set_inactive(&mut self.pin).ok();
+        match self.mode {
+            OutputMode::PushPull => A::set_inactive(&mut self.pin).ok(),
+            OutputMode::OpenDrain => self.pin.set_high().ok(),
+        };
+        self.on = false;
+    }
+
+    fn is_on(&self) -> bool {
+        self.on
     }
 }
 
@@ -80,6 +121,17 @@ where
     }
 }
 
+impl<P, A> NotifyHandler<Toggle> for SimpleLED<P, A>
+where
+    P: OutputPin + 'static,
+    A: ActiveOutput + 'static,
+{
+    fn on_notify(mut self, message: Toggle) -> Completion<Self> {
+        self.toggle();
+        Completion::immediate(self)
+    }
+}
+
 impl<S> Address<S>
 where
     S: NotifyHandler<Off> + NotifyHandler<On>,
@@ -93,3 +145,102 @@ where
         self.notify(Off);
     }
 }
+
+impl<S> Address<S>
+where
+    S: NotifyHandler<Toggle>,
+    S: Actor + 'static,
+{
+    pub fn toggle(&self) {
+        self.notify(Toggle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hal::gpio::{ActiveHigh, ActiveLow};
+    use core::convert::Infallible;
+
+    #[derive(Default)]
+    struct MockPin {
+        high: bool,
+        writes: usize,
+    }
+
+    impl OutputPin for MockPin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.high = false;
+            self.writes += 1;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.high = true;
+            self.writes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut led: SimpleLED<MockPin, ActiveLow> =
+            SimpleLED::new(MockPin::default(), Active::Low);
+        for i in 0..6 {
+            let on = led.toggle();
+            assert_eq!(on, i % 2 == 0);
+            assert_eq!(led.is_on(), on);
+            // active low: lit while the pin is low
+            assert_eq!(led.pin.high, !on);
+        }
+        assert_eq!(led.pin.writes, 6);
+
+        led.turn_on();
+        assert!(!led.toggle());
+        assert!(led.pin.high);
+    }
+
+    /// An open-drain output, sinking current while driven low, and floating otherwise.
+    #[derive(Default)]
+    struct OpenDrainPin {
+        sinking: bool,
+    }
+
+    impl OutputPin for OpenDrainPin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.sinking = true;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.sinking = false;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_open_drain() {
+        // lit by driving low, even where active high is assumed
+        let mut led: SimpleLED<OpenDrainPin, ActiveHigh> =
+            SimpleLED::new(OpenDrainPin::default(), Active::High)
+                .with_output_mode(OutputMode::OpenDrain);
+        led.turn_on();
+        assert!(led.pin.sinking);
+        led.turn_off();
+        assert!(!led.pin.sinking);
+        assert!(led.toggle());
+        assert!(led.pin.sinking);
+
+        let mut led: SimpleLED<OpenDrainPin, ActiveLow> =
+            SimpleLED::new(OpenDrainPin::default(), Active::Low)
+                .with_output_mode(OutputMode::OpenDrain);
+        led.turn_on();
+        assert!(led.pin.sinking);
+        led.turn_off();
+        assert!(!led.pin.sinking);
+    }
+}
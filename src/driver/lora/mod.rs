@@ -0,0 +1,17 @@
+//! LoRa radios, for long-range, low-rate telemetry.
+//!
+//! A radio listens for packets whenever it is not sending, publishing each one received
+//! as a `LoRaRx` event, and sends the `Payload`s given to it in between.
+
+pub mod sx127x;
+
+pub use sx127x::{LoRa, Sx127x};
+
+use heapless::{consts::*, Vec};
+
+/// The payload of a single packet, up to the 255 bytes of the radio's FIFO.
+pub type Payload = Vec<u8, U255>;
+
+/// A packet received, with its signal strength in dBm.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoRaRx(pub Payload, pub i16);
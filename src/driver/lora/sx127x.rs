@@ -0,0 +1,606 @@
+//! Semtech SX1276/77/78/79 radios in LoRa mode, over SPI.
+//!
+//! The `Sx127x` actor shares a `SpiPeripheral` with other devices through its mutex,
+//! holding the lock for each exchange with the radio. The chip select of the radio is
+//! expected to be driven by the peripheral for the duration of each transfer.
+//!
+//! The radio raises DIO0 when a packet has been sent or received. The `LoRa` package
+//! mounts a `Dio0` interrupt on the pin alongside the radio, which reads the interrupt
+//! flags to tell the two apart: once a packet is sent, the radio returns to listening,
+//! and a packet received is read from the FIFO and published with its RSSI.
+
+use crate::bind::Bind;
+use crate::driver::lora::{LoRaRx, Payload};
+use crate::driver::spi::{SpiBus, SpiPeripheral};
+use crate::hal::gpio::exti_pin::ExtiPin;
+use crate::hal::spi::SpiDma;
+use crate::handler::EventHandler;
+use crate::package::Package;
+use crate::prelude::*;
+use crate::synchronization::MutexActor;
+use cortex_m::interrupt::Nr;
+use embedded_hal::digital::v2::InputPin;
+
+const REG_FIFO: u8 = 0x00;
+const REG_OP_MODE: u8 = 0x01;
+const REG_FRF_MSB: u8 = 0x06;
+const REG_FRF_MID: u8 = 0x07;
+const REG_FRF_LSB: u8 = 0x08;
+const REG_FIFO_ADDR_PTR: u8 = 0x0D;
+const REG_FIFO_TX_BASE_ADDR: u8 = 0x0E;
+const REG_FIFO_RX_BASE_ADDR: u8 = 0x0F;
+const REG_FIFO_RX_CURRENT_ADDR: u8 = 0x10;
+const REG_IRQ_FLAGS: u8 = 0x12;
+const REG_RX_NB_BYTES: u8 = 0x13;
+const REG_PKT_RSSI_VALUE: u8 = 0x1A;
+const REG_MODEM_CONFIG_1: u8 = 0x1D;
+const REG_MODEM_CONFIG_2: u8 = 0x1E;
+const REG_PAYLOAD_LENGTH: u8 = 0x22;
+const REG_MODEM_CONFIG_3: u8 = 0x26;
+const REG_DIO_MAPPING_1: u8 = 0x40;
+
+/// Set in the address byte of a register write.
+const WRITE: u8 = 0x80;
+
+const LONG_RANGE_MODE: u8 = 0x80;
+const MODE_SLEEP: u8 = 0x00;
+const MODE_STANDBY: u8 = 0x01;
+const MODE_TX: u8 = 0x03;
+const MODE_RX_CONTINUOUS: u8 = 0x05;
+
+const IRQ_RX_DONE: u8 = 0x40;
+const IRQ_PAYLOAD_CRC_ERROR: u8 = 0x20;
+const IRQ_TX_DONE: u8 = 0x08;
+
+const RX_PAYLOAD_CRC_ON: u8 = 0x04;
+const LOW_DATA_RATE_OPTIMIZE: u8 = 0x08;
+const AGC_AUTO_ON: u8 = 0x04;
+
+const DIO0_RX_DONE: u8 = 0x00;
+const DIO0_TX_DONE: u8 = 0x40;
+
+/// The frequency of the crystal, from which the carrier frequency is synthesized.
+const F_XOSC: u64 = 32_000_000;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpreadingFactor {
+    Sf7 = 7,
+    Sf8,
+    Sf9,
+    Sf10,
+    Sf11,
+    Sf12,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Bandwidth {
+    Khz7_8,
+    Khz10_4,
+    Khz15_6,
+    Khz20_8,
+    Khz31_25,
+    Khz41_7,
+    Khz62_5,
+    Khz125,
+    Khz250,
+    Khz500,
+}
+
+impl Bandwidth {
+    fn hz(&self) -> u32 {
+        match self {
+            Bandwidth::Khz7_8 => 7_800,
+            Bandwidth::Khz10_4 => 10_400,
+            Bandwidth::Khz15_6 => 15_600,
+            Bandwidth::Khz20_8 => 20_800,
+            Bandwidth::Khz31_25 => 31_250,
+            Bandwidth::Khz41_7 => 41_700,
+            Bandwidth::Khz62_5 => 62_500,
+            Bandwidth::Khz125 => 125_000,
+            Bandwidth::Khz250 => 250_000,
+            Bandwidth::Khz500 => 500_000,
+        }
+    }
+}
+
+/// The ratio of data bits to coded bits sent.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CodingRate {
+    Cr4_5 = 1,
+    Cr4_6,
+    Cr4_7,
+    Cr4_8,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoRaConfig {
+    /// The carrier frequency, in Hz.
+    pub frequency: u32,
+    pub spreading_factor: SpreadingFactor,
+    pub bandwidth: Bandwidth,
+    pub coding_rate: CodingRate,
+}
+
+impl LoRaConfig {
+    /// The value of the frequency registers, most significant byte first.
+    fn frf(&self) -> [u8; 3] {
+        let frf = ((self.frequency as u64) << 19) / F_XOSC;
+        [(frf >> 16) as u8, (frf >> 8) as u8, frf as u8]
+    }
+
+    /// Whether symbols last longer than 16ms, as the radio must be told.
+    fn low_data_rate(&self) -> bool {
+        (1u32 << self.spreading_factor as u32) * 1000 / self.bandwidth.hz() > 16
+    }
+
+    /// The RSSI of a packet in dBm, from the value of its register. The offset differs
+    /// between the low and high frequency ports.
+    fn rssi(&self, value: u8) -> i16 {
+        if self.frequency > 779_000_000 {
+            -157 + value as i16
+        } else {
+            -164 + value as i16
+        }
+    }
+}
+
+impl Default for LoRaConfig {
+    /// 868.1MHz, the first EU868 channel, at SF7 and 125kHz with a 4/5 coding rate.
+    fn default() -> Self {
+        Self {
+            frequency: 868_100_000,
+            spreading_factor: SpreadingFactor::Sf7,
+            bandwidth: Bandwidth::Khz125,
+            coding_rate: CodingRate::Cr4_5,
+        }
+    }
+}
+
+async fn write_register<B: SpiBus>(bus: &mut B, register: u8, value: u8) -> Result<(), B::Error> {
+    bus.write(&[register | WRITE, value]).await
+}
+
+async fn read_register<B: SpiBus>(bus: &mut B, register: u8) -> Result<u8, B::Error> {
+    let mut rx = [0; 2];
+    bus.transfer(&[register & !WRITE, 0], &mut rx).await?;
+    Ok(rx[1])
+}
+
+/// Put the radio into LoRa mode with `config`, leaving it in standby.
+pub async fn configure<B: SpiBus>(bus: &mut B, config: &LoRaConfig) -> Result<(), B::Error> {
+    // the long range mode may only be changed while asleep
+    write_register(bus, REG_OP_MODE, LONG_RANGE_MODE | MODE_SLEEP).await?;
+    let [msb, mid, lsb] = config.frf();
+    write_register(bus, REG_FRF_MSB, msb).await?;
+    write_register(bus, REG_FRF_MID, mid).await?;
+    write_register(bus, REG_FRF_LSB, lsb).await?;
+    // the whole FIFO is used by whichever direction is active
+    write_register(bus, REG_FIFO_TX_BASE_ADDR, 0).await?;
+    write_register(bus, REG_FIFO_RX_BASE_ADDR, 0).await?;
+    write_register(
+        bus,
+        REG_MODEM_CONFIG_1,
+        (config.bandwidth as u8) << 4 | (config.coding_rate as u8) << 1,
+    )
+    .await?;
+    write_register(
+        bus,
+        REG_MODEM_CONFIG_2,
+        (config.spreading_factor as u8) << 4 | RX_PAYLOAD_CRC_ON,
+    )
+    .await?;
+    let low_data_rate = if config.low_data_rate() {
+        LOW_DATA_RATE_OPTIMIZE
+    } else {
+        0
+    };
+    write_register(bus, REG_MODEM_CONFIG_3, low_data_rate | AGC_AUTO_ON).await?;
+    write_register(bus, REG_OP_MODE, LONG_RANGE_MODE | MODE_STANDBY).await
+}
+
+/// Listen for packets, raising DIO0 as each is received.
+pub async fn listen<B: SpiBus>(bus: &mut B) -> Result<(), B::Error> {
+    write_register(bus, REG_DIO_MAPPING_1, DIO0_RX_DONE).await?;
+    write_register(bus, REG_OP_MODE, LONG_RANGE_MODE | MODE_RX_CONTINUOUS).await
+}
+
+/// Send `payload`, raising DIO0 once it is sent.
+pub async fn send<B: SpiBus>(bus: &mut B, payload: &Payload) -> Result<(), B::Error> {
+    write_register(bus, REG_OP_MODE, LONG_RANGE_MODE | MODE_STANDBY).await?;
+    write_register(bus, REG_FIFO_ADDR_PTR, 0).await?;
+    let mut fifo = [0; 256];
+    fifo[0] = REG_FIFO | WRITE;
+    fifo[1..=payload.len()].copy_from_slice(payload);
+    bus.write(&fifo[..=payload.len()]).await?;
+    write_register(bus, REG_PAYLOAD_LENGTH, payload.len() as u8).await?;
+    write_register(bus, REG_DIO_MAPPING_1, DIO0_TX_DONE).await?;
+    write_register(bus, REG_OP_MODE, LONG_RANGE_MODE | MODE_TX).await
+}
+
+/// What raised DIO0.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dio0Event {
+    TxDone,
+    /// A packet was received, to be read with `read_packet`.
+    RxDone,
+    /// A packet was received, but failed its CRC.
+    CrcError,
+}
+
+/// Read and clear the interrupt flags, returning what raised DIO0, if anything.
+pub async fn dio0<B: SpiBus>(bus: &mut B) -> Result<Option<Dio0Event>, B::Error> {
+    let flags = read_register(bus, REG_IRQ_FLAGS).await?;
+    write_register(bus, REG_IRQ_FLAGS, flags).await?;
+    Ok(if flags & IRQ_TX_DONE != 0 {
+        Some(Dio0Event::TxDone)
+    } else if flags & IRQ_RX_DONE == 0 {
+        None
+    } else if flags & IRQ_PAYLOAD_CRC_ERROR != 0 {
+        Some(Dio0Event::CrcError)
+    } else {
+        Some(Dio0Event::RxDone)
+    })
+}
+
+/// Read the packet last received from the FIFO, with its RSSI.
+pub async fn read_packet<B: SpiBus>(bus: &mut B, config: &LoRaConfig) -> Result<LoRaRx, B::Error> {
+    let len = read_register(bus, REG_RX_NB_BYTES).await? as usize;
+    let current = read_register(bus, REG_FIFO_RX_CURRENT_ADDR).await?;
+    write_register(bus, REG_FIFO_ADDR_PTR, current).await?;
+    let mut tx = [0; 256];
+    tx[0] = REG_FIFO;
+    let mut rx = [0; 256];
+    bus.transfer(&tx[..=len], &mut rx[..=len]).await?;
+    let rssi = config.rssi(read_register(bus, REG_PKT_RSSI_VALUE).await?);
+
+    let mut payload = Payload::new();
+    // the FIFO holds at most 255 bytes, so always fits
+    payload.extend_from_slice(&rx[1..=len]).ok();
+    Ok(LoRaRx(payload, rssi))
+}
+
+pub struct Sx127x<D, S>
+where
+    D: Device + EventHandler<LoRaRx> + 'static,
+    S: SpiDma + 'static,
+{
+    config: LoRaConfig,
+    spi: Option<Address<MutexActor<SpiPeripheral<S>>>>,
+    bus: Option<Address<EventBus<D>>>,
+}
+
+impl<D, S> Sx127x<D, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    S: SpiDma,
+{
+    pub fn new(config: LoRaConfig) -> Self {
+        Self {
+            config,
+            spi: None,
+            bus: None,
+        }
+    }
+}
+
+impl<D, S> Actor for Sx127x<D, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    S: SpiDma,
+{
+    fn on_initialize(self) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(spi) = self.spi {
+                let mut spi = spi.lock().await;
+                let configured = configure(&mut *spi, &self.config).await;
+                if configured.and(listen(&mut *spi).await).is_err() {
+                    warn!("[sx127x] failed to configure radio");
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<D, S> Bind<EventBus<D>> for Sx127x<D, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    S: SpiDma,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, S> Bind<MutexActor<SpiPeripheral<S>>> for Sx127x<D, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    S: SpiDma,
+{
+    fn on_bind(&mut self, address: Address<MutexActor<SpiPeripheral<S>>>) {
+        self.spi.replace(address);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Send(pub Payload);
+
+impl<D, S> NotifyHandler<Send> for Sx127x<D, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    S: SpiDma,
+{
+    fn on_notify(self, message: Send) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(spi) = self.spi {
+                let mut spi = spi.lock().await;
+                if send(&mut *spi, &message.0).await.is_err() {
+                    warn!("[sx127x] failed to send packet");
+                }
+            }
+            self
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Dio0Raised;
+
+impl<D, S> NotifyHandler<Dio0Raised> for Sx127x<D, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    S: SpiDma,
+{
+    fn on_notify(self, _: Dio0Raised) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(spi) = self.spi {
+                let mut spi = spi.lock().await;
+                match dio0(&mut *spi).await {
+                    Ok(Some(Dio0Event::TxDone)) => {
+                        if listen(&mut *spi).await.is_err() {
+                            warn!("[sx127x] failed to resume listening");
+                        }
+                    }
+                    Ok(Some(Dio0Event::RxDone)) => match read_packet(&mut *spi, &self.config).await
+                    {
+                        Ok(rx) => {
+                            if let Some(bus) = self.bus {
+                                bus.publish(rx);
+                            }
+                        }
+                        Err(_) => warn!("[sx127x] failed to read packet"),
+                    },
+                    Ok(Some(Dio0Event::CrcError)) => warn!("[sx127x] dropped corrupt packet"),
+                    Ok(None) => {}
+                    Err(_) => warn!("[sx127x] failed to read interrupt flags"),
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<D, S> Address<Sx127x<D, S>>
+where
+    D: Device + EventHandler<LoRaRx> + 'static,
+    S: SpiDma + 'static,
+{
+    /// Send `payload`, listening again once it is sent.
+    pub fn send(&self, payload: Payload) {
+        self.notify(Send(payload))
+    }
+}
+
+/// Notifies the radio as its DIO0 pin is raised.
+pub struct Dio0<D, P, S>
+where
+    D: Device + EventHandler<LoRaRx> + 'static,
+    P: InputPin + ExtiPin + 'static,
+    S: SpiDma + 'static,
+{
+    pin: P,
+    radio: Option<Address<Sx127x<D, S>>>,
+}
+
+impl<D, P, S> Dio0<D, P, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    P: InputPin + ExtiPin,
+    S: SpiDma,
+{
+    pub fn new(pin: P) -> Self {
+        Self { pin, radio: None }
+    }
+}
+
+impl<D, P, S> Actor for Dio0<D, P, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    P: InputPin + ExtiPin,
+    S: SpiDma,
+{
+}
+
+impl<D, P, S> Interrupt for Dio0<D, P, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    P: InputPin + ExtiPin,
+    S: SpiDma,
+{
+    fn on_interrupt(&mut self) {
+        if self.pin.check_interrupt() {
+            if let Some(radio) = self.radio {
+                radio.notify(Dio0Raised);
+            }
+            self.pin.clear_interrupt_pending_bit();
+        }
+    }
+}
+
+impl<D, P, S> Bind<Sx127x<D, S>> for Dio0<D, P, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    P: InputPin + ExtiPin,
+    S: SpiDma,
+{
+    fn on_bind(&mut self, address: Address<Sx127x<D, S>>) {
+        self.radio.replace(address);
+    }
+}
+
+/// A radio with its DIO0 interrupt. The radio must still be bound to the SPI peripheral
+/// it is wired to.
+pub struct LoRa<D, P, S>
+where
+    D: Device + EventHandler<LoRaRx> + 'static,
+    P: InputPin + ExtiPin + 'static,
+    S: SpiDma + 'static,
+{
+    radio: ActorContext<Sx127x<D, S>>,
+    dio0: InterruptContext<Dio0<D, P, S>>,
+}
+
+impl<D, P, S> LoRa<D, P, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    P: InputPin + ExtiPin,
+    S: SpiDma,
+{
+    pub fn new<N: Nr>(config: LoRaConfig, dio0: P, irq: N) -> Self {
+        Self {
+            radio: ActorContext::new(Sx127x::new(config)).with_name("sx127x"),
+            dio0: InterruptContext::new(Dio0::new(dio0), irq).with_name("sx127x-dio0"),
+        }
+    }
+}
+
+impl<D, P, S> Package<D, Sx127x<D, S>> for LoRa<D, P, S>
+where
+    D: Device + EventHandler<LoRaRx>,
+    P: InputPin + ExtiPin,
+    S: SpiDma,
+{
+    fn mount(
+        &'static self,
+        bus_address: Address<EventBus<D>>,
+        supervisor: &mut Supervisor,
+    ) -> Address<Sx127x<D, S>> {
+        let dio0 = self.dio0.mount(supervisor);
+        let radio = self.radio.mount(supervisor);
+        radio.bind(bus_address);
+        dio0.bind(radio);
+        radio
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::block_on;
+
+    /// Checks each transfer against a script of the bytes expected out, answering with
+    /// the bytes scripted in.
+    struct MockSpi {
+        script: &'static [(&'static [u8], &'static [u8])],
+        next: usize,
+    }
+
+    impl MockSpi {
+        fn new(script: &'static [(&'static [u8], &'static [u8])]) -> Self {
+            Self { script, next: 0 }
+        }
+
+        fn finished(&self) -> bool {
+            self.next == self.script.len()
+        }
+    }
+
+    impl SpiBus for MockSpi {
+        type Error = ();
+
+        async fn transfer(&mut self, tx: &[u8], rx: &mut [u8]) -> Result<(), ()> {
+            let (expected, response) = self.script[self.next];
+            assert_eq!(tx, expected, "transfer {}", self.next);
+            rx.copy_from_slice(response);
+            self.next += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_configure() {
+        let config = LoRaConfig {
+            frequency: 868_000_000,
+            spreading_factor: SpreadingFactor::Sf12,
+            bandwidth: Bandwidth::Khz125,
+            coding_rate: CodingRate::Cr4_5,
+        };
+        let mut spi = MockSpi::new(&[
+            (&[0x81, 0x80], &[]),
+            (&[0x86, 0xD9], &[]),
+            (&[0x87, 0x00], &[]),
+            (&[0x88, 0x00], &[]),
+            (&[0x8E, 0x00], &[]),
+            (&[0x8F, 0x00], &[]),
+            (&[0x9D, 0x72], &[]),
+            (&[0x9E, 0xC4], &[]),
+            // 32ms symbols need the low data rate optimization
+            (&[0xA6, 0x0C], &[]),
+            (&[0x81, 0x81], &[]),
+        ]);
+        block_on(configure(&mut spi, &config)).unwrap();
+        assert!(spi.finished());
+    }
+
+    #[test]
+    fn test_send() {
+        let mut spi = MockSpi::new(&[
+            (&[0x81, 0x81], &[]),
+            (&[0x8D, 0x00], &[]),
+            (&[0x80, b'p', b'i', b'n', b'g'], &[]),
+            (&[0xA2, 0x04], &[]),
+            (&[0xC0, 0x40], &[]),
+            (&[0x81, 0x83], &[]),
+        ]);
+        let payload = Payload::from_slice(b"ping").unwrap();
+        block_on(send(&mut spi, &payload)).unwrap();
+        assert!(spi.finished());
+
+        let mut spi = MockSpi::new(&[(&[0x12, 0x00], &[0x00, 0x08]), (&[0x92, 0x08], &[])]);
+        let event = block_on(dio0(&mut spi)).unwrap();
+        assert_eq!(event, Some(Dio0Event::TxDone));
+        assert!(spi.finished());
+    }
+
+    #[test]
+    fn test_receive() {
+        let mut spi = MockSpi::new(&[
+            (&[0x12, 0x00], &[0x00, 0x50]),
+            (&[0x92, 0x50], &[]),
+            (&[0x13, 0x00], &[0x00, 0x03]),
+            (&[0x10, 0x00], &[0x00, 0x20]),
+            (&[0x8D, 0x20], &[]),
+            (&[0x00, 0x00, 0x00, 0x00], &[0x00, b'a', b'c', b'k']),
+            (&[0x1A, 0x00], &[0x00, 0x5A]),
+        ]);
+        assert_eq!(block_on(dio0(&mut spi)).unwrap(), Some(Dio0Event::RxDone));
+        let rx = block_on(read_packet(&mut spi, &LoRaConfig::default())).unwrap();
+        // 90 above the -157dBm floor of the high frequency port
+        assert_eq!(rx, LoRaRx(Payload::from_slice(b"ack").unwrap(), -67));
+        assert!(spi.finished());
+
+        let mut spi = MockSpi::new(&[(&[0x12, 0x00], &[0x00, 0x60]), (&[0x92, 0x60], &[])]);
+        assert_eq!(block_on(dio0(&mut spi)).unwrap(), Some(Dio0Event::CrcError));
+        assert!(spi.finished());
+    }
+
+    #[test]
+    fn test_rssi() {
+        let config = LoRaConfig {
+            frequency: 433_000_000,
+            ..LoRaConfig::default()
+        };
+        assert_eq!(config.rssi(0x5A), -74);
+        assert!(!LoRaConfig::default().low_data_rate());
+    }
+}
@@ -1,7 +1,9 @@
 use crate::handler::{Completion, Response};
 use crate::prelude::{Actor, NotifyHandler, RequestHandler, ActorInfo};
 
+use crate::alloc::cortex_m::CortexMHeap;
 use crate::alloc::HEAP;
+use core::ptr::addr_of;
 
 pub struct Query;
 pub struct Info {
@@ -9,12 +11,89 @@ pub struct Info {
     pub free: usize,
 }
 
+/// The sizing of a heap, as reported by `Memory`.
+pub trait HeapStats {
+    /// The start address of the heap.
+    fn bottom(&self) -> usize;
+    /// The size of the heap in bytes.
+    fn size(&self) -> usize;
+    /// The most bytes ever in use at once.
+    fn high_water(&self) -> usize;
+}
+
+impl HeapStats for CortexMHeap {
+    fn bottom(&self) -> usize {
+        CortexMHeap::bottom(self)
+    }
+
+    fn size(&self) -> usize {
+        CortexMHeap::size(self)
+    }
+
+    fn high_water(&self) -> usize {
+        CortexMHeap::high_water(self)
+    }
+}
+
+/// How close the heap has come to exhaustion since boot.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Watermark {
+    /// The most bytes ever in use at once.
+    pub high_water: usize,
+    /// The size of the heap in bytes.
+    pub total: usize,
+}
+
+impl Watermark {
+    fn of<H: HeapStats>(heap: &H) -> Self {
+        Self {
+            high_water: heap.high_water(),
+            total: heap.size(),
+        }
+    }
+
+    /// The high-water mark as a whole percentage of the heap, rounded down.
+    pub fn percent_used(&self) -> u8 {
+        match self.total {
+            0 => 0,
+            total => (self.high_water.min(total) as u64 * 100 / total as u64) as u8,
+        }
+    }
+}
+
+/// The heap, if initialized.
+fn heap() -> Option<&'static CortexMHeap> {
+    unsafe { (*addr_of!(HEAP)).as_ref() }
+}
+
 pub struct Memory {}
 
 impl Memory {
     pub fn new() -> Self {
         Self {}
     }
+
+    /// Log where the heap lies and its size, such as once at boot.
+    pub fn report_layout() {
+        match heap() {
+            Some(heap) => Self::report_layout_of(heap),
+            None => warn!("[memory] no heap initialized"),
+        }
+    }
+
+    fn report_layout_of<H: HeapStats>(heap: &H) {
+        info!(
+            "[memory] heap at {:x}, {} bytes",
+            heap.bottom() as u32,
+            heap.size()
+        );
+    }
+
+    /// The heap's high-water mark against its size, to right-size the heap by running a
+    /// representative workload, or `None` if no heap has been initialized.
+    pub fn watermark() -> Option<Watermark> {
+        heap().map(Watermark::of)
+    }
 }
 
 impl Default for Memory {
@@ -43,7 +122,51 @@ impl NotifyHandler<Query> for Memory {
     fn on_notify(self, message: Query) -> Completion<Self> {
         let used = unsafe { HEAP.as_ref().unwrap().used() };
         let free = unsafe { HEAP.as_ref().unwrap().free() };
-        log::info!("[{}] used={}, free={}", ActorInfo::name(), used, free);
+        info!("[{}] used={}, free={}", ActorInfo::name(), used, free);
         Completion::immediate(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockHeap {
+        size: usize,
+        high_water: usize,
+    }
+
+    impl HeapStats for MockHeap {
+        fn bottom(&self) -> usize {
+            0x2000_0000
+        }
+
+        fn size(&self) -> usize {
+            self.size
+        }
+
+        fn high_water(&self) -> usize {
+            self.high_water
+        }
+    }
+
+    fn percent_used(size: usize, high_water: usize) -> u8 {
+        Watermark::of(&MockHeap { size, high_water }).percent_used()
+    }
+
+    #[test]
+    fn test_watermark() {
+        let watermark = Watermark::of(&MockHeap {
+            size: 16 * 1024,
+            high_water: 6 * 1024,
+        });
+        assert_eq!(watermark.total, 16 * 1024);
+        assert_eq!(watermark.high_water, 6 * 1024);
+        assert_eq!(watermark.percent_used(), 37);
+
+        assert_eq!(percent_used(1024, 0), 0);
+        assert_eq!(percent_used(1024, 1024), 100);
+        assert_eq!(percent_used(0, 0), 0);
+        assert_eq!(percent_used(3, 1), 33);
+    }
+}
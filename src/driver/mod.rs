@@ -1,9 +1,26 @@
 //! Device drivers.
 
+pub mod adc;
 pub mod button;
+pub mod can;
+pub mod console;
+pub mod crc;
+pub mod display;
+pub mod flash;
+pub mod gpio;
 pub mod led;
+pub mod lora;
+pub mod rtc;
 pub mod sensor;
 pub mod timer;
 pub mod uart;
+pub mod watchdog;
+pub mod wifi;
 pub mod memory;
+pub mod mqtt;
+pub mod power;
+pub mod reconfigure;
+pub mod socket;
+pub mod spi;
 pub mod i2c;
+pub mod input;
@@ -0,0 +1,358 @@
+//! MQTT 3.1.1 client, publishing and subscribing at QoS 0.
+//!
+//! The client owns a single `Socket` at a time and only reads from it while handling a
+//! request or its keep-alive. Messages on subscribed topics are therefore delivered when
+//! they are seen: while waiting for a SUBACK, on every keep-alive PINGREQ/PINGRESP
+//! exchange, or on an explicit `poll()`. Each is published to the event-bus as an
+//! `MqttMessage`, and at most 4 may be pending between deliveries; further messages are
+//! dropped, as QoS 0 permits.
+
+pub mod packet;
+pub mod session;
+
+pub use session::{Error, MqttMessage, Payload, Session, Topic};
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Seconds;
+use crate::driver::socket::Socket;
+use crate::driver::timer::TimerActor;
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+
+pub struct MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage> + 'static,
+    S: Socket + 'static,
+    T: HalTimer + 'static,
+{
+    client_id: &'static str,
+    keep_alive: Seconds,
+    session: Option<Session<S>>,
+    bus: Option<Address<EventBus<D>>>,
+    timer: Option<Address<TimerActor<T>>>,
+    address: Option<Address<Self>>,
+    /// Counts the sessions opened, to tell the keep-alive scheduled for each.
+    generation: u8,
+}
+
+impl<D, S, T> MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    /// Create a client identifying itself as `client_id`, sending a PINGREQ every
+    /// `keep_alive` while connected (`Seconds(0)` disables keep-alive).
+    pub fn new(client_id: &'static str, keep_alive: Seconds) -> Self {
+        Self {
+            client_id,
+            keep_alive,
+            session: None,
+            bus: None,
+            timer: None,
+            address: None,
+            generation: 0,
+        }
+    }
+
+    /// Replace any session by `session`, starting its keep-alive, so that the keep-alive
+    /// of the session replaced ends when next due rather than running alongside.
+    fn connected(&mut self, session: Session<S>) {
+        self.session.replace(session);
+        self.generation = self.generation.wrapping_add(1);
+        self.schedule_keep_alive();
+    }
+
+    fn schedule_keep_alive(&self) {
+        if self.keep_alive == Seconds(0u32) {
+            return;
+        }
+        if let (Some(timer), Some(address)) = (self.timer, self.address) {
+            let keep_alive = KeepAlive {
+                generation: self.generation,
+            };
+            timer.schedule(self.keep_alive, keep_alive, address);
+        }
+    }
+
+    /// Publish all messages received by the session to the event-bus.
+    fn deliver(&mut self) {
+        if let Some(session) = &mut self.session {
+            while let Some(message) = session.take_message() {
+                if let Some(bus) = self.bus {
+                    bus.publish(message);
+                }
+            }
+        }
+    }
+
+    /// Drop the session if `result` indicates the connection is no longer usable.
+    fn check<R>(&mut self, result: &Result<R, Error<S::Error>>) {
+        if let Err(Error::Socket(_)) | Err(Error::Closed) | Err(Error::Protocol) = result {
+            warn!("[mqtt] connection lost");
+            self.session.take();
+        }
+    }
+}
+
+impl<D, S, T> Actor for MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+}
+
+impl<D, S, T> Bind<EventBus<D>> for MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, S, T> Bind<TimerActor<T>> for MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.timer.replace(address);
+    }
+}
+
+/// Open a session over an already-connected socket, replacing any existing session.
+pub struct Connect<S: Socket>(pub S);
+
+pub struct Publish {
+    pub topic: Topic,
+    pub payload: Payload,
+}
+
+pub struct Subscribe(pub Topic);
+
+/// Wait for the next packet from the broker, delivering any message it carries.
+pub struct Poll;
+
+#[derive(Copy, Clone, Debug)]
+pub struct KeepAlive {
+    generation: u8,
+}
+
+impl<D, S, T> RequestHandler<Connect<S>> for MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    type Response = Result<(), Error<S::Error>>;
+
+    fn on_request(mut self, message: Connect<S>) -> Response<Self, Self::Response> {
+        Response::defer(async move {
+            let keep_alive = self.keep_alive.0.min(u16::MAX as u32) as u16;
+            let mut session = Session::new(message.0);
+            let result = session.connect(self.client_id, keep_alive).await;
+            if result.is_ok() {
+                self.connected(session);
+            }
+            (self, result)
+        })
+    }
+}
+
+impl<D, S, T> RequestHandler<Publish> for MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    type Response = Result<(), Error<S::Error>>;
+
+    fn on_request(mut self, message: Publish) -> Response<Self, Self::Response> {
+        Response::defer(async move {
+            let result = match &mut self.session {
+                Some(session) => session.publish(&message.topic, &message.payload).await,
+                None => Err(Error::NotConnected),
+            };
+            self.check(&result);
+            (self, result)
+        })
+    }
+}
+
+impl<D, S, T> RequestHandler<Subscribe> for MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    type Response = Result<(), Error<S::Error>>;
+
+    fn on_request(mut self, message: Subscribe) -> Response<Self, Self::Response> {
+        Response::defer(async move {
+            let result = match &mut self.session {
+                Some(session) => session.subscribe(&message.0).await,
+                None => Err(Error::NotConnected),
+            };
+            self.deliver();
+            self.check(&result);
+            (self, result)
+        })
+    }
+}
+
+impl<D, S, T> RequestHandler<Poll> for MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    type Response = Result<(), Error<S::Error>>;
+
+    fn on_request(mut self, _: Poll) -> Response<Self, Self::Response> {
+        Response::defer(async move {
+            let result = match &mut self.session {
+                Some(session) => session.poll().await,
+                None => Err(Error::NotConnected),
+            };
+            self.deliver();
+            self.check(&result);
+            (self, result)
+        })
+    }
+}
+
+impl<D, S, T> NotifyHandler<KeepAlive> for MqttClient<D, S, T>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    fn on_notify(mut self, message: KeepAlive) -> Completion<Self> {
+        if message.generation != self.generation {
+            // scheduled for a session since replaced
+            return Completion::immediate(self);
+        }
+        Completion::defer(async move {
+            if let Some(session) = &mut self.session {
+                let result = session.ping().await;
+                self.deliver();
+                self.check(&result);
+                if self.session.is_some() {
+                    self.schedule_keep_alive();
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<D, S, T> Address<MqttClient<D, S, T>>
+where
+    D: Device + EventHandler<MqttMessage>,
+    S: Socket,
+    T: HalTimer,
+{
+    /// Open a session over `socket`.
+    pub async fn connect(&self, socket: S) -> Result<(), Error<S::Error>> {
+        self.request(Connect(socket)).await
+    }
+
+    /// Publish `payload` to `topic` at QoS 0.
+    pub async fn publish(&self, topic: &str, payload: &[u8]) -> Result<(), Error<S::Error>> {
+        let mut message = Publish {
+            topic: Topic::new(),
+            payload: Payload::new(),
+        };
+        message
+            .topic
+            .push_str(topic)
+            .map_err(|_| Error::PacketTooLong)?;
+        message
+            .payload
+            .extend_from_slice(payload)
+            .map_err(|_| Error::PacketTooLong)?;
+        self.request(message).await
+    }
+
+    /// Subscribe to `topic`; its messages are published to the event-bus as `MqttMessage`s.
+    pub async fn subscribe(&self, topic: &str) -> Result<(), Error<S::Error>> {
+        let mut filter = Topic::new();
+        filter.push_str(topic).map_err(|_| Error::PacketTooLong)?;
+        self.request(Subscribe(filter)).await
+    }
+
+    /// Wait for the next packet from the broker, delivering any message it carries.
+    pub async fn poll(&self) -> Result<(), Error<S::Error>> {
+        self.request(Poll).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::time::duration::Milliseconds;
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    impl EventHandler<MqttMessage> for MockDevice {}
+
+    struct MockTimer;
+
+    impl HalTimer for MockTimer {
+        fn start(&mut self, _: Milliseconds) {}
+        fn clear_update_interrupt_flag(&mut self) {}
+    }
+
+    /// Counts the bytes written, such as of a PINGREQ.
+    #[derive(Default)]
+    struct MockSocket {
+        written: usize,
+    }
+
+    impl Socket for MockSocket {
+        type Error = ();
+
+        async fn write(&mut self, bytes: &[u8]) -> Result<usize, ()> {
+            self.written += bytes.len();
+            Ok(bytes.len())
+        }
+
+        async fn read(&mut self, _: &mut [u8]) -> Result<usize, ()> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_reconnect_keep_alive() {
+        let mut client =
+            MqttClient::<MockDevice, MockSocket, MockTimer>::new("drogue", Seconds(60u32));
+        client.connected(Session::new(MockSocket::default()));
+        let first = KeepAlive {
+            generation: client.generation,
+        };
+        client.connected(Session::new(MockSocket::default()));
+        assert_ne!(client.generation, first.generation);
+
+        // the keep-alive of the first session ends, rather than pinging the second
+        let client = match client.on_notify(first) {
+            Completion::Immediate(client) => client,
+            _ => panic!("deferred"),
+        };
+        assert_eq!(client.session.unwrap().free().written, 0);
+    }
+}
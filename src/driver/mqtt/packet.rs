@@ -0,0 +1,261 @@
+//! MQTT 3.1.1 packet encoding and decoding, limited to what a QoS 0 client needs.
+
+use heapless::{consts::*, Vec};
+
+/// Buffer holding a single encoded or received packet.
+pub type Buffer = Vec<u8, U512>;
+
+/// Largest value representable by the variable-length "remaining length" field.
+pub const MAX_REMAINING_LENGTH: usize = 268_435_455;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const SUBSCRIBE: u8 = 0x82;
+const SUBACK: u8 = 0x90;
+const PINGREQ: u8 = 0xC0;
+const PINGRESP: u8 = 0xD0;
+const DISCONNECT: u8 = 0xE0;
+
+/// The packet did not fit in the buffer.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Overflow;
+
+/// A received packet could not be parsed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Malformed;
+
+/// A packet received from the broker.
+#[derive(Debug, PartialEq)]
+pub enum Incoming<'a> {
+    ConnAck {
+        return_code: u8,
+    },
+    Publish {
+        topic: &'a str,
+        payload: &'a [u8],
+    },
+    SubAck {
+        packet_id: u16,
+        return_code: u8,
+    },
+    PingResp,
+    /// A packet a QoS 0 client does not act upon.
+    Other(u8),
+}
+
+fn put(buf: &mut Buffer, bytes: &[u8]) -> Result<(), Overflow> {
+    buf.extend_from_slice(bytes).map_err(|_| Overflow)
+}
+
+fn put_str(buf: &mut Buffer, s: &str) -> Result<(), Overflow> {
+    if s.len() > u16::MAX as usize {
+        return Err(Overflow);
+    }
+    put(buf, &(s.len() as u16).to_be_bytes())?;
+    put(buf, s.as_bytes())
+}
+
+fn put_header(buf: &mut Buffer, kind: u8, remaining: usize) -> Result<(), Overflow> {
+    if remaining > MAX_REMAINING_LENGTH {
+        return Err(Overflow);
+    }
+    put(buf, &[kind])?;
+    let mut remaining = remaining;
+    loop {
+        let mut byte = (remaining % 128) as u8;
+        remaining /= 128;
+        if remaining > 0 {
+            byte |= 0x80;
+        }
+        put(buf, &[byte])?;
+        if remaining == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Encode a CONNECT requesting a clean session.
+pub fn connect(buf: &mut Buffer, client_id: &str, keep_alive: u16) -> Result<(), Overflow> {
+    put_header(buf, CONNECT, 10 + 2 + client_id.len())?;
+    put_str(buf, "MQTT")?;
+    // protocol level 4 (3.1.1), connect flags: clean session
+    put(buf, &[0x04, 0x02])?;
+    put(buf, &keep_alive.to_be_bytes())?;
+    put_str(buf, client_id)
+}
+
+/// Encode a QoS 0 PUBLISH.
+pub fn publish(buf: &mut Buffer, topic: &str, payload: &[u8]) -> Result<(), Overflow> {
+    put_header(buf, PUBLISH, 2 + topic.len() + payload.len())?;
+    put_str(buf, topic)?;
+    put(buf, payload)
+}
+
+/// Encode a SUBSCRIBE to a single topic filter at QoS 0.
+pub fn subscribe(buf: &mut Buffer, packet_id: u16, topic: &str) -> Result<(), Overflow> {
+    put_header(buf, SUBSCRIBE, 2 + 2 + topic.len() + 1)?;
+    put(buf, &packet_id.to_be_bytes())?;
+    put_str(buf, topic)?;
+    put(buf, &[0x00])
+}
+
+/// Encode a PINGREQ.
+pub fn ping(buf: &mut Buffer) -> Result<(), Overflow> {
+    put_header(buf, PINGREQ, 0)
+}
+
+/// Encode a DISCONNECT.
+pub fn disconnect(buf: &mut Buffer) -> Result<(), Overflow> {
+    put_header(buf, DISCONNECT, 0)
+}
+
+/// Accumulates the variable-length "remaining length" field one byte at a time.
+#[derive(Default)]
+pub struct RemainingLength {
+    value: usize,
+    shift: u32,
+}
+
+impl RemainingLength {
+    /// Feed the next byte, returning the length once its final byte has been seen.
+    pub fn feed(&mut self, byte: u8) -> Result<Option<usize>, Malformed> {
+        if self.shift > 21 {
+            return Err(Malformed);
+        }
+        self.value |= ((byte & 0x7F) as usize) << self.shift;
+        self.shift += 7;
+        if byte & 0x80 == 0 {
+            Ok(Some(self.value))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Decode the body of a packet, given the first byte of its fixed header.
+pub fn decode(header: u8, body: &[u8]) -> Result<Incoming<'_>, Malformed> {
+    match header & 0xF0 {
+        CONNACK if body.len() == 2 => Ok(Incoming::ConnAck {
+            return_code: body[1],
+        }),
+        SUBACK if body.len() >= 3 => Ok(Incoming::SubAck {
+            packet_id: u16::from_be_bytes([body[0], body[1]]),
+            return_code: body[2],
+        }),
+        PINGRESP => Ok(Incoming::PingResp),
+        PUBLISH => {
+            if body.len() < 2 {
+                return Err(Malformed);
+            }
+            let len = u16::from_be_bytes([body[0], body[1]]) as usize;
+            let topic = body.get(2..2 + len).ok_or(Malformed)?;
+            let topic = core::str::from_utf8(topic).map_err(|_| Malformed)?;
+            let mut rest = &body[2 + len..];
+            // QoS 1 and 2 carry a packet identifier before the payload
+            if header & 0x06 != 0 {
+                rest = rest.get(2..).ok_or(Malformed)?;
+            }
+            Ok(Incoming::Publish {
+                topic,
+                payload: rest,
+            })
+        }
+        CONNACK | SUBACK => Err(Malformed),
+        _ => Ok(Incoming::Other(header)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connect() {
+        let mut buf = Buffer::new();
+        connect(&mut buf, "drogue", 60).unwrap();
+        assert_eq!(
+            &buf[..],
+            &[
+                0x10, 18, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 60, 0x00, 6, b'd',
+                b'r', b'o', b'g', b'u', b'e'
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_publish() {
+        let mut buf = Buffer::new();
+        publish(&mut buf, "a/b", b"hello").unwrap();
+        assert_eq!(
+            &buf[..],
+            &[0x30, 10, 0x00, 3, b'a', b'/', b'b', b'h', b'e', b'l', b'l', b'o'][..]
+        );
+    }
+
+    #[test]
+    fn test_subscribe_ping_disconnect() {
+        let mut buf = Buffer::new();
+        subscribe(&mut buf, 1, "a/#").unwrap();
+        ping(&mut buf).unwrap();
+        disconnect(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..],
+            &[0x82, 8, 0x00, 1, 0x00, 3, b'a', b'/', b'#', 0x00, 0xC0, 0x00, 0xE0, 0x00][..]
+        );
+    }
+
+    #[test]
+    fn test_remaining_length() {
+        let mut buf = Buffer::new();
+        put_header(&mut buf, PUBLISH, 321).unwrap();
+        assert_eq!(&buf[..], &[0x30, 0xC1, 0x02][..]);
+
+        let mut length = RemainingLength::default();
+        assert_eq!(length.feed(0xC1), Ok(None));
+        assert_eq!(length.feed(0x02), Ok(Some(321)));
+
+        let mut length = RemainingLength::default();
+        for _ in 0..4 {
+            length.feed(0xFF).unwrap();
+        }
+        assert_eq!(length.feed(0x01), Err(Malformed));
+    }
+
+    #[test]
+    fn test_publish_too_long() {
+        let mut buf = Buffer::new();
+        assert_eq!(publish(&mut buf, "t", &[0; 512]), Err(Overflow));
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            decode(0x20, &[0x00, 0x05]),
+            Ok(Incoming::ConnAck { return_code: 5 })
+        );
+        assert_eq!(
+            decode(0x90, &[0x00, 0x01, 0x00]),
+            Ok(Incoming::SubAck {
+                packet_id: 1,
+                return_code: 0
+            })
+        );
+        assert_eq!(decode(0xD0, &[]), Ok(Incoming::PingResp));
+        assert_eq!(
+            decode(0x30, &[0x00, 1, b't', b'h', b'i']),
+            Ok(Incoming::Publish {
+                topic: "t",
+                payload: b"hi"
+            })
+        );
+        assert_eq!(
+            decode(0x32, &[0x00, 1, b't', 0x00, 0x07, b'h', b'i']),
+            Ok(Incoming::Publish {
+                topic: "t",
+                payload: b"hi"
+            })
+        );
+        assert_eq!(decode(0x30, &[0x00, 9, b't']), Err(Malformed));
+    }
+}
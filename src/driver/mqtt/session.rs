@@ -0,0 +1,331 @@
+//! An MQTT 3.1.1 QoS 0 session over a `Socket`.
+
+use crate::driver::mqtt::packet::{self, Buffer, Incoming, RemainingLength};
+use crate::driver::socket::Socket;
+use heapless::{consts::*, spsc::Queue, String, Vec};
+
+pub type Topic = String<U64>;
+pub type Payload = Vec<u8, U256>;
+
+/// A message received on a subscribed topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttMessage {
+    pub topic: Topic,
+    pub payload: Payload,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Error<E> {
+    Socket(E),
+    /// The client is not connected to a broker.
+    NotConnected,
+    /// The broker closed the connection.
+    Closed,
+    /// The broker refused the connection with the given CONNACK return code.
+    Refused(u8),
+    /// The broker rejected the subscription.
+    SubscribeFailed,
+    /// The packet did not fit in the packet buffer.
+    PacketTooLong,
+    /// The broker sent something that could not be parsed.
+    Protocol,
+}
+
+impl<E> From<packet::Overflow> for Error<E> {
+    fn from(_: packet::Overflow) -> Self {
+        Error::PacketTooLong
+    }
+}
+
+pub struct Session<S>
+where
+    S: Socket,
+{
+    socket: S,
+    packet_id: u16,
+    inbox: Queue<MqttMessage, U4>,
+}
+
+impl<S> Session<S>
+where
+    S: Socket,
+{
+    pub fn new(socket: S) -> Self {
+        Self {
+            socket,
+            packet_id: 0,
+            inbox: Queue::new(),
+        }
+    }
+
+    /// Release the underlying socket.
+    pub fn free(self) -> S {
+        self.socket
+    }
+
+    /// Open a clean session identified by `client_id`.
+    ///
+    /// `keep_alive` is the interval, in seconds, within which the client promises to send
+    /// a packet; `0` disables the broker's keep-alive check.
+    pub async fn connect(
+        &mut self,
+        client_id: &str,
+        keep_alive: u16,
+    ) -> Result<(), Error<S::Error>> {
+        let mut buf = Buffer::new();
+        packet::connect(&mut buf, client_id, keep_alive)?;
+        self.send(&buf).await?;
+        loop {
+            let (header, body) = self.receive().await?;
+            if let Incoming::ConnAck { return_code } =
+                packet::decode(header, &body).map_err(|_| Error::Protocol)?
+            {
+                return match return_code {
+                    0 => Ok(()),
+                    code => Err(Error::Refused(code)),
+                };
+            }
+        }
+    }
+
+    /// Publish `payload` to `topic` at QoS 0.
+    pub async fn publish(&mut self, topic: &str, payload: &[u8]) -> Result<(), Error<S::Error>> {
+        let mut buf = Buffer::new();
+        packet::publish(&mut buf, topic, payload)?;
+        self.send(&buf).await
+    }
+
+    /// Subscribe to `topic` at QoS 0, waiting for the broker's acknowledgement.
+    pub async fn subscribe(&mut self, topic: &str) -> Result<(), Error<S::Error>> {
+        self.packet_id = self.packet_id.wrapping_add(1).max(1);
+        let packet_id = self.packet_id;
+        let mut buf = Buffer::new();
+        packet::subscribe(&mut buf, packet_id, topic)?;
+        self.send(&buf).await?;
+        loop {
+            let (header, body) = self.receive().await?;
+            match self.dispatch(header, &body)? {
+                Incoming::SubAck {
+                    packet_id: id,
+                    return_code,
+                } if id == packet_id => {
+                    return match return_code {
+                        0x80 => Err(Error::SubscribeFailed),
+                        _ => Ok(()),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Send a PINGREQ and wait for the broker's PINGRESP.
+    pub async fn ping(&mut self) -> Result<(), Error<S::Error>> {
+        let mut buf = Buffer::new();
+        packet::ping(&mut buf)?;
+        self.send(&buf).await?;
+        loop {
+            let (header, body) = self.receive().await?;
+            if let Incoming::PingResp = self.dispatch(header, &body)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Wait for the next packet from the broker.
+    pub async fn poll(&mut self) -> Result<(), Error<S::Error>> {
+        let (header, body) = self.receive().await?;
+        self.dispatch(header, &body)?;
+        Ok(())
+    }
+
+    /// Close the session.
+    pub async fn disconnect(mut self) -> Result<S, Error<S::Error>> {
+        let mut buf = Buffer::new();
+        packet::disconnect(&mut buf)?;
+        self.send(&buf).await?;
+        Ok(self.socket)
+    }
+
+    /// Take the oldest message received on a subscribed topic.
+    pub fn take_message(&mut self) -> Option<MqttMessage> {
+        self.inbox.dequeue()
+    }
+
+    fn dispatch<'b>(
+        &mut self,
+        header: u8,
+        body: &'b [u8],
+    ) -> Result<Incoming<'b>, Error<S::Error>> {
+        let incoming = packet::decode(header, body).map_err(|_| Error::Protocol)?;
+        if let Incoming::Publish { topic, payload } = incoming {
+            let mut message = MqttMessage {
+                topic: Topic::new(),
+                payload: Payload::new(),
+            };
+            if message.topic.push_str(topic).is_err()
+                || message.payload.extend_from_slice(payload).is_err()
+            {
+                warn!("[mqtt] message too long, dropping");
+            } else if self.inbox.enqueue(message).is_err() {
+                warn!("[mqtt] inbox full, dropping message");
+            }
+        }
+        Ok(incoming)
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> Result<(), Error<S::Error>> {
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            match self.socket.write(bytes).await.map_err(Error::Socket)? {
+                0 => return Err(Error::Closed),
+                n => bytes = &bytes[n..],
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_byte(&mut self) -> Result<u8, Error<S::Error>> {
+        let mut byte = [0];
+        match self.socket.read(&mut byte).await.map_err(Error::Socket)? {
+            0 => Err(Error::Closed),
+            _ => Ok(byte[0]),
+        }
+    }
+
+    /// Read a whole packet, returning its fixed header byte and body.
+    ///
+    /// A body too long for the buffer is consumed and truncated.
+    async fn receive(&mut self) -> Result<(u8, Buffer), Error<S::Error>> {
+        let header = self.read_byte().await?;
+        let mut length = RemainingLength::default();
+        let length = loop {
+            let byte = self.read_byte().await?;
+            if let Some(length) = length.feed(byte).map_err(|_| Error::Protocol)? {
+                break length;
+            }
+        };
+
+        let mut body = Buffer::new();
+        let mut chunk = [0; 32];
+        let mut remaining = length;
+        while remaining > 0 {
+            let len = remaining.min(chunk.len());
+            let n = self
+                .socket
+                .read(&mut chunk[..len])
+                .await
+                .map_err(Error::Socket)?;
+            if n == 0 {
+                return Err(Error::Closed);
+            }
+            if body.extend_from_slice(&chunk[..n]).is_err() {
+                warn!("[mqtt] packet too long, truncating");
+            }
+            remaining -= n;
+        }
+        Ok((header, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::block_on;
+
+    struct MockSocket {
+        rx: &'static [u8],
+        tx: Vec<u8, U512>,
+    }
+
+    impl MockSocket {
+        fn new(rx: &'static [u8]) -> Self {
+            Self { rx, tx: Vec::new() }
+        }
+    }
+
+    impl Socket for MockSocket {
+        type Error = ();
+
+        async fn write(&mut self, bytes: &[u8]) -> Result<usize, Self::Error> {
+            self.tx.extend_from_slice(bytes)?;
+            Ok(bytes.len())
+        }
+
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let len = buf.len().min(self.rx.len());
+            buf[..len].copy_from_slice(&self.rx[..len]);
+            self.rx = &self.rx[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn test_connect_publish() {
+        let mut session = Session::new(MockSocket::new(&[0x20, 0x02, 0x00, 0x00]));
+        block_on(async {
+            assert_eq!(session.connect("drogue", 60).await, Ok(()));
+            assert_eq!(session.publish("a/b", b"hello").await, Ok(()));
+        });
+        assert_eq!(
+            &session.free().tx[..],
+            &[
+                0x10, 18, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 60, 0x00, 6, b'd',
+                b'r', b'o', b'g', b'u', b'e', //
+                0x30, 10, 0x00, 3, b'a', b'/', b'b', b'h', b'e', b'l', b'l', b'o'
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_connect_refused() {
+        let mut session = Session::new(MockSocket::new(&[0x20, 0x02, 0x00, 0x05]));
+        assert_eq!(
+            block_on(session.connect("drogue", 60)),
+            Err(Error::Refused(5))
+        );
+    }
+
+    #[test]
+    fn test_connect_closed() {
+        let mut session = Session::new(MockSocket::new(&[]));
+        assert_eq!(block_on(session.connect("drogue", 60)), Err(Error::Closed));
+    }
+
+    #[test]
+    fn test_subscribe_receives_messages() {
+        let mut session = Session::new(MockSocket::new(&[
+            // a retained message may arrive before the SUBACK
+            0x31, 0x05, 0x00, 0x01, b't', b'h', b'i', //
+            0x90, 0x03, 0x00, 0x01, 0x00, //
+            0xD0, 0x00, //
+            0x30, 0x04, 0x00, 0x01, b't', b'!',
+        ]));
+        block_on(async {
+            assert_eq!(session.subscribe("t").await, Ok(()));
+            assert_eq!(session.ping().await, Ok(()));
+            assert_eq!(session.poll().await, Ok(()));
+        });
+
+        let message = session.take_message().unwrap();
+        assert_eq!(message.topic.as_str(), "t");
+        assert_eq!(&message.payload[..], b"hi");
+        let message = session.take_message().unwrap();
+        assert_eq!(&message.payload[..], b"!");
+        assert_eq!(session.take_message(), None);
+
+        assert_eq!(
+            &session.free().tx[..],
+            &[0x82, 6, 0x00, 1, 0x00, 1, b't', 0x00, 0xC0, 0x00][..]
+        );
+    }
+
+    #[test]
+    fn test_subscribe_failed() {
+        let mut session = Session::new(MockSocket::new(&[0x90, 0x03, 0x00, 0x01, 0x80]));
+        assert_eq!(
+            block_on(session.subscribe("t")),
+            Err(Error::SubscribeFailed)
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Low-power sleep while the system is idle.
+//!
+//! The `PowerManager` runs whenever the supervisor has no actor left to poll. It asks the
+//! timer when its next deadline falls and, if that is far enough off, enters a mode
+//! deeper than WFI, arming a low-power `Wakeup` timer to end the sleep at the deadline.
+//! Interrupts, such as from GPIOs, may end the sleep early.
+//!
+//! A deep sleep stops the clocks driving the timer, which would otherwise count the
+//! sleep towards its deadlines. Instead, on waking, the time measured by the `Wakeup`
+//! timer is counted off them with `TimerActor::resume`, so they fall when they should.
+//! That needs the timer to tell how much of its current countdown had already elapsed
+//! before the sleep, so with a deadline pending and a timer unable to tell, only the
+//! shallow `SleepMode::Sleep` is entered.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::{NextDeadline, TimerActor};
+use crate::hal::power::{Power, SleepMode, Wakeup};
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+use crate::supervisor::Idle;
+use core::cell::{Cell, RefCell};
+
+/// When to sleep in each mode.
+#[derive(Copy, Clone, Debug)]
+pub struct Policy {
+    stop_after: Milliseconds,
+    standby: bool,
+}
+
+impl Policy {
+    /// Enter `SleepMode::Stop` when the next deadline is at least `stop_after` away, or
+    /// there is none.
+    pub fn new(stop_after: Milliseconds) -> Self {
+        Self {
+            stop_after,
+            standby: false,
+        }
+    }
+
+    /// Enter `SleepMode::Standby`, rather than `SleepMode::Stop`, when there is no
+    /// deadline, for applications which can resume from a reset.
+    pub fn with_standby(mut self) -> Self {
+        self.standby = true;
+        self
+    }
+
+    /// The mode to sleep in until `next`, and the time after which to wake, if any.
+    pub fn choose(&self, next: NextDeadline) -> (SleepMode, Option<Milliseconds>) {
+        match next {
+            NextDeadline::None if self.standby => (SleepMode::Standby, None),
+            NextDeadline::None => (SleepMode::Stop, None),
+            NextDeadline::In(after) if after >= self.stop_after => (SleepMode::Stop, Some(after)),
+            _ => (SleepMode::Sleep, None),
+        }
+    }
+}
+
+/// Sleep until `next` in the mode chosen by `policy`, returning the time slept if the
+/// timer was stopped throughout.
+pub fn sleep<P: Power, W: Wakeup>(
+    policy: &Policy,
+    power: &mut P,
+    wakeup: &mut W,
+    next: NextDeadline,
+) -> Option<Milliseconds> {
+    match policy.choose(next) {
+        (SleepMode::Sleep, _) => {
+            power.enter(SleepMode::Sleep);
+            None
+        }
+        (mode, wake) => {
+            match wake {
+                Some(after) => wakeup.arm(after),
+                None => wakeup.start(),
+            }
+            power.enter(mode);
+            Some(wakeup.stop())
+        }
+    }
+}
+
+pub struct PowerManager<T, P, W>
+where
+    T: HalTimer + 'static,
+    P: Power + 'static,
+    W: Wakeup + 'static,
+{
+    policy: Policy,
+    power: RefCell<P>,
+    wakeup: RefCell<W>,
+    timer: Cell<Option<Address<TimerActor<T>>>>,
+}
+
+impl<T, P, W> PowerManager<T, P, W>
+where
+    T: HalTimer,
+    P: Power,
+    W: Wakeup,
+{
+    pub fn new(power: P, wakeup: W, policy: Policy) -> Self {
+        Self {
+            policy,
+            power: RefCell::new(power),
+            wakeup: RefCell::new(wakeup),
+            timer: Cell::new(None),
+        }
+    }
+
+    /// Sleep whenever the supervisor is idle, until the next deadline of `timer`.
+    pub fn mount(&'static self, timer: Address<TimerActor<T>>, supervisor: &mut Supervisor) {
+        self.timer.set(Some(timer));
+        supervisor.set_idle(self);
+    }
+}
+
+impl<T, P, W> Idle for PowerManager<T, P, W>
+where
+    T: HalTimer,
+    P: Power,
+    W: Wakeup,
+{
+    fn on_idle(&self) {
+        if let Some(timer) = self.timer.get() {
+            let next = timer.with_actor(|timer| timer.next_deadline());
+            let slept = sleep(
+                &self.policy,
+                &mut *self.power.borrow_mut(),
+                &mut *self.wakeup.borrow_mut(),
+                next,
+            );
+            if let Some(slept) = slept {
+                timer.with_actor(|timer| timer.resume(slept));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockPower {
+        entered: Option<SleepMode>,
+    }
+
+    impl Power for MockPower {
+        fn enter(&mut self, mode: SleepMode) {
+            self.entered.replace(mode);
+        }
+    }
+
+    /// Wakes after `slept`, or when armed, as soon as it is due.
+    struct MockWakeup {
+        armed: Option<Milliseconds>,
+        started: bool,
+        slept: Milliseconds,
+    }
+
+    impl MockWakeup {
+        fn new(slept: u32) -> Self {
+            Self {
+                armed: None,
+                started: false,
+                slept: Milliseconds(slept),
+            }
+        }
+    }
+
+    impl Wakeup for MockWakeup {
+        fn arm(&mut self, after: Milliseconds) {
+            self.armed.replace(after);
+            self.started = true;
+        }
+
+        fn start(&mut self) {
+            self.started = true;
+        }
+
+        fn stop(&mut self) -> Milliseconds {
+            assert!(self.started);
+            self.armed.map_or(self.slept, |armed| armed.min(self.slept))
+        }
+    }
+
+    fn ms(ms: u32) -> Milliseconds {
+        Milliseconds(ms)
+    }
+
+    #[test]
+    fn test_stop_until_deadline() {
+        let policy = Policy::new(ms(1000));
+        let mut power = MockPower::default();
+        let mut wakeup = MockWakeup::new(u32::MAX);
+
+        let slept = sleep(&policy, &mut power, &mut wakeup, NextDeadline::In(ms(5000)));
+        assert_eq!(power.entered, Some(SleepMode::Stop));
+        assert_eq!(wakeup.armed, Some(ms(5000)));
+        assert_eq!(slept, Some(ms(5000)));
+    }
+
+    #[test]
+    fn test_woken_early() {
+        let policy = Policy::new(ms(1000));
+        let mut power = MockPower::default();
+        let mut wakeup = MockWakeup::new(1200);
+
+        let slept = sleep(&policy, &mut power, &mut wakeup, NextDeadline::In(ms(5000)));
+        assert_eq!(power.entered, Some(SleepMode::Stop));
+        assert_eq!(slept, Some(ms(1200)));
+    }
+
+    #[test]
+    fn test_shallow_sleep() {
+        let policy = Policy::new(ms(1000));
+        for next in [NextDeadline::In(ms(999)), NextDeadline::Unknown] {
+            let mut power = MockPower::default();
+            let mut wakeup = MockWakeup::new(0);
+            assert_eq!(sleep(&policy, &mut power, &mut wakeup, next), None);
+            assert_eq!(power.entered, Some(SleepMode::Sleep));
+            assert!(!wakeup.started);
+        }
+    }
+
+    #[test]
+    fn test_no_deadline() {
+        let mut power = MockPower::default();
+        let mut wakeup = MockWakeup::new(60_000);
+        let slept = sleep(
+            &Policy::new(ms(1000)),
+            &mut power,
+            &mut wakeup,
+            NextDeadline::None,
+        );
+        assert_eq!(power.entered, Some(SleepMode::Stop));
+        assert_eq!(wakeup.armed, None);
+        assert_eq!(slept, Some(ms(60_000)));
+
+        let policy = Policy::new(ms(1000)).with_standby();
+        assert_eq!(
+            policy.choose(NextDeadline::None),
+            (SleepMode::Standby, None)
+        );
+    }
+}
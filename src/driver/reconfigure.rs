@@ -0,0 +1,56 @@
+//! Runtime reconfiguration of drivers.
+//!
+//! Drivers implementing `Reconfigurable` accept a `Reconfigure<...>` notification
+//! carrying a complete replacement of their configuration, such as from a console
+//! command or a downlink, without being re-mounted.
+//!
+//! A configuration is validated before it is applied, and rejected configurations
+//! are dropped with a warning, leaving the current one in place. As the driver
+//! handles no other message while applying it, the new configuration takes effect
+//! atomically.
+
+use crate::prelude::*;
+
+/// Notification replacing the configuration of a driver.
+pub struct Reconfigure<C>(pub C);
+
+/// Trait for drivers whose configuration may be replaced at runtime.
+pub trait Reconfigurable: Actor {
+    /// The configuration of the driver.
+    type Config: 'static;
+
+    /// Check the configuration before it is applied.
+    ///
+    /// The default implementation accepts every configuration.
+    fn validate(&self, config: &Self::Config) -> bool {
+        true
+    }
+
+    /// Apply a validated configuration.
+    fn on_reconfigure(self, config: Self::Config) -> Completion<Self>
+    where
+        Self: 'static;
+}
+
+impl<A> NotifyHandler<Reconfigure<A::Config>> for A
+where
+    A: Reconfigurable + 'static,
+{
+    fn on_notify(self, message: Reconfigure<A::Config>) -> Completion<Self> {
+        if !self.validate(&message.0) {
+            warn!("[reconfigure] rejected invalid configuration");
+            return Completion::immediate(self);
+        }
+        self.on_reconfigure(message.0)
+    }
+}
+
+impl<A> Address<A>
+where
+    A: Reconfigurable + 'static,
+{
+    /// Replace the configuration of the driver behind this address.
+    pub fn reconfigure(&self, config: A::Config) {
+        self.notify(Reconfigure(config))
+    }
+}
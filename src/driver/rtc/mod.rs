@@ -0,0 +1,282 @@
+//! Wall-clock time and alarms from a real-time clock.
+//!
+//! The `Rtc` actor wraps a HAL real-time clock, reading and setting its calendar time and
+//! arming a single alarm. When the clock reaches the alarm, its interrupt publishes an
+//! `RtcAlarm` to the event-bus. A clock which has never been set, such as after its
+//! backup domain lost power, reports `RtcError::Uninitialized` until it is.
+
+use crate::bind::Bind;
+use crate::domain::datetime::DateTime;
+use crate::hal::rtc::Rtc as HalRtc;
+use crate::prelude::*;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RtcError {
+    /// The clock has not been set since it lost power.
+    Uninitialized,
+    /// The alarm is not after the current time.
+    AlarmInPast,
+}
+
+/// The alarm set for the contained time has been reached.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RtcAlarm(pub DateTime);
+
+pub struct Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm> + 'static,
+    R: HalRtc + 'static,
+{
+    rtc: R,
+    alarm: Option<DateTime>,
+    bus: Option<Address<EventBus<D>>>,
+}
+
+impl<D, R> Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+    pub fn new(rtc: R) -> Self {
+        Self {
+            rtc,
+            alarm: None,
+            bus: None,
+        }
+    }
+
+    fn now(&mut self) -> Result<DateTime, RtcError> {
+        self.rtc.now().ok_or(RtcError::Uninitialized)
+    }
+
+    /// Set the clock, returning the alarm if the new time has reached it.
+    fn set(&mut self, time: DateTime) -> Option<RtcAlarm> {
+        self.rtc.set(time);
+        self.check_alarm()
+    }
+
+    fn set_alarm(&mut self, time: DateTime) -> Result<(), RtcError> {
+        if time <= self.now()? {
+            return Err(RtcError::AlarmInPast);
+        }
+        self.alarm.replace(time);
+        self.rtc.set_alarm(Some(time));
+        Ok(())
+    }
+
+    fn cancel_alarm(&mut self) {
+        self.alarm.take();
+        self.rtc.set_alarm(None);
+    }
+
+    /// Disarm and return the alarm if the clock has reached it.
+    fn check_alarm(&mut self) -> Option<RtcAlarm> {
+        match (self.alarm, self.rtc.now()) {
+            (Some(alarm), Some(now)) if now >= alarm => {
+                self.cancel_alarm();
+                Some(RtcAlarm(alarm))
+            }
+            _ => None,
+        }
+    }
+
+    fn publish(&self, alarm: Option<RtcAlarm>) {
+        if let (Some(alarm), Some(bus)) = (alarm, self.bus) {
+            bus.publish(alarm);
+        }
+    }
+}
+
+impl<D, R> Actor for Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+}
+
+impl<D, R> Bind<EventBus<D>> for Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, R> Interrupt for Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+    fn on_interrupt(&mut self) {
+        self.rtc.clear_alarm_interrupt_flag();
+        let alarm = self.check_alarm();
+        self.publish(alarm);
+    }
+}
+
+pub struct Now;
+
+pub struct Set(pub DateTime);
+
+pub struct SetAlarm(pub DateTime);
+
+pub struct CancelAlarm;
+
+impl<D, R> RequestHandler<Now> for Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+    type Response = Result<DateTime, RtcError>;
+
+    fn on_request(mut self, _: Now) -> Response<Self, Self::Response> {
+        let now = self.now();
+        Response::immediate(self, now)
+    }
+}
+
+impl<D, R> NotifyHandler<Set> for Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+    fn on_notify(mut self, message: Set) -> Completion<Self> {
+        let alarm = self.set(message.0);
+        self.publish(alarm);
+        Completion::immediate(self)
+    }
+}
+
+impl<D, R> RequestHandler<SetAlarm> for Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+    type Response = Result<(), RtcError>;
+
+    fn on_request(mut self, message: SetAlarm) -> Response<Self, Self::Response> {
+        let result = self.set_alarm(message.0);
+        Response::immediate(self, result)
+    }
+}
+
+impl<D, R> NotifyHandler<CancelAlarm> for Rtc<D, R>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+    fn on_notify(mut self, _: CancelAlarm) -> Completion<Self> {
+        self.cancel_alarm();
+        Completion::immediate(self)
+    }
+}
+
+impl<D, R> Address<Rtc<D, R>>
+where
+    D: Device + EventHandler<RtcAlarm>,
+    R: HalRtc,
+{
+    /// The current wall-clock time.
+    pub async fn now(&self) -> Result<DateTime, RtcError> {
+        self.request(Now).await
+    }
+
+    /// Set the clock, publishing the pending alarm straight away if `time` is at or after it.
+    pub fn set(&self, time: DateTime) {
+        self.notify(Set(time));
+    }
+
+    /// Publish an `RtcAlarm` once the clock reaches `time`, replacing any pending alarm.
+    pub async fn set_alarm(&self, time: DateTime) -> Result<(), RtcError> {
+        self.request(SetAlarm(time)).await
+    }
+
+    pub fn cancel_alarm(&self) {
+        self.notify(CancelAlarm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockRtc {
+        now: Option<DateTime>,
+        alarm: Option<DateTime>,
+    }
+
+    impl HalRtc for MockRtc {
+        fn now(&mut self) -> Option<DateTime> {
+            self.now
+        }
+
+        fn set(&mut self, time: DateTime) {
+            self.now.replace(time);
+        }
+
+        fn set_alarm(&mut self, time: Option<DateTime>) {
+            self.alarm = time;
+        }
+
+        fn clear_alarm_interrupt_flag(&mut self) {}
+    }
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    impl EventHandler<RtcAlarm> for MockDevice {}
+
+    fn at(hour: u8, minute: u8) -> DateTime {
+        DateTime::new(2021, 3, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_set_and_get() {
+        let mut rtc: Rtc<MockDevice, _> = Rtc::new(MockRtc::default());
+        assert_eq!(rtc.now(), Err(RtcError::Uninitialized));
+        assert_eq!(rtc.set_alarm(at(12, 0)), Err(RtcError::Uninitialized));
+
+        assert_eq!(rtc.set(at(9, 30)), None);
+        assert_eq!(rtc.now(), Ok(at(9, 30)));
+    }
+
+    #[test]
+    fn test_alarm() {
+        let mut rtc: Rtc<MockDevice, _> = Rtc::new(MockRtc::default());
+        rtc.set(at(9, 30));
+        assert_eq!(rtc.set_alarm(at(9, 30)), Err(RtcError::AlarmInPast));
+        assert_eq!(rtc.set_alarm(at(10, 0)), Ok(()));
+        assert_eq!(rtc.rtc.alarm, Some(at(10, 0)));
+
+        // a spurious interrupt before the alarm leaves it pending
+        rtc.rtc.now.replace(at(9, 59));
+        assert_eq!(rtc.check_alarm(), None);
+
+        rtc.rtc.now.replace(at(10, 0));
+        assert_eq!(rtc.check_alarm(), Some(RtcAlarm(at(10, 0))));
+        assert_eq!(rtc.rtc.alarm, None);
+        assert_eq!(rtc.check_alarm(), None);
+    }
+
+    #[test]
+    fn test_set_past_alarm() {
+        let mut rtc: Rtc<MockDevice, _> = Rtc::new(MockRtc::default());
+        rtc.set(at(9, 30));
+        rtc.set_alarm(at(10, 0)).unwrap();
+
+        // setting the clock forward past the alarm fires it
+        assert_eq!(rtc.set(at(11, 0)), Some(RtcAlarm(at(10, 0))));
+
+        rtc.set_alarm(at(12, 0)).unwrap();
+        rtc.cancel_alarm();
+        assert_eq!(rtc.rtc.alarm, None);
+        rtc.rtc.now.replace(at(12, 0));
+        assert_eq!(rtc.check_alarm(), None);
+    }
+}
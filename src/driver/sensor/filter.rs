@@ -0,0 +1,240 @@
+//! Smoothing of sensor readings.
+//!
+//! The `Filtered` actor receives each `SensorAcquisition`, passes its temperature and
+//! humidity through a moving-average or exponential filter, and publishes the result
+//! as a `FilteredReading`. Filters start from the first reading rather than from zero,
+//! so early output tracks the input, and mark readings as `settled` once they reflect a
+//! full window (or time constant) of samples.
+
+use crate::bind::Bind;
+use crate::domain::temperature::{Temperature, TemperatureScale};
+use crate::driver::sensor::hts221::SensorAcquisition;
+use crate::prelude::*;
+use core::fmt::{Debug, Formatter};
+use core::marker::PhantomData;
+
+/// Largest window of a moving average.
+pub const MAX_WINDOW: usize = 16;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Smoothing {
+    /// The mean of the last `n` samples, `n` being at most `MAX_WINDOW`.
+    MovingAverage(usize),
+    /// An exponential moving average, each sample moving the output by `alpha` (in
+    /// `(0, 1]`) of its distance from the previous output.
+    Exponential(f32),
+}
+
+/// Filters a single stream of samples.
+pub struct Smoother {
+    smoothing: Smoothing,
+    window: [f32; MAX_WINDOW],
+    samples: usize,
+    output: f32,
+}
+
+impl Smoother {
+    pub fn new(smoothing: Smoothing) -> Self {
+        let smoothing = match smoothing {
+            Smoothing::MovingAverage(n) => Smoothing::MovingAverage(n.clamp(1, MAX_WINDOW)),
+            Smoothing::Exponential(alpha) => Smoothing::Exponential(alpha.clamp(f32::EPSILON, 1.0)),
+        };
+        Self {
+            smoothing,
+            window: [0.0; MAX_WINDOW],
+            samples: 0,
+            output: 0.0,
+        }
+    }
+
+    /// Feed a sample, returning the filtered value.
+    pub fn update(&mut self, sample: f32) -> f32 {
+        self.output = match self.smoothing {
+            Smoothing::MovingAverage(n) => {
+                self.window[self.samples % n] = sample;
+                let len = (self.samples + 1).min(n);
+                self.window[..len].iter().sum::<f32>() / len as f32
+            }
+            Smoothing::Exponential(_) if self.samples == 0 => sample,
+            Smoothing::Exponential(alpha) => self.output + alpha * (sample - self.output),
+        };
+        self.samples = self.samples.saturating_add(1);
+        self.output
+    }
+
+    /// Whether enough samples have been fed for the output to no longer be dominated by
+    /// the first: a full window, or one time constant (`1 / alpha` samples).
+    pub fn is_settled(&self) -> bool {
+        match self.smoothing {
+            Smoothing::MovingAverage(n) => self.samples >= n,
+            Smoothing::Exponential(alpha) => self.samples as f32 * alpha >= 1.0,
+        }
+    }
+}
+
+pub struct FilteredReading<S: TemperatureScale> {
+    pub temperature: Temperature<S>,
+    pub relative_humidity: f32,
+    /// Whether the filters have seen enough readings to have settled.
+    pub settled: bool,
+}
+
+impl<S: TemperatureScale> Clone for FilteredReading<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: TemperatureScale> Copy for FilteredReading<S> {}
+
+impl<S: TemperatureScale> Debug for FilteredReading<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FilteredReading")
+            .field("temperature", &self.temperature)
+            .field("relative_humidity", &self.relative_humidity)
+            .field("settled", &self.settled)
+            .finish()
+    }
+}
+
+pub struct Filtered<D, S>
+where
+    D: Device + EventHandler<FilteredReading<S>> + 'static,
+    S: TemperatureScale + 'static,
+{
+    temperature: Smoother,
+    relative_humidity: Smoother,
+    bus: Option<Address<EventBus<D>>>,
+    _scale: PhantomData<S>,
+}
+
+impl<D, S> Filtered<D, S>
+where
+    D: Device + EventHandler<FilteredReading<S>>,
+    S: TemperatureScale,
+{
+    /// Smooth both temperature and humidity with `smoothing`.
+    pub fn new(smoothing: Smoothing) -> Self {
+        Self {
+            temperature: Smoother::new(smoothing),
+            relative_humidity: Smoother::new(smoothing),
+            bus: None,
+            _scale: PhantomData,
+        }
+    }
+
+    fn filter(&mut self, acquisition: SensorAcquisition<S>) -> FilteredReading<S> {
+        let temperature = self.temperature.update(acquisition.temperature.value());
+        let relative_humidity = self.relative_humidity.update(acquisition.relative_humidity);
+        FilteredReading {
+            temperature: Temperature::new(temperature),
+            relative_humidity,
+            settled: self.temperature.is_settled(),
+        }
+    }
+}
+
+impl<D, S> Actor for Filtered<D, S>
+where
+    D: Device + EventHandler<FilteredReading<S>>,
+    S: TemperatureScale,
+{
+}
+
+impl<D, S> Bind<EventBus<D>> for Filtered<D, S>
+where
+    D: Device + EventHandler<FilteredReading<S>>,
+    S: TemperatureScale,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, S> NotifyHandler<SensorAcquisition<S>> for Filtered<D, S>
+where
+    D: Device + EventHandler<FilteredReading<S>>,
+    S: TemperatureScale,
+{
+    fn on_notify(mut self, acquisition: SensorAcquisition<S>) -> Completion<Self> {
+        let reading = self.filter(acquisition);
+        if let Some(bus) = self.bus {
+            bus.publish(reading);
+        }
+        Completion::immediate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::temperature::Celsius;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_moving_average_step() {
+        let mut smoother = Smoother::new(Smoothing::MovingAverage(4));
+        // warm-up averages over the samples seen so far
+        assert_close(smoother.update(0.0), 0.0);
+        assert!(!smoother.is_settled());
+        for _ in 0..3 {
+            assert_close(smoother.update(0.0), 0.0);
+        }
+        assert!(smoother.is_settled());
+
+        // a step converges linearly, reaching the input after a full window
+        for expected in &[2.5, 5.0, 7.5, 10.0, 10.0] {
+            assert_close(smoother.update(10.0), *expected);
+        }
+    }
+
+    #[test]
+    fn test_exponential_step() {
+        let mut smoother = Smoother::new(Smoothing::Exponential(0.5));
+        // the first sample seeds the output instead of decaying up from zero
+        assert_close(smoother.update(0.0), 0.0);
+        assert!(!smoother.is_settled());
+        assert_close(smoother.update(0.0), 0.0);
+        assert!(smoother.is_settled());
+
+        // the remaining error halves with every sample
+        for expected in &[5.0, 7.5, 8.75, 9.375] {
+            assert_close(smoother.update(10.0), *expected);
+        }
+    }
+
+    #[test]
+    fn test_filter_acquisition() {
+        struct MockDevice;
+
+        impl Device for MockDevice {
+            fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+        }
+
+        impl EventHandler<FilteredReading<Celsius>> for MockDevice {}
+
+        let mut filtered = Filtered::<MockDevice, Celsius>::new(Smoothing::MovingAverage(2));
+        let reading = filtered.filter(SensorAcquisition {
+            temperature: 20.0.into(),
+            relative_humidity: 40.0,
+        });
+        assert_close(reading.temperature.value(), 20.0);
+        assert!(!reading.settled);
+
+        let reading = filtered.filter(SensorAcquisition {
+            temperature: 21.0.into(),
+            relative_humidity: 50.0,
+        });
+        assert_close(reading.temperature.value(), 20.5);
+        assert_close(reading.relative_humidity, 45.0);
+        assert!(reading.settled);
+    }
+}
@@ -5,10 +5,14 @@ pub mod sensor;
 
 pub use package::Hts221;
 pub use ready::Ready;
-pub use sensor::Sensor;
+pub use sensor::{GetCalibration, Hts221Config, Sample, Sensor, SetCalibration, SetPower};
 
-use crate::domain::temperature::{Temperature, TemperatureScale};
-use core::fmt::{Debug, Formatter};
+use crate::domain::cbor::{self, Encoder};
+use crate::domain::telemetry::{self, Telemetry};
+use crate::domain::temperature::{Celsius, Temperature, TemperatureScale};
+use crate::domain::time::duration::Milliseconds;
+use core::fmt::{Debug, Formatter, Write};
+use heapless::{ArrayLength, String};
 
 #[derive(Copy, Clone)]
 pub struct SensorAcquisition<S:TemperatureScale> {
@@ -25,3 +29,77 @@ impl<S:TemperatureScale> Debug for SensorAcquisition<S> {
     }
 }
 
+
+impl SensorAcquisition<Celsius> {
+    /// Encode as `{"temp_c":<°C>,"humidity":<%RH>}`, failing if it does not fit in `N` bytes.
+    pub fn to_json<N: ArrayLength<u8>>(&self) -> Result<String<N>, core::fmt::Error> {
+        let mut json = String::new();
+        json.write_char('{')?;
+        telemetry::write_reading(&mut json, self.temperature, self.relative_humidity)?;
+        Ok(json)
+    }
+
+    /// Encode as a CBOR map with the keys of the JSON encoding into `buf`, returning
+    /// the number of bytes written.
+    pub fn to_cbor(&self, buf: &mut [u8]) -> Result<usize, cbor::Error> {
+        let mut encoder = Encoder::new(buf);
+        encoder.map(2)?;
+        telemetry::encode_reading(&mut encoder, self.temperature, self.relative_humidity)?;
+        Ok(encoder.len())
+    }
+
+    /// Wrap this reading in a `Telemetry` envelope stamped with the uptime `ts`.
+    pub fn telemetry(&self, ts: Milliseconds) -> Telemetry {
+        Telemetry::new(ts, self.temperature, self.relative_humidity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::cbor::tests::Decoder;
+    use heapless::consts::*;
+
+    #[test]
+    fn test_to_json() {
+        let acquisition = SensorAcquisition {
+            temperature: (-12.75).into(),
+            relative_humidity: 55.5,
+        };
+        assert_eq!(
+            acquisition.to_json::<U64>().unwrap().as_str(),
+            r#"{"temp_c":-12.75,"humidity":55.5}"#
+        );
+        assert_eq!(
+            acquisition
+                .telemetry(Milliseconds(1000u32))
+                .to_json::<U64>()
+                .unwrap()
+                .as_str(),
+            r#"{"ts":1000,"temp_c":-12.75,"humidity":55.5}"#
+        );
+    }
+
+    #[test]
+    fn test_to_cbor() {
+        let acquisition = SensorAcquisition {
+            temperature: 19.5.into(),
+            relative_humidity: 61.0,
+        };
+        let mut buf = [0; 32];
+        let len = acquisition.to_cbor(&mut buf).unwrap();
+
+        let mut decoder = Decoder::new(&buf[..len]);
+        assert_eq!(decoder.map(), 2);
+        assert_eq!(decoder.text(), "temp_c");
+        assert_eq!(decoder.f32(), 19.5);
+        assert_eq!(decoder.text(), "humidity");
+        assert_eq!(decoder.f32(), 61.0);
+        assert!(decoder.is_empty());
+
+        assert_eq!(
+            acquisition.to_cbor(&mut buf[..len - 1]),
+            Err(cbor::Error::BufferTooSmall)
+        );
+    }
+}
@@ -4,27 +4,30 @@ use crate::driver::sensor::hts221::SensorAcquisition;
 use crate::hal::gpio::exti_pin::ExtiPin;
 use crate::handler::EventHandler;
 use crate::package::Package;
+use crate::driver::timer::Clock;
 use crate::prelude::*;
 use cortex_m::interrupt::Nr;
 use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
 use embedded_hal::digital::v2::InputPin;
 use crate::domain::temperature::Celsius;
 
-pub struct Hts221<D, P, I>
+pub struct Hts221<D, P, I, C>
 where
     D: Device + EventHandler<SensorAcquisition<Celsius>> + 'static,
     P: InputPin + ExtiPin + 'static,
     I: WriteRead + Read + Write + 'static,
+    C: Clock,
 {
-    sensor: ActorContext<Sensor<D, I>>,
-    ready: InterruptContext<Ready<D, P, I>>,
+    sensor: ActorContext<Sensor<D, I, C>>,
+    ready: InterruptContext<Ready<D, P, I, C>>,
 }
 
-impl<D, P, I> Hts221<D, P, I>
+impl<D, P, I, C> Hts221<D, P, I, C>
 where
     D: Device + EventHandler<SensorAcquisition<Celsius>>,
     P: InputPin + ExtiPin,
     I: WriteRead + Read + Write,
+    C: Clock,
 {
     pub fn new<N: Nr>(ready: P, irq: N) -> Self {
         Self {
@@ -34,17 +37,18 @@ where
     }
 }
 
-impl<D, P, I> Package<D, Sensor<D, I>> for Hts221<D, P, I>
+impl<D, P, I, C> Package<D, Sensor<D, I, C>> for Hts221<D, P, I, C>
 where
     D: Device + EventHandler<SensorAcquisition<Celsius>>,
     P: InputPin + ExtiPin,
     I: WriteRead + Read + Write,
+    C: Clock,
 {
     fn mount(
         &'static self,
         bus_address: Address<EventBus<D>>,
         supervisor: &mut Supervisor,
-    ) -> Address<Sensor<D, I>> {
+    ) -> Address<Sensor<D, I, C>> {
         let ready_addr = self.ready.mount(supervisor);
         let sensor_addr = self.sensor.mount(supervisor);
         sensor_addr.bind(bus_address);
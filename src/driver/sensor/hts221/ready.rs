@@ -3,6 +3,7 @@ use crate::driver::sensor::hts221::sensor::Sensor;
 use crate::driver::sensor::hts221::SensorAcquisition;
 use crate::hal::gpio::exti_pin::ExtiPin;
 use crate::handler::EventHandler;
+use crate::driver::timer::Clock;
 use crate::prelude::*;
 use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
 use embedded_hal::digital::v2::InputPin;
@@ -10,40 +11,44 @@ use crate::domain::temperature::Celsius;
 
 pub struct DataReady;
 
-pub struct Ready<D, P, I>
+pub struct Ready<D, P, I, C>
 where
     D: Device + 'static,
     P: InputPin + ExtiPin + 'static,
     I: WriteRead + Read + Write + 'static,
+    C: Clock,
 {
     pin: P,
-    sensor: Option<Address<Sensor<D, I>>>,
+    sensor: Option<Address<Sensor<D, I, C>>>,
 }
 
-impl<D, P, I> Ready<D, P, I>
+impl<D, P, I, C> Ready<D, P, I, C>
 where
     D: Device,
     P: InputPin + ExtiPin,
     I: WriteRead + Read + Write,
+    C: Clock,
 {
     pub fn new(pin: P) -> Self {
         Self { pin, sensor: None }
     }
 }
 
-impl<D, P, I> Actor for Ready<D, P, I>
+impl<D, P, I, C> Actor for Ready<D, P, I, C>
 where
     D: Device,
     P: InputPin + ExtiPin,
     I: WriteRead + Read + Write + 'static,
+    C: Clock,
 {
 }
 
-impl<D, P, I> Interrupt for Ready<D, P, I>
+impl<D, P, I, C> Interrupt for Ready<D, P, I, C>
 where
     D: Device + EventHandler<SensorAcquisition<Celsius>> + 'static,
     P: InputPin + ExtiPin,
     I: WriteRead + Read + Write + 'static,
+    C: Clock,
 {
     fn on_interrupt(&mut self) {
         if self.pin.check_interrupt() {
@@ -55,13 +60,14 @@ where
     }
 }
 
-impl<D, P, I> Bind<Sensor<D, I>> for Ready<D, P, I>
+impl<D, P, I, C> Bind<Sensor<D, I, C>> for Ready<D, P, I, C>
 where
     D: Device,
     P: InputPin + ExtiPin,
     I: WriteRead + Read + Write,
+    C: Clock,
 {
-    fn on_bind(&mut self, address: Address<Sensor<D, I>>) {
+    fn on_bind(&mut self, address: Address<Sensor<D, I, C>>) {
         self.sensor.replace(address);
     }
 }
@@ -3,21 +3,31 @@ use crate::hal::i2c::I2cAddress;
 use crate::prelude::Address;
 use embedded_hal::blocking::i2c::WriteRead;
 use crate::driver::i2c::I2cPeripheral;
+use crate::error::DeviceError;
 
 // 16-byte block of calibration at 0x30 with high bit for auto-increment
 const CALIBRATION_16: u8 = 0xB0;
 
+/// The factory calibration of a sensor: two points each mapping raw temperature and
+/// humidity readings to their values, interpolated between.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Calibration {
     pub temperature: TemperatureCalibration,
     pub humidity: HumidityCalibration,
 }
 
 impl Calibration {
+    pub fn new(temperature: TemperatureCalibration, humidity: HumidityCalibration) -> Self {
+        Self {
+            temperature,
+            humidity,
+        }
+    }
 
     pub async fn read<I: WriteRead>(
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
-    ) -> Result<Calibration, I::Error> {
+    ) -> Result<Calibration, DeviceError> {
         unsafe {
             // # Safety
             // The call to `.write_read` is properly awaited for completion before allowing the buffer to drop.
@@ -38,6 +48,7 @@ impl Calibration {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct TemperatureCalibration {
     pub t0_out: i16,
     pub t1_out: i16,
@@ -47,11 +58,29 @@ pub struct TemperatureCalibration {
 }
 
 impl TemperatureCalibration {
+    /// Calibrate by the raw readings `t0_out` at `t0_degc` and `t1_out` at `t1_degc`.
+    pub fn new(
+        t0_out: i16,
+        t0_degc: Temperature<Celsius>,
+        t1_out: i16,
+        t1_degc: Temperature<Celsius>,
+    ) -> Self {
+        let slope = (t1_degc - t0_degc) / ((t1_out - t0_out) as f32);
+        Self {
+            t0_out,
+            t1_out,
+            t0_degc,
+            t1_degc,
+            slope,
+        }
+    }
+
     pub fn calibrated(&self, t_out: i16) -> Temperature<Celsius> {
         self.t0_degc + (self.slope * (t_out - self.t0_out) as f32)
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct HumidityCalibration {
     pub h0_out: i16,
     pub h1_out: i16,
@@ -61,6 +90,18 @@ pub struct HumidityCalibration {
 }
 
 impl HumidityCalibration {
+    /// Calibrate by the raw readings `h0_out` at `h0_rh` and `h1_out` at `h1_rh` %RH.
+    pub fn new(h0_out: i16, h0_rh: f32, h1_out: i16, h1_rh: f32) -> Self {
+        let slope = (h1_rh - h0_rh) / ((h1_out - h0_out) as f32);
+        Self {
+            h0_out,
+            h1_out,
+            h0_rh,
+            h1_rh,
+            slope,
+        }
+    }
+
     pub fn calibrated(&self, h_out: i16) -> f32 {
         self.h0_rh + (self.slope * (h_out - self.h0_out) as f32)
     }
@@ -77,21 +118,13 @@ impl Into<Calibration> for [u8; 16] {
 
         let t_msb = self[5];
 
-        let t0_msb = (t_msb & 0b00000011) >> 2;
+        let t0_msb = t_msb & 0b00000011;
         let t1_msb = (t_msb & 0b00001100) >> 2;
 
         let t0_degc = (i16::from_le_bytes([t0_degc, t0_msb]) as f32 / 8.0).into();
         let t1_degc = (i16::from_le_bytes([t1_degc, t1_msb]) as f32 / 8.0).into();
 
-        let slope = (t1_degc - t0_degc) / ((t1_out - t0_out) as f32);
-
-        let temperature = TemperatureCalibration {
-            t0_out,
-            t1_out,
-            t0_degc,
-            t1_degc,
-            slope,
-        };
+        let temperature = TemperatureCalibration::new(t0_out, t0_degc, t1_out, t1_degc);
 
         let h0_rh = self[0] as f32 / 2.0;
         let h1_rh = self[1] as f32 / 2.0;
@@ -100,19 +133,45 @@ impl Into<Calibration> for [u8; 16] {
 
         let h1_out = i16::from_le_bytes([self[10], self[11]]);
 
-        let slope = (h1_rh - h0_rh) / ((h1_out - h0_out) as f32);
+        let humidity = HumidityCalibration::new(h0_out, h0_rh, h1_out, h1_rh);
 
-        let humidity = HumidityCalibration {
-            h0_out,
-            h1_out,
-            h0_rh,
-            h1_rh,
-            slope,
-        };
+        Calibration::new(temperature, humidity)
+    }
+}
 
-        Calibration {
-            temperature,
-            humidity,
-        }
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Calibration bytes of a part reading 0 at 35°C and 560 at 70°C, and -100 at
+    /// 32%RH and 540 at 72%RH.
+    pub(crate) const CALIBRATION: [u8; 16] = [
+        0x40, 0x90, // H0_rH_x2, H1_rH_x2
+        0x18, 0x30, // T0_degC_x8, T1_degC_x8, low bytes
+        0x00, // reserved
+        0x09, // T1/T0 msb: T1 0b10, T0 0b01
+        0x9c, 0xff, // H0_T0_OUT
+        0x00, 0x00, // reserved
+        0x1c, 0x02, // H1_T0_OUT
+        0x00, 0x00, // T0_OUT
+        0x30, 0x02, // T1_OUT
+    ];
+
+    #[test]
+    fn test_parse() {
+        let calibration: Calibration = CALIBRATION.into();
+        assert_eq!(
+            calibration.temperature,
+            TemperatureCalibration::new(0, 35.0.into(), 560, 70.0.into())
+        );
+        assert_eq!(calibration.temperature.slope, 0.0625);
+        assert_eq!(
+            calibration.humidity,
+            HumidityCalibration::new(-100, 32.0, 540, 72.0)
+        );
+        assert_eq!(calibration.humidity.slope, 0.0625);
+
+        assert_eq!(calibration.calibrated_temperature(280), 52.5.into());
+        assert_eq!(calibration.calibrated_humidity(220), 52.0);
     }
 }
@@ -2,9 +2,12 @@ use crate::hal::i2c::I2cAddress;
 use crate::prelude::Address;
 use embedded_hal::blocking::i2c::{Write, WriteRead};
 use crate::driver::sensor::hts221::register::ModifyError;
-use crate::driver::i2c::I2cPeripheral;
+use crate::driver::i2c::{I2cPeripheral, I2cReadBus};
+use crate::error::DeviceError;
 
 const CTRL_REG1: u8 = 0x20;
+/// CTRL_REG1 power-down control bit, set while active.
+const PD: u8 = 0x80;
 
 #[derive(Debug, Copy, Clone)]
 pub enum Power {
@@ -18,7 +21,7 @@ pub enum BlockDataUpdate {
     MsbLsbReading,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum OutputDataRate {
     OneShot,
     Hz1,
@@ -37,7 +40,7 @@ impl Ctrl1 {
     pub async fn read<I: WriteRead>(
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
-    ) -> Result<Ctrl1, I::Error> {
+    ) -> Result<Ctrl1, DeviceError> {
         unsafe {
             // # Safety
             // The call to `.write_read` is properly awaited for completion before allowing the buffer to drop.
@@ -53,7 +56,7 @@ impl Ctrl1 {
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
         reg: Ctrl1,
-    ) -> Result<(), I::Error> {
+    ) -> Result<(), DeviceError> {
         unsafe {
             // # Safety
             // The call to `.write` is properly awaited for completion before allowing the buffer to drop.
@@ -67,7 +70,7 @@ impl Ctrl1 {
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
         modify: F,
-    ) -> Result<(),ModifyError< <I as WriteRead>::Error, <I as Write>::Error>> {
+    ) -> Result<(),ModifyError<DeviceError, DeviceError>> {
         let mut reg = Self::read(address, i2c).await.map_err( ModifyError::Read)?;
         modify(&mut reg);
         Self::write(address, i2c, reg).await.map_err(ModifyError::Write)
@@ -94,6 +97,21 @@ impl Ctrl1 {
     }
 }
 
+/// Power the sensor at `address` up or down, keeping the rest of its configuration.
+pub async fn set_power<B: I2cReadBus>(
+    bus: &mut B,
+    address: I2cAddress,
+    power: Power,
+) -> Result<(), B::Error> {
+    let mut reg = [0];
+    bus.write_read(address, &[CTRL_REG1], &mut reg).await?;
+    let reg = match power {
+        Power::Active => reg[0] | PD,
+        Power::PowerDown => reg[0] & !PD,
+    };
+    bus.write(address, &[CTRL_REG1, reg]).await
+}
+
 impl Into<Power> for u8 {
     fn into(self) -> Power {
         if (self & 0x80) != 0 {
@@ -2,9 +2,12 @@ use crate::driver::sensor::hts221::register::ModifyError;
 use crate::hal::i2c::I2cAddress;
 use crate::prelude::Address;
 use embedded_hal::blocking::i2c::{Write, WriteRead};
-use crate::driver::i2c::I2cPeripheral;
+use crate::driver::i2c::{I2cPeripheral, I2cReadBus};
+use crate::error::DeviceError;
 
 const CTRL_REG2: u8 = 0x21;
+/// CTRL_REG2 one-shot enable bit, cleared by the sensor once the conversion is done.
+const ONE_SHOT: u8 = 0x01;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Ctrl2 {
@@ -17,7 +20,7 @@ impl Ctrl2 {
     pub async fn read<I: WriteRead>(
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
-    ) -> Result<Ctrl2, I::Error> {
+    ) -> Result<Ctrl2, DeviceError> {
         unsafe {
             // # Safety
             // The call to `.write_read` is properly awaited for completion before allowing the buffer to drop.
@@ -27,7 +30,7 @@ impl Ctrl2 {
         }
     }
 
-    pub async fn write<I: Write>(address: I2cAddress, i2c: Address<I2cPeripheral<I>>, reg: Ctrl2) -> Result<(), I::Error>{
+    pub async fn write<I: Write>(address: I2cAddress, i2c: Address<I2cPeripheral<I>>, reg: Ctrl2) -> Result<(), DeviceError>{
         unsafe {
             // # Safety
             // The call to `.write` is properly awaited for completion before allowing the buffer to drop.
@@ -40,7 +43,7 @@ impl Ctrl2 {
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
         modify: F,
-    ) -> Result<(), ModifyError<<I as WriteRead>::Error, <I as Write>::Error>> {
+    ) -> Result<(), ModifyError<DeviceError, DeviceError>> {
         let mut reg = Self::read(address, i2c).await.map_err( ModifyError::Read)?;
         modify(&mut reg);
         Self::write(address, i2c, reg).await.map_err( ModifyError::Write)
@@ -62,6 +65,17 @@ impl Ctrl2 {
     }
 }
 
+/// Start a single conversion on the sensor at `address`, powered up with an output data
+/// rate of `OneShot`.
+pub async fn start_one_shot<B: I2cReadBus>(
+    bus: &mut B,
+    address: I2cAddress,
+) -> Result<(), B::Error> {
+    let mut reg = [0];
+    bus.write_read(address, &[CTRL_REG2], &mut reg).await?;
+    bus.write(address, &[CTRL_REG2, reg[0] | ONE_SHOT]).await
+}
+
 impl Into<Ctrl2> for u8 {
     fn into(self) -> Ctrl2 {
         let boot = (self & 0b10000000) != 0;
@@ -3,6 +3,7 @@ use crate::prelude::Address;
 use embedded_hal::blocking::i2c::{Write, WriteRead};
 use crate::driver::sensor::hts221::register::ModifyError;
 use crate::driver::i2c::I2cPeripheral;
+use crate::error::DeviceError;
 
 const CTRL_REG3: u8 = 0x22;
 
@@ -25,7 +26,7 @@ pub struct Ctrl3 {
 }
 
 impl Ctrl3 {
-    pub async fn read<I: WriteRead>(address: I2cAddress, i2c: Address<I2cPeripheral<I>>) -> Result<Ctrl3, I::Error> {
+    pub async fn read<I: WriteRead>(address: I2cAddress, i2c: Address<I2cPeripheral<I>>) -> Result<Ctrl3, DeviceError> {
         unsafe {
             // # Safety
             // The call to `.write_read` is properly awaited for completion before allowing the buffer to drop.
@@ -35,7 +36,7 @@ impl Ctrl3 {
         }
     }
 
-    pub async fn write<I: Write>(address: I2cAddress, i2c: Address<I2cPeripheral<I>>, reg: Ctrl3) -> Result<(), I::Error>{
+    pub async fn write<I: Write>(address: I2cAddress, i2c: Address<I2cPeripheral<I>>, reg: Ctrl3) -> Result<(), DeviceError>{
         unsafe {
             // # Safety
             // The call to `.write` is properly awaited for completion before allowing the buffer to drop.
@@ -49,7 +50,7 @@ impl Ctrl3 {
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
         modify: F,
-    ) -> Result<(), ModifyError< <I as WriteRead>::Error, <I as Write>::Error>>{
+    ) -> Result<(), ModifyError<DeviceError, DeviceError>>{
         let mut reg = Self::read(address, i2c).await.map_err( ModifyError::Read)?;
         modify(&mut reg);
         Self::write(address, i2c, reg).await.map_err( ModifyError::Write)
@@ -2,9 +2,10 @@ use crate::hal::i2c::I2cAddress;
 use crate::prelude::Address;
 use embedded_hal::blocking::i2c::WriteRead;
 use crate::driver::i2c::I2cPeripheral;
+use crate::error::DeviceError;
 
 // auto-increment variant of 2 bytes
-const H_OUT: u8 = 0xA8;
+pub(crate) const H_OUT: u8 = 0xA8;
 
 pub struct Hout;
 
@@ -12,7 +13,7 @@ impl Hout {
     pub async fn read<I: WriteRead>(
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
-    ) -> Result<i16, I::Error> {
+    ) -> Result<i16, DeviceError> {
         unsafe {
             // # Safety
             // The call to `.write_read` is properly awaited for completion before allowing the buffer to drop.
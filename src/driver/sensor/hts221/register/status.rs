@@ -1,9 +1,14 @@
 use crate::hal::i2c::I2cAddress;
 use crate::prelude::Address;
 use embedded_hal::blocking::i2c::WriteRead;
-use crate::driver::i2c::I2cPeripheral;
+use crate::driver::i2c::{I2cPeripheral, I2cReadBus};
+use core::cell::RefCell;
+use crate::error::DeviceError;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::Clock;
+use crate::util::poll::poll_until;
 
-const STATUS: u8 = 0x27;
+pub(crate) const STATUS: u8 = 0x27;
 
 pub struct Status {
     temperature_available: bool,
@@ -14,7 +19,7 @@ impl Status {
     pub async fn read<I: WriteRead>(
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
-    ) -> Result<Status, I::Error> {
+    ) -> Result<Status, DeviceError> {
         unsafe {
             // # Safety
             // The call to `.write_read` is properly awaited for completion before allowing the buffer to drop.
@@ -24,6 +29,36 @@ impl Status {
         }
     }
 
+    /// Wait for a new reading of both temperature and humidity, reading the status
+    /// every `interval` on `clock` rather than busy-polling the bus, for at most
+    /// `timeout`.
+    pub async fn wait_available<B, C>(
+        address: I2cAddress,
+        bus: &mut B,
+        clock: &C,
+        interval: Milliseconds,
+        timeout: Milliseconds,
+    ) -> Result<(), DeviceError>
+    where
+        B: I2cReadBus<Error = DeviceError>,
+        C: Clock,
+    {
+        let bus = RefCell::new(bus);
+        let bus = &bus;
+        // each read of the status is awaited before the next is started, so the bus is
+        // never borrowed twice
+        #[allow(clippy::await_holding_refcell_ref)]
+        let poll = || async move {
+            let mut status = [0];
+            bus.borrow_mut()
+                .write_read(address, &[STATUS], &mut status)
+                .await?;
+            let status: Status = status[0].into();
+            Ok(status.all_available())
+        };
+        poll_until(clock, poll, interval, timeout).await
+    }
+
     pub fn temperature_available(&self) -> bool {
         self.temperature_available
     }
@@ -35,6 +70,10 @@ impl Status {
     pub fn any_available(&self) -> bool {
         self.temperature_available || self.humidity_available
     }
+
+    pub fn all_available(&self) -> bool {
+        self.temperature_available && self.humidity_available
+    }
 }
 
 impl Into<Status> for u8 {
@@ -2,6 +2,7 @@ use crate::hal::i2c::I2cAddress;
 use crate::prelude::Address;
 use embedded_hal::blocking::i2c::WriteRead;
 use crate::driver::i2c::I2cPeripheral;
+use crate::error::DeviceError;
 
 const WHO_AM_I: u8 = 0x0F;
 
@@ -11,7 +12,7 @@ impl WhoAmI {
     pub async fn read<I: WriteRead>(
         address: I2cAddress,
         i2c: Address<I2cPeripheral<I>>,
-    ) -> Result<I2cAddress, I::Error> {
+    ) -> Result<I2cAddress, DeviceError> {
         unsafe {
             // # Safety
             // The call to `.write_read` is properly awaited for completion before allowing the buffer to drop.
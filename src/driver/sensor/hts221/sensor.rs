@@ -1,37 +1,105 @@
 use crate::bind::Bind;
-use crate::domain::temperature::Celsius;
+use crate::domain::temperature::{Celsius, Temperature, TemperatureDelta};
+use crate::driver::reconfigure::Reconfigurable;
 use crate::driver::sensor::hts221::ready::DataReady;
 use crate::driver::sensor::hts221::register::calibration::*;
-use crate::driver::sensor::hts221::register::ctrl1::{BlockDataUpdate, Ctrl1, OutputDataRate};
-use crate::driver::sensor::hts221::register::ctrl2::Ctrl2;
+use crate::driver::sensor::hts221::register::ctrl1::{
+    set_power, BlockDataUpdate, Ctrl1, OutputDataRate, Power,
+};
+use crate::driver::sensor::hts221::register::ctrl2::{start_one_shot, Ctrl2};
 use crate::driver::sensor::hts221::register::ctrl3::Ctrl3;
-use crate::driver::sensor::hts221::register::h_out::Hout;
+use crate::driver::sensor::hts221::register::h_out::{Hout, H_OUT};
 use crate::driver::sensor::hts221::register::status::Status;
-use crate::driver::sensor::hts221::register::t_out::Tout;
+use crate::driver::sensor::hts221::register::t_out::{Tout, T_OUT};
 use crate::driver::sensor::hts221::SensorAcquisition;
 use crate::hal::i2c::I2cAddress;
 use crate::handler::EventHandler;
 use crate::prelude::*;
 use embedded_hal::blocking::i2c::{Read, Write, WriteRead};
-use crate::driver::i2c::I2cPeripheral;
+use crate::driver::i2c::{I2cPeripheral, I2cReadBus};
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::{Clock, TimerActor};
+use crate::hal::timer::Timer as HalTimer;
 
 pub const ADDR: u8 = 0x5F;
 
-pub struct Sensor<D, I>
+/// How long to wait between reads of the status for a one-shot conversion to complete.
+const ONE_SHOT_INTERVAL: Milliseconds = Milliseconds(5);
+
+/// How long a one-shot conversion may take, before it times out.
+const ONE_SHOT_TIMEOUT: Milliseconds = Milliseconds(250);
+
+/// Take a single reading from the sensor at `address`, powering it up for the
+/// conversion and down again after, returning the raw temperature and humidity.
+/// The conversion is waited on by `clock`.
+///
+/// The sensor is to be configured with an output data rate of `OneShot`.
+pub async fn one_shot<B: I2cReadBus<Error = DeviceError>, C: Clock>(
+    bus: &mut B,
+    clock: &C,
+    address: I2cAddress,
+) -> Result<(i16, i16), DeviceError> {
+    set_power(bus, address, Power::Active).await?;
+    let result = convert(bus, clock, address).await;
+    set_power(bus, address, Power::PowerDown).await?;
+    result
+}
+
+/// Run a single conversion on the powered sensor, and read its outputs once done.
+async fn convert<B: I2cReadBus<Error = DeviceError>, C: Clock>(
+    bus: &mut B,
+    clock: &C,
+    address: I2cAddress,
+) -> Result<(i16, i16), DeviceError> {
+    start_one_shot(bus, address).await?;
+    Status::wait_available(address, bus, clock, ONE_SHOT_INTERVAL, ONE_SHOT_TIMEOUT).await?;
+    let mut t_out = [0; 2];
+    bus.write_read(address, &[T_OUT], &mut t_out).await?;
+    let mut h_out = [0; 2];
+    bus.write_read(address, &[H_OUT], &mut h_out).await?;
+    Ok((i16::from_le_bytes(t_out), i16::from_le_bytes(h_out)))
+}
+
+/// The runtime configuration of the sensor.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hts221Config {
+    /// How often the sensor takes a reading.
+    pub output_data_rate: OutputDataRate,
+    /// When set, readings are only published once the temperature differs
+    /// from the last published one by at least this much.
+    pub threshold: Option<TemperatureDelta<Celsius>>,
+}
+
+impl Default for Hts221Config {
+    fn default() -> Self {
+        Self {
+            output_data_rate: OutputDataRate::Hz1,
+            threshold: None,
+        }
+    }
+}
+
+pub struct Sensor<D, I, C>
 where
     D: Device + 'static,
     I: WriteRead + Read + Write + 'static,
+    C: Clock,
 {
     address: I2cAddress,
     i2c: Option<Address<I2cPeripheral<I>>>,
     calibration: Option<Calibration>,
     bus: Option<Address<EventBus<D>>>,
+    config: Hts221Config,
+    published: Option<Temperature<Celsius>>,
+    /// Times one-shot conversions, needed at an output data rate of `OneShot` only.
+    clock: Option<C>,
 }
 
-impl<D, I> Sensor<D, I>
+impl<D, I, C> Sensor<D, I, C>
 where
     D: Device,
     I: WriteRead + Read + Write + 'static,
+    C: Clock,
 {
     pub fn new() -> Self {
         Self {
@@ -39,35 +107,82 @@ where
             i2c: None,
             calibration: None,
             bus: None,
+            config: Hts221Config::default(),
+            published: None,
+            clock: None,
+        }
+    }
+
+    /// Time one-shot conversions by `clock` rather than by a bound timer.
+    pub fn with_clock(mut self, clock: C) -> Self {
+        self.clock.replace(clock);
+        self
+    }
+
+    /// Take a reading, first reading the calibration if it has not been yet. At an
+    /// output data rate of `OneShot`, the sensor is powered up for the reading only.
+    async fn sample(
+        &mut self,
+        mut i2c: Address<I2cPeripheral<I>>,
+    ) -> Result<SensorAcquisition<Celsius>, DeviceError> {
+        let calibration = match self.calibration {
+            Some(calibration) => calibration,
+            None => {
+                let calibration = Calibration::read(self.address, i2c).await?;
+                self.calibration.replace(calibration);
+                calibration
+            }
+        };
+        let (t_out, h_out) = if self.config.output_data_rate == OutputDataRate::OneShot {
+            let clock = self.clock.ok_or(DeviceError::NotBound)?;
+            one_shot(&mut i2c, &clock, self.address).await?
+        } else {
+            (
+                Tout::read(self.address, i2c).await?,
+                Hout::read(self.address, i2c).await?,
+            )
+        };
+        Ok(SensorAcquisition {
+            temperature: calibration.calibrated_temperature(t_out),
+            relative_humidity: calibration.calibrated_humidity(h_out),
+        })
+    }
+
+    fn exceeds_threshold(&self, temperature: Temperature<Celsius>) -> bool {
+        match (self.config.threshold, self.published) {
+            (Some(threshold), Some(published)) => (temperature - published).abs() >= threshold,
+            _ => true,
         }
     }
 }
 
-impl<D, I> Default for Sensor<D, I>
+impl<D, I, C> Default for Sensor<D, I, C>
 where
     D: Device,
     I: WriteRead + Read + Write + 'static,
+    C: Clock,
 {
     fn default() -> Self {
         Sensor::new()
     }
 }
 
-impl<D, I> Actor for Sensor<D, I>
+impl<D, I, C> Actor for Sensor<D, I, C>
 where
     D: Device,
     I: WriteRead + Read + Write,
+    C: Clock,
 {
     fn on_initialize(self) -> Completion<Self> {
         Completion::defer(async move {
-            if let Some(i2c) = self.i2c {
+            if let Some(mut i2c) = self.i2c {
                 Ctrl2::modify(self.address, i2c, |reg| {
                     reg.boot();
                 }).await.ok();
 
                 Ctrl1::modify(self.address, i2c, |reg| {
                     reg.power_active()
-                        .output_data_rate(OutputDataRate::Hz1)
+                        .output_data_rate(self.config.output_data_rate)
                         .block_data_update(BlockDataUpdate::MsbLsbReading);
                 }).await.ok();
 
@@ -85,6 +200,13 @@ where
                     Hout::read(self.address, i2c).await.ok();
                     Tout::read(self.address, i2c).await.ok();
                 }
+
+                if self.config.output_data_rate == OutputDataRate::OneShot {
+                    // powered up for each reading only
+                    set_power(&mut i2c, self.address, Power::PowerDown)
+                        .await
+                        .ok();
+                }
             }
             self
         })
@@ -92,6 +214,10 @@ where
 
     fn on_start(mut self) -> Completion<Self> {
         Completion::defer(async move {
+            if self.calibration.is_some() {
+                // overridden before start
+                return self;
+            }
             if let Some(i2c) = self.i2c {
                 if let Ok(calibration) = Calibration::read(self.address, i2c).await {
                     self.calibration.replace( calibration );
@@ -102,32 +228,46 @@ where
     }
 }
 
-impl<D, I> Bind<EventBus<D>> for Sensor<D, I>
+impl<D, I, C> Bind<EventBus<D>> for Sensor<D, I, C>
 where
     D: Device,
     I: WriteRead + Read + Write,
+    C: Clock,
 {
     fn on_bind(&mut self, address: Address<EventBus<D>>) {
         self.bus.replace(address);
     }
 }
 
-impl<D, I> Bind<I2cPeripheral<I>> for Sensor<D, I>
+impl<D, I, C> Bind<I2cPeripheral<I>> for Sensor<D, I, C>
 where
     D: Device,
     I: WriteRead + Read + Write + 'static,
+    C: Clock,
 {
     fn on_bind(&mut self, address: Address<I2cPeripheral<I>>) {
         self.i2c.replace(address);
     }
 }
 
-impl<D, I> NotifyHandler<DataReady> for Sensor<D, I>
+impl<D, I, T> Bind<TimerActor<T>> for Sensor<D, I, Address<TimerActor<T>>>
+where
+    D: Device,
+    I: WriteRead + Read + Write + 'static,
+    T: HalTimer + 'static,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.clock.replace(address);
+    }
+}
+
+impl<D, I, C> NotifyHandler<DataReady> for Sensor<D, I, C>
 where
     D: Device + EventHandler<SensorAcquisition<Celsius>>,
     I: WriteRead + Read + Write,
+    C: Clock,
 {
-    fn on_notify(self, message: DataReady) -> Completion<Self> {
+    fn on_notify(mut self, message: DataReady) -> Completion<Self> {
         Completion::defer(async move {
             if self.i2c.is_some() {
                 let i2c = self.i2c.unwrap();
@@ -135,18 +275,27 @@ where
                 if let Some(ref calibration) = self.calibration {
                     if let Ok(t_out) = Tout::read(self.address, i2c).await {
                         let temperature = calibration.calibrated_temperature(t_out);
+                        if !self.exceeds_threshold(temperature) {
+                            return self;
+                        }
 
                         if let Ok(h_out) = Hout::read(self.address, i2c).await {
                             let relative_humidity = calibration.calibrated_humidity(h_out);
 
-                            self.bus.unwrap().publish(SensorAcquisition {
-                                temperature,
-                                relative_humidity,
-                            });
+                            match self.bus {
+                                Some(bus) => {
+                                    bus.publish(SensorAcquisition {
+                                        temperature,
+                                        relative_humidity,
+                                    });
+                                    self.published.replace(temperature);
+                                }
+                                None => warn!("[hts221] no event bus bound"),
+                            }
                         }
                     }
                 } else {
-                    log::warn!("[hts221] no calibration data available")
+                    warn!("[hts221] no calibration data available")
                 }
             }
             self
@@ -154,13 +303,414 @@ where
     }
 }
 
+/// Request for the calibration the sensor's readings are converted by, if read yet.
+#[derive(Copy, Clone, Debug)]
+pub struct GetCalibration;
+
+impl<D, I, C> RequestHandler<GetCalibration> for Sensor<D, I, C>
+where
+    D: Device + 'static,
+    I: WriteRead + Read + Write + 'static,
+    C: Clock,
+{
+    type Response = Option<Calibration>;
+
+    fn on_request(self, _: GetCalibration) -> Response<Self, Self::Response> {
+        let calibration = self.calibration;
+        Response::immediate(self, calibration)
+    }
+}
+
+/// Request for a reading taken now, whatever the output data rate, and without
+/// publishing it.
+#[derive(Copy, Clone, Debug)]
+pub struct Sample;
+
+impl<D, I, C> RequestHandler<Sample> for Sensor<D, I, C>
+where
+    D: Device + 'static,
+    I: WriteRead + Read + Write + 'static,
+    C: Clock,
+{
+    type Response = Result<SensorAcquisition<Celsius>, DeviceError>;
+
+    fn on_request(mut self, _: Sample) -> Response<Self, Self::Response> {
+        match self.i2c {
+            Some(i2c) => Response::defer(async move {
+                let result = self.sample(i2c).await;
+                (self, result)
+            }),
+            None => Response::immediate(self, Err(DeviceError::NotBound)),
+        }
+    }
+}
+
+/// Request to power the sensor up or down. At an output data rate of `OneShot`, the
+/// sensor is kept powered down, and powered up for each reading only.
+#[derive(Copy, Clone, Debug)]
+pub struct SetPower(pub Power);
+
+impl<D, I, C> RequestHandler<SetPower> for Sensor<D, I, C>
+where
+    D: Device + 'static,
+    I: WriteRead + Read + Write + 'static,
+    C: Clock,
+{
+    type Response = Result<(), DeviceError>;
+
+    fn on_request(self, message: SetPower) -> Response<Self, Self::Response> {
+        match self.i2c {
+            Some(mut i2c) => Response::defer(async move {
+                let result = set_power(&mut i2c, self.address, message.0).await;
+                (self, result)
+            }),
+            None => Response::immediate(self, Err(DeviceError::NotBound)),
+        }
+    }
+}
+
+/// Convert readings by the given calibration in place of the sensor's own, such as to
+/// correct a miscalibrated part. Set before the sensor starts, the factory calibration
+/// is not read at all.
+#[derive(Copy, Clone, Debug)]
+pub struct SetCalibration(pub Calibration);
+
+impl<D, I, C> NotifyHandler<SetCalibration> for Sensor<D, I, C>
+where
+    D: Device + 'static,
+    I: WriteRead + Read + Write + 'static,
+    C: Clock,
+{
+    fn on_notify(mut self, message: SetCalibration) -> Completion<Self> {
+        self.calibration.replace(message.0);
+        Completion::immediate(self)
+    }
+}
+
+/// A new output data rate is written to the sensor before the configuration is
+/// applied, powering the sensor down for `OneShot` and up otherwise, while a new
+/// threshold applies from the next reading.
+impl<D, I, C> Reconfigurable for Sensor<D, I, C>
+where
+    D: Device + 'static,
+    I: WriteRead + Read + Write + 'static,
+    C: Clock,
+{
+    type Config = Hts221Config;
+
+    fn validate(&self, config: &Hts221Config) -> bool {
+        match config.threshold {
+            Some(threshold) => threshold.value() >= 0.0,
+            None => true,
+        }
+    }
+
+    fn on_reconfigure(mut self, config: Hts221Config) -> Completion<Self> {
+        match self.i2c {
+            Some(mut i2c) if config.output_data_rate != self.config.output_data_rate => {
+                Completion::defer(async move {
+                    let power = match config.output_data_rate {
+                        OutputDataRate::OneShot => Power::PowerDown,
+                        _ => Power::Active,
+                    };
+                    if Ctrl1::modify(self.address, i2c, |reg| {
+                        reg.output_data_rate(config.output_data_rate);
+                    })
+                    .await
+                    .is_ok()
+                        && set_power(&mut i2c, self.address, power).await.is_ok()
+                    {
+                        self.config = config;
+                    } else {
+                        warn!("[hts221] failed to reconfigure");
+                    }
+                    self
+                })
+            }
+            _ => {
+                self.config = config;
+                Completion::immediate(self)
+            }
+        }
+    }
+}
+
 #[doc(hidden)]
-impl<D, I> Address<Sensor<D, I>>
+impl<D, I, C> Address<Sensor<D, I, C>>
 where
     D: Device + EventHandler<SensorAcquisition<Celsius>> + 'static,
     I: WriteRead + Read + Write,
+    C: Clock,
 {
     pub fn signal_data_ready(&self) {
         self.notify(DataReady)
     }
 }
+
+impl<D, I, C> Address<Sensor<D, I, C>>
+where
+    D: Device + 'static,
+    I: WriteRead + Read + Write,
+    C: Clock,
+{
+    /// The calibration readings are converted by, or `None` until read from the sensor.
+    pub async fn calibration(&self) -> Option<Calibration> {
+        self.request(GetCalibration).await
+    }
+
+    /// Take a reading now, failing with `NotBound` if the sensor has no I2C bus, or the
+    /// error of the bus.
+    pub async fn sample(&self) -> Result<SensorAcquisition<Celsius>, DeviceError> {
+        self.request(Sample).await
+    }
+
+    /// Override the calibration readings are converted by.
+    pub fn set_calibration(&self, calibration: Calibration) {
+        self.notify(SetCalibration(calibration))
+    }
+
+    /// Power the sensor down, keeping its configuration, until powered up again.
+    pub async fn power_down(&self) -> Result<(), DeviceError> {
+        self.request(SetPower(Power::PowerDown)).await
+    }
+
+    /// Power the sensor up after `power_down()`, the calibration being kept from before.
+    pub async fn power_up(&self) -> Result<(), DeviceError> {
+        self.request(SetPower(Power::Active)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::i2c::I2cBus;
+    use crate::driver::reconfigure::Reconfigure;
+    use crate::driver::sensor::hts221::register::calibration::tests::CALIBRATION;
+    use crate::driver::sensor::hts221::register::status::STATUS;
+    use crate::driver::timer::MockClock;
+    use crate::testing::block_on_advancing;
+    use core::convert::Infallible;
+    use std::boxed::Box;
+    use std::vec::Vec;
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    struct NoI2c;
+
+    impl Read for NoI2c {
+        type Error = Infallible;
+
+        fn read(&mut self, _: u8, _: &mut [u8]) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    impl Write for NoI2c {
+        type Error = Infallible;
+
+        fn write(&mut self, _: u8, _: &[u8]) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    impl WriteRead for NoI2c {
+        type Error = Infallible;
+
+        fn write_read(&mut self, _: u8, _: &[u8], _: &mut [u8]) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    type TestSensor = Sensor<MockDevice, NoI2c, &'static MockClock<()>>;
+
+    fn reconfigure(sensor: TestSensor, threshold: f32) -> TestSensor {
+        let config = Hts221Config {
+            output_data_rate: OutputDataRate::Hz7,
+            threshold: Some(TemperatureDelta::new(threshold)),
+        };
+        match sensor.on_notify(Reconfigure(config)) {
+            Completion::Immediate(sensor) => sensor,
+            Completion::Defer(_) => panic!("deferred"),
+        }
+    }
+
+    fn calibration(sensor: TestSensor) -> (TestSensor, Option<Calibration>) {
+        match sensor.on_request(GetCalibration) {
+            Response::Immediate(sensor, calibration) => (sensor, calibration),
+            _ => panic!("deferred"),
+        }
+    }
+
+    #[test]
+    fn test_calibration() {
+        let (mut sensor, unread) = calibration(TestSensor::new());
+        assert_eq!(unread, None);
+
+        // as read at start, from the calibration block
+        let factory: Calibration = CALIBRATION.into();
+        sensor.calibration.replace(factory);
+        let (sensor, read) = calibration(sensor);
+        assert_eq!(read, Some(factory));
+        assert_eq!(read.unwrap().temperature.t0_degc, 35.0.into());
+        assert_eq!(read.unwrap().humidity.h1_rh, 72.0);
+
+        // reading 1.5°C and 2%RH high
+        let corrected = Calibration::new(
+            TemperatureCalibration::new(0, 33.5.into(), 560, 68.5.into()),
+            HumidityCalibration::new(-100, 30.0, 540, 70.0),
+        );
+        let sensor = match sensor.on_notify(SetCalibration(corrected)) {
+            Completion::Immediate(sensor) => sensor,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        let (sensor, read) = calibration(sensor);
+        assert_eq!(read, Some(corrected));
+
+        let calibration = sensor.calibration.unwrap();
+        assert_eq!(factory.calibrated_temperature(280), 52.5.into());
+        assert_eq!(calibration.calibrated_temperature(280), 51.0.into());
+        assert_eq!(factory.calibrated_humidity(220), 52.0);
+        assert_eq!(calibration.calibrated_humidity(220), 50.0);
+    }
+
+    #[test]
+    fn test_sample_unbound() {
+        match TestSensor::new().on_request(Sample) {
+            Response::Immediate(_, result) => {
+                assert_eq!(result.err(), Some(DeviceError::NotBound))
+            }
+            _ => panic!("deferred"),
+        }
+    }
+
+    #[test]
+    fn test_reconfigure() {
+        let mut sensor = TestSensor::new();
+        sensor.published.replace(20.0.into());
+        assert!(sensor.exceeds_threshold(20.1.into()));
+
+        let sensor = reconfigure(sensor, 0.5);
+        assert_eq!(sensor.config.output_data_rate, OutputDataRate::Hz7);
+        assert!(!sensor.exceeds_threshold(20.3.into()));
+        assert!(sensor.exceeds_threshold(19.5.into()));
+
+        // a negative threshold is rejected, keeping the previous one
+        let sensor = reconfigure(sensor, -1.0);
+        assert!(!sensor.exceeds_threshold(20.3.into()));
+    }
+
+    const CTRL_REG1: u8 = 0x20;
+    const CTRL_REG2: u8 = 0x21;
+
+    /// The sensor's registers, completing a one-shot conversion after a number of
+    /// reads of the status while powered.
+    struct OneShotI2c {
+        registers: [u8; 0x40],
+        ready_after: u32,
+        polls: u32,
+        /// The values written to CTRL_REG1, in order.
+        power: Vec<u8>,
+        /// Whether the sensor was powered at each read of its outputs.
+        outputs_read: Vec<bool>,
+    }
+
+    impl OneShotI2c {
+        fn new(ready_after: u32) -> Self {
+            let mut registers = [0; 0x40];
+            // block data update, one-shot and powered down
+            registers[CTRL_REG1 as usize] = 0x04;
+            registers[0x2A..0x2C].copy_from_slice(&280i16.to_le_bytes());
+            registers[0x28..0x2A].copy_from_slice(&220i16.to_le_bytes());
+            Self {
+                registers,
+                ready_after,
+                polls: 0,
+                power: Vec::new(),
+                outputs_read: Vec::new(),
+            }
+        }
+
+        fn powered(&self) -> bool {
+            self.registers[CTRL_REG1 as usize] & 0x80 != 0
+        }
+    }
+
+    impl I2cBus for OneShotI2c {
+        type Error = DeviceError;
+
+        async fn write(&mut self, _: I2cAddress, bytes: &[u8]) -> Result<(), DeviceError> {
+            if let [register, value] = *bytes {
+                self.registers[register as usize] = value;
+                if register == CTRL_REG1 {
+                    self.power.push(value);
+                }
+                if register == CTRL_REG2 && value & 0x01 != 0 {
+                    assert!(self.powered(), "converting while powered down");
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl I2cReadBus for OneShotI2c {
+        async fn write_read(
+            &mut self,
+            _: I2cAddress,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), DeviceError> {
+            // the top bit of the register address asks for auto-increment
+            let register = (bytes[0] & 0x7F) as usize;
+            if bytes[0] == STATUS {
+                self.polls += 1;
+                let converting = self.registers[CTRL_REG2 as usize] & 0x01 != 0;
+                if converting && self.powered() && self.polls > self.ready_after {
+                    self.registers[CTRL_REG2 as usize] &= !0x01;
+                    self.registers[STATUS as usize] = 0b11;
+                }
+            }
+            if bytes[0] == T_OUT || bytes[0] == H_OUT {
+                let powered = self.powered();
+                self.outputs_read.push(powered);
+            }
+            buffer.copy_from_slice(&self.registers[register..register + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_one_shot_power() {
+        let clock: &'static MockClock<()> = Box::leak(Box::new(MockClock::new()));
+        let mut i2c = OneShotI2c::new(3);
+        let result = block_on_advancing(clock, one_shot(&mut i2c, &clock, I2cAddress::new(ADDR)));
+        assert_eq!(result, Ok((280, 220)));
+        assert_eq!(i2c.polls, 4);
+        // waiting between reads of the status
+        assert_eq!(clock.now(), Milliseconds(3 * ONE_SHOT_INTERVAL.0));
+
+        // powered up for the conversion, read while powered, then powered down again
+        assert_eq!(i2c.power, [0x84, 0x04]);
+        assert_eq!(i2c.outputs_read, [true, true]);
+        assert!(!i2c.powered());
+    }
+
+    #[test]
+    fn test_one_shot_timeout() {
+        let clock: &'static MockClock<()> = Box::leak(Box::new(MockClock::new()));
+        let mut i2c = OneShotI2c::new(u32::MAX);
+        let result = block_on_advancing(clock, one_shot(&mut i2c, &clock, I2cAddress::new(ADDR)));
+        assert_eq!(result, Err(DeviceError::Timeout));
+        assert_eq!(i2c.polls, ONE_SHOT_TIMEOUT.0 / ONE_SHOT_INTERVAL.0 + 1);
+        assert_eq!(clock.now(), ONE_SHOT_TIMEOUT);
+
+        // powered down all the same
+        assert_eq!(i2c.power, [0x84, 0x04]);
+        assert!(i2c.outputs_read.is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! LPS22HB barometric pressure sensor.
+//!
+//! The `Lps22` actor configures the sensor to sample continuously at its output data rate,
+//! and each time it is asked to `acquire()` reads the latest 24-bit pressure and 16-bit
+//! temperature, publishing them to the event-bus as a `PressureReading`. Block data
+//! update is enabled, so a reading is never torn between two samples.
+
+use crate::bind::Bind;
+use crate::domain::pressure::Pressure;
+use crate::domain::temperature::{Celsius, Temperature};
+use crate::driver::i2c::{I2cPeripheral, I2cReadBus};
+use crate::hal::i2c::I2cAddress;
+use crate::prelude::*;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// The address of the sensor with its SA0 pin low.
+pub const ADDR: u8 = 0x5C;
+/// The address of the sensor with its SA0 pin high.
+pub const ADDR_SA0_HIGH: u8 = 0x5D;
+
+const WHO_AM_I: u8 = 0x0F;
+const CTRL_REG1: u8 = 0x10;
+const PRESS_OUT_XL: u8 = 0x28;
+
+const ID: u8 = 0xB1;
+/// CTRL_REG1 block data update bit.
+const BDU: u8 = 1 << 1;
+
+const LSB_PER_HPA: f32 = 4096.0;
+const LSB_PER_CELSIUS: f32 = 100.0;
+
+#[derive(Copy, Clone, Debug)]
+pub enum OutputDataRate {
+    Hz1 = 1,
+    Hz10 = 2,
+    Hz25 = 3,
+    Hz50 = 4,
+    Hz75 = 5,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct PressureReading {
+    pub pressure: Pressure,
+    pub temperature: Temperature<Celsius>,
+}
+
+/// Convert the raw output registers, from `PRESS_OUT_XL` to `TEMP_OUT_H`.
+pub fn convert(raw: &[u8; 5]) -> PressureReading {
+    // sign-extend the 24-bit two's complement pressure through the top byte
+    let pressure = i32::from_le_bytes([0, raw[0], raw[1], raw[2]]) >> 8;
+    let temperature = i16::from_le_bytes([raw[3], raw[4]]);
+    PressureReading {
+        pressure: Pressure::from_hectopascals(pressure as f32 / LSB_PER_HPA),
+        temperature: Temperature::new(temperature as f32 / LSB_PER_CELSIUS),
+    }
+}
+
+/// Start continuous sampling at `odr` on the sensor at `address`.
+pub async fn init<B: I2cReadBus>(
+    bus: &mut B,
+    address: I2cAddress,
+    odr: OutputDataRate,
+) -> Result<(), B::Error> {
+    let mut id = [0];
+    bus.write_read(address, &[WHO_AM_I], &mut id).await?;
+    if id[0] != ID {
+        warn!("[lps22hb] unexpected WHO_AM_I {:x}", id[0]);
+    }
+    bus.write(address, &[CTRL_REG1, (odr as u8) << 4 | BDU])
+        .await
+}
+
+/// Read the latest sample from the sensor at `address`.
+pub async fn read<B: I2cReadBus>(
+    bus: &mut B,
+    address: I2cAddress,
+) -> Result<PressureReading, B::Error> {
+    // the register address auto-increments across the five output registers
+    let mut raw = [0; 5];
+    bus.write_read(address, &[PRESS_OUT_XL], &mut raw).await?;
+    Ok(convert(&raw))
+}
+
+pub struct Lps22<D, I>
+where
+    D: Device + EventHandler<PressureReading> + 'static,
+    I: WriteRead + Write<Error = <I as WriteRead>::Error> + 'static,
+{
+    address: I2cAddress,
+    odr: OutputDataRate,
+    i2c: Option<Address<I2cPeripheral<I>>>,
+    bus: Option<Address<EventBus<D>>>,
+}
+
+impl<D, I> Lps22<D, I>
+where
+    D: Device + EventHandler<PressureReading>,
+    I: WriteRead + Write<Error = <I as WriteRead>::Error>,
+{
+    /// Sample at `odr` on the sensor at the default address, `ADDR`.
+    pub fn new(odr: OutputDataRate) -> Self {
+        Self {
+            address: I2cAddress::new(ADDR),
+            odr,
+            i2c: None,
+            bus: None,
+        }
+    }
+
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = I2cAddress::new(address);
+        self
+    }
+}
+
+impl<D, I> Actor for Lps22<D, I>
+where
+    D: Device + EventHandler<PressureReading>,
+    I: WriteRead + Write<Error = <I as WriteRead>::Error>,
+{
+    fn on_initialize(self) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(mut i2c) = self.i2c {
+                if init(&mut i2c, self.address, self.odr).await.is_err() {
+                    warn!("[lps22hb] failed to configure");
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<D, I> Bind<EventBus<D>> for Lps22<D, I>
+where
+    D: Device + EventHandler<PressureReading>,
+    I: WriteRead + Write<Error = <I as WriteRead>::Error>,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, I> Bind<I2cPeripheral<I>> for Lps22<D, I>
+where
+    D: Device + EventHandler<PressureReading>,
+    I: WriteRead + Write<Error = <I as WriteRead>::Error>,
+{
+    fn on_bind(&mut self, address: Address<I2cPeripheral<I>>) {
+        self.i2c.replace(address);
+    }
+}
+
+pub struct Acquire;
+
+impl<D, I> NotifyHandler<Acquire> for Lps22<D, I>
+where
+    D: Device + EventHandler<PressureReading>,
+    I: WriteRead + Write<Error = <I as WriteRead>::Error>,
+{
+    fn on_notify(self, _: Acquire) -> Completion<Self> {
+        Completion::defer(async move {
+            if let Some(mut i2c) = self.i2c {
+                match read(&mut i2c, self.address).await {
+                    Ok(reading) => {
+                        if let Some(bus) = self.bus {
+                            bus.publish(reading);
+                        }
+                    }
+                    Err(_) => warn!("[lps22hb] failed to read"),
+                }
+            }
+            self
+        })
+    }
+}
+
+impl<D, I> Address<Lps22<D, I>>
+where
+    D: Device + EventHandler<PressureReading>,
+    I: WriteRead + Write<Error = <I as WriteRead>::Error>,
+{
+    /// Read the latest sample, publishing it as a `PressureReading`.
+    pub fn acquire(&self) {
+        self.notify(Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::i2c::I2cBus;
+    use crate::testing::block_on;
+
+    /// The sensor's register file, auto-incrementing across reads.
+    struct MockI2c {
+        registers: [u8; 0x40],
+    }
+
+    impl MockI2c {
+        fn new() -> Self {
+            let mut registers = [0; 0x40];
+            registers[WHO_AM_I as usize] = ID;
+            Self { registers }
+        }
+    }
+
+    impl I2cBus for MockI2c {
+        type Error = ();
+
+        async fn write(&mut self, address: I2cAddress, bytes: &[u8]) -> Result<(), Self::Error> {
+            assert_eq!(address, I2cAddress::new(ADDR));
+            let start = bytes[0] as usize;
+            self.registers[start..start + bytes.len() - 1].copy_from_slice(&bytes[1..]);
+            Ok(())
+        }
+    }
+
+    impl I2cReadBus for MockI2c {
+        async fn write_read(
+            &mut self,
+            address: I2cAddress,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), Self::Error> {
+            assert_eq!(address, I2cAddress::new(ADDR));
+            let start = bytes[0] as usize;
+            buffer.copy_from_slice(&self.registers[start..start + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_init() {
+        let mut i2c = MockI2c::new();
+        block_on(init(&mut i2c, I2cAddress::new(ADDR), OutputDataRate::Hz25)).unwrap();
+        assert_eq!(i2c.registers[CTRL_REG1 as usize], 0b0011_0010);
+    }
+
+    #[test]
+    fn test_read() {
+        let mut i2c = MockI2c::new();
+        // 0x3FF58D / 4096 hPa, 0x0A28 / 100 °C
+        i2c.registers[0x28..0x2D].copy_from_slice(&[0x8D, 0xF5, 0x3F, 0x28, 0x0A]);
+
+        let reading = block_on(read(&mut i2c, I2cAddress::new(ADDR))).unwrap();
+        assert_close(reading.pressure.hectopascals(), 1023.3469);
+        assert!((reading.pressure.pascals() - 102_334.69).abs() < 0.01);
+        assert_close(reading.temperature.value(), 26.0);
+    }
+
+    #[test]
+    fn test_convert_negative() {
+        // below-zero temperatures and (nonsensical) negative pressures are sign-extended
+        let reading = convert(&[0x00, 0xF0, 0xFF, 0x38, 0xFF]);
+        assert_close(reading.pressure.hectopascals(), -1.0);
+        assert_close(reading.temperature.value(), -2.0);
+    }
+}
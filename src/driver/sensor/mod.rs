@@ -1 +1,4 @@
+pub mod filter;
 pub mod hts221;
+pub mod lps22hb;
+pub mod telemetry;
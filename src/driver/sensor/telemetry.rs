@@ -0,0 +1,216 @@
+//! Batching of sensor readings into periodic snapshots.
+//!
+//! The `Telemetry` actor receives the readings of the various sensors, keeps the latest
+//! of each, and publishes them together as a `TelemetrySnapshot` every interval, so a
+//! single uplink message can carry them all. A reading stays in every snapshot until
+//! replaced by the next, and fields without any reading yet are `None`.
+
+use crate::bind::Bind;
+use crate::domain::pressure::Pressure;
+use crate::domain::temperature::{Celsius, Temperature};
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::sensor::hts221::SensorAcquisition;
+use crate::driver::sensor::lps22hb::PressureReading;
+use crate::driver::timer::TimerActor;
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+
+/// The latest reading of each sensor.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TelemetrySnapshot {
+    pub temperature: Option<Temperature<Celsius>>,
+    pub relative_humidity: Option<f32>,
+    pub pressure: Option<Pressure>,
+}
+
+pub struct Telemetry<D, T>
+where
+    D: Device + EventHandler<TelemetrySnapshot> + 'static,
+    T: HalTimer + 'static,
+{
+    snapshot: TelemetrySnapshot,
+    interval: Milliseconds,
+    bus: Option<Address<EventBus<D>>>,
+    timer: Option<Address<TimerActor<T>>>,
+    address: Option<Address<Self>>,
+}
+
+impl<D, T> Telemetry<D, T>
+where
+    D: Device + EventHandler<TelemetrySnapshot>,
+    T: HalTimer,
+{
+    /// Publish a snapshot every `interval`.
+    pub fn new(interval: Milliseconds) -> Self {
+        Self {
+            snapshot: TelemetrySnapshot::default(),
+            interval,
+            bus: None,
+            timer: None,
+            address: None,
+        }
+    }
+
+    fn schedule_tick(&self) {
+        if let (Some(timer), Some(address)) = (self.timer, self.address) {
+            timer.schedule(self.interval, Tick, address);
+        }
+    }
+}
+
+impl<D, T> Actor for Telemetry<D, T>
+where
+    D: Device + EventHandler<TelemetrySnapshot>,
+    T: HalTimer,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_start(self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.schedule_tick();
+        Completion::immediate(self)
+    }
+}
+
+impl<D, T> Bind<EventBus<D>> for Telemetry<D, T>
+where
+    D: Device + EventHandler<TelemetrySnapshot>,
+    T: HalTimer,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, T> Bind<TimerActor<T>> for Telemetry<D, T>
+where
+    D: Device + EventHandler<TelemetrySnapshot>,
+    T: HalTimer,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.timer.replace(address);
+    }
+}
+
+impl<D, T> NotifyHandler<SensorAcquisition<Celsius>> for Telemetry<D, T>
+where
+    D: Device + EventHandler<TelemetrySnapshot>,
+    T: HalTimer,
+{
+    fn on_notify(mut self, acquisition: SensorAcquisition<Celsius>) -> Completion<Self> {
+        self.snapshot.temperature.replace(acquisition.temperature);
+        self.snapshot
+            .relative_humidity
+            .replace(acquisition.relative_humidity);
+        Completion::immediate(self)
+    }
+}
+
+impl<D, T> NotifyHandler<PressureReading> for Telemetry<D, T>
+where
+    D: Device + EventHandler<TelemetrySnapshot>,
+    T: HalTimer,
+{
+    fn on_notify(mut self, reading: PressureReading) -> Completion<Self> {
+        self.snapshot.pressure.replace(reading.pressure);
+        Completion::immediate(self)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Tick;
+
+impl<D, T> NotifyHandler<Tick> for Telemetry<D, T>
+where
+    D: Device + EventHandler<TelemetrySnapshot>,
+    T: HalTimer,
+{
+    fn on_notify(self, _: Tick) -> Completion<Self> {
+        if let Some(bus) = self.bus {
+            bus.publish(self.snapshot);
+        }
+        self.schedule_tick();
+        Completion::immediate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    impl EventHandler<TelemetrySnapshot> for MockDevice {}
+
+    struct MockTimer;
+
+    impl HalTimer for MockTimer {
+        fn start(&mut self, _: Milliseconds) {}
+        fn clear_update_interrupt_flag(&mut self) {}
+    }
+
+    fn notify<M>(
+        telemetry: Telemetry<MockDevice, MockTimer>,
+        message: M,
+    ) -> Telemetry<MockDevice, MockTimer>
+    where
+        Telemetry<MockDevice, MockTimer>: NotifyHandler<M>,
+    {
+        match telemetry.on_notify(message) {
+            Completion::Immediate(telemetry) => telemetry,
+            _ => panic!("deferred"),
+        }
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let telemetry = Telemetry::<MockDevice, MockTimer>::new(Milliseconds(1000u32));
+        let snapshot = telemetry.snapshot;
+        assert!(snapshot.temperature.is_none());
+        assert!(snapshot.relative_humidity.is_none());
+        assert!(snapshot.pressure.is_none());
+
+        let telemetry = notify(
+            telemetry,
+            SensorAcquisition {
+                temperature: 21.5.into(),
+                relative_humidity: 40.0,
+            },
+        );
+        let telemetry = notify(telemetry, Tick);
+        let snapshot = telemetry.snapshot;
+        assert_eq!(snapshot.temperature.map(|t| t.value()), Some(21.5));
+        assert_eq!(snapshot.relative_humidity, Some(40.0));
+        assert!(snapshot.pressure.is_none());
+
+        let telemetry = notify(
+            telemetry,
+            PressureReading {
+                pressure: Pressure::from_hectopascals(1013.25),
+                temperature: 22.0.into(),
+            },
+        );
+        let telemetry = notify(
+            telemetry,
+            SensorAcquisition {
+                temperature: 22.5.into(),
+                relative_humidity: 41.0,
+            },
+        );
+        let snapshot = notify(telemetry, Tick).snapshot;
+        assert_eq!(snapshot.temperature.map(|t| t.value()), Some(22.5));
+        assert_eq!(snapshot.relative_humidity, Some(41.0));
+        assert_eq!(snapshot.pressure.map(|p| p.hectopascals()), Some(1013.25));
+    }
+}
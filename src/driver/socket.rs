@@ -0,0 +1,33 @@
+//! Stream socket abstraction for protocol drivers layered on a network connection.
+
+use crate::driver::uart::serial::SerialIo;
+use crate::driver::wifi::esp8266;
+
+/// An open, connected stream socket.
+#[allow(async_fn_in_trait)]
+pub trait Socket {
+    type Error;
+
+    /// Transmit `bytes` to the peer, returning the number of bytes written.
+    async fn write(&mut self, bytes: &[u8]) -> Result<usize, Self::Error>;
+
+    /// Receive data from the peer into `buf`, returning the number of bytes read.
+    ///
+    /// Returns `0` once the peer has closed the connection.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+impl<'a, T> Socket for esp8266::Socket<'a, T>
+where
+    T: SerialIo,
+{
+    type Error = esp8266::Error<T::Error>;
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<usize, Self::Error> {
+        esp8266::Socket::write(self, bytes).await
+    }
+
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        esp8266::Socket::read(self, buf).await
+    }
+}
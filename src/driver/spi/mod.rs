@@ -0,0 +1,544 @@
+//! SPI transfers by DMA.
+//!
+//! The `Spi` package shares a `SpiPeripheral` through a mutex, and completes its transfers
+//! from the peripheral's interrupt. While a transfer is in flight the CPU is free, and the
+//! actor awaiting it is woken once the DMA is done.
+
+use crate::actor::Configurable;
+use crate::hal::spi::SpiDma;
+use crate::interrupt::{Interrupt, InterruptContext};
+use crate::package::Package;
+use crate::prelude::*;
+use crate::synchronization::{Mutex, MutexActor};
+
+pub use crate::hal::spi::Error;
+
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::ops::Range;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+use cortex_m::interrupt::Nr;
+
+/// Async transfers on a SPI bus, allowing device drivers to be exercised against a mock bus.
+#[allow(async_fn_in_trait)]
+pub trait SpiBus {
+    type Error;
+
+    /// Clock out `tx_buffer` while clocking into `rx_buffer`, either of which may be empty.
+    async fn transfer(&mut self, tx_buffer: &[u8], rx_buffer: &mut [u8])
+        -> Result<(), Self::Error>;
+
+    /// Clock out `tx_buffer`, discarding whatever is clocked in.
+    async fn write(&mut self, tx_buffer: &[u8]) -> Result<(), Self::Error> {
+        self.transfer(tx_buffer, &mut []).await
+    }
+}
+
+/// Completion of a DMA transfer, handed from the interrupt to the awaiting actor.
+///
+/// The waker is stored before the transfer starts, and the result before the transfer is
+/// marked done. As the interrupt only touches them while armed, and the actor only once
+/// done, neither side needs a critical section. The waker of an actor never changes, so
+/// the one stored when arming is still current when the transfer completes.
+pub struct DmaDone {
+    armed: AtomicBool,
+    done: AtomicBool,
+    waker: UnsafeCell<Option<Waker>>,
+    result: UnsafeCell<Option<Result<(), Error>>>,
+}
+
+unsafe impl Sync for DmaDone {}
+
+impl DmaDone {
+    pub const fn new() -> Self {
+        Self {
+            armed: AtomicBool::new(false),
+            done: AtomicBool::new(false),
+            waker: UnsafeCell::new(None),
+            result: UnsafeCell::new(None),
+        }
+    }
+
+    /// Prepare for a transfer about to start, waking `waker` once it is done.
+    fn arm(&self, waker: &Waker) {
+        self.done.store(false, Ordering::Release);
+        unsafe {
+            (*self.waker.get()).replace(waker.clone());
+        }
+        self.armed.store(true, Ordering::Release);
+    }
+
+    /// Ignore the completion of a cancelled transfer.
+    fn disarm(&self) {
+        self.armed.store(false, Ordering::Release);
+    }
+
+    /// Complete the armed transfer, from the interrupt.
+    fn complete(&self, result: Result<(), Error>) {
+        if !self.armed.load(Ordering::Acquire) {
+            return;
+        }
+        self.armed.store(false, Ordering::Release);
+        let waker = unsafe {
+            (*self.result.get()).replace(result);
+            (*self.waker.get()).take()
+        };
+        self.done.store(true, Ordering::Release);
+        if let Some(waker) = waker {
+            waker.wake()
+        }
+    }
+
+    /// The result of the transfer, once it is done.
+    fn take(&self) -> Option<Result<(), Error>> {
+        if self.done.load(Ordering::Acquire) {
+            self.done.store(false, Ordering::Release);
+            unsafe { (*self.result.get()).take() }
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for DmaDone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The most bytes a `SpiBus` transfer of a `SpiPeripheral` clocks out or in at once.
+pub const CHUNK_LEN: usize = 64;
+
+/// Buffers in RAM the DMA of `SpiBus` transfers is done from and into, as long-lived as
+/// the package.
+pub struct Buffers {
+    tx: UnsafeCell<[u8; CHUNK_LEN]>,
+    rx: UnsafeCell<[u8; CHUNK_LEN]>,
+}
+
+pub struct Shared<S>
+where
+    S: SpiDma + 'static,
+{
+    spi: S,
+    done: DmaDone,
+    buffers: Buffers,
+}
+
+impl<S> Shared<S>
+where
+    S: SpiDma + 'static,
+{
+    fn new(spi: S) -> Self {
+        Self {
+            spi,
+            done: DmaDone::new(),
+            buffers: Buffers {
+                tx: UnsafeCell::new([0; CHUNK_LEN]),
+                rx: UnsafeCell::new([0; CHUNK_LEN]),
+            },
+        }
+    }
+}
+
+pub struct Spi<S>
+where
+    S: SpiDma + 'static,
+{
+    peripheral: Mutex<SpiPeripheral<S>>,
+    irq: InterruptContext<SpiInterrupt<S>>,
+    shared: Shared<S>,
+}
+
+impl<S> Spi<S>
+where
+    S: SpiDma + 'static,
+{
+    pub fn new<IRQ>(spi: S, irq: IRQ) -> Self
+    where
+        IRQ: Nr,
+    {
+        Self {
+            peripheral: Mutex::new(SpiPeripheral::new()),
+            irq: InterruptContext::new(SpiInterrupt::new(), irq).with_name("spi"),
+            shared: Shared::new(spi),
+        }
+    }
+}
+
+impl<D, S> Package<D, MutexActor<SpiPeripheral<S>>> for Spi<S>
+where
+    D: Device,
+    S: SpiDma,
+{
+    fn mount(
+        &'static self,
+        bus_address: Address<EventBus<D>>,
+        supervisor: &mut Supervisor,
+    ) -> Address<MutexActor<SpiPeripheral<S>>> {
+        let peripheral = self.peripheral.mount(bus_address, supervisor);
+        self.irq.mount(supervisor);
+        self.peripheral.configure(&self.shared);
+        self.irq.configure(&self.shared);
+        peripheral
+    }
+}
+
+pub struct SpiPeripheral<S>
+where
+    S: SpiDma + 'static,
+{
+    spi: Option<&'static S>,
+    done: Option<&'static DmaDone>,
+    buffers: Option<&'static Buffers>,
+}
+
+impl<S> SpiPeripheral<S>
+where
+    S: SpiDma,
+{
+    pub fn new() -> Self {
+        Self {
+            spi: None,
+            done: None,
+            buffers: None,
+        }
+    }
+
+    /// Transfer by DMA, clocking out `tx_buffer` while clocking into `rx_buffer`.
+    ///
+    /// The transfer starts when the returned future is first polled, and is cancelled if
+    /// the future is dropped before completing.
+    ///
+    /// # Safety
+    /// The future *must* be polled to completion or dropped, never leaked, such as by
+    /// `core::mem::forget`, while the transfer is in flight. A leaked transfer is never
+    /// cancelled, and the DMA goes on accessing the buffers after they are gone.
+    pub unsafe fn transfer_dma<'a>(
+        &'a mut self,
+        tx_buffer: &'a [u8],
+        rx_buffer: &'a mut [u8],
+    ) -> TransferFuture<'a, S> {
+        TransferFuture {
+            peripheral: self,
+            tx_buffer,
+            rx_buffer,
+            state: State::Ready,
+        }
+    }
+}
+
+impl<S> Default for SpiPeripheral<S>
+where
+    S: SpiDma,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Configurable for SpiPeripheral<S>
+where
+    S: SpiDma + 'static,
+{
+    type Configuration = Shared<S>;
+
+    fn configure(&mut self, config: &'static Self::Configuration) {
+        self.spi.replace(&config.spi);
+        self.done.replace(&config.done);
+        self.buffers.replace(&config.buffers);
+    }
+}
+
+impl<S> Actor for SpiPeripheral<S> where S: SpiDma {}
+
+/// Transfers are copied through the buffers of the package, `CHUNK_LEN` bytes at a
+/// time, so that the DMA only ever accesses buffers outliving it, even should the
+/// transfer be leaked.
+impl<S> SpiBus for SpiPeripheral<S>
+where
+    S: SpiDma,
+{
+    type Error = Error;
+
+    async fn transfer(&mut self, tx_buffer: &[u8], rx_buffer: &mut [u8]) -> Result<(), Error> {
+        let buffers = self.buffers.unwrap();
+        let len = tx_buffer.len().max(rx_buffer.len());
+        for start in (0..len).step_by(CHUNK_LEN) {
+            let tx = chunk(tx_buffer.len(), start);
+            let rx = chunk(rx_buffer.len(), start);
+            // only transfers of the peripheral, borrowed mutably, touch the buffers
+            let (tx_chunk, rx_chunk) = unsafe {
+                (
+                    &mut (&mut *buffers.tx.get())[..tx.len()],
+                    &mut (&mut *buffers.rx.get())[..rx.len()],
+                )
+            };
+            tx_chunk.copy_from_slice(&tx_buffer[tx]);
+            // the buffers are static, so outlive the transfer even if it is leaked
+            unsafe { self.transfer_dma(tx_chunk, rx_chunk) }.await?;
+            rx_buffer[rx].copy_from_slice(rx_chunk);
+        }
+        Ok(())
+    }
+}
+
+/// The range of the chunk of a buffer `len` bytes long from `start`, empty past its end.
+fn chunk(len: usize, start: usize) -> Range<usize> {
+    start.min(len)..(start + CHUNK_LEN).min(len)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum State {
+    Ready,
+    InProgress,
+    Done,
+}
+
+pub struct TransferFuture<'a, S>
+where
+    S: SpiDma + 'static,
+{
+    peripheral: &'a mut SpiPeripheral<S>,
+    tx_buffer: &'a [u8],
+    rx_buffer: &'a mut [u8],
+    state: State,
+}
+
+impl<'a, S> Future for TransferFuture<'a, S>
+where
+    S: SpiDma + 'static,
+{
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let done = this.peripheral.done.unwrap();
+        if this.state == State::Ready {
+            done.arm(cx.waker());
+            if let Err(e) = this
+                .peripheral
+                .spi
+                .unwrap()
+                .start_transfer(this.tx_buffer, this.rx_buffer)
+            {
+                done.disarm();
+                this.state = State::Done;
+                return Poll::Ready(Err(e));
+            }
+            this.state = State::InProgress;
+        }
+        match done.take() {
+            Some(result) => {
+                this.state = State::Done;
+                Poll::Ready(result)
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<'a, S> Drop for TransferFuture<'a, S>
+where
+    S: SpiDma + 'static,
+{
+    fn drop(&mut self) {
+        if self.state == State::InProgress {
+            self.peripheral.done.unwrap().disarm();
+            self.peripheral.spi.unwrap().cancel_transfer();
+        }
+    }
+}
+
+pub struct SpiInterrupt<S>
+where
+    S: SpiDma + 'static,
+{
+    spi: Option<&'static S>,
+    done: Option<&'static DmaDone>,
+}
+
+impl<S> SpiInterrupt<S>
+where
+    S: SpiDma,
+{
+    pub fn new() -> Self {
+        Self {
+            spi: None,
+            done: None,
+        }
+    }
+}
+
+impl<S> Default for SpiInterrupt<S>
+where
+    S: SpiDma,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Configurable for SpiInterrupt<S>
+where
+    S: SpiDma + 'static,
+{
+    type Configuration = Shared<S>;
+
+    fn configure(&mut self, config: &'static Self::Configuration) {
+        self.spi.replace(&config.spi);
+        self.done.replace(&config.done);
+    }
+}
+
+impl<S> Actor for SpiInterrupt<S> where S: SpiDma {}
+
+impl<S> Interrupt for SpiInterrupt<S>
+where
+    S: SpiDma,
+{
+    fn on_interrupt(&mut self) {
+        let spi = self.spi.unwrap();
+        if spi.process_interrupts() {
+            self.done.unwrap().complete(spi.finish_transfer());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::testing::poll;
+    use core::cell::Cell;
+    use core::pin::pin;
+    use std::boxed::Box;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    /// Loops transfers back, completing them once told to.
+    #[derive(Default)]
+    struct MockDma {
+        started: Cell<usize>,
+        fired: Cell<bool>,
+        cancelled: Cell<bool>,
+    }
+
+    impl SpiDma for MockDma {
+        fn start_transfer(&self, tx_buffer: &[u8], rx_buffer: &mut [u8]) -> Result<(), Error> {
+            if tx_buffer.len() > CHUNK_LEN {
+                return Err(Error::BufferTooLong);
+            }
+            rx_buffer.copy_from_slice(tx_buffer);
+            self.started.set(self.started.get() + 1);
+            Ok(())
+        }
+
+        fn finish_transfer(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn cancel_transfer(&self) {
+            self.cancelled.set(true);
+        }
+
+        fn process_interrupts(&self) -> bool {
+            self.fired.replace(false)
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn spi() -> (
+        SpiPeripheral<MockDma>,
+        SpiInterrupt<MockDma>,
+        &'static MockDma,
+    ) {
+        let shared: &'static Shared<MockDma> = Box::leak(Box::new(Shared::new(MockDma::default())));
+        let mut peripheral = SpiPeripheral::new();
+        peripheral.configure(shared);
+        let mut irq = SpiInterrupt::new();
+        irq.configure(shared);
+        (peripheral, irq, &shared.spi)
+    }
+
+    #[test]
+    fn test_transfer_completes_on_interrupt() {
+        let (mut peripheral, mut irq, dma) = spi();
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut rx = [0; 3];
+        {
+            let mut transfer = pin!(unsafe { peripheral.transfer_dma(&[1, 2, 3], &mut rx) });
+            assert_eq!(transfer.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(dma.started.get(), 1);
+
+            // an interrupt for something else completes nothing
+            irq.on_interrupt();
+            assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+            assert_eq!(transfer.as_mut().poll(&mut cx), Poll::Pending);
+
+            dma.fired.set(true);
+            irq.on_interrupt();
+            assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+            assert_eq!(transfer.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        }
+        assert_eq!(rx, [1, 2, 3]);
+        assert!(!dma.cancelled.get());
+    }
+
+    #[test]
+    fn test_transfer_errors_and_cancels() {
+        let (mut peripheral, mut irq, dma) = spi();
+
+        let mut rx = [0; CHUNK_LEN + 1];
+        {
+            let transfer = pin!(unsafe { peripheral.transfer_dma(&[0; CHUNK_LEN + 1], &mut rx) });
+            assert_eq!(poll(transfer), Poll::Ready(Err(Error::BufferTooLong)));
+        }
+        assert!(!dma.cancelled.get());
+
+        let mut rx = [0; 1];
+        let mut transfer = Box::pin(unsafe { peripheral.transfer_dma(&[7], &mut rx) });
+        assert_eq!(poll(transfer.as_mut()), Poll::Pending);
+        drop(transfer);
+        assert!(dma.cancelled.get());
+
+        // the interrupt of the cancelled transfer is ignored
+        dma.fired.set(true);
+        irq.on_interrupt();
+        assert_eq!(peripheral.done.unwrap().take(), None);
+    }
+
+    #[test]
+    fn test_bus_transfers_in_chunks() {
+        let (mut peripheral, mut irq, dma) = spi();
+        let tx: std::vec::Vec<u8> = (0..CHUNK_LEN as u8 + 6).collect();
+        let mut rx = [0; CHUNK_LEN + 6];
+        {
+            let mut transfer = pin!(SpiBus::transfer(&mut peripheral, &tx, &mut rx));
+            for chunk in 1..=2 {
+                assert_eq!(poll(transfer.as_mut()), Poll::Pending);
+                assert_eq!(dma.started.get(), chunk);
+                dma.fired.set(true);
+                irq.on_interrupt();
+            }
+            assert_eq!(poll(transfer.as_mut()), Poll::Ready(Ok(())));
+        }
+        assert_eq!(&rx[..], &tx[..]);
+        assert!(!dma.cancelled.get());
+    }
+}
@@ -0,0 +1,238 @@
+//! A source of time for drivers.
+//!
+//! Drivers waiting on or scheduling around time are generic over a `Clock` rather
+//! than bound to a `TimerActor`, so that they can run against a `MockClock` advanced
+//! by hand in tests, or against another source of time on boards without a timer.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::domain::time::uptime::Instant;
+use crate::driver::timer::{Now, TimerActor};
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+use core::any::Any;
+use core::cell::{Cell, RefCell};
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use heapless::{consts::*, Vec};
+
+/// Trait for sources of time.
+#[allow(async_fn_in_trait)]
+pub trait Clock: Copy + 'static {
+    /// Wait for `duration` to pass.
+    async fn delay(&self, duration: Milliseconds);
+
+    /// Notify `address` of `event` once `delay` has passed.
+    fn schedule<E, A>(&self, delay: Milliseconds, event: E, address: Address<A>)
+    where
+        E: Clone + 'static,
+        A: Actor + NotifyHandler<E> + 'static;
+
+    /// The time passed since the clock started.
+    async fn now(&self) -> Milliseconds;
+
+    /// The current instant, to measure or schedule from.
+    async fn instant(&self) -> Instant {
+        Instant::from_uptime(self.now().await)
+    }
+
+    /// The time passed since `since`.
+    async fn elapsed(&self, since: Instant) -> Milliseconds {
+        self.instant().await - since
+    }
+
+    /// Block until `duration` has passed, polling `delay` until it completes.
+    fn delay_blocking(&self, duration: Milliseconds) {
+        let mut delay = pin!(self.delay(duration));
+        let mut cx = Context::from_waker(Waker::noop());
+        while delay.as_mut().poll(&mut cx).is_pending() {}
+    }
+}
+
+/// The time of a `TimerActor` is counted from its start, the timer running an
+/// `IDLE_PERIOD` at a time while nothing is pending.
+impl<T: HalTimer + 'static> Clock for Address<TimerActor<T>> {
+    async fn delay(&self, duration: Milliseconds) {
+        Address::<TimerActor<T>>::delay(self, duration).await
+    }
+
+    fn schedule<E, A>(&self, delay: Milliseconds, event: E, address: Address<A>)
+    where
+        E: Clone + 'static,
+        A: Actor + NotifyHandler<E> + 'static,
+    {
+        Address::<TimerActor<T>>::schedule(self, delay, event, address)
+    }
+
+    async fn now(&self) -> Milliseconds {
+        self.request(Now).await
+    }
+
+    fn delay_blocking(&self, duration: Milliseconds) {
+        Address::<TimerActor<T>>::delay_blocking(self, duration)
+    }
+}
+
+/// A clock advanced by hand, handing back the events of type `E` it has scheduled
+/// once they are due, rather than notifying their actors.
+///
+/// Its time wraps after about 49.7 days, as that of a `TimerActor` does.
+pub struct MockClock<E: Clone + 'static> {
+    now: Cell<Instant>,
+    scheduled: RefCell<Vec<(Instant, E), U16>>,
+}
+
+impl<E: Clone + 'static> MockClock<E> {
+    pub fn new() -> Self {
+        Self {
+            now: Cell::new(Instant::from_uptime(Milliseconds(0u32))),
+            scheduled: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The time the clock has been advanced by.
+    pub fn now(&self) -> Milliseconds {
+        self.now.get().uptime()
+    }
+
+    /// The number of scheduled events not yet due.
+    pub fn pending(&self) -> usize {
+        self.scheduled.borrow().len()
+    }
+
+    /// Advance the clock by `by`, returning the events that became due, earliest first.
+    ///
+    /// Events scheduled while handling those returned count from the new time, so
+    /// advance in steps no longer than the shortest delay to observe every event.
+    pub fn advance(&self, by: Milliseconds) -> Vec<E, U16> {
+        let now = self.now.get() + by;
+        self.now.set(now);
+
+        let mut due: Vec<(Instant, usize, E), U16> = Vec::new();
+        let mut pending: Vec<(Instant, E), U16> = Vec::new();
+        for (index, (at, event)) in self.scheduled.borrow().iter().enumerate() {
+            if *at <= now {
+                let _ = due.push((*at, index, event.clone()));
+            } else {
+                let _ = pending.push((*at, event.clone()));
+            }
+        }
+        self.scheduled.replace(pending);
+
+        // events due at the same time keep the order they were scheduled in
+        due.sort_unstable_by_key(|(at, index, _)| (*at, *index));
+        due.iter().map(|(_, _, event)| event.clone()).collect()
+    }
+}
+
+impl<E: Clone + 'static> Default for MockClock<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Delays are polled rather than woken, and complete once the clock has been
+/// advanced past them. Events of other types than `E` are dropped when scheduled.
+impl<E: Clone + 'static> Clock for &'static MockClock<E> {
+    async fn delay(&self, duration: Milliseconds) {
+        let until = self.now.get() + duration;
+        poll_fn(|_| {
+            if self.now.get() >= until {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    fn schedule<F, A>(&self, delay: Milliseconds, event: F, _: Address<A>)
+    where
+        F: Clone + 'static,
+        A: Actor + NotifyHandler<F> + 'static,
+    {
+        if let Some(event) = (&event as &dyn Any).downcast_ref::<E>() {
+            let at = self.now.get() + delay;
+            let _ = self.scheduled.borrow_mut().push((at, event.clone()));
+        }
+    }
+
+    async fn now(&self) -> Milliseconds {
+        MockClock::now(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::testing::{block_on, poll};
+    use std::boxed::Box;
+
+    struct Sink;
+
+    impl Actor for Sink {}
+
+    impl NotifyHandler<char> for Sink {
+        fn on_notify(self, _: char) -> Completion<Self> {
+            Completion::immediate(self)
+        }
+    }
+
+    fn sink() -> Address<Sink> {
+        Address::new(Box::leak(Box::new(ActorContext::new(Sink))))
+    }
+
+    #[test]
+    fn test_advance() {
+        let clock: &'static MockClock<char> = Box::leak(Box::new(MockClock::new()));
+        let sink = sink();
+        clock.schedule(Milliseconds(30u32), 'c', sink);
+        clock.schedule(Milliseconds(10u32), 'a', sink);
+        clock.schedule(Milliseconds(10u32), 'b', sink);
+
+        assert!(clock.advance(Milliseconds(9u32)).is_empty());
+        assert_eq!(clock.advance(Milliseconds(25u32)), ['a', 'b', 'c']);
+        assert_eq!(clock.now(), Milliseconds(34u32));
+        assert_eq!(clock.pending(), 0);
+    }
+
+    #[test]
+    fn test_delay() {
+        let clock: &'static MockClock<char> = Box::leak(Box::new(MockClock::new()));
+        let mut delay = pin!(Clock::delay(&clock, Milliseconds(20u32)));
+
+        assert_eq!(poll(delay.as_mut()), Poll::Pending);
+        clock.advance(Milliseconds(19u32));
+        assert_eq!(poll(delay.as_mut()), Poll::Pending);
+        clock.advance(Milliseconds(1u32));
+        assert_eq!(poll(delay.as_mut()), Poll::Ready(()));
+    }
+
+    #[test]
+    fn test_elapsed() {
+        let clock: &'static MockClock<char> = Box::leak(Box::new(MockClock::new()));
+        clock.advance(Milliseconds(100u32));
+
+        let started = block_on(clock.instant());
+        assert_eq!(started.uptime(), Milliseconds(100u32));
+        clock.advance(Milliseconds(42u32));
+        assert_eq!(block_on(clock.elapsed(started)), Milliseconds(42u32));
+    }
+
+    #[test]
+    fn test_wraparound() {
+        // about 49.7 days in, where a count of milliseconds in 32 bits runs out
+        let clock: &'static MockClock<char> = Box::leak(Box::new(MockClock::new()));
+        clock.advance(Milliseconds(u32::MAX - 50));
+        let started = block_on(clock.instant());
+        clock.schedule(Milliseconds(100u32), 'a', sink());
+
+        assert!(clock.advance(Milliseconds(99u32)).is_empty());
+        assert_eq!(clock.now(), Milliseconds(48u32));
+        assert_eq!(block_on(clock.elapsed(started)), Milliseconds(99u32));
+        assert_eq!(clock.advance(Milliseconds(1u32)), ['a']);
+        assert_eq!(block_on(clock.elapsed(started)), Milliseconds(100u32));
+    }
+}
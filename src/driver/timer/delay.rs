@@ -0,0 +1,126 @@
+//! Blocking delays for `embedded-hal` drivers.
+//!
+//! Drivers from the wider ecosystem wait with the blocking `DelayMs` and `DelayUs` of
+//! `embedded-hal`, such as between the steps of a sensor's initialization. A
+//! `BlockingDelay` provides both over a `Clock`, such as the address of a `TimerActor`,
+//! by blocking until the clock's delay has passed.
+//!
+//! Blocking stalls every actor for as long as the delay, so a `BlockingDelay` must not
+//! be used from the supervisor's poll loop, which is from within any actor's handlers.
+//! It is meant for where blocking is acceptable anyway, such as initializing a device
+//! before the supervisor is started. Over a `TimerActor`, the delay is reached by the
+//! timer's interrupt, so must not be waited on from an interrupt the timer's cannot
+//! preempt either.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::Clock;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+
+/// `DelayMs` and `DelayUs` over a `Clock`, counting in whole milliseconds.
+///
+/// Delays in microseconds are rounded up to the next millisecond, so never end early.
+#[derive(Copy, Clone)]
+pub struct BlockingDelay<C: Clock> {
+    clock: C,
+}
+
+impl<C: Clock> BlockingDelay<C> {
+    pub fn new(clock: C) -> Self {
+        Self { clock }
+    }
+}
+
+impl<C: Clock> DelayMs<u32> for BlockingDelay<C> {
+    fn delay_ms(&mut self, ms: u32) {
+        self.clock.delay_blocking(Milliseconds(ms));
+    }
+}
+
+impl<C: Clock> DelayMs<u16> for BlockingDelay<C> {
+    fn delay_ms(&mut self, ms: u16) {
+        self.delay_ms(ms as u32);
+    }
+}
+
+impl<C: Clock> DelayMs<u8> for BlockingDelay<C> {
+    fn delay_ms(&mut self, ms: u8) {
+        self.delay_ms(ms as u32);
+    }
+}
+
+impl<C: Clock> DelayUs<u32> for BlockingDelay<C> {
+    fn delay_us(&mut self, us: u32) {
+        self.delay_ms(us.div_ceil(1_000));
+    }
+}
+
+impl<C: Clock> DelayUs<u16> for BlockingDelay<C> {
+    fn delay_us(&mut self, us: u16) {
+        self.delay_us(us as u32);
+    }
+}
+
+impl<C: Clock> DelayUs<u8> for BlockingDelay<C> {
+    fn delay_us(&mut self, us: u8) {
+        self.delay_us(us as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::prelude::*;
+    use core::cell::RefCell;
+    use heapless::{consts::*, Vec};
+    use std::boxed::Box;
+
+    /// A clock passing each delay at once, recording its duration.
+    #[derive(Copy, Clone)]
+    struct Recording(&'static RefCell<Vec<Milliseconds, U8>>);
+
+    impl Clock for Recording {
+        async fn delay(&self, duration: Milliseconds) {
+            self.0.borrow_mut().push(duration).ok();
+        }
+
+        fn schedule<E, A>(&self, _: Milliseconds, _: E, _: Address<A>)
+        where
+            E: Clone + 'static,
+            A: Actor + NotifyHandler<E> + 'static,
+        {
+        }
+
+        async fn now(&self) -> Milliseconds {
+            self.0
+                .borrow()
+                .iter()
+                .fold(Milliseconds(0u32), |now, delay| now + *delay)
+        }
+    }
+
+    /// Stands in for a third-party driver, waiting as its device powers up.
+    fn power_up<D: DelayMs<u8> + DelayUs<u16>>(delay: &mut D) {
+        delay.delay_ms(10);
+        delay.delay_us(1_500);
+        delay.delay_us(2_000);
+    }
+
+    #[test]
+    fn test_delay() {
+        let delays: &'static _ = Box::leak(Box::new(RefCell::new(Vec::new())));
+        let mut delay = BlockingDelay::new(Recording(delays));
+        power_up(&mut delay);
+
+        // microseconds are rounded up to whole milliseconds
+        assert_eq!(
+            &delays.borrow()[..],
+            &[
+                Milliseconds(10u32),
+                Milliseconds(2u32),
+                Milliseconds(2u32)
+            ]
+        );
+    }
+}
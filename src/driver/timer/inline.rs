@@ -0,0 +1,107 @@
+use super::Schedulable;
+use crate::error::DeviceError;
+use core::mem::{align_of, size_of, MaybeUninit};
+use core::ptr;
+
+/// The words of room for a schedule kept inline.
+pub const INLINE_WORDS: usize = 8;
+
+/// A schedule kept inline in its deadline rather than on the heap, for the timer to
+/// schedule without an allocator under the `no-alloc` feature.
+///
+/// Any `Schedulable` fitting in `INLINE_WORDS` words, and aligned no stricter than a
+/// word, may be kept. A `Schedule` holds its delay, its event and an address, so the
+/// event is what counts against the room.
+pub struct InlineSchedule {
+    buffer: MaybeUninit<[usize; INLINE_WORDS]>,
+    run: unsafe fn(*const u8),
+    drop: unsafe fn(*mut u8),
+}
+
+impl InlineSchedule {
+    /// Keep `schedule` inline, or `ResourceExhausted` if it does not fit.
+    pub fn new<S: Schedulable + 'static>(schedule: S) -> Result<Self, DeviceError> {
+        if size_of::<S>() > size_of::<[usize; INLINE_WORDS]>()
+            || align_of::<S>() > align_of::<usize>()
+        {
+            return Err(DeviceError::ResourceExhausted);
+        }
+        let mut buffer = MaybeUninit::<[usize; INLINE_WORDS]>::uninit();
+        unsafe {
+            ptr::write(buffer.as_mut_ptr() as *mut S, schedule);
+        }
+        Ok(Self {
+            buffer,
+            run: run::<S>,
+            drop: drop::<S>,
+        })
+    }
+}
+
+unsafe fn run<S: Schedulable>(schedule: *const u8) {
+    (*(schedule as *const S)).run()
+}
+
+unsafe fn drop<S>(schedule: *mut u8) {
+    ptr::drop_in_place(schedule as *mut S)
+}
+
+impl Schedulable for InlineSchedule {
+    fn run(&self) {
+        unsafe { (self.run)(self.buffer.as_ptr() as *const u8) }
+    }
+}
+
+impl Drop for InlineSchedule {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.buffer.as_mut_ptr() as *mut u8) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::cell::Cell;
+    use std::boxed::Box;
+
+    struct Counting {
+        ran: &'static Cell<u32>,
+        dropped: &'static Cell<u32>,
+    }
+
+    impl Schedulable for Counting {
+        fn run(&self) {
+            self.ran.set(self.ran.get() + 1);
+        }
+    }
+
+    impl Drop for Counting {
+        fn drop(&mut self) {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+
+    impl Schedulable for [usize; INLINE_WORDS + 1] {
+        fn run(&self) {}
+    }
+
+    #[test]
+    fn test_inline_schedule() {
+        let ran: &'static Cell<u32> = Box::leak(Box::new(Cell::new(0)));
+        let dropped: &'static Cell<u32> = Box::leak(Box::new(Cell::new(0)));
+
+        let schedule = InlineSchedule::new(Counting { ran, dropped }).ok().unwrap();
+        assert_eq!(dropped.get(), 0);
+        schedule.run();
+        assert_eq!(ran.get(), 1);
+        core::mem::drop(schedule);
+        assert_eq!(dropped.get(), 1);
+
+        assert!(matches!(
+            InlineSchedule::new([0usize; INLINE_WORDS + 1]),
+            Err(DeviceError::ResourceExhausted)
+        ));
+    }
+}
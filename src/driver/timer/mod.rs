@@ -1,21 +1,40 @@
+pub mod clock;
+pub mod delay;
+#[cfg(feature = "no-alloc")]
+pub mod inline;
+pub mod periodic;
+pub mod stopwatch;
+
+pub use clock::{Clock, MockClock};
+pub use delay::BlockingDelay;
+#[cfg(feature = "no-alloc")]
+pub use inline::InlineSchedule;
+pub use periodic::Periodic;
+pub use stopwatch::Stopwatch;
+
 use crate::actor::Configurable;
+#[cfg(not(feature = "no-alloc"))]
 use crate::alloc::{alloc, Box};
 use crate::domain::time::duration::{Duration, Milliseconds};
+use crate::driver::reconfigure::Reconfigurable;
+use crate::error::DeviceError;
+#[cfg(any(test, feature = "mock"))]
+use crate::hal::timer::mock::MockTimer;
 use crate::hal::timer::Timer as HalTimer;
 use crate::prelude::*;
-use core::cell::RefCell;
+use core::cell::{Cell, RefCell};
 use core::future::Future;
 use core::pin::Pin;
 use core::task::{Context, Poll, Waker};
 use cortex_m::interrupt::Nr;
+use heapless::binary_heap::{BinaryHeap, Min};
+use heapless::{consts::*, Vec};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Delay<DUR: Duration + Into<Milliseconds>>(pub DUR);
 
 pub trait Schedulable {
     fn run(&self);
-    fn get_expiration(&self) -> Milliseconds;
-    fn set_expiration(&mut self, expiration: Milliseconds);
 }
 
 #[derive(Clone)]
@@ -45,39 +64,268 @@ where
     }
 }
 
+impl<A, DUR, E> Schedulable for Schedule<A, DUR, E>
+where
+    A: Actor + NotifyHandler<E> + 'static,
+    DUR: Duration + Into<Milliseconds>,
+    E: Clone + 'static,
+{
+    fn run(&self) {
+        self.address.notify(self.event.clone());
+    }
+}
+
+/// Runs a function once a delay has passed, from the timer's interrupt.
+pub struct ScheduleFn<DUR, F>
+where
+    DUR: Duration + Into<Milliseconds>,
+    F: FnOnce() + 'static,
+{
+    delay: DUR,
+    f: Cell<Option<F>>,
+}
+
+impl<DUR, F> ScheduleFn<DUR, F>
+where
+    DUR: Duration + Into<Milliseconds>,
+    F: FnOnce() + 'static,
+{
+    pub fn new(delay: DUR, f: F) -> Self {
+        Self {
+            delay,
+            f: Cell::new(Some(f)),
+        }
+    }
+}
+
+impl<DUR, F> Schedulable for ScheduleFn<DUR, F>
+where
+    DUR: Duration + Into<Milliseconds>,
+    F: FnOnce() + 'static,
+{
+    fn run(&self) {
+        if let Some(f) = self.f.take() {
+            f();
+        }
+    }
+}
+
+/// A schedule held by its deadline: boxed on the heap, or kept inline under the
+/// `no-alloc` feature.
+#[cfg(not(feature = "no-alloc"))]
+type Scheduled = Box<dyn Schedulable>;
+#[cfg(feature = "no-alloc")]
+type Scheduled = InlineSchedule;
+
+#[cfg(not(feature = "no-alloc"))]
+fn scheduled<S: Schedulable + 'static>(schedule: S) -> Option<Scheduled> {
+    alloc(schedule).map(|schedule| Box::new(schedule as &mut dyn Schedulable))
+}
+
+#[cfg(feature = "no-alloc")]
+fn scheduled<S: Schedulable + 'static>(schedule: S) -> Option<Scheduled> {
+    InlineSchedule::new(schedule).ok()
+}
+
+/// What happens once a deadline is reached.
+enum Action {
+    /// Wake the future of a `Delay`, once it has been polled.
+    Delay(Option<Waker>),
+    /// A `Delay` reached, until its future sees it.
+    Reached,
+    /// Notify the actor of a `Schedule`, or run a `ScheduleFn`.
+    Schedule(Scheduled),
+}
+
+struct Deadline {
+    /// The time counted at which the deadline falls, in milliseconds, counted wide enough
+    /// never to wrap.
+    at: u64,
+    action: Action,
+}
+
+/// A deadline not yet reached, in the queue of the earliest first.
+///
+/// Ordered by time, then by index, so deadlines falling together are reached in the
+/// order of their slots.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Queued {
+    at: u64,
+    index: u8,
+}
+
+/// The most deadlines pending at once, no more than the 32 bits of the free slots.
+pub const MAX_DEADLINES: usize = 32;
+
+/// The period the timer is kept counting for while no deadline is pending, so that the
+/// time it counts goes on advancing.
+///
+/// The timer interrupts once a period while idle. Timers unable to tell how much of a
+/// period has elapsed count the time a period at a time.
+pub const IDLE_PERIOD: Milliseconds = Milliseconds(1_000);
+
 pub struct Shared {
+    uptime: RefCell<Milliseconds>,
+    /// The time counted off the deadlines, in milliseconds.
+    counted: RefCell<u64>,
+    /// The time from the last start of the timer to the earliest deadline, if any.
     current_deadline: RefCell<Option<Milliseconds>>,
-    delay_deadlines: RefCell<[Option<DelayDeadline>; 16]>,
-    schedule_deadlines: RefCell<[Option<Box<dyn Schedulable>>; 16]>,
+    /// The time the timer was last started for: the current deadline, or the
+    /// `IDLE_PERIOD` while nothing is pending.
+    period: RefCell<Option<Milliseconds>>,
+    /// The deadlines, each keeping its slot until done, for the future of a delay to
+    /// find it by index.
+    deadlines: RefCell<[Option<Deadline>; MAX_DEADLINES]>,
+    /// The free slots of `deadlines`, one bit each, for the first to be found at once.
+    free: RefCell<u32>,
+    /// The deadlines not yet reached, the earliest at the top.
+    queue: RefCell<BinaryHeap<Queued, U32, Min>>,
+    /// How late a deadline may be reached before it counts as missed.
+    miss_threshold: RefCell<Option<Milliseconds>>,
+    /// The most a deadline has been missed by.
+    missed_by: RefCell<Option<Milliseconds>>,
 }
 
 impl Shared {
     pub fn new() -> Self {
         Self {
+            uptime: RefCell::new(Milliseconds(0u32)),
+            counted: RefCell::new(0),
             current_deadline: RefCell::new(None),
-            delay_deadlines: RefCell::new(Default::default()),
-            schedule_deadlines: RefCell::new(Default::default()),
+            period: RefCell::new(None),
+            deadlines: RefCell::new(Default::default()),
+            free: RefCell::new(u32::MAX >> (32 - MAX_DEADLINES)),
+            queue: RefCell::new(BinaryHeap::new()),
+            miss_threshold: RefCell::new(None),
+            missed_by: RefCell::new(None),
         }
     }
 
+    /// Place a deadline `ms` from the time counted so far in the first free slot,
+    /// returning its index, or `ResourceExhausted` if the table is full.
+    fn insert(&self, ms: Milliseconds, action: Action) -> Result<usize, DeviceError> {
+        let mut free = self.free.borrow_mut();
+        if *free == 0 {
+            return Err(DeviceError::ResourceExhausted);
+        }
+        let index = free.trailing_zeros() as usize;
+        *free &= !(1 << index);
+        let at = *self.counted.borrow() + ms.0 as u64;
+        self.deadlines.borrow_mut()[index].replace(Deadline { at, action });
+        // never more queued than there are slots
+        self.queue
+            .borrow_mut()
+            .push(Queued {
+                at,
+                index: index as u8,
+            })
+            .ok();
+        Ok(index)
+    }
+
     fn has_expired(&self, index: usize) -> bool {
-        let expired = self.delay_deadlines.borrow()[index]
-            .as_ref()
-            .unwrap()
-            .expiration
-            == Milliseconds(0u32);
+        let mut deadlines = self.deadlines.borrow_mut();
+        let expired = matches!(
+            deadlines[index],
+            Some(Deadline {
+                action: Action::Reached,
+                ..
+            })
+        );
         if expired {
-            self.delay_deadlines.borrow_mut()[index].take();
+            deadlines[index].take();
+            self.release(index);
         }
         expired
     }
 
+    /// Remove the deadline at `index` before it is done, such as a delay no longer
+    /// awaited, freeing its slot.
+    fn cancel(&self, index: usize) {
+        if self.deadlines.borrow_mut()[index].take().is_none() {
+            return;
+        }
+        let mut queue = self.queue.borrow_mut();
+        let kept: Vec<Queued, U32> = core::iter::from_fn(|| queue.pop())
+            .filter(|queued| queued.index as usize != index)
+            .collect();
+        for queued in kept {
+            // no more than were queued before
+            queue.push(queued).ok();
+        }
+        self.release(index);
+    }
+
+    /// Mark the slot at `index` free again, once its deadline is done.
+    fn release(&self, index: usize) {
+        *self.free.borrow_mut() |= 1 << index;
+    }
+
     fn register_waker(&self, index: usize, waker: Waker) {
-        self.delay_deadlines.borrow_mut()[index]
-            .as_mut()
-            .unwrap()
-            .waker
-            .replace(waker);
+        if let Some(Deadline {
+            action: Action::Delay(slot),
+            ..
+        }) = &mut self.deadlines.borrow_mut()[index]
+        {
+            slot.replace(waker);
+        }
+    }
+
+    /// Count `expired` off the deadlines, completing those reached, earliest first, and
+    /// return the time to the next.
+    fn count_off(&self, expired: Milliseconds) -> Option<Milliseconds> {
+        let now = {
+            let mut counted = self.counted.borrow_mut();
+            *counted += expired.0 as u64;
+            *counted
+        };
+        let mut queue = self.queue.borrow_mut();
+        let mut deadlines = self.deadlines.borrow_mut();
+        while let Some(next) = queue.peek().copied() {
+            if next.at > now {
+                // no deadline is placed further off than a `Milliseconds` reaches
+                return Some(Milliseconds((next.at - now) as u32));
+            }
+            queue.pop();
+            let late = (now - next.at).min(u32::MAX as u64) as u32;
+            self.check_missed(Milliseconds(late));
+            let slot = &mut deadlines[next.index as usize];
+            let completed = match slot.as_mut().map(|deadline| &mut deadline.action) {
+                Some(action @ Action::Delay(_)) => {
+                    if let Action::Delay(Some(waker)) = core::mem::replace(action, Action::Reached)
+                    {
+                        waker.wake();
+                    }
+                    false
+                }
+                Some(Action::Schedule(schedule)) => {
+                    schedule.run();
+                    true
+                }
+                _ => false,
+            };
+            if completed {
+                slot.take();
+                self.release(next.index as usize);
+            }
+        }
+        None
+    }
+}
+
+impl Shared {
+    /// Report a deadline reached `late`, if later than the threshold.
+    fn check_missed(&self, late: Milliseconds) {
+        match *self.miss_threshold.borrow() {
+            Some(threshold) if late > threshold => {
+                warn!("deadline missed by {}", late);
+                let mut missed_by = self.missed_by.borrow_mut();
+                if missed_by.is_none_or(|worst| late > worst) {
+                    missed_by.replace(late);
+                }
+            }
+            _ => {}
+        }
     }
 }
 
@@ -99,6 +347,15 @@ impl<T: HalTimer> Timer<T> {
             shared: Shared::default(),
         }
     }
+
+    /// Warn of each deadline reached later than `threshold`, such as while the timer's
+    /// interrupt waits on higher-priority work, and report the worst in `missed_by`.
+    ///
+    /// Lateness is only seen by timers able to tell the time elapsed past their deadline.
+    pub fn with_miss_threshold<DUR: Into<Milliseconds>>(self, threshold: DUR) -> Self {
+        self.shared.miss_threshold.borrow_mut().replace(threshold.into());
+        self
+    }
 }
 
 impl<D: Device, T: HalTimer> Package<D, TimerActor<T>> for Timer<T> {
@@ -135,7 +392,18 @@ impl<T: HalTimer> TimerActor<T> {
     }
 }
 
-impl<T: HalTimer> Actor for TimerActor<T> {}
+impl<T: HalTimer> Actor for TimerActor<T> {
+    fn on_start(mut self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        // count from the start, rather than from the first deadline
+        if self.shared.unwrap().period.borrow().is_none() {
+            self.restart(None);
+        }
+        Completion::immediate(self)
+    }
+}
 
 impl<T, DUR> RequestHandler<Delay<DUR>> for TimerActor<T>
 where
@@ -145,44 +413,9 @@ where
     type Response = ();
 
     fn on_request(mut self, message: Delay<DUR>) -> Response<Self, Self::Response> {
-        let ms: Milliseconds = message.0.into();
-
-        if let Some((index, slot)) = self
-            .shared
-            .unwrap()
-            .delay_deadlines
-            .borrow_mut()
-            .iter_mut()
-            .enumerate()
-            .find(|e| matches!(e, (_, None)))
-        {
-            self.shared.unwrap().delay_deadlines.borrow_mut()[index]
-                .replace(DelayDeadline::new(ms));
-            if let Some(current_deadline) = &*self.shared.unwrap().current_deadline.borrow() {
-                if *current_deadline > ms {
-                    self.shared
-                        .unwrap()
-                        .current_deadline
-                        .borrow_mut()
-                        .replace(ms);
-                    //log::info!("start shorter timer for {:?}", ms);
-                    self.timer.start(ms);
-                } else {
-                    //log::info!("timer already running for {:?}", current_deadline );
-                }
-            } else {
-                self.shared
-                    .unwrap()
-                    .current_deadline
-                    .borrow_mut()
-                    .replace(ms);
-                //log::info!("start new timer for {:?}", ms);
-                self.timer.start(ms);
-            }
-            let future = DelayFuture::new(index, self.shared.as_ref().unwrap());
-            Response::immediate_future(self, future)
-        } else {
-            Response::immediate(self, ())
+        match self.start_delay(message.0.into()) {
+            Some(delay) => Response::immediate_future(self, delay),
+            None => Response::immediate(self, ()),
         }
     }
 }
@@ -196,115 +429,292 @@ where
 {
     fn on_notify(mut self, message: Schedule<A, DUR, E>) -> Completion<Self> {
         let ms: Milliseconds = message.delay.into();
-        // log::info!("schedule request {:?}", ms);
-        let mut deadlines = self.shared.unwrap().schedule_deadlines.borrow_mut();
-        let mut current_deadline = self.shared.unwrap().current_deadline.borrow_mut();
+        trace!("schedule in {}", ms);
+        self.insert_schedule(ms, scheduled(message));
+        Completion::immediate(self)
+    }
+}
 
-        if let Some((index, slot)) = deadlines
-            .iter_mut()
-            .enumerate()
-            .find(|e| matches!(e, (_, None)))
-        {
-            deadlines[index].replace(Box::new(alloc(ScheduleDeadline::new(ms, message)).unwrap()));
-            if let Some(current) = &*current_deadline {
-                if *current > ms {
-                    current_deadline.replace(ms);
-                    self.timer.start(ms);
-                } else {
-                    //log::info!("timer already running for {:?}", current_deadline );
-                }
-            } else {
-                current_deadline.replace(ms);
-                //log::info!("start new timer for {:?}", ms);
-                self.timer.start(ms);
-            }
-        }
+impl<T, DUR, F> NotifyHandler<ScheduleFn<DUR, F>> for TimerActor<T>
+where
+    T: HalTimer + 'static,
+    DUR: Duration + Into<Milliseconds> + 'static,
+    F: FnOnce() + 'static,
+{
+    fn on_notify(mut self, message: ScheduleFn<DUR, F>) -> Completion<Self> {
+        let ms: Milliseconds = message.delay.into();
+        self.insert_schedule(ms, scheduled(message));
+        Completion::immediate(self)
+    }
+}
+
+/// The timer has no tunable parameters, and accepts every reconfiguration
+/// without touching pending deadlines, so drivers may be reconfigured uniformly.
+impl<T: HalTimer + 'static> Reconfigurable for TimerActor<T> {
+    type Config = ();
+
+    fn on_reconfigure(self, _: ()) -> Completion<Self> {
         Completion::immediate(self)
     }
 }
 
 impl<T: HalTimer> Interrupt for TimerActor<T> {
     fn on_interrupt(&mut self) {
+        if !self.timer.fired() {
+            return;
+        }
         self.timer.clear_update_interrupt_flag();
-        let expired = self.shared.unwrap().current_deadline.borrow().unwrap();
-
-        let mut delay_deadlines = self.shared.unwrap().delay_deadlines.borrow_mut();
-
-        let mut next_deadline = None;
-        //log::info!("timer expired! {:?}", expired);
-        for slot in delay_deadlines.iter_mut() {
-            if let Some(deadline) = slot {
-                if deadline.expiration >= expired {
-                    deadline.expiration = deadline.expiration - expired;
-                } else {
-                    deadline.expiration = Milliseconds(0u32);
-                }
+        let period = *self.shared.unwrap().period.borrow();
+        if let Some(expired) = period {
+            self.expire(expired);
+        }
+    }
+}
 
-                if deadline.expiration == Milliseconds(0u32) {
-                    deadline.waker.take().unwrap().wake();
-                } else {
-                    match next_deadline {
-                        None => {
-                            next_deadline.replace(deadline.expiration);
-                        }
-                        Some(soonest) if soonest > deadline.expiration => {
-                            next_deadline.replace(deadline.expiration);
-                        }
-                        _ => { /* ignore */ }
-                    }
-                }
+#[cfg(any(test, feature = "mock"))]
+impl TimerActor<MockTimer> {
+    /// Advance the mock timer by `by`, handling each interrupt it raises on the way.
+    pub fn advance(&mut self, by: Milliseconds) {
+        let mut left = by;
+        while let Some(remaining) = self.timer.remaining() {
+            if remaining > left {
+                break;
             }
+            self.timer.advance(remaining);
+            left = left - remaining;
+            self.on_interrupt();
         }
+        self.timer.advance(left);
+    }
+}
 
-        let mut schedule_deadlines = self.shared.unwrap().schedule_deadlines.borrow_mut();
+/// When the earliest pending deadline falls.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NextDeadline {
+    /// Nothing is pending.
+    None,
+    /// The earliest deadline falls in the given time.
+    In(Milliseconds),
+    /// Something is pending, but the timer cannot tell how much of it has elapsed.
+    Unknown,
+}
 
-        for slot in schedule_deadlines.iter_mut() {
-            if let Some(deadline) = slot {
-                let expiration = deadline.get_expiration();
-                if expiration >= expired {
-                    deadline.set_expiration(expiration - expired);
-                } else {
-                    deadline.set_expiration(Milliseconds(0u32));
-                }
+impl<T: HalTimer> TimerActor<T> {
+    pub fn next_deadline(&self) -> NextDeadline {
+        match *self.shared.unwrap().current_deadline.borrow() {
+            None => NextDeadline::None,
+            Some(current) => match self.timer.elapsed() {
+                Some(elapsed) => NextDeadline::In(current - elapsed.min(current)),
+                None => NextDeadline::Unknown,
+            },
+        }
+    }
 
-                if deadline.get_expiration() == Milliseconds(0u32) {
-                    deadline.run();
-                    slot.take();
-                } else {
-                    match next_deadline {
-                        None => {
-                            next_deadline.replace(deadline.get_expiration());
-                        }
-                        Some(soonest) if soonest > deadline.get_expiration() => {
-                            next_deadline.replace(deadline.get_expiration());
-                        }
-                        _ => { /* ignore */ }
-                    }
-                }
+    /// The most a deadline has been missed by since start, if any has.
+    pub fn missed_by(&self) -> Option<Milliseconds> {
+        *self.shared.unwrap().missed_by.borrow()
+    }
+
+    /// The time left until the earliest pending deadline, or, if the timer cannot tell
+    /// how much has elapsed, the whole of it.
+    fn remaining(&self) -> Option<Milliseconds> {
+        match self.next_deadline() {
+            NextDeadline::None => None,
+            NextDeadline::In(remaining) => Some(remaining),
+            NextDeadline::Unknown => *self.shared.unwrap().current_deadline.borrow(),
+        }
+    }
+
+    /// Catch up with `slept`, passed with the timer stopped, such as in a low-power mode,
+    /// counting the whole of it, and what the timer had counted before, off every
+    /// deadline, completing those it passed and restarting the timer for the rest.
+    pub fn resume(&mut self, slept: Milliseconds) {
+        let running = match *self.shared.unwrap().period.borrow() {
+            Some(_) => self.timer.elapsed().unwrap_or(Milliseconds(0u32)),
+            None => Milliseconds(0u32),
+        };
+        let next_deadline = self.count_off(running + slept);
+        self.restart(next_deadline);
+    }
+
+    /// Place a delay of `ms`, returning the future reaching it, or `None` if the table
+    /// is full.
+    fn start_delay(&mut self, ms: Milliseconds) -> Option<DelayFuture> {
+        let shared = self.shared.unwrap();
+        self.catch_up();
+        let index = shared.insert(ms, Action::Delay(None)).ok()?;
+        self.arm(ms);
+        Some(DelayFuture::new(index, shared))
+    }
+
+    /// Run `schedule` once `ms` have passed, unless it could not be held or the table
+    /// is full.
+    fn insert_schedule(&mut self, ms: Milliseconds, schedule: Option<Scheduled>) {
+        let schedule = match schedule {
+            Some(schedule) => schedule,
+            None => {
+                warn!("[timer] no room to hold schedule; dropped");
+                return;
+            }
+        };
+        self.catch_up();
+        if self
+            .shared
+            .unwrap()
+            .insert(ms, Action::Schedule(schedule))
+            .is_ok()
+        {
+            self.arm(ms);
+        }
+    }
+
+    /// Count the time the timer has counted so far off every deadline, and restart it,
+    /// so that a deadline placed next is counted from now, and restarting the timer
+    /// for it loses no time.
+    ///
+    /// Left to the interrupt once the timer has counted its whole period, and not done
+    /// by timers unable to tell the time elapsed.
+    fn catch_up(&mut self) {
+        let period = *self.shared.unwrap().period.borrow();
+        if let (Some(period), Some(elapsed)) = (period, self.timer.elapsed()) {
+            if elapsed > Milliseconds(0u32) && elapsed < period {
+                let next_deadline = self.count_off(elapsed);
+                self.restart(next_deadline);
             }
         }
+    }
 
+    /// Restart the timer for a new deadline in `ms`, if it falls before the current one,
+    /// or nothing else is pending.
+    fn arm(&mut self, ms: Milliseconds) {
         let mut current_deadline = self.shared.unwrap().current_deadline.borrow_mut();
-        //log::info!("next deadline {:?}", next_deadline );
-
-        if let Some(next_deadline) = next_deadline {
-            if next_deadline > Milliseconds(0u32) {
-                current_deadline.replace(next_deadline);
-                self.timer.start(next_deadline);
-            } else {
-                current_deadline.take();
+        match *current_deadline {
+            Some(current) if current <= ms => {}
+            _ => {
+                current_deadline.replace(ms);
+                self.start(ms);
+            }
+        }
+    }
+
+    fn start(&mut self, period: Milliseconds) {
+        self.shared.unwrap().period.borrow_mut().replace(period);
+        self.timer.start(period);
+    }
+
+    /// Count `by` onto the uptime, wrapping after about 49.7 days as an `Instant` does.
+    fn advance_uptime(&self, by: Milliseconds) {
+        let mut uptime = self.shared.unwrap().uptime.borrow_mut();
+        *uptime = Milliseconds(uptime.0.wrapping_add(by.0));
+    }
+
+    /// The time counted by the timer: that of the periods passed, and of the one running.
+    fn now(&self) -> Milliseconds {
+        let uptime = *self.shared.unwrap().uptime.borrow();
+        match *self.shared.unwrap().period.borrow() {
+            Some(_) => {
+                let elapsed = self.timer.elapsed().unwrap_or(Milliseconds(0u32));
+                Milliseconds(uptime.0.wrapping_add(elapsed.0))
+            }
+            None => uptime,
+        }
+    }
+
+    /// Count `expired` (at most the current deadline) off every deadline, completing
+    /// those reached, and restart the timer for the earliest left.
+    ///
+    /// The timer goes on counting while the deadlines are completed, and in the
+    /// interrupt latency before, so for timers able to tell, that time is counted off
+    /// too before restarting, lest each restart push the later deadlines, and so any
+    /// periodic schedule, back by as much.
+    fn expire(&mut self, expired: Milliseconds) {
+        let mut counted = self
+            .timer
+            .elapsed()
+            .map_or(expired, |elapsed| elapsed.max(expired));
+        let mut next_deadline = self.count_off(counted);
+        while let Some(elapsed) = self.timer.elapsed() {
+            if elapsed <= counted {
+                break;
+            }
+            next_deadline = self.count_off(elapsed - counted);
+            counted = elapsed;
+        }
+        self.restart(next_deadline);
+    }
+
+    /// Restart the timer for the deadline `next_deadline` away, or, if there is none,
+    /// for an `IDLE_PERIOD`.
+    fn restart(&mut self, next_deadline: Option<Milliseconds>) {
+        match next_deadline {
+            Some(next_deadline) => {
+                trace!("next deadline in {}", next_deadline);
+                self.shared.unwrap().current_deadline.borrow_mut().replace(next_deadline);
+                self.start(next_deadline);
+            }
+            None => {
+                self.shared.unwrap().current_deadline.borrow_mut().take();
+                self.start(IDLE_PERIOD);
             }
-        } else {
-            current_deadline.take();
         }
     }
+
+    /// Count `expired` off every deadline, completing those reached, and return the
+    /// earliest left.
+    fn count_off(&mut self, expired: Milliseconds) -> Option<Milliseconds> {
+        trace!("timer expired after {}", expired);
+        self.advance_uptime(expired);
+        self.shared.unwrap().count_off(expired)
+    }
+}
+
+/// Request for the time counted by the timer.
+#[derive(Copy, Clone, Debug)]
+pub struct Now;
+
+impl<T: HalTimer> RequestHandler<Now> for TimerActor<T> {
+    type Response = Milliseconds;
+
+    fn on_request(self, _: Now) -> Response<Self, Self::Response> {
+        let now = TimerActor::now(&self);
+        Response::immediate(self, now)
+    }
 }
 
 impl<T: HalTimer + 'static> Address<TimerActor<T>> {
+    /// The time left until the next deadline fires, or `None` if nothing is pending.
+    ///
+    /// Timers unable to tell how much time has elapsed report the whole of the current
+    /// deadline, an upper bound. The timer is read directly, and must be at rest, such
+    /// as when called from an `Idle` hook or another actor.
+    pub fn next_deadline(&self) -> Option<Milliseconds> {
+        cortex_m::interrupt::free(|_| self.with_actor(|timer| timer.remaining()))
+    }
+
+    /// The most a deadline has been missed by since start, beyond the threshold set with
+    /// `Timer::with_miss_threshold`, if any has.
+    pub fn missed_by(&self) -> Option<Milliseconds> {
+        cortex_m::interrupt::free(|_| self.with_actor(|timer| timer.missed_by()))
+    }
+
     pub async fn delay<DUR: Duration + Into<Milliseconds> + 'static>(&self, duration: DUR) {
         self.request(Delay(duration)).await
     }
 
+    /// Block until `duration` has passed, spinning until the timer's interrupt reaches it.
+    ///
+    /// The delay is placed with the timer directly rather than requested, the timer
+    /// not being polled while the caller blocks. Returns at once if the table is full.
+    pub fn delay_blocking(&self, duration: Milliseconds) {
+        let delay =
+            cortex_m::interrupt::free(|_| self.with_actor(|timer| timer.start_delay(duration)));
+        if let Some(mut delay) = delay {
+            while !delay.has_expired() {
+                cortex_m::asm::nop();
+            }
+        }
+    }
+
     pub fn schedule<
         DUR: Duration + Into<Milliseconds> + 'static,
         E: Clone + 'static,
@@ -317,61 +727,18 @@ impl<T: HalTimer + 'static> Address<TimerActor<T>> {
     ) {
         self.notify(Schedule::new(delay, event, address));
     }
-}
-
-struct DelayDeadline {
-    expiration: Milliseconds,
-    waker: Option<Waker>,
-}
 
-impl DelayDeadline {
-    fn new(expiration: Milliseconds) -> Self {
-        Self {
-            expiration,
-            waker: None,
-        }
-    }
-}
-
-pub struct ScheduleDeadline<
-    A: Actor + NotifyHandler<E> + 'static,
-    DUR: Duration + Into<Milliseconds>,
-    E: Clone + 'static,
-> {
-    expiration: Milliseconds,
-    schedule: Schedule<A, DUR, E>,
-}
-
-impl<
-        A: Actor + NotifyHandler<E> + 'static,
-        DUR: Duration + Into<Milliseconds>,
-        E: Clone + 'static,
-    > Schedulable for ScheduleDeadline<A, DUR, E>
-{
-    fn run(&self) {
-        self.schedule.address.notify(self.schedule.event.clone());
-    }
-
-    fn set_expiration(&mut self, expiration: Milliseconds) {
-        self.expiration = expiration;
-    }
-
-    fn get_expiration(&self) -> Milliseconds {
-        self.expiration
-    }
-}
-
-impl<
-        A: Actor + NotifyHandler<E> + 'static,
-        DUR: Duration + Into<Milliseconds>,
-        E: Clone + 'static,
-    > ScheduleDeadline<A, DUR, E>
-{
-    fn new(expiration: Milliseconds, schedule: Schedule<A, DUR, E>) -> Self {
-        Self {
-            expiration,
-            schedule,
-        }
+    /// Run `f` once `delay` has passed.
+    ///
+    /// `f` runs in the timer's interrupt, with the other deadlines waiting on it, so must
+    /// be short, must not block, and must not allocate. Anything more belongs in an actor
+    /// notified with `schedule`.
+    pub fn schedule_fn<DUR, F>(&self, delay: DUR, f: F)
+    where
+        DUR: Duration + Into<Milliseconds> + 'static,
+        F: FnOnce() + 'static,
+    {
+        self.notify(ScheduleFn::new(delay, f));
     }
 }
 
@@ -393,7 +760,7 @@ impl DelayFuture {
     fn has_expired(&mut self) -> bool {
         if !self.expired {
             // critical section to avoid being trampled by the timer's own IRQ
-            self.expired = cortex_m::interrupt::free(|cs| self.shared.has_expired(self.index))
+            self.expired = free(|| self.shared.has_expired(self.index))
         }
 
         self.expired
@@ -407,6 +774,29 @@ impl DelayFuture {
     }
 }
 
+/// A delay no longer awaited gives up its deadline, rather than holding the slot until
+/// the deadline is reached and seen.
+impl Drop for DelayFuture {
+    fn drop(&mut self) {
+        if !self.expired {
+            free(|| self.shared.cancel(self.index));
+        }
+    }
+}
+
+/// Run `f` free of the timer's interrupt, in a critical section, or directly in unit
+/// tests on the host, where there is no interrupt to exclude.
+fn free<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(not(test))]
+    {
+        cortex_m::interrupt::free(|_| f())
+    }
+    #[cfg(test)]
+    {
+        f()
+    }
+}
+
 impl Future for DelayFuture {
     type Output = ();
 
@@ -421,3 +811,542 @@ impl Future for DelayFuture {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::reconfigure::Reconfigure;
+    use std::boxed::Box;
+    use std::sync::{Arc, Mutex};
+    use std::task::Wake;
+
+    /// Records the deadlines completed, in order.
+    struct Completed {
+        index: usize,
+        order: &'static Mutex<std::vec::Vec<usize>>,
+    }
+
+    impl Wake for Completed {
+        fn wake(self: Arc<Self>) {
+            self.order.lock().unwrap().push(self.index);
+        }
+    }
+
+    impl Schedulable for Completed {
+        fn run(&self) {
+            self.order.lock().unwrap().push(self.index);
+        }
+    }
+
+    /// Hold `schedule` as the timer would, leaked rather than allocated, there being no
+    /// heap in tests.
+    #[cfg(not(feature = "no-alloc"))]
+    fn held<S: Schedulable + 'static>(schedule: S) -> Scheduled {
+        crate::alloc::Box::new(Box::leak(Box::new(schedule)) as &mut dyn Schedulable)
+    }
+
+    #[cfg(feature = "no-alloc")]
+    fn held<S: Schedulable + 'static>(schedule: S) -> Scheduled {
+        InlineSchedule::new(schedule).ok().unwrap()
+    }
+
+    #[derive(Copy, Clone)]
+    enum Kind {
+        Delay,
+        Schedule,
+    }
+
+    fn timer(delays: &[u32]) -> TimerActor<MockTimer> {
+        let deadlines: std::vec::Vec<_> = delays.iter().map(|ms| (Kind::Delay, *ms)).collect();
+        timer_recording(&deadlines, Box::leak(Box::default()))
+    }
+
+    fn timer_recording(
+        deadlines: &[(Kind, u32)],
+        order: &'static Mutex<std::vec::Vec<usize>>,
+    ) -> TimerActor<MockTimer> {
+        let shared: &'static Shared = Box::leak(Box::new(Shared::new()));
+        let mut timer = TimerActor::new(MockTimer::new());
+        timer.configure(shared);
+        for (index, (kind, ms)) in deadlines.iter().enumerate() {
+            let completed = Completed { index, order };
+            let action = match kind {
+                Kind::Delay => Action::Delay(Some(Waker::from(Arc::new(completed)))),
+                Kind::Schedule => Action::Schedule(held(completed)),
+            };
+            shared.insert(Milliseconds(*ms), action).unwrap();
+            timer.arm(Milliseconds(*ms));
+        }
+        timer
+    }
+
+    fn remaining(timer: &TimerActor<MockTimer>, index: usize) -> Option<Milliseconds> {
+        let shared = timer.shared.unwrap();
+        let counted = *shared.counted.borrow();
+        shared.deadlines.borrow()[index]
+            .as_ref()
+            .map(|deadline| Milliseconds(deadline.at.saturating_sub(counted) as u32))
+    }
+
+    #[test]
+    fn test_resume() {
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let mut timer = timer_recording(
+            &[
+                (Kind::Delay, 100),
+                (Kind::Delay, 300),
+                (Kind::Delay, 2000),
+            ],
+            order,
+        );
+        timer.timer.advance(Milliseconds(30u32));
+        assert_eq!(timer.next_deadline(), NextDeadline::In(Milliseconds(70u32)));
+
+        // 30ms counted before sleeping and 50ms asleep are both counted off
+        timer.resume(Milliseconds(50u32));
+        assert_eq!(remaining(&timer, 0), Some(Milliseconds(20u32)));
+        assert_eq!(remaining(&timer, 1), Some(Milliseconds(220u32)));
+        assert_eq!(timer.timer.armed(), Some(Milliseconds(20u32)));
+        assert_eq!(timer.next_deadline(), NextDeadline::In(Milliseconds(20u32)));
+
+        // sleeping past several deadlines completes each, and the whole of the sleep is
+        // counted off those left
+        timer.resume(Milliseconds(1000u32));
+        assert_eq!(*order.lock().unwrap(), [0, 1]);
+        assert_eq!(remaining(&timer, 0), Some(Milliseconds(0u32)));
+        assert_eq!(remaining(&timer, 1), Some(Milliseconds(0u32)));
+        assert_eq!(remaining(&timer, 2), Some(Milliseconds(920u32)));
+        assert_eq!(timer.timer.armed(), Some(Milliseconds(920u32)));
+        assert_eq!(TimerActor::now(&timer), Milliseconds(1080u32));
+
+        // and with nothing pending, the sleep is still counted
+        timer.advance(Milliseconds(920u32));
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+        timer.resume(Milliseconds(500u32));
+        assert_eq!(TimerActor::now(&timer), Milliseconds(2500u32));
+    }
+
+    #[test]
+    fn test_next_deadline() {
+        assert_eq!(timer(&[]).next_deadline(), NextDeadline::None);
+
+        struct Opaque;
+
+        impl HalTimer for Opaque {
+            fn start(&mut self, _: Milliseconds) {}
+            fn clear_update_interrupt_flag(&mut self) {}
+        }
+
+        let shared: &'static Shared = Box::leak(Box::new(Shared::new()));
+        shared.current_deadline.borrow_mut().replace(Milliseconds(10u32));
+        let mut timer = TimerActor::new(Opaque);
+        timer.configure(shared);
+        assert_eq!(timer.next_deadline(), NextDeadline::Unknown);
+        assert_eq!(timer.remaining(), Some(Milliseconds(10u32)));
+    }
+
+    #[test]
+    fn test_remaining() {
+        assert_eq!(timer(&[]).remaining(), None);
+
+        // two pending delays, the nearest 100ms away
+        let mut timer = timer(&[300, 120]);
+        timer.advance(Milliseconds(20u32));
+        assert_eq!(timer.remaining(), Some(Milliseconds(100u32)));
+    }
+
+    #[test]
+    fn test_reconfigure() {
+        let mut timer = timer(&[100]);
+        timer.timer.advance(Milliseconds(40u32));
+        let timer = match timer.on_notify(Reconfigure(())) {
+            Completion::Immediate(timer) => timer,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(remaining(&timer, 0), Some(Milliseconds(100u32)));
+        assert_eq!(timer.next_deadline(), NextDeadline::In(Milliseconds(60u32)));
+    }
+
+    #[test]
+    fn test_ordering() {
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let mut timer = timer_recording(
+            &[
+                (Kind::Delay, 300),
+                (Kind::Delay, 100),
+                (Kind::Delay, 200),
+                (Kind::Delay, 100),
+            ],
+            order,
+        );
+
+        timer.advance(Milliseconds(99u32));
+        assert!(order.lock().unwrap().is_empty());
+        timer.advance(Milliseconds(1u32));
+        assert_eq!(*order.lock().unwrap(), [1, 3]);
+
+        // one advance past several deadlines completes them in turn
+        timer.advance(Milliseconds(250u32));
+        assert_eq!(*order.lock().unwrap(), [1, 3, 2, 0]);
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+        // with nothing pending, the timer goes on counting
+        assert_eq!(TimerActor::now(&timer), Milliseconds(350u32));
+    }
+
+    #[test]
+    fn test_idle() {
+        let mut timer = match timer(&[]).on_start() {
+            Completion::Immediate(timer) => timer,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert_eq!(timer.timer.armed(), Some(IDLE_PERIOD));
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+
+        // the time passes with nothing pending, across idle periods
+        timer.advance(Milliseconds(2_500u32));
+        assert_eq!(TimerActor::now(&timer), Milliseconds(2_500u32));
+        assert_eq!(timer.timer.armed(), Some(IDLE_PERIOD));
+
+        // a deadline placed part way through an idle period is counted from then
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let completed = Completed { index: 0, order };
+        timer.insert_schedule(Milliseconds(100u32), Some(held(completed)));
+        assert_eq!(timer.timer.armed(), Some(Milliseconds(100u32)));
+        timer.advance(Milliseconds(99u32));
+        assert!(order.lock().unwrap().is_empty());
+        timer.advance(Milliseconds(1u32));
+        assert_eq!(*order.lock().unwrap(), [0]);
+        assert_eq!(TimerActor::now(&timer), Milliseconds(2_600u32));
+        assert_eq!(timer.timer.armed(), Some(IDLE_PERIOD));
+    }
+
+    #[test]
+    fn test_dropped_delays() {
+        let mut timer = timer(&[]);
+
+        // a delay no longer awaited, such as the loser of a timeout, gives up its slot
+        for _ in 0..MAX_DEADLINES * 2 {
+            drop(timer.start_delay(Milliseconds(100u32)).unwrap());
+        }
+        assert!(timer.shared.unwrap().queue.borrow().is_empty());
+
+        let mut delay = timer.start_delay(Milliseconds(50u32)).unwrap();
+        let reached = timer.start_delay(Milliseconds(10u32)).unwrap();
+        // as does one reached but never seen
+        timer.advance(Milliseconds(10u32));
+        drop(reached);
+        assert!(!delay.has_expired());
+        timer.advance(Milliseconds(40u32));
+        assert!(delay.has_expired());
+        assert_eq!(*timer.shared.unwrap().free.borrow(), u32::MAX);
+    }
+
+    #[test]
+    fn test_rearm() {
+        let mut running = timer(&[500]);
+        assert_eq!(running.timer.armed(), Some(Milliseconds(500u32)));
+
+        // a sooner deadline restarts the timer, a later one leaves it running
+        running.arm(Milliseconds(200u32));
+        assert_eq!(running.timer.armed(), Some(Milliseconds(200u32)));
+        running.arm(Milliseconds(300u32));
+        assert_eq!(running.timer.armed(), Some(Milliseconds(200u32)));
+
+        // once the soonest deadline passes, the timer is restarted for the next
+        let mut timer = timer(&[500, 200]);
+        timer.advance(Milliseconds(200u32));
+        assert!(!timer.timer.interrupt_pending());
+        assert_eq!(timer.timer.armed(), Some(Milliseconds(300u32)));
+        assert_eq!(timer.next_deadline(), NextDeadline::In(Milliseconds(300u32)));
+    }
+
+    #[test]
+    fn test_schedule_fn() {
+        let ran: &'static Cell<u32> = Box::leak(Box::new(Cell::new(0)));
+        let mut timer = timer(&[]);
+        let schedule = ScheduleFn::new(Milliseconds(100u32), move || ran.set(ran.get() + 1));
+        timer.insert_schedule(Milliseconds(100u32), Some(held(schedule)));
+
+        timer.advance(Milliseconds(99u32));
+        assert_eq!(ran.get(), 0);
+        timer.advance(Milliseconds(1u32));
+        assert_eq!(ran.get(), 1);
+
+        // run once, and gone from the table
+        timer.advance(Milliseconds(100u32));
+        assert_eq!(ran.get(), 1);
+        assert_eq!(remaining(&timer, 0), None);
+    }
+
+    #[cfg(feature = "no-alloc")]
+    #[test]
+    fn test_schedule_without_heap() {
+        // no heap is initialized in tests, so this can only be held inline
+        assert!(crate::alloc::alloc(0u8).is_none());
+
+        let ran: &'static Cell<u32> = Box::leak(Box::new(Cell::new(0)));
+        let timer = timer(&[]);
+        let mut timer = match timer.on_notify(ScheduleFn::new(Milliseconds(50u32), move || {
+            ran.set(ran.get() + 1)
+        })) {
+            Completion::Immediate(timer) => timer,
+            _ => panic!("schedule deferred"),
+        };
+
+        timer.advance(Milliseconds(50u32));
+        assert_eq!(ran.get(), 1);
+        assert_eq!(remaining(&timer, 0), None);
+    }
+
+    #[test]
+    fn test_latency() {
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let mut timer = timer_recording(
+            &[
+                (Kind::Schedule, 100),
+                (Kind::Schedule, 200),
+                (Kind::Schedule, 300),
+                (Kind::Schedule, 400),
+            ],
+            order,
+        );
+
+        // each interrupt is handled 30ms late, but the deadlines stay 100ms apart
+        let mut now = 0;
+        for fired in 1..=4 {
+            let remaining = timer.timer.remaining().unwrap().0;
+            assert_eq!(now + remaining, 100 * fired);
+            timer.timer.advance(Milliseconds(remaining + 30));
+            now += remaining + 30;
+            timer.on_interrupt();
+            assert_eq!(order.lock().unwrap().len(), fired as usize);
+        }
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+        assert_eq!(TimerActor::now(&timer), Milliseconds(430u32));
+
+        // a deadline passed in the latency is completed by the same interrupt
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let mut timer = timer_recording(&[(Kind::Schedule, 100), (Kind::Schedule, 150)], order);
+        timer.timer.advance(Milliseconds(160u32));
+        timer.on_interrupt();
+        assert_eq!(*order.lock().unwrap(), [0, 1]);
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        // a full table, inserted out of order, with pairs falling together
+        let deadlines: std::vec::Vec<_> = (0..MAX_DEADLINES as u32)
+            .map(|i| (Kind::Schedule, (i * 7 % 16 + 1) * 10))
+            .collect();
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let mut timer = timer_recording(&deadlines, order);
+        let shared = timer.shared.unwrap();
+        assert_eq!(
+            shared.insert(Milliseconds(5u32), Action::Delay(None)),
+            Err(DeviceError::ResourceExhausted)
+        );
+
+        timer.advance(Milliseconds(160u32));
+        let fired: std::vec::Vec<u32> = order
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|index| deadlines[*index].1)
+            .collect();
+        assert_eq!(fired.len(), MAX_DEADLINES);
+        assert!(fired.windows(2).all(|pair| pair[0] <= pair[1]));
+        // those falling together are reached in the order of their slots
+        assert_eq!(order.lock().unwrap()[..2], [0, 16]);
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+    }
+
+    /// Reaches the deadline at `at`, checking none before it is left.
+    struct Reached {
+        at: u32,
+        last: &'static Cell<u32>,
+        count: &'static Cell<u32>,
+    }
+
+    impl Schedulable for Reached {
+        fn run(&self) {
+            assert!(self.at >= self.last.get());
+            self.last.set(self.at);
+            self.count.set(self.count.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_queue_throughput() {
+        let shared: &'static Shared = Box::leak(Box::new(Shared::new()));
+        let last: &'static Cell<u32> = Box::leak(Box::new(Cell::new(0)));
+        let count: &'static Cell<u32> = Box::leak(Box::new(Cell::new(0)));
+        let mut seed = 0x2545_f491u32;
+        let rounds = 2_000;
+
+        for _ in 0..rounds {
+            let base = last.get();
+            for _ in 0..MAX_DEADLINES {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                let ms = seed % 1_000 + 1;
+                let action = Action::Schedule(held(Reached {
+                    at: base + ms,
+                    last,
+                    count,
+                }));
+                assert!(shared.insert(Milliseconds(ms), action).is_ok());
+            }
+            // reach them in uneven steps
+            let mut next = shared.count_off(Milliseconds(0u32));
+            while let Some(ms) = next {
+                next = shared.count_off(Milliseconds(ms.0 / 2 + 1));
+            }
+        }
+        assert_eq!(count.get() as usize, rounds * MAX_DEADLINES);
+        assert_eq!(*shared.free.borrow(), u32::MAX);
+    }
+
+    #[test]
+    fn test_long_uptime() {
+        // about 49.7 days in, where a count of milliseconds in 32 bits runs out
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let mut timer = timer_recording(&[], order);
+        let shared = timer.shared.unwrap();
+        *shared.counted.borrow_mut() = u32::MAX as u64 - 50;
+        *shared.uptime.borrow_mut() = Milliseconds(u32::MAX - 50);
+
+        for (index, ms) in [(0, 100), (1, 30), (2, 60)] {
+            let completed = Completed { index, order };
+            shared
+                .insert(Milliseconds(ms), Action::Schedule(held(completed)))
+                .unwrap();
+            timer.arm(Milliseconds(ms));
+        }
+        assert_eq!(remaining(&timer, 0), Some(Milliseconds(100u32)));
+
+        // deadlines on either side of the 32-bit count still fall when they should
+        timer.advance(Milliseconds(30u32));
+        assert_eq!(*order.lock().unwrap(), [1]);
+        assert_eq!(timer.next_deadline(), NextDeadline::In(Milliseconds(30u32)));
+        timer.advance(Milliseconds(30u32));
+        assert_eq!(*order.lock().unwrap(), [1, 2]);
+        assert_eq!(timer.next_deadline(), NextDeadline::In(Milliseconds(40u32)));
+        timer.advance(Milliseconds(40u32));
+        assert_eq!(*order.lock().unwrap(), [1, 2, 0]);
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+
+        // and the uptime wraps with them, rather than stopping at the end of its count
+        assert_eq!(TimerActor::now(&timer), Milliseconds(49u32));
+        timer.advance(Milliseconds(10u32));
+        assert_eq!(TimerActor::now(&timer), Milliseconds(59u32));
+    }
+
+    #[test]
+    fn test_missed() {
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let mut timer = timer_recording(
+            &[
+                (Kind::Schedule, 100),
+                (Kind::Schedule, 200),
+                (Kind::Schedule, 210),
+            ],
+            order,
+        );
+        let shared = timer.shared.unwrap();
+        shared.miss_threshold.borrow_mut().replace(Milliseconds(20u32));
+
+        // within the threshold, nothing is missed
+        timer.timer.advance(Milliseconds(115u32));
+        timer.on_interrupt();
+        assert_eq!(timer.missed_by(), None);
+
+        // beyond it, the deadline is missed by as much, and one passed in the latency
+        // by less
+        timer.timer.advance(Milliseconds(85u32 + 35));
+        timer.on_interrupt();
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2]);
+        assert_eq!(timer.missed_by(), Some(Milliseconds(35u32)));
+
+        // the worst is kept
+        shared.insert(Milliseconds(10u32), Action::Delay(None)).unwrap();
+        timer.arm(Milliseconds(10u32));
+        timer.timer.advance(Milliseconds(10u32 + 25));
+        timer.on_interrupt();
+        assert_eq!(timer.missed_by(), Some(Milliseconds(35u32)));
+    }
+
+    #[test]
+    fn test_shared_table() {
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let mut timer = timer_recording(
+            &[
+                (Kind::Schedule, 250),
+                (Kind::Delay, 100),
+                (Kind::Schedule, 100),
+                (Kind::Delay, 400),
+                (Kind::Schedule, 300),
+            ],
+            order,
+        );
+
+        timer.advance(Milliseconds(100u32));
+        assert_eq!(*order.lock().unwrap(), [1, 2]);
+
+        // schedules leave the table once run, delays once their future sees them
+        assert_eq!(remaining(&timer, 1), Some(Milliseconds(0u32)));
+        assert_eq!(remaining(&timer, 2), None);
+
+        timer.advance(Milliseconds(300u32));
+        assert_eq!(*order.lock().unwrap(), [1, 2, 0, 4, 3]);
+        assert_eq!(remaining(&timer, 0), None);
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+
+        // a freed slot is reused
+        let shared = timer.shared.unwrap();
+        assert_eq!(
+            shared.insert(Milliseconds(10u32), Action::Delay(None)),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn test_software_timer() {
+        use crate::hal::timer::systick::{SoftwareTimer, SysTick};
+
+        struct Ticking;
+
+        impl SysTick for Ticking {
+            fn restart(&mut self) {}
+        }
+
+        let order: &'static _ = Box::leak(Box::new(Mutex::new(std::vec::Vec::new())));
+        let shared: &'static Shared = Box::leak(Box::new(Shared::new()));
+        let mut timer = TimerActor::new(SoftwareTimer::with_tick(Ticking, Milliseconds(10u32)));
+        timer.configure(shared);
+        for (index, ms) in [25u32, 40].iter().enumerate() {
+            let completed = Completed { index, order };
+            shared
+                .insert(Milliseconds(*ms), Action::Schedule(held(completed)))
+                .unwrap();
+            timer.arm(Milliseconds(*ms));
+        }
+
+        // SysTick interrupts every 10ms, the deadlines falling on the ticks reaching them
+        let mut fired = std::vec::Vec::new();
+        for tick in 1..=5 {
+            timer.on_interrupt();
+            if order.lock().unwrap().len() > fired.len() {
+                fired.push(tick);
+            }
+        }
+        assert_eq!(*order.lock().unwrap(), [0, 1]);
+        assert_eq!(fired, [3, 4]);
+        assert_eq!(timer.next_deadline(), NextDeadline::None);
+        // and the ticks go on being counted with nothing pending
+        assert_eq!(TimerActor::now(&timer), Milliseconds(50u32));
+    }
+}
@@ -0,0 +1,238 @@
+//! Actors woken at a fixed interval.
+//!
+//! Sensors sampling on a schedule, health checks and the like each start a schedule
+//! on a clock when started, and schedule again each time it fires. `Periodic` does so
+//! for them: it wraps an actor, notifying it of an event every interval from start, so
+//! the actor need only implement `NotifyHandler<E>` for the event.
+//!
+//! The wrapped actor is mounted as the `Periodic`, so is not told an address of its
+//! own, and takes requests and bindings through it. Notifications other than the event
+//! cannot be passed through, and belong to an actor of their own.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::Clock;
+use crate::prelude::*;
+
+/// Notifies an actor of `E` every interval of a `Clock`.
+pub struct Periodic<A, C, E>
+where
+    A: Actor + NotifyHandler<E> + 'static,
+    C: Clock,
+    E: Clone + 'static,
+{
+    inner: A,
+    clock: C,
+    interval: Milliseconds,
+    event: E,
+    address: Option<Address<Self>>,
+}
+
+impl<A, C, E> Periodic<A, C, E>
+where
+    A: Actor + NotifyHandler<E> + 'static,
+    C: Clock,
+    E: Clone + 'static,
+{
+    /// Notify `inner` of `event` every `interval` of `clock`, the first an interval
+    /// after start.
+    pub fn new<DUR: Into<Milliseconds>>(inner: A, clock: C, interval: DUR, event: E) -> Self {
+        Self {
+            inner,
+            clock,
+            interval: interval.into(),
+            event,
+            address: None,
+        }
+    }
+
+    fn schedule_tick(&self) {
+        if let Some(address) = self.address {
+            self.clock.schedule(self.interval, Tick, address);
+        }
+    }
+
+    /// Take the wrapped actor out, along with the means to wrap it again.
+    fn unwrap(self) -> (A, impl FnOnce(A) -> Self) {
+        let Self {
+            inner,
+            clock,
+            interval,
+            event,
+            address,
+        } = self;
+        let wrap = move |inner| Self {
+            inner,
+            clock,
+            interval,
+            event,
+            address,
+        };
+        (inner, wrap)
+    }
+
+    /// Hand the wrapped actor to `f`, wrapping it again once `f` has completed.
+    fn forward<F: FnOnce(A) -> Completion<A>>(self, f: F) -> Completion<Self> {
+        let (inner, wrap) = self.unwrap();
+        match f(inner) {
+            Completion::Immediate(inner) => Completion::immediate(wrap(inner)),
+            Completion::Defer(inner) => Completion::defer(async move { wrap(inner.await) }),
+        }
+    }
+}
+
+impl<A, C, E> Actor for Periodic<A, C, E>
+where
+    A: Actor + NotifyHandler<E> + 'static,
+    C: Clock,
+    E: Clone + 'static,
+{
+    fn on_mount(&mut self, address: Address<Self>) {
+        self.address.replace(address);
+    }
+
+    fn on_initialize(self) -> Completion<Self> {
+        self.forward(A::on_initialize)
+    }
+
+    fn on_start(self) -> Completion<Self> {
+        self.schedule_tick();
+        self.forward(A::on_start)
+    }
+
+    fn on_sleep(self) -> Completion<Self> {
+        self.forward(A::on_sleep)
+    }
+
+    fn on_hibernate(self) -> Completion<Self> {
+        self.forward(A::on_hibernate)
+    }
+
+    fn on_stop(self) -> Completion<Self> {
+        self.forward(A::on_stop)
+    }
+}
+
+impl<A, C, E, OA> Bind<OA> for Periodic<A, C, E>
+where
+    A: Actor + NotifyHandler<E> + Bind<OA> + 'static,
+    C: Clock,
+    E: Clone + 'static,
+    OA: Actor + 'static,
+{
+    fn on_bind(&mut self, address: Address<OA>) {
+        self.inner.on_bind(address);
+    }
+}
+
+/// Message for the interval having passed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tick;
+
+impl<A, C, E> NotifyHandler<Tick> for Periodic<A, C, E>
+where
+    A: Actor + NotifyHandler<E> + 'static,
+    C: Clock,
+    E: Clone + 'static,
+{
+    fn on_notify(self, _: Tick) -> Completion<Self> {
+        self.schedule_tick();
+        let event = self.event.clone();
+        self.forward(|inner| inner.on_notify(event))
+    }
+}
+
+impl<A, C, E, M> RequestHandler<M> for Periodic<A, C, E>
+where
+    A: Actor + NotifyHandler<E> + RequestHandler<M> + 'static,
+    C: Clock,
+    E: Clone + 'static,
+{
+    type Response = A::Response;
+
+    fn on_request(self, message: M) -> Response<Self, Self::Response> {
+        let (inner, wrap) = self.unwrap();
+        match inner.on_request(message) {
+            Response::Immediate(inner, response) => Response::immediate(wrap(inner), response),
+            Response::ImmediateFuture(inner, response) => {
+                Response::immediate_future(wrap(inner), response)
+            }
+            Response::Defer(inner) => Response::defer(async move {
+                let (inner, response) = inner.await;
+                (wrap(inner), response)
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::timer::MockClock;
+    use std::boxed::Box;
+
+    struct Sample;
+
+    #[derive(Clone)]
+    struct Count;
+
+    struct Counter(u32);
+
+    impl Actor for Counter {}
+
+    impl NotifyHandler<Count> for Counter {
+        fn on_notify(mut self, _: Count) -> Completion<Self> {
+            self.0 += 1;
+            Completion::immediate(self)
+        }
+    }
+
+    impl RequestHandler<Sample> for Counter {
+        type Response = u32;
+
+        fn on_request(self, _: Sample) -> Response<Self, u32> {
+            let count = self.0;
+            Response::immediate(self, count)
+        }
+    }
+
+    type TestPeriodic = Periodic<Counter, &'static MockClock<Tick>, Count>;
+
+    fn completed(completion: Completion<TestPeriodic>) -> TestPeriodic {
+        match completion {
+            Completion::Immediate(periodic) => periodic,
+            Completion::Defer(_) => panic!("deferred"),
+        }
+    }
+
+    #[test]
+    fn test_ticks() {
+        let clock: &'static MockClock<Tick> = Box::leak(Box::new(MockClock::new()));
+        let context = Box::leak(Box::new(ActorContext::new(TestPeriodic::new(
+            Counter(0),
+            clock,
+            Milliseconds(0u32),
+            Count,
+        ))));
+        let mut periodic = TestPeriodic::new(Counter(0), clock, Milliseconds(250u32), Count);
+        periodic.on_mount(Address::new(context));
+        let mut periodic = completed(periodic.on_start());
+
+        for ticks in 1..=4 {
+            assert!(clock.advance(Milliseconds(249u32)).is_empty());
+            assert_eq!(clock.advance(Milliseconds(1u32)), [Tick]);
+            periodic = completed(periodic.on_notify(Tick));
+            assert_eq!(periodic.inner.0, ticks);
+        }
+        assert_eq!(clock.pending(), 1);
+        assert_eq!(clock.now(), Milliseconds(1000u32));
+
+        // requests reach the wrapped actor
+        match periodic.on_request(Sample) {
+            Response::Immediate(_, count) => assert_eq!(count, 4),
+            _ => panic!("deferred"),
+        }
+    }
+}
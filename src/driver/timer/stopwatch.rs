@@ -0,0 +1,104 @@
+//! Ad-hoc measurement of elapsed time against a driver clock.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::domain::time::uptime::Instant;
+use crate::driver::timer::Clock;
+
+/// Measures the time elapsed since it was started, such as how long a sensor read took.
+///
+/// A `Stopwatch` only records the instant it was started, so any number may share the
+/// same clock, such as the address of a `TimerActor`. A stopwatch which is not running
+/// reads zero.
+pub struct Stopwatch<C: Clock> {
+    clock: C,
+    started: Option<Instant>,
+}
+
+impl<C: Clock> Stopwatch<C> {
+    /// Create a stopped stopwatch measuring time against `clock`.
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            started: None,
+        }
+    }
+
+    /// Start measuring from now, restarting if already running.
+    pub async fn start(&mut self) {
+        self.started.replace(self.clock.instant().await);
+    }
+
+    /// Stop the stopwatch, reading zero until started again.
+    pub fn reset(&mut self) {
+        self.started.take();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// Time elapsed since the stopwatch was started.
+    pub async fn elapsed(&self) -> Milliseconds {
+        match self.started {
+            Some(started) => self.clock.elapsed(started).await,
+            None => Milliseconds(0u32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::timer::MockClock;
+    use crate::testing::block_on;
+    use std::boxed::Box;
+
+    fn clock() -> &'static MockClock<()> {
+        Box::leak(Box::new(MockClock::new()))
+    }
+
+    #[test]
+    fn test_elapsed() {
+        let clock = clock();
+        clock.advance(Milliseconds(1_234u32));
+        let mut stopwatch = Stopwatch::new(clock);
+        assert_eq!(block_on(stopwatch.elapsed()), Milliseconds(0u32));
+
+        block_on(stopwatch.start());
+        assert_eq!(block_on(stopwatch.elapsed()), Milliseconds(0u32));
+        clock.advance(Milliseconds(25u32));
+        assert_eq!(block_on(stopwatch.elapsed()), Milliseconds(25u32));
+        clock.advance(Milliseconds(100u32));
+        assert_eq!(block_on(stopwatch.elapsed()), Milliseconds(125u32));
+    }
+
+    #[test]
+    fn test_reset() {
+        let clock = clock();
+        let mut stopwatch = Stopwatch::new(clock);
+        block_on(stopwatch.start());
+        clock.advance(Milliseconds(40u32));
+        assert_eq!(block_on(stopwatch.elapsed()), Milliseconds(40u32));
+
+        stopwatch.reset();
+        assert!(!stopwatch.is_running());
+        clock.advance(Milliseconds(40u32));
+        assert_eq!(block_on(stopwatch.elapsed()), Milliseconds(0u32));
+
+        block_on(stopwatch.start());
+        clock.advance(Milliseconds(10u32));
+        assert_eq!(block_on(stopwatch.elapsed()), Milliseconds(10u32));
+    }
+
+    #[test]
+    fn test_wrapping_clock() {
+        let clock = clock();
+        clock.advance(Milliseconds(u32::MAX - 50));
+        let mut stopwatch = Stopwatch::new(clock);
+        block_on(stopwatch.start());
+        clock.advance(Milliseconds(150u32));
+        assert_eq!(block_on(stopwatch.elapsed()), Milliseconds(150u32));
+    }
+}
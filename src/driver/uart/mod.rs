@@ -1,3 +1,5 @@
+pub mod serial;
+
 use crate::actor::{Actor, Configurable};
 use crate::address::Address;
 use crate::bus::EventBus;
@@ -113,13 +115,13 @@ where
     pub fn read<'a>(&'a mut self, rx_buffer: &mut [u8]) -> RxFuture<'a, U> {
         match self.rx_state {
             State::Ready => {
-                log::trace!("NO RX in progress");
+                trace!("NO RX in progress");
                 self.rx_done.unwrap().reset();
                 self.rx_state = State::InProgress;
                 let uart = self.uart.unwrap();
                 match uart.start_read(rx_buffer) {
                     Ok(_) => {
-                        log::trace!("Starting RX");
+                        trace!("Starting RX");
                         RxFuture::Defer(self)
                     }
                     Err(e) => RxFuture::Error(e),
@@ -133,13 +135,13 @@ where
     pub fn write<'a>(&'a mut self, tx_buffer: &[u8]) -> TxFuture<'a, U> {
         match self.tx_state {
             State::Ready => {
-                log::trace!("NO TX in progress");
+                trace!("NO TX in progress");
                 self.tx_done.unwrap().reset();
                 self.tx_state = State::InProgress;
                 let uart = self.uart.unwrap();
                 match uart.start_write(tx_buffer) {
                     Ok(_) => {
-                        log::trace!("Starting TX");
+                        trace!("Starting TX");
                         TxFuture::Defer(self)
                     }
                     Err(e) => TxFuture::Error(e),
@@ -207,7 +209,7 @@ where
     fn on_interrupt(&mut self) {
         let uart = self.uart.unwrap();
         let (tx_done, rx_done) = uart.process_interrupts();
-        log::trace!(
+        trace!(
             "[UART ISR] TX WAKER: {}. RX WAKER: {}. TX DONE: {}. RX DONE: {}",
             self.tx_done.as_ref().unwrap().signaled(),
             self.rx_done.as_ref().unwrap().signaled(),
@@ -247,7 +249,7 @@ where
                     let done = p.tx_done.unwrap();
                     if let Poll::Ready(result) = done.poll_wait(cx) {
                         p.tx_state = State::Ready;
-                        log::trace!("Marking future complete");
+                        trace!("Marking future complete");
                         return Poll::Ready(result);
                     }
                 }
@@ -294,7 +296,7 @@ where
                     let done = p.rx_done.unwrap();
                     if let Poll::Ready(result) = done.poll_wait(cx) {
                         p.rx_state = State::Ready;
-                        log::trace!("Marking future complete");
+                        trace!("Marking future complete");
                         return Poll::Ready(result);
                     }
                 }
@@ -0,0 +1,412 @@
+//! Line-oriented serial I/O over an `embedded-hal` serial peripheral.
+//!
+//! The peripheral's RX interrupt must be enabled (this is HAL-specific, e.g. `listen(Event::Rxne)`)
+//! so received bytes can be moved into a queue and the reader woken.
+
+use crate::actor::{Actor, Configurable};
+use crate::address::Address;
+use crate::bus::EventBus;
+use crate::device::Device;
+use crate::interrupt::{Interrupt, InterruptContext};
+use crate::package::Package;
+use crate::prelude::*;
+use crate::synchronization::{Exclusive, Mutex, MutexActor, Signal};
+use crate::util::task::yield_now;
+
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use cortex_m::interrupt::Nr;
+use embedded_hal::serial::{Read, Write};
+use heapless::{consts::*, spsc::Queue, ArrayLength, String, Vec};
+
+/// A line of text received by `read_line()`, without its line terminator.
+pub type Line = String<U128>;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineError {
+    /// The line did not fit in the buffer. The whole line, up to and including
+    /// its newline, has been discarded.
+    TooLong,
+    /// The line was not valid UTF-8.
+    InvalidUtf8,
+}
+
+/// Async byte-level access to a serial line, allowing protocol drivers
+/// to be layered on top of a `SerialPeripheral`.
+#[allow(async_fn_in_trait)]
+pub trait SerialIo {
+    type Error;
+
+    /// Transmit all of `bytes`.
+    async fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Receive the next byte.
+    async fn read_byte(&mut self) -> Result<u8, Self::Error>;
+}
+
+impl<T: SerialIo> SerialIo for Exclusive<T> {
+    type Error = T::Error;
+
+    async fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        (**self).write_all(bytes).await
+    }
+
+    async fn read_byte(&mut self) -> Result<u8, Self::Error> {
+        (**self).read_byte().await
+    }
+}
+
+/// Accumulates bytes into lines terminated by `\n`, dropping a trailing `\r`.
+pub struct LineReader<N: ArrayLength<u8>> {
+    buffer: Vec<u8, N>,
+    overflow: bool,
+}
+
+impl<N: ArrayLength<u8>> LineReader<N> {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            overflow: false,
+        }
+    }
+
+    /// Feed a single byte, returning the line once its newline has been seen.
+    pub fn feed(&mut self, byte: u8) -> Option<Result<String<N>, LineError>> {
+        if byte == b'\n' {
+            let line = core::mem::replace(&mut self.buffer, Vec::new());
+            if core::mem::replace(&mut self.overflow, false) {
+                return Some(Err(LineError::TooLong));
+            }
+            let mut line = String::from_utf8(line).map_err(|_| LineError::InvalidUtf8);
+            if let Ok(ref mut line) = line {
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            return Some(line);
+        }
+        if !self.overflow && self.buffer.push(byte).is_err() {
+            self.overflow = true;
+        }
+        None
+    }
+}
+
+impl<N: ArrayLength<u8>> Default for LineReader<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Move every byte currently available from `serial` into `rx`, returning `true` if any were read.
+fn drain<S: Read<u8>>(serial: &mut S, rx: &mut Queue<u8, U64>) -> bool {
+    let mut received = false;
+    loop {
+        match serial.read() {
+            Ok(byte) => {
+                received = true;
+                if rx.enqueue(byte).is_err() {
+                    warn!("[serial] rx queue full, dropping byte");
+                }
+            }
+            Err(nb::Error::WouldBlock) => break,
+            Err(nb::Error::Other(_)) => {
+                warn!("[serial] rx error");
+                break;
+            }
+        }
+    }
+    received
+}
+
+pub struct Shared<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    serial: RefCell<S>,
+    rx: RefCell<Queue<u8, U64>>,
+    rx_ready: Signal<()>,
+}
+
+/// A serial package exposing `write_all(...)` and `read_line()` through
+/// the `Exclusive<SerialPeripheral<...>>` obtained by locking its address.
+pub struct Serial<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    peripheral: Mutex<SerialPeripheral<S>>,
+    irq: InterruptContext<SerialInterrupt<S>>,
+    shared: Shared<S>,
+}
+
+impl<S> Serial<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    pub fn new<IRQ>(serial: S, irq: IRQ) -> Self
+    where
+        IRQ: Nr,
+    {
+        Self {
+            peripheral: Mutex::new(SerialPeripheral::new()),
+            irq: InterruptContext::new(SerialInterrupt::new(), irq),
+            shared: Shared {
+                serial: RefCell::new(serial),
+                rx: RefCell::new(Queue::new()),
+                rx_ready: Signal::new(),
+            },
+        }
+    }
+}
+
+impl<D, S> Package<D, MutexActor<SerialPeripheral<S>>> for Serial<S>
+where
+    D: Device,
+    S: Read<u8> + Write<u8> + 'static,
+{
+    fn mount(
+        &'static self,
+        bus_address: Address<EventBus<D>>,
+        supervisor: &mut Supervisor,
+    ) -> Address<MutexActor<SerialPeripheral<S>>> {
+        let peripheral = self.peripheral.mount(bus_address, supervisor);
+        self.irq.mount(supervisor);
+        self.peripheral.configure(&self.shared);
+        self.irq.configure(&self.shared);
+        peripheral
+    }
+}
+
+pub struct SerialPeripheral<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    shared: Option<&'static Shared<S>>,
+    reader: LineReader<U128>,
+}
+
+impl<S> SerialPeripheral<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            shared: None,
+            reader: LineReader::new(),
+        }
+    }
+
+    /// Transmit all of `bytes`, polling the peripheral until each byte is accepted.
+    ///
+    /// While the peripheral is busy, the actor yields between polls so that others are
+    /// not starved for the length of the transmission.
+    pub async fn write_all(&mut self, bytes: &[u8]) -> Result<(), <S as Write<u8>>::Error> {
+        let serial = &self.shared.unwrap().serial;
+        for byte in bytes {
+            loop {
+                match cortex_m::interrupt::free(|_| serial.borrow_mut().write(*byte)) {
+                    Ok(_) => break,
+                    Err(nb::Error::WouldBlock) => yield_now().await,
+                    Err(nb::Error::Other(e)) => return Err(e),
+                }
+            }
+        }
+        loop {
+            match cortex_m::interrupt::free(|_| serial.borrow_mut().flush()) {
+                Ok(_) => return Ok(()),
+                Err(nb::Error::WouldBlock) => yield_now().await,
+                Err(nb::Error::Other(e)) => return Err(e),
+            }
+        }
+    }
+
+    /// Receive the next `\n`-terminated line.
+    ///
+    /// A line longer than `Line`'s capacity is discarded in its entirety and
+    /// reported as `LineError::TooLong`; the following line is read normally.
+    pub async fn read_line(&mut self) -> Result<Line, LineError> {
+        let shared = self.shared.unwrap();
+        loop {
+            while let Some(byte) = cortex_m::interrupt::free(|_| shared.rx.borrow_mut().dequeue())
+            {
+                if let Some(line) = self.reader.feed(byte) {
+                    return line;
+                }
+            }
+            RxReady(&shared.rx_ready).await;
+        }
+    }
+
+    /// Receive the next byte, bypassing line framing.
+    pub async fn read_byte(&mut self) -> u8 {
+        let shared = self.shared.unwrap();
+        loop {
+            if let Some(byte) = cortex_m::interrupt::free(|_| shared.rx.borrow_mut().dequeue()) {
+                return byte;
+            }
+            RxReady(&shared.rx_ready).await;
+        }
+    }
+}
+
+impl<S> SerialIo for SerialPeripheral<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    type Error = <S as Write<u8>>::Error;
+
+    async fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        SerialPeripheral::write_all(self, bytes).await
+    }
+
+    async fn read_byte(&mut self) -> Result<u8, Self::Error> {
+        Ok(SerialPeripheral::read_byte(self).await)
+    }
+}
+
+impl<S> Default for SerialPeripheral<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Configurable for SerialPeripheral<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    type Configuration = Shared<S>;
+
+    fn configure(&mut self, config: &'static Self::Configuration) {
+        self.shared.replace(config);
+    }
+}
+
+impl<S> Actor for SerialPeripheral<S> where S: Read<u8> + Write<u8> + 'static {}
+
+pub struct SerialInterrupt<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    shared: Option<&'static Shared<S>>,
+}
+
+impl<S> SerialInterrupt<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    pub fn new() -> Self {
+        Self { shared: None }
+    }
+}
+
+impl<S> Default for SerialInterrupt<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Configurable for SerialInterrupt<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    type Configuration = Shared<S>;
+
+    fn configure(&mut self, config: &'static Self::Configuration) {
+        self.shared.replace(config);
+    }
+}
+
+impl<S> Actor for SerialInterrupt<S> where S: Read<u8> + Write<u8> + 'static {}
+
+impl<S> Interrupt for SerialInterrupt<S>
+where
+    S: Read<u8> + Write<u8> + 'static,
+{
+    fn on_interrupt(&mut self) {
+        let shared = self.shared.unwrap();
+        let received = drain(&mut *shared.serial.borrow_mut(), &mut shared.rx.borrow_mut());
+        if received {
+            shared.rx_ready.signal(());
+        }
+    }
+}
+
+struct RxReady<'a>(&'a Signal<()>);
+
+impl<'a> Future for RxReady<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.poll_wait(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockSerial {
+        bytes: &'static [u8],
+    }
+
+    impl Read<u8> for MockSerial {
+        type Error = ();
+
+        fn read(&mut self) -> nb::Result<u8, Self::Error> {
+            match self.bytes.split_first() {
+                Some((byte, rest)) => {
+                    self.bytes = rest;
+                    Ok(*byte)
+                }
+                None => Err(nb::Error::WouldBlock),
+            }
+        }
+    }
+
+    fn lines(input: &'static [u8]) -> Vec<Result<String<U8>, LineError>, U8> {
+        let mut serial = MockSerial { bytes: input };
+        let mut rx = Queue::new();
+        let mut reader = LineReader::<U8>::new();
+        let mut lines = Vec::new();
+        while drain(&mut serial, &mut rx) {
+            while let Some(byte) = rx.dequeue() {
+                if let Some(line) = reader.feed(byte) {
+                    lines.push(line).ok();
+                }
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn test_lines() {
+        let lines = lines(b"hello\r\nworld\n\npartial");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].as_ref().unwrap().as_str(), "hello");
+        assert_eq!(lines[1].as_ref().unwrap().as_str(), "world");
+        assert_eq!(lines[2].as_ref().unwrap().as_str(), "");
+    }
+
+    #[test]
+    fn test_line_too_long() {
+        let lines = lines(b"much too long for eight\nok\n");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], Err(LineError::TooLong));
+        assert_eq!(lines[1].as_ref().unwrap().as_str(), "ok");
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let lines = lines(b"\xff\xfe\nok\n");
+        assert_eq!(lines[0], Err(LineError::InvalidUtf8));
+        assert_eq!(lines[1].as_ref().unwrap().as_str(), "ok");
+    }
+}
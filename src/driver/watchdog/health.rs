@@ -0,0 +1,344 @@
+//! Health checks of actors, for system-wide monitoring.
+//!
+//! Actors implementing `Monitored` answer a `Ping` request, sent with `ping()` on their
+//! address, once they have handled every message queued ahead of it. The `Pong` tells
+//! how many messages were pending as the ping was sent.
+//!
+//! The `HealthMonitor` actor watches a set of monitored actors, and every interval
+//! pings each in turn, then publishes a `HealthReport` of the pending messages their
+//! `Pong`s tell of to the bus. An actor wedged on a message never answers, and so no further report is
+//! published; a device may kick a `Watchdog` with each report, to reset the system
+//! should they stop.
+
+use crate::actor::ActorContext;
+use crate::alloc::{alloc, Box};
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::{Clock, TimerActor};
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+use core::future::Future;
+use heapless::{consts::*, Vec};
+
+/// Request asking an actor whether it is alive.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ping;
+
+/// The answer of an actor to a `Ping`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pong {
+    /// The messages queued for the actor as it was pinged.
+    pub pending: usize,
+}
+
+/// Trait for actors answering a `Ping`, to be watched by a `HealthMonitor`.
+pub trait Monitored: Actor {}
+
+impl<A> RequestHandler<Ping> for A
+where
+    A: Monitored + 'static,
+{
+    type Response = ();
+
+    fn on_request(self, _: Ping) -> Response<Self, ()> {
+        Response::immediate(self, ())
+    }
+}
+
+impl<A> Address<A>
+where
+    A: Monitored + 'static,
+{
+    /// Ping the actor behind this address, resolving once it has handled every message
+    /// queued ahead of the ping.
+    pub async fn ping(&self) -> Pong {
+        let pending = self.pending_len();
+        self.request(Ping).await;
+        Pong { pending }
+    }
+}
+
+/// The most actors a `HealthMonitor` watches.
+pub const MAX_WATCHED: usize = 8;
+
+/// The health of a watched actor, as it answered its ping.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health {
+    /// The name of the actor.
+    pub name: &'static str,
+    /// The messages queued for the actor as it was pinged.
+    pub pending: usize,
+}
+
+/// The health of every watched actor, in the order they are watched, published once
+/// each has answered its ping. An actor there was no room to ping is left out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HealthReport {
+    pub actors: Vec<Health, U8>,
+}
+
+/// A monitored actor, of any type.
+trait Watched {
+    fn name(&'static self) -> &'static str;
+
+    /// Ping the actor, or `None` if there is no room to allocate the ping.
+    fn ping(&'static self) -> Option<Box<dyn Future<Output = Pong>>>;
+}
+
+impl<A> Watched for ActorContext<A>
+where
+    A: Monitored + 'static,
+{
+    fn name(&'static self) -> &'static str {
+        ActorContext::name(self)
+    }
+
+    fn ping(&'static self) -> Option<Box<dyn Future<Output = Pong>>> {
+        let address = self.address();
+        Some(Box::new(alloc(async move { address.ping().await })?))
+    }
+}
+
+pub struct HealthMonitor<D, C>
+where
+    D: Device + EventHandler<HealthReport> + 'static,
+    C: Clock,
+{
+    interval: Milliseconds,
+    watched: Vec<&'static dyn Watched, U8>,
+    bus: Option<Address<EventBus<D>>>,
+    clock: Option<C>,
+    address: Option<Address<Self>>,
+}
+
+impl<D, C> HealthMonitor<D, C>
+where
+    D: Device + EventHandler<HealthReport> + 'static,
+    C: Clock,
+{
+    /// Create a monitor checking on the actors it watches every `interval`.
+    pub fn new<DUR: Into<Milliseconds>>(interval: DUR) -> Self {
+        Self {
+            interval: interval.into(),
+            watched: Vec::new(),
+            bus: None,
+            clock: None,
+            address: None,
+        }
+    }
+
+    /// Time the checks by `clock` rather than by a bound timer.
+    pub fn with_clock(mut self, clock: C) -> Self {
+        self.clock.replace(clock);
+        self
+    }
+
+    /// Watch the actor at `address`.
+    ///
+    /// # Panics
+    ///
+    /// If `MAX_WATCHED` actors are watched already.
+    pub fn watch<A: Monitored + 'static>(&mut self, address: Address<A>) {
+        self.watched
+            .push(address.context())
+            .unwrap_or_else(|_| panic!("[health] too many actors watched"));
+    }
+
+    /// Ping each watched actor in turn, returning the health each answered with once
+    /// every one has.
+    async fn check(&self) -> HealthReport {
+        let mut report = HealthReport::default();
+        for watched in self.watched.iter() {
+            match watched.ping() {
+                Some(ping) => {
+                    let pong = ping.await;
+                    let health = Health {
+                        name: watched.name(),
+                        pending: pong.pending,
+                    };
+                    // never more watched than fit in a report
+                    report.actors.push(health).ok();
+                }
+                None => warn!("[health] no room to ping [{}]", watched.name()),
+            }
+        }
+        report
+    }
+
+    /// Check on the watched actors, publish the report, and schedule the next check.
+    async fn on_check(self) -> Self {
+        let report = self.check().await;
+        if let Some(bus) = self.bus {
+            bus.publish(report);
+        }
+        self.schedule_check();
+        self
+    }
+
+    fn schedule_check(&self) {
+        if let (Some(clock), Some(address)) = (self.clock, self.address) {
+            clock.schedule(self.interval, Check, address);
+        }
+    }
+}
+
+impl<D, C> Actor for HealthMonitor<D, C>
+where
+    D: Device + EventHandler<HealthReport> + 'static,
+    C: Clock,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_start(self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.schedule_check();
+        Completion::immediate(self)
+    }
+}
+
+impl<D, C> Bind<EventBus<D>> for HealthMonitor<D, C>
+where
+    D: Device + EventHandler<HealthReport> + 'static,
+    C: Clock,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, T> Bind<TimerActor<T>> for HealthMonitor<D, Address<TimerActor<T>>>
+where
+    D: Device + EventHandler<HealthReport> + 'static,
+    T: HalTimer + 'static,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.clock.replace(address);
+    }
+}
+
+impl<D, C> Address<HealthMonitor<D, C>>
+where
+    D: Device + EventHandler<HealthReport> + 'static,
+    C: Clock,
+{
+    /// Watch the actor at `address`, such as while mounting the device.
+    ///
+    /// # Panics
+    ///
+    /// If `MAX_WATCHED` actors are watched already.
+    pub fn watch<A: Monitored + 'static>(&self, address: Address<A>) {
+        self.with_actor(|monitor| monitor.watch(address))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Check;
+
+impl<D, C> NotifyHandler<Check> for HealthMonitor<D, C>
+where
+    D: Device + EventHandler<HealthReport> + 'static,
+    C: Clock,
+{
+    fn on_notify(self, _: Check) -> Completion<Self> {
+        Completion::defer(self.on_check())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::timer::MockClock;
+    use crate::testing::block_on;
+    use core::cell::Cell;
+    use std::boxed::Box;
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    impl EventHandler<HealthReport> for MockDevice {}
+
+    type TestMonitor = HealthMonitor<MockDevice, &'static MockClock<Check>>;
+
+    /// Stands in for a monitored actor, answering each ping at once with the messages
+    /// it was told are pending, counting the pings.
+    struct Answering {
+        name: &'static str,
+        pending: usize,
+        pings: Cell<usize>,
+    }
+
+    impl Watched for Answering {
+        fn name(&'static self) -> &'static str {
+            self.name
+        }
+
+        fn ping(&'static self) -> Option<crate::alloc::Box<dyn Future<Output = Pong>>> {
+            self.pings.set(self.pings.get() + 1);
+            let pong = Box::leak(Box::new(core::future::ready(Pong {
+                pending: self.pending,
+            })));
+            Some(crate::alloc::Box::new(pong as &mut dyn Future<Output = Pong>))
+        }
+    }
+
+    fn answering(name: &'static str, pending: usize) -> &'static Answering {
+        Box::leak(Box::new(Answering {
+            name,
+            pending,
+            pings: Cell::new(0),
+        }))
+    }
+
+    #[test]
+    fn test_check() {
+        let idle = answering("idle", 0);
+        let busy = answering("busy", 3);
+
+        let clock: &'static MockClock<Check> = Box::leak(Box::new(MockClock::new()));
+        let mut monitor = TestMonitor::new(Milliseconds(1000u32)).with_clock(clock);
+        monitor.watched.push(idle).ok();
+        monitor.watched.push(busy).ok();
+        let context = Box::leak(Box::new(ActorContext::new(TestMonitor::new(
+            Milliseconds(0u32),
+        ))));
+        monitor.on_mount(Address::new(context));
+
+        // each watched actor is pinged, and reported as it answered
+        let report = block_on(monitor.check());
+        assert_eq!(
+            &report.actors[..],
+            &[
+                Health {
+                    name: "idle",
+                    pending: 0
+                },
+                Health {
+                    name: "busy",
+                    pending: 3
+                },
+            ]
+        );
+
+        // a check pings every watched actor again, and schedules the next one
+        let monitor = block_on(monitor.on_check());
+        assert_eq!((idle.pings.get(), busy.pings.get()), (2, 2));
+        assert!(clock.advance(Milliseconds(999u32)).is_empty());
+        assert_eq!(clock.advance(Milliseconds(1u32)), [Check]);
+
+        // checks are scheduled every interval once started
+        let _ = monitor.on_start();
+        assert_eq!(clock.advance(Milliseconds(1000u32)), [Check]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Periodic heartbeat, for watchdogs and external liveness monitoring.
+//!
+//! The `Heartbeat` actor publishes a `HeartbeatEvent` to the bus every interval,
+//! carrying a sequence number incremented with each beat. A device may forward the
+//! events to a `Watchdog`, which treats each as a kick. Should the heartbeat actor
+//! wedge, no further beats are published, and the watchdog lets the system reset.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::{Clock, TimerActor};
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HeartbeatEvent {
+    /// Counts the beats since start, wrapping around.
+    pub sequence: u32,
+}
+
+pub struct Heartbeat<D, C>
+where
+    D: Device + EventHandler<HeartbeatEvent> + 'static,
+    C: Clock,
+{
+    interval: Milliseconds,
+    sequence: u32,
+    bus: Option<Address<EventBus<D>>>,
+    clock: Option<C>,
+    address: Option<Address<Self>>,
+}
+
+impl<D, C> Heartbeat<D, C>
+where
+    D: Device + EventHandler<HeartbeatEvent> + 'static,
+    C: Clock,
+{
+    /// Create a heartbeat beating every `interval`, starting with sequence number 0.
+    pub fn new<DUR: Into<Milliseconds>>(interval: DUR) -> Self {
+        Self {
+            interval: interval.into(),
+            sequence: 0,
+            bus: None,
+            clock: None,
+            address: None,
+        }
+    }
+
+    /// Time the heartbeat by `clock` rather than by a bound timer.
+    pub fn with_clock(mut self, clock: C) -> Self {
+        self.clock.replace(clock);
+        self
+    }
+
+    /// The event for the next beat.
+    fn beat(&mut self) -> HeartbeatEvent {
+        let event = HeartbeatEvent {
+            sequence: self.sequence,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+        event
+    }
+
+    fn schedule_beat(&self) {
+        if let (Some(clock), Some(address)) = (self.clock, self.address) {
+            clock.schedule(self.interval, Beat, address);
+        }
+    }
+}
+
+impl<D, C> Actor for Heartbeat<D, C>
+where
+    D: Device + EventHandler<HeartbeatEvent> + 'static,
+    C: Clock,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_start(self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.schedule_beat();
+        Completion::immediate(self)
+    }
+}
+
+impl<D, C> Bind<EventBus<D>> for Heartbeat<D, C>
+where
+    D: Device + EventHandler<HeartbeatEvent> + 'static,
+    C: Clock,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, T> Bind<TimerActor<T>> for Heartbeat<D, Address<TimerActor<T>>>
+where
+    D: Device + EventHandler<HeartbeatEvent> + 'static,
+    T: HalTimer + 'static,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.clock.replace(address);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Beat;
+
+impl<D, C> NotifyHandler<Beat> for Heartbeat<D, C>
+where
+    D: Device + EventHandler<HeartbeatEvent> + 'static,
+    C: Clock,
+{
+    fn on_notify(mut self, _: Beat) -> Completion<Self> {
+        let event = self.beat();
+        if let Some(bus) = self.bus {
+            bus.publish(event);
+        }
+        self.schedule_beat();
+        Completion::immediate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::timer::MockClock;
+    use std::boxed::Box;
+
+    struct MockDevice;
+
+    impl Device for MockDevice {
+        fn mount(&'static self, _: Address<EventBus<Self>>, _: &mut Supervisor) {}
+    }
+
+    impl EventHandler<HeartbeatEvent> for MockDevice {}
+
+    type TestHeartbeat = Heartbeat<MockDevice, &'static MockClock<Beat>>;
+
+    #[test]
+    fn test_beats() {
+        let clock: &'static MockClock<Beat> = Box::leak(Box::new(MockClock::new()));
+        let context = Box::leak(Box::new(ActorContext::new(TestHeartbeat::new(
+            Milliseconds(0u32),
+        ))));
+        let mut heartbeat = TestHeartbeat::new(Milliseconds(1000u32)).with_clock(clock);
+        heartbeat.on_mount(Address::new(context));
+        let mut heartbeat = match heartbeat.on_start() {
+            Completion::Immediate(heartbeat) => heartbeat,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+
+        for sequence in 0..5 {
+            assert!(clock.advance(Milliseconds(999u32)).is_empty());
+            assert_eq!(clock.advance(Milliseconds(1u32)), [Beat]);
+            assert_eq!(heartbeat.sequence, sequence);
+            heartbeat = match heartbeat.on_notify(Beat) {
+                Completion::Immediate(heartbeat) => heartbeat,
+                Completion::Defer(_) => panic!("deferred"),
+            };
+        }
+        assert_eq!(heartbeat.sequence, 5);
+        assert_eq!(clock.pending(), 1);
+
+        heartbeat.sequence = u32::MAX;
+        assert_eq!(heartbeat.beat(), HeartbeatEvent { sequence: u32::MAX });
+        assert_eq!(heartbeat.beat(), HeartbeatEvent { sequence: 0 });
+    }
+}
@@ -0,0 +1,234 @@
+//! Independent watchdog, fed only while the application reports it is alive.
+//!
+//! The `Watchdog` actor starts the hardware watchdog with its timeout when initialized,
+//! then wakes every feed period. It feeds the hardware only if it has been kicked since
+//! it last woke, so the main loop (or a supervising actor) must `kick()` it more often
+//! than the feed period. Should the application wedge and stop kicking, the watchdog
+//! goes unfed and resets the MCU once its timeout expires. The feed period should be
+//! well under the timeout so that a single late kick does not cause a reset.
+//!
+//! Rather than kicking it directly, a device may forward the events of a `Heartbeat`
+//! to the watchdog, each counting as a kick.
+
+pub mod health;
+pub mod heartbeat;
+
+pub use health::{HealthMonitor, HealthReport, Monitored};
+pub use heartbeat::{Heartbeat, HeartbeatEvent};
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::TimerActor;
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+use embedded_hal::watchdog::{Watchdog as HalWatchdog, WatchdogEnable};
+
+pub struct Watchdog<T, W>
+where
+    T: HalTimer + 'static,
+    W: HalWatchdog + WatchdogEnable + 'static,
+{
+    watchdog: W,
+    timeout: Option<W::Time>,
+    period: Milliseconds,
+    alive: bool,
+    timer: Option<Address<TimerActor<T>>>,
+    address: Option<Address<Self>>,
+}
+
+impl<T, W> Watchdog<T, W>
+where
+    T: HalTimer,
+    W: HalWatchdog + WatchdogEnable,
+{
+    /// Create a watchdog resetting the MCU after `timeout` without being fed, and checking
+    /// for liveness every `period`.
+    pub fn new(watchdog: W, timeout: W::Time, period: Milliseconds) -> Self {
+        Self {
+            watchdog,
+            timeout: Some(timeout),
+            period,
+            alive: true,
+            timer: None,
+            address: None,
+        }
+    }
+
+    /// Feed the hardware watchdog if kicked since the last check, returning whether it was fed.
+    fn check(&mut self) -> bool {
+        let alive = self.alive;
+        if alive {
+            self.watchdog.feed();
+        } else {
+            warn!("[watchdog] not kicked, withholding feed");
+        }
+        self.alive = false;
+        alive
+    }
+
+    fn schedule_check(&self) {
+        if let (Some(timer), Some(address)) = (self.timer, self.address) {
+            timer.schedule(self.period, Check, address);
+        }
+    }
+}
+
+impl<T, W> Actor for Watchdog<T, W>
+where
+    T: HalTimer,
+    W: HalWatchdog + WatchdogEnable,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+
+    fn on_initialize(mut self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        if let Some(timeout) = self.timeout.take() {
+            self.watchdog.start(timeout);
+        }
+        Completion::immediate(self)
+    }
+
+    fn on_start(self) -> Completion<Self>
+    where
+        Self: 'static,
+    {
+        self.schedule_check();
+        Completion::immediate(self)
+    }
+}
+
+impl<T, W> Bind<TimerActor<T>> for Watchdog<T, W>
+where
+    T: HalTimer,
+    W: HalWatchdog + WatchdogEnable,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.timer.replace(address);
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Check;
+
+pub struct Kick;
+
+impl<T, W> NotifyHandler<Check> for Watchdog<T, W>
+where
+    T: HalTimer,
+    W: HalWatchdog + WatchdogEnable,
+{
+    fn on_notify(mut self, _: Check) -> Completion<Self> {
+        self.check();
+        self.schedule_check();
+        Completion::immediate(self)
+    }
+}
+
+impl<T, W> NotifyHandler<Kick> for Watchdog<T, W>
+where
+    T: HalTimer,
+    W: HalWatchdog + WatchdogEnable,
+{
+    fn on_notify(mut self, _: Kick) -> Completion<Self> {
+        self.alive = true;
+        Completion::immediate(self)
+    }
+}
+
+impl<T, W> NotifyHandler<HeartbeatEvent> for Watchdog<T, W>
+where
+    T: HalTimer,
+    W: HalWatchdog + WatchdogEnable,
+{
+    fn on_notify(mut self, _: HeartbeatEvent) -> Completion<Self> {
+        self.alive = true;
+        Completion::immediate(self)
+    }
+}
+
+impl<T, W> Address<Watchdog<T, W>>
+where
+    T: HalTimer,
+    W: HalWatchdog + WatchdogEnable,
+{
+    /// Report that the application is alive, allowing the next check to feed the watchdog.
+    pub fn kick(&self) {
+        self.notify(Kick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockWatchdog {
+        fed: usize,
+    }
+
+    impl HalWatchdog for MockWatchdog {
+        fn feed(&mut self) {
+            self.fed += 1;
+        }
+    }
+
+    impl WatchdogEnable for MockWatchdog {
+        type Time = u32;
+
+        fn start<P: Into<u32>>(&mut self, _: P) {}
+    }
+
+    struct MockTimer;
+
+    impl HalTimer for MockTimer {
+        fn start(&mut self, _: Milliseconds) {}
+
+        fn clear_update_interrupt_flag(&mut self) {}
+    }
+
+    #[test]
+    fn test_stops_feeding_when_stale() {
+        let mut watchdog: Watchdog<MockTimer, MockWatchdog> =
+            Watchdog::new(MockWatchdog::default(), 2000, Milliseconds(500u32));
+
+        // fed on the first check, then only after each kick
+        assert!(watchdog.check());
+        watchdog.alive = true;
+        assert!(watchdog.check());
+        watchdog.alive = true;
+        assert!(watchdog.check());
+        assert_eq!(watchdog.watchdog.fed, 3);
+
+        // no more kicks: never fed again
+        assert!(!watchdog.check());
+        assert!(!watchdog.check());
+        assert_eq!(watchdog.watchdog.fed, 3);
+
+        // kicking again resumes feeding
+        watchdog.alive = true;
+        assert!(watchdog.check());
+        assert_eq!(watchdog.watchdog.fed, 4);
+    }
+
+    #[test]
+    fn test_fed_by_heartbeat() {
+        let mut watchdog: Watchdog<MockTimer, MockWatchdog> =
+            Watchdog::new(MockWatchdog::default(), 2000, Milliseconds(500u32));
+        assert!(watchdog.check());
+        assert!(!watchdog.check());
+
+        let mut watchdog = match watchdog.on_notify(HeartbeatEvent { sequence: 0 }) {
+            Completion::Immediate(watchdog) => watchdog,
+            Completion::Defer(_) => panic!("deferred"),
+        };
+        assert!(watchdog.check());
+        assert_eq!(watchdog.watchdog.fed, 2);
+    }
+}
@@ -0,0 +1,411 @@
+//! ESP8266 WiFi modem driven by AT commands.
+//!
+//! The modem is used in single-connection mode (`AT+CIPMUX=0`, its default), so at most one
+//! `Socket` is open at a time. Data received from the peer arrives as `+IPD,<len>:<data>` frames,
+//! which may interleave with command responses; their payload is buffered until read.
+
+use crate::driver::uart::serial::SerialIo;
+use core::fmt::Write;
+use heapless::{consts::*, spsc::Queue, String, Vec};
+
+/// Maximum payload of a single `AT+CIPSEND`.
+const MAX_SEND: usize = 2048;
+
+type Command = String<U128>;
+type Line = String<U128>;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Error<E> {
+    Serial(E),
+    /// The modem failed to join the access point.
+    JoinFailed,
+    /// The modem failed to open the connection.
+    ConnectFailed,
+    /// The modem failed to transmit the data.
+    SendFailed,
+    /// The modem answered a command with `ERROR`.
+    Command,
+    /// The command did not fit in the command buffer.
+    CommandTooLong,
+    /// The modem sent something that could not be parsed.
+    Protocol,
+}
+
+enum Event {
+    Line(Line),
+    Prompt,
+}
+
+pub struct Esp8266<T>
+where
+    T: SerialIo,
+{
+    serial: T,
+    inbound: Queue<u8, U256>,
+    connected: bool,
+}
+
+impl<T> Esp8266<T>
+where
+    T: SerialIo,
+{
+    pub fn new(serial: T) -> Self {
+        Self {
+            serial,
+            inbound: Queue::new(),
+            connected: false,
+        }
+    }
+
+    /// Release the underlying serial line.
+    pub fn free(self) -> T {
+        self.serial
+    }
+
+    /// Join the access point `ssid` using `password`.
+    pub async fn join(&mut self, ssid: &str, password: &str) -> Result<(), Error<T::Error>> {
+        let mut command = Command::new();
+        write_join(&mut command, ssid, password).map_err(|_| Error::CommandTooLong)?;
+        self.serial
+            .write_all(command.as_bytes())
+            .await
+            .map_err(Error::Serial)?;
+        self.wait_ok().await.map_err(|e| match e {
+            Error::Command => Error::JoinFailed,
+            e => e,
+        })
+    }
+
+    /// Open a TCP connection to `host` on `port`.
+    pub async fn connect(
+        &mut self,
+        host: &str,
+        port: u16,
+    ) -> Result<Socket<'_, T>, Error<T::Error>> {
+        let mut command = Command::new();
+        write!(command, "AT+CIPSTART=\"TCP\",\"{}\",{}\r\n", host, port)
+            .map_err(|_| Error::CommandTooLong)?;
+        self.serial
+            .write_all(command.as_bytes())
+            .await
+            .map_err(Error::Serial)?;
+        self.wait_ok().await.map_err(|e| match e {
+            Error::Command => Error::ConnectFailed,
+            e => e,
+        })?;
+        self.connected = true;
+        Ok(Socket { modem: self })
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> Result<(), Error<T::Error>> {
+        let mut command = Command::new();
+        write!(command, "AT+CIPSEND={}\r\n", bytes.len()).map_err(|_| Error::CommandTooLong)?;
+        self.serial
+            .write_all(command.as_bytes())
+            .await
+            .map_err(Error::Serial)?;
+
+        loop {
+            match self.next_event().await? {
+                Event::Prompt => break,
+                Event::Line(line) if is_failure(&line) => return Err(Error::SendFailed),
+                Event::Line(_) => {}
+            }
+        }
+
+        self.serial.write_all(bytes).await.map_err(Error::Serial)?;
+
+        loop {
+            if let Event::Line(line) = self.next_event().await? {
+                match line.as_str() {
+                    "SEND OK" => return Ok(()),
+                    "SEND FAIL" | "ERROR" => return Err(Error::SendFailed),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    async fn close(&mut self) -> Result<(), Error<T::Error>> {
+        self.serial
+            .write_all(b"AT+CIPCLOSE\r\n")
+            .await
+            .map_err(Error::Serial)?;
+        let result = self.wait_ok().await;
+        self.connected = false;
+        result
+    }
+
+    async fn wait_ok(&mut self) -> Result<(), Error<T::Error>> {
+        loop {
+            if let Event::Line(line) = self.next_event().await? {
+                if line == "OK" {
+                    return Ok(());
+                }
+                if is_failure(&line) {
+                    return Err(Error::Command);
+                }
+            }
+        }
+    }
+
+    /// Read the next response line or `>` prompt, buffering any `+IPD` payload on the way.
+    ///
+    /// A line too long for the buffer is discarded whole, so that no part of it is taken
+    /// for a `+IPD` header.
+    async fn next_event(&mut self) -> Result<Event, Error<T::Error>> {
+        let mut line: Vec<u8, U128> = Vec::new();
+        let mut overflowed = false;
+        loop {
+            let byte = self.serial.read_byte().await.map_err(Error::Serial)?;
+            if byte == b'\n' && overflowed {
+                warn!("[esp8266] response line too long, discarded");
+                overflowed = false;
+                continue;
+            }
+            if overflowed {
+                continue;
+            }
+            if byte == b'\n' {
+                let text = core::str::from_utf8(&line).map_err(|_| Error::Protocol)?;
+                let text = text.trim();
+                if text.is_empty() {
+                    line = Vec::new();
+                    continue;
+                }
+                if text == "CLOSED" {
+                    self.connected = false;
+                }
+                return Ok(Event::Line(Line::from(text)));
+            }
+
+            if line.push(byte).is_err() {
+                overflowed = true;
+                line = Vec::new();
+                continue;
+            }
+
+            if line == b">" {
+                return Ok(Event::Prompt);
+            }
+
+            if byte == b':' && line.starts_with(b"+IPD,") {
+                let len = core::str::from_utf8(&line[5..line.len() - 1])
+                    .ok()
+                    .and_then(|len| len.parse::<usize>().ok())
+                    .ok_or(Error::Protocol)?;
+                for _ in 0..len {
+                    let byte = self.serial.read_byte().await.map_err(Error::Serial)?;
+                    if self.inbound.enqueue(byte).is_err() {
+                        warn!("[esp8266] inbound buffer full, dropping byte");
+                    }
+                }
+                line = Vec::new();
+            }
+        }
+    }
+}
+
+/// Write the `AT+CWJAP` command joining `ssid` using `password`.
+fn write_join<W: Write>(command: &mut W, ssid: &str, password: &str) -> core::fmt::Result {
+    command.write_str("AT+CWJAP=")?;
+    write_quoted(command, ssid)?;
+    command.write_char(',')?;
+    write_quoted(command, password)?;
+    command.write_str("\r\n")
+}
+
+/// Write `value` as a quoted parameter, escaping the characters the modem takes as
+/// delimiters with a backslash.
+fn write_quoted<W: Write>(command: &mut W, value: &str) -> core::fmt::Result {
+    command.write_char('"')?;
+    for c in value.chars() {
+        if matches!(c, '"' | ',' | '\\') {
+            command.write_char('\\')?;
+        }
+        command.write_char(c)?;
+    }
+    command.write_char('"')
+}
+
+fn is_failure(line: &str) -> bool {
+    matches!(line, "ERROR" | "FAIL" | "ALREADY CONNECTED")
+}
+
+/// An open TCP connection through the modem.
+pub struct Socket<'a, T>
+where
+    T: SerialIo,
+{
+    modem: &'a mut Esp8266<T>,
+}
+
+impl<'a, T> Socket<'a, T>
+where
+    T: SerialIo,
+{
+    /// Transmit all of `bytes` to the peer.
+    pub async fn write(&mut self, bytes: &[u8]) -> Result<usize, Error<T::Error>> {
+        for chunk in bytes.chunks(MAX_SEND) {
+            self.modem.send(chunk).await?;
+        }
+        Ok(bytes.len())
+    }
+
+    /// Receive data from the peer into `buf`, returning the number of bytes read.
+    ///
+    /// Returns `0` once the peer has closed the connection and all data has been read.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error<T::Error>> {
+        loop {
+            let mut len = 0;
+            while len < buf.len() {
+                match self.modem.inbound.dequeue() {
+                    Some(byte) => {
+                        buf[len] = byte;
+                        len += 1;
+                    }
+                    None => break,
+                }
+            }
+            if len > 0 || !self.modem.connected || buf.is_empty() {
+                return Ok(len);
+            }
+            self.modem.next_event().await?;
+        }
+    }
+
+    /// Close the connection.
+    pub async fn close(self) -> Result<(), Error<T::Error>> {
+        self.modem.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::testing::block_on;
+
+    struct MockSerial {
+        rx: &'static [u8],
+        tx: Vec<u8, U256>,
+    }
+
+    impl MockSerial {
+        fn new(rx: &'static [u8]) -> Self {
+            Self { rx, tx: Vec::new() }
+        }
+    }
+
+    impl SerialIo for MockSerial {
+        type Error = ();
+
+        async fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+            self.tx.extend_from_slice(bytes)
+        }
+
+        async fn read_byte(&mut self) -> Result<u8, Self::Error> {
+            let (byte, rest) = self.rx.split_first().ok_or(())?;
+            self.rx = rest;
+            Ok(*byte)
+        }
+    }
+
+    #[test]
+    fn test_join() {
+        let mut modem = Esp8266::new(MockSerial::new(
+            b"WIFI CONNECTED\r\nWIFI GOT IP\r\n\r\nOK\r\n",
+        ));
+        assert_eq!(block_on(modem.join("drogue", "secret")), Ok(()));
+        assert_eq!(
+            &modem.free().tx[..],
+            &b"AT+CWJAP=\"drogue\",\"secret\"\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn test_join_escaped() {
+        let mut modem = Esp8266::new(MockSerial::new(b"OK\r\n"));
+        assert_eq!(block_on(modem.join("a \"b\", c", "x\\y")), Ok(()));
+        assert_eq!(
+            &modem.free().tx[..],
+            &b"AT+CWJAP=\"a \\\"b\\\"\\, c\",\"x\\\\y\"\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn test_join_failed() {
+        let mut modem = Esp8266::new(MockSerial::new(b"+CWJAP:3\r\n\r\nFAIL\r\n"));
+        assert_eq!(
+            block_on(modem.join("drogue", "wrong")),
+            Err(Error::JoinFailed)
+        );
+    }
+
+    #[test]
+    fn test_connect_write_read() {
+        let mut modem = Esp8266::new(MockSerial::new(
+            b"CONNECT\r\n\r\nOK\r\n\
+              OK\r\n> \
+              \r\nRecv 5 bytes\r\n\r\nSEND OK\r\n\
+              \r\n+IPD,5:world\r\nCLOSED\r\n",
+        ));
+        block_on(async {
+            let mut socket = modem.connect("example.com", 80).await.unwrap();
+            assert_eq!(socket.write(b"hello").await, Ok(5));
+
+            let mut buf = [0; 16];
+            assert_eq!(socket.read(&mut buf).await, Ok(5));
+            assert_eq!(&buf[..5], b"world");
+            assert_eq!(socket.read(&mut buf).await, Ok(0));
+        });
+        assert_eq!(
+            &modem.free().tx[..],
+            &b"AT+CIPSTART=\"TCP\",\"example.com\",80\r\nAT+CIPSEND=5\r\nhello"[..]
+        );
+    }
+
+    #[test]
+    fn test_connect_failed() {
+        let mut modem = Esp8266::new(MockSerial::new(b"ALREADY CONNECTED\r\n\r\nERROR\r\n"));
+        assert!(matches!(
+            block_on(modem.connect("example.com", 80)),
+            Err(Error::ConnectFailed)
+        ));
+    }
+
+    #[test]
+    fn test_ipd_during_command() {
+        let mut modem = Esp8266::new(MockSerial::new(
+            b"CONNECT\r\n\r\nOK\r\n\
+              +IPD,3:abcOK\r\n> \
+              SEND OK\r\n",
+        ));
+        block_on(async {
+            let mut socket = modem.connect("example.com", 80).await.unwrap();
+            assert_eq!(socket.write(b"x").await, Ok(1));
+            let mut buf = [0; 2];
+            assert_eq!(socket.read(&mut buf).await, Ok(2));
+            assert_eq!(&buf, b"ab");
+            assert_eq!(socket.read(&mut buf).await, Ok(1));
+            assert_eq!(buf[0], b'c');
+        });
+    }
+
+    #[test]
+    fn test_line_overflow() {
+        // a line overflowing the buffer, ending in what reads as a `+IPD` header
+        let mut rx = std::vec::Vec::new();
+        rx.extend_from_slice(b"+IPD,");
+        rx.extend(core::iter::repeat_n(b'1', 130));
+        rx.extend_from_slice(b":\r\n+IPD,2:hi\r\nOK\r\n");
+        let mut modem = Esp8266::new(MockSerial::new(std::vec::Vec::leak(rx)));
+
+        // the overflowing line is discarded whole, and the next frame read as usual
+        assert_eq!(block_on(modem.wait_ok()), Ok(()));
+        assert_eq!(modem.inbound.dequeue(), Some(b'h'));
+        assert_eq!(modem.inbound.dequeue(), Some(b'i'));
+        assert_eq!(modem.inbound.dequeue(), None);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod esp8266;
+
+pub use esp8266::{Esp8266, Socket};
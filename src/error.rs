@@ -0,0 +1,34 @@
+//! Errors common to drivers.
+//!
+//! Operations which may fail for reasons an application can act upon report a
+//! `DeviceError`, whatever the peripheral or HAL underneath, so that failures can be
+//! matched on without knowing the error types of each HAL.
+
+use crate::driver::i2c::I2cTimeout;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeviceError {
+    /// The bus failed the transfer, such as on a NACK or a lost arbitration.
+    BusError,
+    /// The operation took longer than allowed, and was abandoned.
+    Timeout,
+    /// A fixed-size resource, such as a message queue or the timer's deadlines, is full.
+    ResourceExhausted,
+    /// The resource is taken by another operation, such as a bus by a transfer
+    /// started from an interrupt handler.
+    Busy,
+    /// The actor has not been bound to a peripheral it needs.
+    NotBound,
+    /// The async pool has no room for the allocation, or has not been initialized.
+    AllocFailed,
+}
+
+impl<E> From<I2cTimeout<E>> for DeviceError {
+    fn from(error: I2cTimeout<E>) -> Self {
+        match error {
+            I2cTimeout::Bus(_) => DeviceError::BusError,
+            I2cTimeout::TimedOut => DeviceError::Timeout,
+        }
+    }
+}
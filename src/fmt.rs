@@ -0,0 +1,50 @@
+//! Internal logging macros.
+//!
+//! Logging goes through `defmt` when the `defmt` feature is enabled, otherwise through `log`
+//! when the (default) `log` feature is enabled, and is compiled out when neither is. Messages
+//! must therefore stick to the format syntax common to both, and their arguments must
+//! implement `defmt::Format` as well as the `core::fmt` trait they are formatted with.
+
+#![macro_use]
+#![allow(unused_macros)]
+
+macro_rules! log_impl {
+    ($level:ident, $s:literal $(, $x:expr)* $(,)?) => {{
+        #[cfg(feature = "defmt")]
+        ::defmt::$level!($s $(, $x)*);
+        #[cfg(all(feature = "log", not(feature = "defmt")))]
+        ::log::$level!($s $(, $x)*);
+        #[cfg(not(any(feature = "log", feature = "defmt")))]
+        let _ = ($(&$x),*);
+    }};
+}
+
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        log_impl!(trace, $($arg)*)
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        log_impl!(debug, $($arg)*)
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        log_impl!(info, $($arg)*)
+    };
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        log_impl!(warn, $($arg)*)
+    };
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {
+        log_impl!(error, $($arg)*)
+    };
+}
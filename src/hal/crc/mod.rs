@@ -0,0 +1,8 @@
+/// A CRC unit, such as a hardware CRC peripheral.
+pub trait Crc {
+    /// The CRC-32 (as used by Ethernet and zlib) of `data`.
+    fn crc32(&mut self, data: &[u8]) -> u32;
+
+    /// The CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xFFFF) of `data`.
+    fn crc16_ccitt(&mut self, data: &[u8]) -> u16;
+}
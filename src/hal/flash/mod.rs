@@ -0,0 +1,22 @@
+/// NOR-style flash memory, addressed by absolute byte offset.
+///
+/// Erasing a sector sets every byte to `0xFF`; writing may only clear bits,
+/// so a location must be erased before it can be written again.
+pub trait Flash {
+    type Error;
+
+    /// Size of a single erasable sector, in bytes.
+    fn sector_size(&self) -> usize;
+
+    /// Minimum write granularity, in bytes. Writes must be aligned to, and a multiple of, this size.
+    fn write_size(&self) -> usize;
+
+    /// Read `buf.len()` bytes starting at `offset`.
+    fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Program `data` starting at `offset`.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Erase the sector with the given index.
+    fn erase(&mut self, sector: usize) -> Result<(), Self::Error>;
+}
@@ -2,6 +2,17 @@ use embedded_hal::digital::v2::OutputPin;
 
 pub mod exti_pin;
 
+/// How an output drives its pin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// Driven both high and low.
+    #[default]
+    PushPull,
+    /// Driven low only, and released otherwise, for the line to be pulled up or left
+    /// floating. Setting the pin high releases it.
+    OpenDrain,
+}
+
 pub trait ActiveOutput {
     fn set_active<P: OutputPin>(pin: &mut P) -> Result<(), P::Error>;
     fn set_inactive<P: OutputPin>(pin: &mut P) -> Result<(), P::Error>;
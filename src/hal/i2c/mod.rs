@@ -32,3 +32,26 @@ impl UpperHex for I2cAddress {
         UpperHex::fmt(&self.0, f)
     }
 }
+
+/// An I2C peripheral running each transaction in the background, so that one which
+/// stalls, such as while a device holds SCL low, can be abandoned and the bus recovered.
+pub trait I2cTransfer {
+    type Error;
+
+    /// Start writing `bytes` to the device at `address`, then, unless `read_len` is
+    /// zero, reading `read_len` bytes from it after a repeated start.
+    fn start_transfer(
+        &mut self,
+        address: I2cAddress,
+        bytes: &[u8],
+        read_len: usize,
+    ) -> Result<(), Self::Error>;
+
+    /// Complete the transaction under way, copying the bytes read into `buffer`, or
+    /// `WouldBlock` while it is still under way.
+    fn finish_transfer(&mut self, buffer: &mut [u8]) -> nb::Result<(), Self::Error>;
+
+    /// Abandon the transaction under way and clear the bus: clock SCL until the device
+    /// releases SDA, typically nine times, then issue a stop.
+    fn bus_clear(&mut self);
+}
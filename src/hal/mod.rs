@@ -1,7 +1,13 @@
 //! General HAL types and traits.
 
+pub mod crc;
+pub mod flash;
 pub mod gpio;
 pub mod i2c;
+pub mod power;
+pub mod reset;
+pub mod rtc;
+pub mod spi;
 pub mod timer;
 pub mod uart;
 
@@ -10,3 +16,11 @@ pub enum Active {
     High,
     Low,
 }
+
+/// Enum for denoting the edges of a signal to react to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Both,
+}
@@ -0,0 +1,37 @@
+use crate::domain::time::duration::Milliseconds;
+
+/// The low-power modes of a chip, from the shallowest to the deepest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SleepMode {
+    /// The core stops, with every peripheral and clock left running (as with WFI).
+    Sleep,
+    /// The core and its main clocks stop, and RAM is retained. Timers driven by those
+    /// clocks stop counting, and only low-power peripherals, such as the RTC and GPIO
+    /// interrupts, can wake the chip.
+    Stop,
+    /// Nearly everything is powered down, and RAM is lost, so waking is a reset.
+    Standby,
+}
+
+/// Entry into a chip's low-power modes.
+pub trait Power {
+    /// Enter `mode` until woken by an interrupt, and restore the clocks on return.
+    ///
+    /// Called with interrupts masked, so that an interrupt pending before entry still
+    /// wakes the chip, and is only handled after this returns. Entering
+    /// `SleepMode::Standby` does not return.
+    fn enter(&mut self, mode: SleepMode);
+}
+
+/// A low-power timer which keeps counting in `SleepMode::Stop`, such as an RTC wakeup
+/// timer, used to end and measure sleeps.
+pub trait Wakeup {
+    /// Raise the wakeup interrupt after `after`, and start measuring the time elapsed.
+    fn arm(&mut self, after: Milliseconds);
+
+    /// Start measuring the time elapsed, without raising the wakeup interrupt.
+    fn start(&mut self);
+
+    /// Stop, returning the time elapsed since `arm` or `start`.
+    fn stop(&mut self) -> Milliseconds;
+}
@@ -0,0 +1,148 @@
+//! The cause of the last reset.
+//!
+//! Chips record why they were reset in sticky status flags, which survive the reset and
+//! accumulate until cleared. The application should `capture()` them once at boot,
+//! before mounting its device, after which `reason()` may be queried from anywhere,
+//! including `mount`.
+
+#[cfg(feature = "nrf52833")]
+pub mod nrf;
+#[cfg(feature = "stm32l4xx")]
+pub mod stm32l4xx;
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResetReason {
+    PowerOn = 1,
+    /// The reset pin was asserted.
+    Pin,
+    Watchdog,
+    /// The application requested a reset, such as with `SCB::sys_reset()`.
+    Software,
+    /// The supply dropped below the brown-out threshold.
+    BrownOut,
+    /// The core locked up.
+    Lockup,
+    /// The chip woke from a low-power mode ending in reset, such as nRF System OFF.
+    Wake,
+    /// A cause this abstraction does not distinguish.
+    Other,
+}
+
+impl ResetReason {
+    /// Decode the status register value `status`, as the reason of the first of `causes`
+    /// whose flag mask is set in it, `PowerOn` when no flag is set, or `Other` when only
+    /// flags absent from `causes` are.
+    ///
+    /// More than one flag may be set, so `causes` is in order of precedence.
+    pub fn decode(status: u32, causes: &[(u32, ResetReason)]) -> Self {
+        if status == 0 {
+            return ResetReason::PowerOn;
+        }
+        causes
+            .iter()
+            .find(|(mask, _)| status & mask != 0)
+            .map(|(_, reason)| *reason)
+            .unwrap_or(ResetReason::Other)
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        [
+            ResetReason::PowerOn,
+            ResetReason::Pin,
+            ResetReason::Watchdog,
+            ResetReason::Software,
+            ResetReason::BrownOut,
+            ResetReason::Lockup,
+            ResetReason::Wake,
+            ResetReason::Other,
+        ]
+        .iter()
+        .copied()
+        .find(|reason| *reason as u8 == value)
+    }
+}
+
+/// A chip's reset status flags.
+pub trait ResetStatus {
+    /// The reason for the last reset, as of the flags accumulated since they were last
+    /// cleared.
+    fn reason(&self) -> ResetReason;
+
+    /// Clear the flags, so the next reset is reported on its own.
+    fn clear(&mut self);
+}
+
+/// The captured reason, or 0 if not yet captured.
+static REASON: AtomicU8 = AtomicU8::new(0);
+
+/// Read and clear `status`, returning the reason for the last reset and retaining it
+/// for `reason()`.
+pub fn capture<S: ResetStatus>(status: &mut S) -> ResetReason {
+    let reason = status.reason();
+    status.clear();
+    REASON.store(reason as u8, Ordering::Release);
+    reason
+}
+
+/// The reason for the last reset, or `None` if it has not been captured.
+pub fn reason() -> Option<ResetReason> {
+    ResetReason::from_u8(REASON.load(Ordering::Acquire))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAUSES: &[(u32, ResetReason)] = &[
+        (1 << 1, ResetReason::Watchdog),
+        (1 << 2, ResetReason::Software),
+        (1 << 3, ResetReason::Lockup),
+        (1 << 0, ResetReason::Pin),
+        (1 << 16, ResetReason::Wake),
+        (1 << 20, ResetReason::BrownOut),
+    ];
+
+    struct MockStatus {
+        register: u32,
+    }
+
+    impl ResetStatus for MockStatus {
+        fn reason(&self) -> ResetReason {
+            ResetReason::decode(self.register, CAUSES)
+        }
+
+        fn clear(&mut self) {
+            self.register = 0;
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        for (register, expected) in [
+            (0, ResetReason::PowerOn),
+            (1 << 0, ResetReason::Pin),
+            (1 << 1, ResetReason::Watchdog),
+            (1 << 2, ResetReason::Software),
+            (1 << 3, ResetReason::Lockup),
+            (1 << 16, ResetReason::Wake),
+            (1 << 20, ResetReason::BrownOut),
+            // the watchdog takes precedence over the pin reset it may trigger
+            (1 << 0 | 1 << 1, ResetReason::Watchdog),
+            // a flag with no cause listed
+            (1 << 31, ResetReason::Other),
+        ] {
+            assert_eq!(MockStatus { register }.reason(), expected);
+        }
+    }
+
+    #[test]
+    fn test_capture() {
+        assert_eq!(reason(), None);
+        let mut status = MockStatus { register: 1 << 2 };
+        assert_eq!(capture(&mut status), ResetReason::Software);
+        assert_eq!(status.register, 0);
+        assert_eq!(reason(), Some(ResetReason::Software));
+    }
+}
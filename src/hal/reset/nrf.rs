@@ -0,0 +1,45 @@
+//! Reset status for nRF series
+#[cfg(feature = "nrf52833")]
+use nrf52833_hal as hal;
+
+use super::ResetReason;
+use hal::pac::POWER;
+
+/// RESETREAS flags, in order of precedence.
+const CAUSES: &[(u32, ResetReason)] = &[
+    // DOG
+    (1 << 1, ResetReason::Watchdog),
+    // SREQ
+    (1 << 2, ResetReason::Software),
+    // LOCKUP
+    (1 << 3, ResetReason::Lockup),
+    // RESETPIN
+    (1 << 0, ResetReason::Pin),
+    // OFF, LPCOMP, NFC and VBUS: woken from System OFF
+    (1 << 16 | 1 << 17 | 1 << 19 | 1 << 20, ResetReason::Wake),
+];
+
+/// The RESETREAS register of the POWER peripheral.
+///
+/// A brown-out reset is a power-on reset on nRF, and so is reported as `PowerOn`.
+pub struct ResetStatus<'a> {
+    power: &'a POWER,
+}
+
+impl<'a> ResetStatus<'a> {
+    pub fn new(power: &'a POWER) -> Self {
+        Self { power }
+    }
+}
+
+impl<'a> crate::hal::reset::ResetStatus for ResetStatus<'a> {
+    fn reason(&self) -> ResetReason {
+        ResetReason::decode(self.power.resetreas.read().bits(), CAUSES)
+    }
+
+    fn clear(&mut self) {
+        // the flags are cleared by writing 1s
+        let flags = self.power.resetreas.read().bits();
+        self.power.resetreas.write(|w| unsafe { w.bits(flags) });
+    }
+}
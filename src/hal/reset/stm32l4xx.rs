@@ -0,0 +1,38 @@
+//! Reset status
+
+use super::ResetReason;
+use stm32l4xx_hal::pac::RCC;
+
+/// RCC_CSR flags, in order of precedence.
+const CAUSES: &[(u32, ResetReason)] = &[
+    // IWDGRSTF and WWDGRSTF
+    (1 << 29 | 1 << 30, ResetReason::Watchdog),
+    // SFTRSTF
+    (1 << 28, ResetReason::Software),
+    // BORRSTF
+    (1 << 27, ResetReason::BrownOut),
+    // PINRSTF, also set by every reset driving the NRST pin, and so last
+    (1 << 26, ResetReason::Pin),
+];
+
+/// RCC_CSR register bit clearing the reset flags.
+const RMVF: u32 = 1 << 23;
+
+/// The reset flags of the RCC_CSR register.
+///
+/// The brown-out reset also serves as the power-on reset of the STM32L4, so a power-on
+/// is reported as `BrownOut`. Resets on leaving low-power modes, and on loading option
+/// bytes or firewall violations, are reported as `Other`.
+pub struct ResetStatus;
+
+impl crate::hal::reset::ResetStatus for ResetStatus {
+    fn reason(&self) -> ResetReason {
+        let rcc = unsafe { &*RCC::ptr() };
+        ResetReason::decode(rcc.csr.read().bits() & 0xFF00_0000, CAUSES)
+    }
+
+    fn clear(&mut self) {
+        let rcc = unsafe { &*RCC::ptr() };
+        rcc.csr.modify(|r, w| unsafe { w.bits(r.bits() | RMVF) });
+    }
+}
@@ -0,0 +1,15 @@
+use crate::domain::datetime::DateTime;
+
+/// A real-time clock keeping calendar time, typically across resets within a backup domain.
+pub trait Rtc {
+    /// The current time, or `None` if the clock has never been set (or has lost power).
+    fn now(&mut self) -> Option<DateTime>;
+
+    /// Set the clock to `time`.
+    fn set(&mut self, time: DateTime);
+
+    /// Raise the alarm interrupt once the clock reaches `time`, or disable the alarm.
+    fn set_alarm(&mut self, time: Option<DateTime>);
+
+    fn clear_alarm_interrupt_flag(&mut self);
+}
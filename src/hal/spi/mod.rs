@@ -0,0 +1,26 @@
+/// A SPI master transferring buffers by DMA, completing with an interrupt.
+pub trait SpiDma {
+    /// Start a transfer clocking out `tx_buffer` while clocking into `rx_buffer`, either
+    /// of which may be empty. Implementations can return BufferTooLong if a buffer is too
+    /// big, or BufferNotInRAM if the DMA cannot read it.
+    ///
+    /// Both buffers must remain available until the transfer is finished or cancelled.
+    fn start_transfer(&self, tx_buffer: &[u8], rx_buffer: &mut [u8]) -> Result<(), Error>;
+
+    /// Complete a transfer.
+    fn finish_transfer(&self) -> Result<(), Error>;
+
+    /// Cancel a transfer.
+    fn cancel_transfer(&self);
+
+    /// Process interrupts for the peripheral, returning whether a transfer is done.
+    fn process_interrupts(&self) -> bool;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    InProgress,
+    BufferTooLong,
+    BufferNotInRAM,
+    Transfer,
+}
@@ -0,0 +1,87 @@
+//! A timer counting time only when told to, so that the logic built on timers can
+//! be tested on the host.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::hal::timer::Timer;
+
+/// Timer advanced by hand, raising its update interrupt flag once the duration it
+/// was started for has been counted.
+///
+/// Like the hardware timers, it fires once per `start`.
+#[derive(Default)]
+pub struct MockTimer {
+    armed: Option<Milliseconds>,
+    elapsed: Milliseconds,
+    interrupt: bool,
+}
+
+impl MockTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The duration the timer was last started for, unless it has fired since.
+    pub fn armed(&self) -> Option<Milliseconds> {
+        self.armed
+    }
+
+    /// The time left before the timer fires, if it is armed.
+    pub fn remaining(&self) -> Option<Milliseconds> {
+        self.armed.map(|armed| armed - self.elapsed.min(armed))
+    }
+
+    /// Whether the update interrupt flag is raised.
+    pub fn interrupt_pending(&self) -> bool {
+        self.interrupt
+    }
+
+    /// Count `by`, returning whether the timer fired.
+    pub fn advance(&mut self, by: Milliseconds) -> bool {
+        self.elapsed = self.elapsed + by;
+        match self.armed {
+            Some(armed) if self.elapsed >= armed => {
+                self.armed.take();
+                self.interrupt = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Timer for MockTimer {
+    fn start(&mut self, duration: Milliseconds) {
+        self.armed.replace(duration);
+        self.elapsed = Milliseconds(0u32);
+    }
+
+    fn clear_update_interrupt_flag(&mut self) {
+        self.interrupt = false;
+    }
+
+    fn elapsed(&self) -> Option<Milliseconds> {
+        Some(self.elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fires_once() {
+        let mut timer = MockTimer::new();
+        assert!(!timer.advance(Milliseconds(100u32)));
+
+        timer.start(Milliseconds(50u32));
+        assert!(!timer.advance(Milliseconds(49u32)));
+        assert_eq!(timer.remaining(), Some(Milliseconds(1u32)));
+        assert!(timer.advance(Milliseconds(1u32)));
+        assert!(timer.interrupt_pending());
+        assert_eq!(timer.armed(), None);
+        assert!(!timer.advance(Milliseconds(50u32)));
+
+        timer.clear_update_interrupt_flag();
+        assert!(!timer.interrupt_pending());
+    }
+}
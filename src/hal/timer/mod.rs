@@ -7,10 +7,28 @@
 pub mod nrf;
 #[cfg(feature = "stm32l4xx")]
 pub mod stm32l4xx;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod systick;
 
 use crate::domain::time::duration::Milliseconds;
 
 pub trait Timer {
     fn start(&mut self, duration: Milliseconds);
     fn clear_update_interrupt_flag(&mut self);
+
+    /// Account for an interrupt of the timer, returning whether it is for the duration
+    /// last started for having been counted.
+    ///
+    /// Hardware timers interrupt only then, while a timer counting a periodic tick,
+    /// such as the `SoftwareTimer`, interrupts on every tick.
+    fn fired(&mut self) -> bool {
+        true
+    }
+
+    /// The time counted since the last `start`, if the timer can tell, including any
+    /// counted past the duration it was started for.
+    fn elapsed(&self) -> Option<Milliseconds> {
+        None
+    }
 }
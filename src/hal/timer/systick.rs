@@ -0,0 +1,164 @@
+//! A timer counting SysTick ticks, for boards with no hardware timer to spare.
+//!
+//! SysTick interrupts at a fixed tick, its reload value counting off core clock cycles,
+//! and the `SoftwareTimer` counts the ticks off the duration it was started for,
+//! reporting it fired on the tick reaching it. Driving the `Timer` package with it runs
+//! delays, schedules and everything built on them without a dedicated TIM peripheral:
+//!
+//! ```ignore
+//! let timer = SoftwareTimer::new(core.SYST, Hertz(64_000_000), Milliseconds(1u32));
+//! let timer = Timer::new(timer, SysTickException);
+//! ```
+//!
+//! SysTick is dispatched by the device's default handler, so the application must not
+//! define a `SysTick` exception handler of its own.
+//!
+//! # Resolution
+//!
+//! Time is counted in whole ticks. A duration is rounded up to the next tick, and SysTick
+//! restarts its count on `start`, so a deadline is never reached early but may be
+//! reached up to a tick minus a millisecond late. A shorter tick is more precise, at the
+//! cost of an interrupt per tick, taken even with nothing pending. The reload value is
+//! 24 bits wide, limiting the tick to 2^24 core clock cycles, such as 262ms at 64MHz.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::domain::time::rate::Hertz;
+use crate::hal::timer::Timer;
+use crate::interrupt::SYSTICK;
+use cortex_m::interrupt::Nr;
+use cortex_m::peripheral::syst::SystClkSource;
+use cortex_m::peripheral::SYST;
+
+/// The SysTick exception, to mount the actor driving a `SoftwareTimer` on.
+pub struct SysTickException;
+
+unsafe impl Nr for SysTickException {
+    fn nr(&self) -> u8 {
+        SYSTICK
+    }
+}
+
+/// The SysTick counter, as driven by a `SoftwareTimer`.
+pub trait SysTick {
+    /// Count the current tick from its start again, making the next tick a whole one.
+    fn restart(&mut self);
+}
+
+impl SysTick for SYST {
+    fn restart(&mut self) {
+        self.clear_current();
+    }
+}
+
+/// Timer counting the ticks of SysTick, firing on the tick reaching the duration it
+/// was started for.
+pub struct SoftwareTimer<S: SysTick = SYST> {
+    systick: S,
+    tick: Milliseconds,
+    ticks: u32,
+    armed: Option<u32>,
+}
+
+impl SoftwareTimer<SYST> {
+    /// Configure SysTick to interrupt every `tick`, counting cycles of the `core_clock`.
+    ///
+    /// # Panics
+    ///
+    /// If the tick is zero, or more cycles of the core clock than SysTick can count.
+    pub fn new(mut syst: SYST, core_clock: Hertz, tick: Milliseconds) -> Self {
+        let cycles = (core_clock.0 / 1_000).saturating_mul(tick.0);
+        assert!(
+            cycles > 0 && cycles <= 1 << 24,
+            "tick out of range for SysTick"
+        );
+        syst.disable_counter();
+        syst.set_clock_source(SystClkSource::Core);
+        syst.set_reload(cycles - 1);
+        syst.clear_current();
+        syst.enable_interrupt();
+        syst.enable_counter();
+        Self::with_tick(syst, tick)
+    }
+}
+
+impl<S: SysTick> SoftwareTimer<S> {
+    /// Count the ticks of a SysTick already configured to interrupt every `tick`.
+    pub fn with_tick(systick: S, tick: Milliseconds) -> Self {
+        Self {
+            systick,
+            tick,
+            ticks: 0,
+            armed: None,
+        }
+    }
+}
+
+impl<S: SysTick> Timer for SoftwareTimer<S> {
+    fn start(&mut self, duration: Milliseconds) {
+        let ticks = duration.0.div_ceil(self.tick.0);
+        self.systick.restart();
+        self.armed.replace(ticks.max(1));
+        self.ticks = 0;
+    }
+
+    fn clear_update_interrupt_flag(&mut self) {
+        // the exception is no longer pending once taken
+    }
+
+    fn fired(&mut self) -> bool {
+        self.ticks = self.ticks.saturating_add(1);
+        match self.armed {
+            Some(armed) if self.ticks >= armed => {
+                self.armed.take();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn elapsed(&self) -> Option<Milliseconds> {
+        Some(Milliseconds(self.ticks.saturating_mul(self.tick.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockSysTick {
+        restarts: u32,
+    }
+
+    impl SysTick for MockSysTick {
+        fn restart(&mut self) {
+            self.restarts += 1;
+        }
+    }
+
+    /// Tick until the timer fires, returning the ticks taken.
+    fn ticks_to_fire(timer: &mut SoftwareTimer<MockSysTick>) -> u32 {
+        (1..=100).find(|_| timer.fired()).unwrap()
+    }
+
+    #[test]
+    fn test_fires() {
+        let mut timer = SoftwareTimer::with_tick(MockSysTick::default(), Milliseconds(10u32));
+        assert!(!timer.fired());
+
+        // rounded up to whole ticks, never early
+        timer.start(Milliseconds(25u32));
+        assert_eq!(timer.systick.restarts, 1);
+        assert_eq!(ticks_to_fire(&mut timer), 3);
+        assert_eq!(timer.elapsed(), Some(Milliseconds(30u32)));
+
+        // fires once per start, counting on past it
+        assert!(!timer.fired());
+        assert_eq!(timer.elapsed(), Some(Milliseconds(40u32)));
+
+        timer.start(Milliseconds(20u32));
+        assert_eq!(ticks_to_fire(&mut timer), 2);
+        timer.start(Milliseconds(0u32));
+        assert_eq!(ticks_to_fire(&mut timer), 1);
+    }
+}
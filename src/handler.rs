@@ -1,9 +1,13 @@
 //! Traits and types for notify, request and event handlers.
 
-use core::future::Future;
+use core::future::{ready, Future};
+use core::pin::Pin;
+use core::task::{Context, Poll};
 
+use crate::address::Address;
 use crate::alloc::{alloc, Box};
 use crate::prelude::Actor;
+use crate::synchronization::channel::Sender;
 
 /// Return value from a `RequestHandler` to allow for synchronous or
 /// asynchronous handling of the request.
@@ -47,6 +51,30 @@ impl<T, A: Actor + 'static> Response<A, T> {
     {
         Self::ImmediateFuture(actor, Box::new(alloc(f).unwrap()))
     }
+
+    /// Notify the actor at `address` of `event` once the response is delivered.
+    ///
+    /// An immediate response is delivered as the requester receives it, and a deferred
+    /// one as the future providing it completes.
+    pub fn then_notify<B, E>(self, address: Address<B>, event: E) -> Self
+    where
+        B: Actor + NotifyHandler<E> + 'static,
+        E: 'static,
+    {
+        self.then(Notification { address, event })
+    }
+
+    pub(crate) fn then<U: FollowUp + 'static>(self, follow_up: U) -> Self {
+        match self {
+            Self::Immediate(actor, val) => {
+                Self::immediate_future(actor, Then::new(ready(val), follow_up))
+            }
+            Self::ImmediateFuture(actor, f) => {
+                Self::immediate_future(actor, Then::new(f, follow_up))
+            }
+            Self::Defer(f) => Self::defer(Then::new(f, follow_up)),
+        }
+    }
 }
 
 /// Trait denoting the capability to respond to an asynchronous request.
@@ -67,6 +95,17 @@ where
 
 /// Return value from a `NotifyHandler` to allow for immediate synchronous handling
 /// of the notification or asynchronous handling.
+///
+/// # Re-entrancy
+///
+/// While a deferred completion is pending, the actor is owned by its future and
+/// handles nothing else:
+///
+/// * Further notifications and requests are queued, up to 16, and handled in order
+///   once the future completes.
+/// * The future may notify its own actor, but must not `request(...)` from it, as the
+///   request could only be handled after the future completes, and so never would be.
+/// * The interrupts of an `Interrupt` actor are masked, and so dropped, until it completes.
 pub enum Completion<A: Actor> {
     /// See `immediate()`
     Immediate(A),
@@ -86,6 +125,116 @@ impl<A: Actor + 'static> Completion<A> {
     pub fn defer<F: Future<Output = A> + 'static>(f: F) -> Self {
         Self::Defer(Box::new(alloc(f).unwrap()))
     }
+
+    /// Keep ownership of the actor until the supplied future, such as a multi-step
+    /// operation with delays between steps, has completed.
+    ///
+    /// Unlike `defer(future)`, the future does not have access to the actor.
+    pub fn defer_with<F: Future<Output = ()> + 'static>(actor: A, f: F) -> Self {
+        Self::defer(Deferred::new(actor, f))
+    }
+
+    /// Notify the actor at `address` of `event` once the notification is handled,
+    /// including any future deferred to.
+    pub fn then_notify<B, E>(self, address: Address<B>, event: E) -> Self
+    where
+        B: Actor + NotifyHandler<E> + 'static,
+        E: 'static,
+    {
+        self.then(Notification { address, event })
+    }
+
+    pub(crate) fn then<U: FollowUp + 'static>(self, follow_up: U) -> Self {
+        match self {
+            Self::Immediate(actor) => Self::defer(Then::new(ready(actor), follow_up)),
+            Self::Defer(f) => Self::defer(Then::new(f, follow_up)),
+        }
+    }
+}
+
+/// Something to do once a handler completes.
+pub(crate) trait FollowUp {
+    fn run(self);
+}
+
+struct Notification<B: Actor + 'static, E> {
+    address: Address<B>,
+    event: E,
+}
+
+impl<B, E> FollowUp for Notification<B, E>
+where
+    B: Actor + NotifyHandler<E> + 'static,
+    E: 'static,
+{
+    fn run(self) {
+        self.address.notify(self.event)
+    }
+}
+
+/// Completes a future, then runs a follow-up.
+struct Then<F, U> {
+    future: F,
+    follow_up: Option<U>,
+}
+
+impl<F, U> Then<F, U> {
+    fn new(future: F, follow_up: U) -> Self {
+        Self {
+            future,
+            follow_up: Some(follow_up),
+        }
+    }
+}
+
+impl<F: Future, U: FollowUp> Future for Then<F, U> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // # Safety
+        // The future is never moved out of `self`, and the follow-up is not structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        match future.poll(cx) {
+            Poll::Ready(output) => {
+                if let Some(follow_up) = this.follow_up.take() {
+                    follow_up.run();
+                }
+                Poll::Ready(output)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Holds an actor until a future completes, returning it.
+struct Deferred<A, F> {
+    actor: Option<A>,
+    future: F,
+}
+
+impl<A, F> Deferred<A, F> {
+    fn new(actor: A, future: F) -> Self {
+        Self {
+            actor: Some(actor),
+            future,
+        }
+    }
+}
+
+impl<A, F: Future<Output = ()>> Future for Deferred<A, F> {
+    type Output = A;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // # Safety
+        // The future is never moved out of `self`, and the actor is not structurally pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        match future.poll(cx) {
+            Poll::Ready(()) => Poll::Ready(this.actor.take().expect("polled after completion")),
+            Poll::Pending => Poll::Pending,
+        }
+    }
 }
 
 /// Trait denoting the capability of being notified.
@@ -97,6 +246,27 @@ where
     fn on_notify(self, message: M) -> Completion<Self>;
 }
 
+/// Trait denoting the capability of streaming a sequence of items in response to a message.
+///
+/// The items are sent through the supplied `Sender`, and the stream ends once the
+/// sender is dropped, usually when the completion returned by `on_stream(...)` finishes.
+/// Deferred completions may `send(...).await` items, waiting for the receiver whenever
+/// the channel is full; immediate completions can only `try_send(...)` as many items
+/// as fit into the channel.
+///
+/// The receiver must not be the streaming actor itself, as it could only receive
+/// items after the stream has completed.
+pub trait StreamHandler<M>
+where
+    Self: Actor + Sized,
+{
+    /// The type of the streamed items.
+    type Item: 'static;
+
+    /// Stream items in response to the message.
+    fn on_stream(self, message: M, sender: Sender<Self::Item>) -> Completion<Self>;
+}
+
 /// Trait to be implemented by a `Device` implementation in order to receive
 /// messages for the `EventBus`.
 ///
@@ -113,3 +283,120 @@ pub trait EventHandler<E> {
     /// The default implementation simply drops the event.
     fn on_event(&'static self, event: E) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::poll;
+    use core::cell::Cell;
+    use core::pin::pin;
+
+    /// A delay whose timer is fired by hand.
+    struct Delay<'t> {
+        fired: &'t Cell<bool>,
+    }
+
+    impl Future for Delay<'_> {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+            if self.fired.get() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    struct Sensor;
+
+    impl Actor for Sensor {}
+
+    #[test]
+    fn test_deferred_until_delay() {
+        let fired = Cell::new(false);
+        let steps = Cell::new(0);
+        let init = async {
+            steps.set(1);
+            Delay { fired: &fired }.await;
+            steps.set(2);
+        };
+        let mut deferred = pin!(Deferred::new(Sensor, init));
+
+        // the actor stays held while the timer has not fired
+        assert!(poll(deferred.as_mut()).is_pending());
+        assert!(poll(deferred.as_mut()).is_pending());
+        assert_eq!(steps.get(), 1);
+
+        fired.set(true);
+        assert!(matches!(poll(deferred.as_mut()), Poll::Ready(Sensor)));
+        assert_eq!(steps.get(), 2);
+    }
+
+    /// Stands in for the mailbox of a second actor.
+    struct Mailbox<'m> {
+        received: &'m Cell<Option<u8>>,
+        event: u8,
+    }
+
+    impl FollowUp for Mailbox<'_> {
+        fn run(self) {
+            self.received.set(Some(self.event));
+        }
+    }
+
+    #[test]
+    fn test_then() {
+        let fired = Cell::new(false);
+        let received = Cell::new(None);
+        let mailbox = Mailbox {
+            received: &received,
+            event: 7,
+        };
+        let init = async {
+            Delay { fired: &fired }.await;
+            (Sensor, 42)
+        };
+        let mut response = pin!(Then::new(init, mailbox));
+
+        // the follow-up waits for the response
+        assert!(poll(response.as_mut()).is_pending());
+        assert_eq!(received.get(), None);
+
+        fired.set(true);
+        assert!(matches!(poll(response.as_mut()), Poll::Ready((Sensor, 42))));
+        assert_eq!(received.get(), Some(7));
+    }
+
+    struct Readings;
+
+    impl StreamHandler<Readings> for Sensor {
+        type Item = u8;
+
+        fn on_stream(self, _: Readings, sender: Sender<u8>) -> Completion<Self> {
+            for reading in 1..=3 {
+                sender.try_send(reading).unwrap();
+            }
+            Completion::immediate(self)
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let (sender, mut receiver) = crate::synchronization::channel::tests::channel();
+        assert!(matches!(
+            Sensor.on_stream(Readings, sender),
+            Completion::Immediate(Sensor)
+        ));
+
+        let mut collected = [0; 3];
+        for item in collected.iter_mut() {
+            match poll(pin!(receiver.next())) {
+                Poll::Ready(Some(reading)) => *item = reading,
+                _ => panic!("missing item"),
+            }
+        }
+        assert_eq!(collected, [1, 2, 3]);
+        assert!(matches!(poll(pin!(receiver.next())), Poll::Ready(None)));
+    }
+}
@@ -1,6 +1,7 @@
 //! Types and traits for interrupt-capable actors.
 
 use cortex_m::interrupt::Nr;
+use cortex_m::peripheral::scb::SystemHandler;
 use cortex_m::peripheral::NVIC;
 
 use crate::actor::{Actor, ActorContext, Configurable};
@@ -26,15 +27,61 @@ pub trait Interrupt: Actor {
 /// and connecting it to the actor system.
 pub struct InterruptContext<I: Interrupt + 'static> {
     pub(crate) irq: u8,
+    priority: Option<u8>,
     pub(crate) actor_context: ActorContext<I>,
 }
 
+/// The parts of the NVIC an `InterruptContext` configures when mounted.
+pub(crate) trait InterruptController {
+    fn set_priority(&mut self, irq: u8, priority: u8);
+    fn unmask(&mut self, irq: u8);
+}
+
+/// The number the SysTick exception is dispatched under, as it reaches the device's
+/// default handler numbered -1.
+pub(crate) const SYSTICK: u8 = -1i16 as u8;
+
+struct IrqNr(u8);
+
+unsafe impl Nr for IrqNr {
+    fn nr(&self) -> u8 {
+        self.0
+    }
+}
+
+/// The NVIC of the core, as used by contexts when mounted.
+///
+/// SysTick is an exception rather than an interrupt of the NVIC, so its priority is
+/// set in the SCB instead, and it cannot be masked.
+struct CoreNvic;
+
+impl InterruptController for CoreNvic {
+    fn set_priority(&mut self, irq: u8, priority: u8) {
+        // only written while mounting, before the interrupt is unmasked
+        unsafe {
+            let mut peripherals = cortex_m::Peripherals::steal();
+            if irq == SYSTICK {
+                peripherals.SCB.set_priority(SystemHandler::SysTick, priority)
+            } else {
+                peripherals.NVIC.set_priority(IrqNr(irq), priority)
+            }
+        }
+    }
+
+    fn unmask(&mut self, irq: u8) {
+        if irq != SYSTICK {
+            unsafe { NVIC::unmask(IrqNr(irq)) }
+        }
+    }
+}
+
 impl<I: Interrupt> InterruptContext<I> {
     /// Create a new context, taking ownership of the provided actor instance.
     /// When mounted, the context and the contained actor will be moved to the static lifetime.
     pub fn new<N: Nr>(interrupt: I, irq: N) -> Self {
         Self {
             irq: irq.nr(),
+            priority: None,
             actor_context: ActorContext::new(interrupt),
         }
     }
@@ -45,6 +92,20 @@ impl<I: Interrupt> InterruptContext<I> {
         self
     }
 
+    /// Set the NVIC priority of the interrupt when the context is mounted. Otherwise the
+    /// interrupt keeps the priority it has at reset, the highest.
+    ///
+    /// The priority is written as-is to the 8-bit priority register, where lower values
+    /// preempt higher ones. Cores implement only the most significant bits of the
+    /// register, such as 3 on nRF52 and 4 on STM32L4, so priorities differing only in
+    /// the low bits are equal: use multiples of `1 << (8 - <priority bits>)`. When the
+    /// priority grouping (`AIRCR.PRIGROUP`) splits the bits into group and subpriority,
+    /// only the group part decides whether one interrupt preempts another.
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority.replace(priority);
+        self
+    }
+
     pub fn address(&'static self) -> Address<I> {
         self.actor_context.address()
     }
@@ -60,15 +121,59 @@ impl<I: Interrupt> InterruptContext<I> {
     pub fn mount(&'static self, supervisor: &mut Supervisor) -> Address<I> {
         let addr = self.actor_context.mount(supervisor);
         supervisor.activate_interrupt(self, self.irq);
+        self.enable(&mut CoreNvic);
+        addr
+    }
 
-        struct IrqNr(u8);
-        unsafe impl Nr for IrqNr {
-            fn nr(&self) -> u8 {
-                self.0
-            }
+    /// Configure the priority of the interrupt, if set, and unmask it.
+    fn enable<C: InterruptController>(&self, nvic: &mut C) {
+        if let Some(priority) = self.priority {
+            nvic.set_priority(self.irq, priority);
         }
-        unsafe { NVIC::unmask(IrqNr(self.irq)) }
+        nvic.unmask(self.irq);
+    }
+}
 
-        addr
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Idle;
+
+    impl Actor for Idle {}
+
+    impl Interrupt for Idle {
+        fn on_interrupt(&mut self) {}
+    }
+
+    #[derive(Default)]
+    struct MockNvic {
+        priorities: [Option<u8>; 32],
+        unmasked: [bool; 32],
+    }
+
+    impl InterruptController for MockNvic {
+        fn set_priority(&mut self, irq: u8, priority: u8) {
+            self.priorities[irq as usize].replace(priority);
+        }
+
+        fn unmask(&mut self, irq: u8) {
+            self.unmasked[irq as usize] = true;
+        }
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut nvic = MockNvic::default();
+        InterruptContext::new(Idle, IrqNr(8))
+            .with_priority(0x40)
+            .enable(&mut nvic);
+        assert_eq!(nvic.priorities[8], Some(0x40));
+        assert!(nvic.unmasked[8]);
+
+        // without a priority, the register is left alone
+        InterruptContext::new(Idle, IrqNr(9)).enable(&mut nvic);
+        assert_eq!(nvic.priorities[9], None);
+        assert!(nvic.unmasked[9]);
     }
 }
@@ -2,6 +2,9 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
+// This must go first, so the logging macros are visible to the other modules.
+mod fmt;
+
 pub mod actor;
 pub mod address;
 #[doc(hidden)]
@@ -11,25 +14,38 @@ pub mod bus;
 pub mod device;
 pub mod domain;
 pub mod driver;
+pub mod error;
 pub mod handler;
 pub mod interrupt;
 #[doc(hidden)]
 pub mod macros;
 pub mod package;
+pub mod panic_led;
 pub mod supervisor;
 pub mod synchronization;
+pub mod system;
+#[cfg(test)]
+pub(crate) mod testing;
+#[cfg(feature = "trace")]
+pub mod trace;
+pub mod util;
 
 pub mod hal;
 
 /// Easy imports for common types and traits.
 pub mod prelude {
     pub use crate::actor::{Actor, ActorContext, ActorInfo, Configurable};
-    pub use crate::address::Address;
+    pub use crate::address::{Address, WeakAddress};
     pub use crate::bind::Bind;
     pub use crate::bus::EventBus;
     pub use crate::device;
     pub use crate::device::Device;
-    pub use crate::handler::{Completion, EventHandler, NotifyHandler, RequestHandler, Response};
+    #[cfg(feature = "derive")]
+    pub use crate::device::EventRouter;
+    pub use crate::error::DeviceError;
+    pub use crate::handler::{
+        Completion, EventHandler, NotifyHandler, RequestHandler, Response, StreamHandler,
+    };
     pub use crate::interrupt::{Interrupt, InterruptContext};
     pub use crate::package::Package;
     pub use crate::supervisor::Supervisor;
@@ -0,0 +1,149 @@
+//! Blinking a status LED when the firmware panics.
+//!
+//! A board without a console gives no sign of a panic beyond going quiet. Registering
+//! an LED with `set_panic_led` lets `panic_led` blink SOS on it, in Morse, until the
+//! board is reset. `panic_led` can be called from the application's own panic handler,
+//! or installed as the panic handler by invoking `panic_led_handler!` in the application.
+//!
+//! With no timer to be trusted once panicking, the blinks are timed by spinning the
+//! core, so `set_panic_led` is given the core clock to count cycles against.
+
+use crate::domain::time::rate::Hertz;
+use crate::hal::gpio::ActiveOutput;
+use crate::util::morse::{Morse, Signal, WORD_GAP};
+use core::iter::{once, Chain, Once};
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+use embedded_hal::digital::v2::OutputPin;
+
+/// The length of a Morse unit, in milliseconds.
+pub const UNIT_MS: u32 = 200;
+
+static PIN: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+static DRIVE: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+static UNIT_CYCLES: AtomicU32 = AtomicU32::new(0);
+
+/// Blink `pin`, active as `A`, on a panic, counting time by the `core_clock`.
+///
+/// The pin is given up for good, so is best not used by the application as well.
+pub fn set_panic_led<P, A>(pin: &'static mut P, core_clock: Hertz)
+where
+    P: OutputPin,
+    A: ActiveOutput,
+{
+    UNIT_CYCLES.store(core_clock.0 / 1_000 * UNIT_MS, Ordering::Release);
+    DRIVE.store(drive::<P, A> as *mut (), Ordering::Release);
+    PIN.store(pin as *mut P as *mut (), Ordering::Release);
+}
+
+fn drive<P, A>(pin: *mut (), on: bool)
+where
+    P: OutputPin,
+    A: ActiveOutput,
+{
+    // only ever stored from a `&'static mut P` in `set_panic_led`
+    let pin = unsafe { &mut *(pin as *mut P) };
+    if on {
+        A::set_active(pin).ok();
+    } else {
+        A::set_inactive(pin).ok();
+    }
+}
+
+/// The signals of a single SOS, ending with the gap before the next.
+pub fn sos() -> Chain<Morse<'static>, Once<Signal>> {
+    Morse::new("SOS").chain(once(Signal::Off(WORD_GAP)))
+}
+
+/// Disable interrupts and blink SOS on the LED registered with `set_panic_led`,
+/// forever. Without an LED registered, just spin.
+pub fn panic_led(_info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+
+    let pin = PIN.load(Ordering::Acquire);
+    let unit = UNIT_CYCLES.load(Ordering::Acquire);
+    if pin.is_null() {
+        loop {
+            cortex_m::asm::nop();
+        }
+    }
+    // only ever set from a `fn(*mut (), bool)` in `set_panic_led`, before the pin
+    let drive: fn(*mut (), bool) = unsafe { core::mem::transmute(DRIVE.load(Ordering::Acquire)) };
+
+    loop {
+        for signal in sos() {
+            let (on, units) = match signal {
+                Signal::On(units) => (true, units),
+                Signal::Off(units) => (false, units),
+            };
+            drive(pin, on);
+            cortex_m::asm::delay(unit.saturating_mul(units));
+        }
+    }
+}
+
+/// Install `panic_led` as the panic handler of the application.
+///
+/// The handler is defined in the application rather than in this crate, so that
+/// linking the crate never brings a second panic handler along, such as into tests.
+///
+/// Usage:
+/// ```ignore
+/// use drogue_device::panic_led_handler;
+///
+/// panic_led_handler!();
+/// ```
+#[macro_export]
+macro_rules! panic_led_handler {
+    () => {
+        #[panic_handler]
+        fn panic(info: &core::panic::PanicInfo) -> ! {
+            $crate::panic_led::panic_led(info)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::morse::{DASH, DOT, LETTER_GAP, SYMBOL_GAP};
+    use heapless::{consts::*, Vec};
+
+    #[test]
+    fn test_sos() {
+        use Signal::*;
+        let signals: Vec<Signal, U32> = sos().collect();
+        assert_eq!(
+            signals,
+            [
+                On(DOT),
+                Off(SYMBOL_GAP),
+                On(DOT),
+                Off(SYMBOL_GAP),
+                On(DOT),
+                Off(LETTER_GAP),
+                On(DASH),
+                Off(SYMBOL_GAP),
+                On(DASH),
+                Off(SYMBOL_GAP),
+                On(DASH),
+                Off(LETTER_GAP),
+                On(DOT),
+                Off(SYMBOL_GAP),
+                On(DOT),
+                Off(SYMBOL_GAP),
+                On(DOT),
+                Off(WORD_GAP),
+            ]
+        );
+
+        // an SOS takes 34 units, 6.8s at 200ms a unit
+        let units: u32 = signals
+            .iter()
+            .map(|signal| match signal {
+                On(units) | Off(units) => units,
+            })
+            .sum();
+        assert_eq!(units, 34);
+    }
+}
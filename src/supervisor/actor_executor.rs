@@ -2,8 +2,10 @@ use heapless::{consts::*, Vec};
 
 use crate::actor::{Actor, ActorContext, CURRENT};
 use crate::prelude::device::Lifecycle;
+use crate::supervisor::Idle;
 use core::cmp::PartialEq;
-use core::sync::atomic::{AtomicU8, Ordering};
+use core::num::NonZeroUsize;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
 
 #[derive(PartialEq)]
@@ -42,7 +44,7 @@ impl Supervised {
     }
 
     fn signal_idle(&self) {
-        log::trace!(
+        trace!(
             "[{}] signal idle {:x}",
             self.actor.name(),
             &self.state as *const _ as u32
@@ -55,7 +57,7 @@ impl Supervised {
     }
 
     fn signal_waiting(&self) {
-        log::trace!(
+        trace!(
             "[{}] signal waiting {:x}",
             self.actor.name(),
             &self.state as *const _ as u32
@@ -69,7 +71,7 @@ impl Supervised {
     }
 
     fn signal_ready(&self) {
-        log::trace!(
+        trace!(
             "[{}] signal ready {:x}",
             self.actor.name(),
             &self.state as *const _ as u32
@@ -80,17 +82,18 @@ impl Supervised {
 
     fn poll(&mut self) -> bool {
         if self.is_ready() {
-            unsafe {
-                CURRENT.name.replace(self.actor.name());
-            }
-            log::trace!("polling actor {:x}", &self.actor as *const _ as u32);
-            match self.actor.do_poll(self.get_state_flag_handle()) {
-                Poll::Ready(_) => self.signal_idle(),
-                Poll::Pending => self.signal_waiting(),
-            }
-            unsafe {
-                CURRENT.name.take();
+            CURRENT.name.set(Some(self.actor.name()));
+            #[cfg(feature = "trace")]
+            CURRENT.type_name.set(Some(self.actor.type_name()));
+            trace!("polling actor {:x}", &self.actor as *const _ as u32);
+            // Wait before polling, so that the actor being woken while polled leaves it ready.
+            self.signal_waiting();
+            if self.actor.do_poll(self.get_state_flag_handle()).is_ready() {
+                self.signal_idle()
             }
+            CURRENT.name.set(None);
+            #[cfg(feature = "trace")]
+            CURRENT.type_name.set(None);
             true
         } else {
             false
@@ -100,12 +103,24 @@ impl Supervised {
     fn dispatch_lifecycle_event(&self, event: Lifecycle) {
         self.actor.dispatch_lifecycle_event(event);
     }
+
+    fn is_stopping(&self) -> bool {
+        self.actor.is_stopping()
+    }
 }
 
 pub(crate) trait ActiveActor {
     fn name(&self) -> &str;
+    #[cfg(feature = "trace")]
+    fn type_name(&self) -> &'static str {
+        core::any::type_name::<Self>()
+    }
     fn do_poll(&self, state_flag_handle: *const ()) -> Poll<()>;
     fn dispatch_lifecycle_event(&'static self, event: Lifecycle);
+    /// Whether a stop has been dispatched, and the actor is still handling it.
+    fn is_stopping(&self) -> bool {
+        false
+    }
 }
 
 impl<A: Actor> ActiveActor for ActorContext<A> {
@@ -113,24 +128,29 @@ impl<A: Actor> ActiveActor for ActorContext<A> {
         ActorContext::name(self)
     }
 
+    #[cfg(feature = "trace")]
+    fn type_name(&self) -> &'static str {
+        core::any::type_name::<A>()
+    }
+
     fn do_poll(&self, state_flag_handle: *const ()) -> Poll<()> {
-        log::trace!("[{}] executor: do_poll", self.name());
+        trace!("[{}] executor: do_poll", self.name());
         loop {
             if self.current.borrow().is_none() {
                 //cortex_m::interrupt::free(|cs| {
                 if let Some(next) = self.items_consumer.borrow_mut().as_mut().unwrap().dequeue() {
                 //if let Some(next) = self.items.dequeue() {
-                    log::trace!("[{}] executor: set current task", self.name());
+                    trace!("[{}] executor: set current task", self.name());
                     //(&mut *self.current.get()).replace(next);
                     self.current.borrow_mut().replace(next);
                     self.in_flight.store(true, Ordering::Release);
                 } else {
-                    log::trace!("[{}] executor: no current task", self.name());
+                    trace!("[{}] executor: no current task", self.name());
                     self.in_flight.store(false, Ordering::Release);
                 }
             //});
             } else {
-                log::trace!("[{}] executor: in-flight current task", self.name());
+                trace!("[{}] executor: in-flight current task", self.name());
             }
 
             let should_drop;
@@ -143,14 +163,14 @@ impl<A: Actor> ActiveActor for ActorContext<A> {
                 let result = item.poll(&mut cx);
                 match result {
                     Poll::Ready(_) => {
-                        log::trace!("[{}] executor: task complete", self.name());
+                        trace!("[{}] executor: task complete", self.name());
                         should_drop = true;
                         // "dequeue" it and allow it to drop
                         //(&mut *self.current.get()).take();
                         //self.current.borrow_mut().take();
                     }
                     Poll::Pending => {
-                        log::trace!("[{}] executor: task pending", self.name());
+                        trace!("[{}] executor: task pending", self.name());
                         break;
                     }
                 }
@@ -158,7 +178,7 @@ impl<A: Actor> ActiveActor for ActorContext<A> {
                 break;
             }
             if should_drop {
-                log::trace!("[{}] executor: task drop", self.name());
+                trace!("[{}] executor: task drop", self.name());
                 self.current.borrow_mut().take().unwrap();
             }
         }
@@ -169,15 +189,36 @@ impl<A: Actor> ActiveActor for ActorContext<A> {
     fn dispatch_lifecycle_event(&'static self, event: Lifecycle) {
         self.lifecycle(event)
     }
+
+    fn is_stopping(&self) -> bool {
+        ActorContext::is_stopping(self)
+    }
 }
 
 pub struct ActorExecutor {
     actors: Vec<Supervised, U16>,
+    /// Index of the actor each sweep begins polling at, rotating for round-robin fairness.
+    next: usize,
+    max_polls_per_pass: Option<NonZeroUsize>,
+    idle: Option<&'static dyn Idle>,
 }
 
 impl ActorExecutor {
     pub(crate) fn new() -> Self {
-        Self { actors: Vec::new() }
+        Self {
+            actors: Vec::new(),
+            next: 0,
+            max_polls_per_pass: None,
+            idle: None,
+        }
+    }
+
+    pub(crate) fn set_max_polls_per_pass(&mut self, polls: Option<NonZeroUsize>) {
+        self.max_polls_per_pass = polls;
+    }
+
+    pub(crate) fn set_idle(&mut self, idle: &'static dyn Idle) {
+        self.idle.replace(idle);
     }
 
     pub(crate) fn dispatch_lifecycle_event(&mut self, event: Lifecycle) {
@@ -197,25 +238,112 @@ impl ActorExecutor {
         )
     }
 
-    pub(crate) fn run_until_quiescence(&mut self) {
-        let mut run_again = true;
-        while run_again {
-            run_again = false;
-            for actor in self.actors.iter_mut().filter(|e| !e.is_idle()) {
-                if actor.poll() {
-                    run_again = true
+    /// Poll every ready actor, in sweeps, until none are left ready or the
+    /// `max_polls_per_pass` budget is spent, returning whether any are still ready.
+    ///
+    /// Each sweep begins at the actor following the one the previous sweep began at, so
+    /// that no actor is always polled ahead of (or behind) the others. Actors have no
+    /// priorities, and so all share a single round-robin; were priority bands added,
+    /// the rotation would apply within each band. A sweep cut short by the budget is
+    /// resumed, by the next pass, at the first actor it left unpolled.
+    pub(crate) fn run_until_quiescence(&mut self) -> bool {
+        let mut budget = self.max_polls_per_pass.map(NonZeroUsize::get);
+        while self.sweep(&mut budget) {
+            if budget == Some(0) {
+                return self.actors.iter().any(|e| e.is_ready());
+            }
+        }
+        false
+    }
+
+    /// Poll each ready actor once, until `budget` polls are spent, returning whether
+    /// any were polled.
+    fn sweep(&mut self, budget: &mut Option<usize>) -> bool {
+        let len = self.actors.len();
+        if len == 0 {
+            return false;
+        }
+        let start = self.next % len;
+        self.next = (start + 1) % len;
+
+        let mut polled = false;
+        for i in (start..len).chain(0..start) {
+            let actor = &mut self.actors[i];
+            if actor.is_ready() && *budget == Some(0) {
+                self.next = i;
+                break;
+            }
+            if !actor.is_idle() && actor.poll() {
+                polled = true;
+                if let Some(budget) = budget {
+                    *budget -= 1;
                 }
             }
         }
+        polled
     }
 
     pub fn run_forever(&mut self) -> ! {
         self.dispatch_lifecycle_event(Lifecycle::Initialize);
         self.dispatch_lifecycle_event(Lifecycle::Start);
         loop {
-            self.run_until_quiescence();
-            // self.dispatch_lifecycle_event( Lifecycle::Sleep );
-            // WFI
+            if !self.run_until_quiescence() {
+                self.on_idle();
+            }
+        }
+    }
+
+    /// Run the idle hook, if any, unless an interrupt has readied an actor since the last
+    /// pass. Interrupts are masked throughout, so one arriving during the hook still
+    /// ends a sleep it enters, but is only handled once the hook returns.
+    fn on_idle(&self) {
+        if self.idle.is_some() {
+            cortex_m::interrupt::free(|_| self.idle_if_quiescent());
+        }
+    }
+
+    /// Run the idle hook, if any, should no actor be ready, returning whether it ran.
+    fn idle_if_quiescent(&self) -> bool {
+        match self.idle {
+            Some(idle) if !self.actors.iter().any(|e| e.is_ready()) => {
+                idle.on_idle();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Run as `run_forever()` does, but shut down and return once `shutdown` is found set
+    /// at the end of a pass, rather than running the idle hook.
+    pub fn run_until_shutdown(&mut self, shutdown: &AtomicBool) {
+        self.dispatch_lifecycle_event(Lifecycle::Initialize);
+        self.dispatch_lifecycle_event(Lifecycle::Start);
+        loop {
+            let ready = self.run_until_quiescence();
+            if shutdown.load(Ordering::Acquire) {
+                break;
+            }
+            if !ready {
+                self.on_idle();
+            }
+        }
+        self.shutdown();
+    }
+
+    /// Stop every actor, running until their `on_stop` handlers complete.
+    ///
+    /// A handler waiting on an interrupt, such as of a timer, leaves no actor ready
+    /// meanwhile, so the idle hook is run between passes until every stop completes.
+    pub fn shutdown(&mut self) {
+        debug!("shutting down");
+        self.dispatch_lifecycle_event(Lifecycle::Stop);
+        loop {
+            if !self.run_until_quiescence() {
+                if !self.actors.iter().any(|e| e.is_stopping()) {
+                    break;
+                }
+                self.on_idle();
+            }
         }
     }
 }
@@ -230,7 +358,7 @@ static VTABLE: RawWakerVTable = {
     }
 
     unsafe fn wake_by_ref(p: *const ()) {
-        log::trace!("[waker] signal ready {:x}", p as *const _ as u32);
+        trace!("[waker] signal ready {:x}", p as *const _ as u32);
         (*(p as *const AtomicU8)).store(ActorState::READY.into(), Ordering::Release);
     }
 
@@ -238,3 +366,249 @@ static VTABLE: RawWakerVTable = {
 
     RawWakerVTable::new(clone, wake, wake_by_ref, drop)
 };
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::cell::{Cell, RefCell};
+    use std::boxed::Box;
+
+    /// Stands in for an actor requesting shutdown when polled, and recording the
+    /// lifecycle events dispatched to it.
+    struct Stopper {
+        polls: Cell<u32>,
+        shutdown: &'static AtomicBool,
+        events: RefCell<Vec<Lifecycle, U8>>,
+    }
+
+    impl ActiveActor for Stopper {
+        fn name(&self) -> &str {
+            "stopper"
+        }
+
+        fn do_poll(&self, _: *const ()) -> Poll<()> {
+            self.polls.set(self.polls.get() + 1);
+            assert!(matches!(self.events.borrow().last(), Some(Lifecycle::Start)));
+            self.shutdown.store(true, Ordering::Release);
+            Poll::Pending
+        }
+
+        fn dispatch_lifecycle_event(&'static self, event: Lifecycle) {
+            self.events.borrow_mut().push(event).ok();
+        }
+    }
+
+    #[test]
+    fn test_run_until_shutdown() {
+        let shutdown: &'static AtomicBool = Box::leak(Box::new(AtomicBool::new(false)));
+        let stopper: &'static Stopper = Box::leak(Box::new(Stopper {
+            polls: Cell::new(0),
+            shutdown,
+            events: RefCell::new(Vec::new()),
+        }));
+
+        let mut executor = ActorExecutor::new();
+        executor.activate_actor(stopper);
+        executor.run_until_shutdown(shutdown);
+
+        // returns once the pass setting the flag reaches quiescence, then stops actors
+        assert_eq!(stopper.polls.get(), 1);
+        let events = stopper.events.borrow();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], Lifecycle::Initialize));
+        assert!(matches!(events[1], Lifecycle::Start));
+        assert!(matches!(events[2], Lifecycle::Stop));
+    }
+
+    /// Stands in for an actor whose `on_stop` waits on an interrupt, such as of a timer,
+    /// arriving once the executor has found it still stopping `waits` times.
+    struct SlowStopper {
+        waits: u32,
+        checks: Cell<u32>,
+        stopping: Cell<bool>,
+        stopped: Cell<bool>,
+        state_flag_handle: Cell<Option<*const ()>>,
+    }
+
+    impl ActiveActor for SlowStopper {
+        fn name(&self) -> &str {
+            "slow-stopper"
+        }
+
+        fn do_poll(&self, _: *const ()) -> Poll<()> {
+            if self.stopping.get() && self.checks.get() >= self.waits {
+                self.stopping.set(false);
+                self.stopped.set(true);
+            }
+            Poll::Pending
+        }
+
+        fn dispatch_lifecycle_event(&'static self, event: Lifecycle) {
+            if let Lifecycle::Stop = event {
+                self.stopping.set(true);
+            }
+        }
+
+        fn is_stopping(&self) -> bool {
+            if self.stopping.get() {
+                self.checks.set(self.checks.get() + 1);
+                if self.checks.get() == self.waits {
+                    let handle = self.state_flag_handle.get().unwrap();
+                    let state = unsafe { &*(handle as *const AtomicU8) };
+                    state.store(ActorState::READY.into(), Ordering::Release);
+                }
+            }
+            self.stopping.get()
+        }
+    }
+
+    #[test]
+    fn test_shutdown_waits_for_stop() {
+        let stopper: &'static SlowStopper = Box::leak(Box::new(SlowStopper {
+            waits: 2,
+            checks: Cell::new(0),
+            stopping: Cell::new(false),
+            stopped: Cell::new(false),
+            state_flag_handle: Cell::new(None),
+        }));
+
+        let mut executor = ActorExecutor::new();
+        let (_, handle) = executor.activate_actor(stopper);
+        stopper.state_flag_handle.set(Some(handle));
+        executor.shutdown();
+
+        // returns only once the stop completed, though nothing was ready meanwhile
+        assert!(stopper.stopped.get());
+        assert_eq!(stopper.checks.get(), 2);
+    }
+
+    /// Stands in for an actor waking itself while polled, `spins` times, and recording
+    /// each poll in a log shared with other actors.
+    struct Spinner {
+        name: &'static str,
+        spins: Cell<u32>,
+        log: &'static RefCell<Vec<&'static str, U16>>,
+    }
+
+    impl ActiveActor for Spinner {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn do_poll(&self, state_flag_handle: *const ()) -> Poll<()> {
+            self.log.borrow_mut().push(self.name).ok();
+            if self.spins.get() > 0 {
+                self.spins.set(self.spins.get() - 1);
+                let state = unsafe { &*(state_flag_handle as *const AtomicU8) };
+                state.store(ActorState::READY.into(), Ordering::Release);
+            }
+            Poll::Pending
+        }
+
+        fn dispatch_lifecycle_event(&'static self, _: Lifecycle) {}
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let log: &'static RefCell<Vec<&'static str, U16>> =
+            Box::leak(Box::new(RefCell::new(Vec::new())));
+        let spinner = |name, spins| -> &'static Spinner {
+            Box::leak(Box::new(Spinner {
+                name,
+                spins: Cell::new(spins),
+                log,
+            }))
+        };
+
+        let mut executor = ActorExecutor::new();
+        executor.activate_actor(spinner("a", 3));
+        executor.activate_actor(spinner("b", 1));
+        executor.run_until_quiescence();
+
+        // the second pass begins at "b", rather than at "a" which is always ready
+        assert_eq!(&log.borrow()[..], &["a", "b", "b", "a", "a", "a"]);
+    }
+
+    #[test]
+    fn test_max_polls_per_pass() {
+        let log: &'static RefCell<Vec<&'static str, U16>> =
+            Box::leak(Box::new(RefCell::new(Vec::new())));
+        let spinner = |name| -> &'static Spinner {
+            Box::leak(Box::new(Spinner {
+                name,
+                spins: Cell::new(0),
+                log,
+            }))
+        };
+
+        let mut executor = ActorExecutor::new();
+        executor.activate_actor(spinner("a"));
+        executor.activate_actor(spinner("b"));
+        executor.activate_actor(spinner("c"));
+        executor.set_max_polls_per_pass(NonZeroUsize::new(2));
+
+        // returns with "c" still ready, then resumes at it
+        assert!(executor.run_until_quiescence());
+        assert_eq!(&log.borrow()[..], &["a", "b"]);
+        assert!(!executor.run_until_quiescence());
+        assert_eq!(&log.borrow()[..], &["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_single_poll_per_pass() {
+        let log: &'static RefCell<Vec<&'static str, U16>> =
+            Box::leak(Box::new(RefCell::new(Vec::new())));
+        let mut executor = ActorExecutor::new();
+        executor.activate_actor(Box::leak(Box::new(Spinner {
+            name: "a",
+            spins: Cell::new(2),
+            log,
+        })));
+        executor.set_max_polls_per_pass(NonZeroUsize::new(1));
+
+        // the smallest budget still polls an actor each pass
+        assert!(executor.run_until_quiescence());
+        assert!(executor.run_until_quiescence());
+        assert!(!executor.run_until_quiescence());
+        assert_eq!(&log.borrow()[..], &["a", "a", "a"]);
+    }
+
+    /// Counts the times the supervisor found nothing left to poll.
+    #[derive(Default)]
+    struct Idled(Cell<u32>);
+
+    impl Idle for Idled {
+        fn on_idle(&self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_idle() {
+        let log: &'static RefCell<Vec<&'static str, U16>> =
+            Box::leak(Box::new(RefCell::new(Vec::new())));
+        let idled: &'static Idled = Box::leak(Box::new(Idled::default()));
+
+        let mut executor = ActorExecutor::new();
+        executor.activate_actor(Box::leak(Box::new(Spinner {
+            name: "a",
+            spins: Cell::new(2),
+            log,
+        })));
+        executor.set_max_polls_per_pass(NonZeroUsize::new(2));
+        executor.set_idle(idled);
+
+        // not while work remains
+        assert!(executor.run_until_quiescence());
+        assert!(!executor.idle_if_quiescent());
+        assert_eq!(idled.0.get(), 0);
+
+        // once, when every actor is idle
+        assert!(!executor.run_until_quiescence());
+        assert!(executor.idle_if_quiescent());
+        assert_eq!(idled.0.get(), 1);
+        assert_eq!(log.borrow().len(), 3);
+    }
+}
@@ -3,10 +3,19 @@
 use crate::supervisor::actor_executor::{ActiveActor, ActorExecutor};
 use crate::supervisor::interrupt_dispatcher::{ActiveInterrupt, InterruptDispatcher};
 use core::cell::RefCell;
+use core::num::NonZeroUsize;
+use core::sync::atomic::AtomicBool;
 
 pub(crate) mod actor_executor;
 pub(crate) mod interrupt_dispatcher;
 
+/// Work done whenever the supervisor has no actor left to poll, such as sleeping until
+/// the next interrupt.
+pub trait Idle {
+    /// Called with interrupts masked, once each time every actor is waiting or idle.
+    fn on_idle(&self);
+}
+
 /// An opaque object used during the mounting of actors into the system.
 pub struct Supervisor {
     executor: RefCell<ActorExecutor>,
@@ -22,6 +31,25 @@ impl Supervisor {
         }
     }
 
+    /// Limit each pass of the supervisor to `polls` polls of ready actors, or lift the
+    /// limit with `None`, the default. A pass always polls at least one ready actor.
+    ///
+    /// Between passes, the supervisor returns to its outer loop, where it checks for
+    /// shutdown and, in time, sleeps until the next interrupt. Without a limit, a pass
+    /// runs until no actor is ready, so a burst of work delays everything else until
+    /// it is done. A small limit bounds that delay, at the cost of more time spent
+    /// between passes rather than polling actors.
+    pub fn set_max_polls_per_pass(&mut self, polls: Option<NonZeroUsize>) {
+        self.executor.borrow_mut().set_max_polls_per_pass(polls)
+    }
+
+    /// Run `idle` whenever no actor is left to poll, in place of returning straight to
+    /// polling, such as to sleep until the next interrupt, or for a test harness to find
+    /// the system has settled. It is not run while any actor is still ready.
+    pub fn set_idle(&mut self, idle: &'static dyn Idle) {
+        self.executor.borrow_mut().set_idle(idle)
+    }
+
     pub(crate) fn activate_actor<S: ActiveActor>(&mut self, actor: &'static S) -> (usize, *const ()) {
         self.executor.borrow_mut().activate_actor(actor)
     }
@@ -38,6 +66,23 @@ impl Supervisor {
         self.executor.borrow_mut().run_forever()
     }
 
+    pub(crate) fn run_until_shutdown(&self, shutdown: &AtomicBool) {
+        self.executor.borrow_mut().run_until_shutdown(shutdown)
+    }
+
+    /// Stop every actor, driving their `on_stop` handlers to completion, including any
+    /// waiting on an interrupt.
+    ///
+    /// Once shut down, actors are no longer polled unless the supervisor is run again.
+    ///
+    /// # Panics
+    ///
+    /// If the supervisor is running, as from within an actor; set the flag given to
+    /// `DeviceContext::mount_until_shutdown()` instead.
+    pub fn shutdown(&self) {
+        self.executor.borrow_mut().shutdown()
+    }
+
     pub(crate) fn on_interrupt(&self, irqn: i16) {
         self.dispatcher.borrow().on_interrupt(irqn);
     }
@@ -0,0 +1,268 @@
+//! A bounded, single-producer single-consumer channel carrying streamed items
+//! from an actor to whoever holds the receiving end.
+//!
+//! # Backpressure
+//!
+//! The channel holds at most `CAPACITY` items. `Sender::try_send` fails with
+//! `SendError::Full` once the channel is full, while `Sender::send(...).await`
+//! waits until the receiver has taken an item.
+//!
+//! # Termination
+//!
+//! Dropping the `Sender` ends the stream: the receiver drains the remaining items,
+//! after which `Receiver::next()` resolves to `None`. Dropping the `Receiver`
+//! closes the channel, and every further send fails with `SendError::Closed`.
+//!
+//! Channels are not interrupt-safe, and must only be used from actor context.
+
+use core::cell::{Cell, RefCell};
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::ptr::NonNull;
+use core::task::{Context, Poll, Waker};
+
+use heapless::{consts::*, spsc::Queue};
+
+use crate::alloc::{alloc, Box};
+
+/// The number of items a channel holds before senders have to wait.
+pub const CAPACITY: usize = 4;
+
+/// Error returned when an item could not be sent, handing the item back.
+#[derive(Debug, PartialEq)]
+pub enum SendError<T> {
+    /// The channel is full, and the item may be sent again later.
+    Full(T),
+    /// The receiver has been dropped, and no item will ever be received.
+    Closed(T),
+}
+
+struct Channel<T> {
+    items: RefCell<Queue<T, U4>>,
+    receiver_waker: RefCell<Option<Waker>>,
+    sender_waker: RefCell<Option<Waker>>,
+    sender_closed: Cell<bool>,
+    receiver_closed: Cell<bool>,
+    handles: Cell<u8>,
+    release: unsafe fn(NonNull<Channel<T>>),
+}
+
+impl<T> Channel<T> {
+    fn new(release: unsafe fn(NonNull<Channel<T>>)) -> Self {
+        Self {
+            items: RefCell::new(Queue::new()),
+            receiver_waker: RefCell::new(None),
+            sender_waker: RefCell::new(None),
+            sender_closed: Cell::new(false),
+            receiver_closed: Cell::new(false),
+            handles: Cell::new(2),
+            release,
+        }
+    }
+}
+
+fn wake(waker: &RefCell<Option<Waker>>) {
+    if let Some(waker) = waker.borrow_mut().take() {
+        waker.wake()
+    }
+}
+
+unsafe fn release_heap<T>(channel: NonNull<Channel<T>>) {
+    drop(Box::new(&mut *channel.as_ptr()));
+}
+
+/// Create a channel on the heap, returning its sending and receiving ends.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let channel = alloc(Channel::new(release_heap::<T>)).unwrap();
+    split(NonNull::from(channel))
+}
+
+fn split<T>(channel: NonNull<Channel<T>>) -> (Sender<T>, Receiver<T>) {
+    (
+        Sender {
+            handle: Handle { channel },
+        },
+        Receiver {
+            handle: Handle { channel },
+        },
+    )
+}
+
+/// One of the two ends of a channel, releasing it once both are dropped.
+struct Handle<T> {
+    channel: NonNull<Channel<T>>,
+}
+
+impl<T> Handle<T> {
+    fn channel(&self) -> &Channel<T> {
+        unsafe { self.channel.as_ref() }
+    }
+}
+
+impl<T> Drop for Handle<T> {
+    fn drop(&mut self) {
+        let channel = self.channel();
+        let handles = channel.handles.get() - 1;
+        channel.handles.set(handles);
+        if handles == 0 {
+            unsafe { (channel.release)(self.channel) }
+        }
+    }
+}
+
+/// The sending end of a channel.
+pub struct Sender<T> {
+    handle: Handle<T>,
+}
+
+impl<T> Sender<T> {
+    /// Send an item if there is room for it, without waiting.
+    pub fn try_send(&self, item: T) -> Result<(), SendError<T>> {
+        let channel = self.handle.channel();
+        if channel.receiver_closed.get() {
+            return Err(SendError::Closed(item));
+        }
+        channel
+            .items
+            .borrow_mut()
+            .enqueue(item)
+            .map_err(SendError::Full)?;
+        wake(&channel.receiver_waker);
+        Ok(())
+    }
+
+    /// Send an item, waiting for room if the channel is full.
+    ///
+    /// Fails only once the receiver has been dropped.
+    pub fn send(&self, item: T) -> SendFuture<'_, T> {
+        SendFuture {
+            sender: self,
+            item: Some(item),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let channel = self.handle.channel();
+        channel.sender_closed.set(true);
+        wake(&channel.receiver_waker);
+    }
+}
+
+/// Future returned by `Sender::send(...)`, resolving to the item if it could not be sent.
+pub struct SendFuture<'s, T> {
+    sender: &'s Sender<T>,
+    item: Option<T>,
+}
+
+impl<T> Unpin for SendFuture<'_, T> {}
+
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let item = self.item.take().expect("polled after completion");
+        match self.sender.try_send(item) {
+            Ok(()) => Poll::Ready(Ok(())),
+            Err(SendError::Closed(item)) => Poll::Ready(Err(item)),
+            Err(SendError::Full(item)) => {
+                self.item.replace(item);
+                self.sender
+                    .handle
+                    .channel()
+                    .sender_waker
+                    .borrow_mut()
+                    .replace(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The receiving end of a channel.
+pub struct Receiver<T> {
+    handle: Handle<T>,
+}
+
+impl<T> Receiver<T> {
+    /// Receive the next item, or `None` once the sender has been dropped and
+    /// every item has been received.
+    pub async fn next(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let channel = self.handle.channel();
+        if let Some(item) = channel.items.borrow_mut().dequeue() {
+            wake(&channel.sender_waker);
+            return Poll::Ready(Some(item));
+        }
+        if channel.sender_closed.get() {
+            return Poll::Ready(None);
+        }
+        channel
+            .receiver_waker
+            .borrow_mut()
+            .replace(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let channel = self.handle.channel();
+        channel.receiver_closed.set(true);
+        wake(&channel.sender_waker);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::testing::poll;
+    use core::pin::pin;
+
+    unsafe fn release_box<T>(channel: NonNull<Channel<T>>) {
+        drop(std::boxed::Box::from_raw(channel.as_ptr()));
+    }
+
+    /// Create a channel on the host's heap rather than the device's.
+    pub(crate) fn channel<T>() -> (Sender<T>, Receiver<T>) {
+        let channel = std::boxed::Box::new(Channel::new(release_box::<T>));
+        split(NonNull::from(std::boxed::Box::leak(channel)))
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let (sender, mut receiver) = channel();
+        for i in 0..CAPACITY {
+            assert_eq!(sender.try_send(i), Ok(()));
+        }
+        assert_eq!(sender.try_send(9), Err(SendError::Full(9)));
+
+        let mut send = pin!(sender.send(4));
+        assert_eq!(poll(send.as_mut()), Poll::Pending);
+        assert_eq!(poll(pin!(receiver.next())), Poll::Ready(Some(0)));
+        assert_eq!(poll(send.as_mut()), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn test_termination() {
+        let (sender, mut receiver) = channel();
+        sender.try_send(1).unwrap();
+        assert_eq!(poll(pin!(receiver.next())), Poll::Ready(Some(1)));
+        assert_eq!(poll(pin!(receiver.next())), Poll::Pending);
+        sender.try_send(2).unwrap();
+        drop(sender);
+        assert_eq!(poll(pin!(receiver.next())), Poll::Ready(Some(2)));
+        assert_eq!(poll(pin!(receiver.next())), Poll::Ready(None));
+
+        let (sender, receiver) = channel();
+        drop(receiver);
+        assert_eq!(sender.try_send(1), Err(SendError::Closed(1)));
+        assert_eq!(poll(pin!(sender.send(2))), Poll::Ready(Err(2)));
+    }
+}
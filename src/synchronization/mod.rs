@@ -1,6 +1,8 @@
 //! Synchronization primitive actors.
 
+pub mod channel;
 mod mutex;
+pub mod rate_limiter;
 mod sempahore;
 mod signal;
 
@@ -9,3 +11,5 @@ pub use signal::Signal;
 pub use mutex::{Exclusive, Lock, Mutex, MutexActor, Unlock};
 
 pub use sempahore::{Permit, SemaphoreActor};
+
+pub use rate_limiter::RateLimiter;
@@ -144,7 +144,7 @@ where
                 address: self.address.unwrap(),
                 val: Some(self.lock().await),
             };
-            log::trace!("[Mutex<T> lock");
+            trace!("[Mutex<T> lock");
             self.respond_with(lock)
         })
     }
@@ -155,7 +155,7 @@ where
     T: 'static,
 {
     fn on_notify(mut self, message: Unlock<T>) -> Completion<Self> {
-        log::trace!("[Mutex<T> unlock");
+        trace!("[Mutex<T> unlock");
         self.unlock(message.0);
         Completion::immediate(self)
     }
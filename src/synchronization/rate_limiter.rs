@@ -0,0 +1,225 @@
+//! Rate-limiting of events published to the bus.
+
+use crate::bind::Bind;
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::TimerActor;
+use crate::hal::timer::Timer as HalTimer;
+use crate::prelude::*;
+use core::marker::PhantomData;
+
+/// Which event of a burst a `RateLimiter` forwards.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+    /// Forward the first event of a burst at once, and drop the events following it
+    /// until the interval has passed.
+    Leading,
+    /// Hold the events of a burst, and forward the last of them once the interval
+    /// following the first has passed.
+    Trailing,
+}
+
+/// The window following a forwarded or held event, during which further events are
+/// suppressed.
+struct Window<E> {
+    mode: Mode,
+    open: bool,
+    held: Option<E>,
+}
+
+impl<E> Window<E> {
+    fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            open: false,
+            held: None,
+        }
+    }
+
+    /// Offer an event, returning whether a window was opened, and any event to forward.
+    fn offer(&mut self, event: E) -> (bool, Option<E>) {
+        let opened = !self.open;
+        self.open = true;
+        match self.mode {
+            Mode::Leading if opened => (true, Some(event)),
+            Mode::Leading => (false, None),
+            Mode::Trailing => {
+                self.held.replace(event);
+                (opened, None)
+            }
+        }
+    }
+
+    /// Close the window, returning any held event to forward.
+    fn close(&mut self) -> Option<E> {
+        self.open = false;
+        self.held.take()
+    }
+}
+
+/// Actor forwarding events of type `E` to the bus at most once per interval, such
+/// as the edges of a bouncing button or the crossings of a noisy sensor reading.
+///
+/// See `Mode` for which event of a burst is forwarded.
+pub struct RateLimiter<D, T, E>
+where
+    D: Device + EventHandler<E> + 'static,
+    T: HalTimer + 'static,
+    E: 'static,
+{
+    interval: Milliseconds,
+    window: Window<E>,
+    bus: Option<Address<EventBus<D>>>,
+    timer: Option<Address<TimerActor<T>>>,
+    address: Option<Address<Self>>,
+}
+
+impl<D, T, E> RateLimiter<D, T, E>
+where
+    D: Device + EventHandler<E> + 'static,
+    T: HalTimer + 'static,
+    E: 'static,
+{
+    pub fn new<DUR: Into<Milliseconds>>(interval: DUR, mode: Mode) -> Self {
+        Self {
+            interval: interval.into(),
+            window: Window::new(mode),
+            bus: None,
+            timer: None,
+            address: None,
+        }
+    }
+
+    fn forward(&self, event: Option<E>) {
+        if let (Some(event), Some(bus)) = (event, self.bus) {
+            bus.publish(event);
+        }
+    }
+}
+
+impl<D, T, E> Actor for RateLimiter<D, T, E>
+where
+    D: Device + EventHandler<E> + 'static,
+    T: HalTimer + 'static,
+    E: 'static,
+{
+    fn on_mount(&mut self, address: Address<Self>)
+    where
+        Self: Sized,
+    {
+        self.address.replace(address);
+    }
+}
+
+impl<D, T, E> Bind<EventBus<D>> for RateLimiter<D, T, E>
+where
+    D: Device + EventHandler<E> + 'static,
+    T: HalTimer + 'static,
+    E: 'static,
+{
+    fn on_bind(&mut self, address: Address<EventBus<D>>) {
+        self.bus.replace(address);
+    }
+}
+
+impl<D, T, E> Bind<TimerActor<T>> for RateLimiter<D, T, E>
+where
+    D: Device + EventHandler<E> + 'static,
+    T: HalTimer + 'static,
+    E: 'static,
+{
+    fn on_bind(&mut self, address: Address<TimerActor<T>>) {
+        self.timer.replace(address);
+    }
+}
+
+impl<D, T, E> NotifyHandler<E> for RateLimiter<D, T, E>
+where
+    D: Device + EventHandler<E> + 'static,
+    T: HalTimer + 'static,
+    E: 'static,
+{
+    fn on_notify(mut self, event: E) -> Completion<Self> {
+        let (opened, forward) = self.window.offer(event);
+        self.forward(forward);
+        if opened {
+            if let (Some(timer), Some(address)) = (self.timer, self.address) {
+                timer.schedule(self.interval, WindowClosed(PhantomData), address);
+            }
+        }
+        Completion::immediate(self)
+    }
+}
+
+/// Scheduled once the interval following the start of a window has passed.
+pub struct WindowClosed<E>(PhantomData<E>);
+
+impl<E> Clone for WindowClosed<E> {
+    fn clone(&self) -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<D, T, E> NotifyHandler<WindowClosed<E>> for RateLimiter<D, T, E>
+where
+    D: Device + EventHandler<E> + 'static,
+    T: HalTimer + 'static,
+    E: 'static,
+{
+    fn on_notify(mut self, _: WindowClosed<E>) -> Completion<Self> {
+        let held = self.window.close();
+        self.forward(held);
+        Completion::immediate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::{consts::*, Vec};
+
+    /// Offer one event every 10ms from 0ms to 90ms, with windows closing 35ms after
+    /// opening, returning the events forwarded and when.
+    fn burst(mode: Mode) -> Vec<(u32, u32), U16> {
+        let mut window = Window::new(mode);
+        let mut forwarded = Vec::new();
+        let mut closes_at = None;
+        for now in 0..=130 {
+            if closes_at == Some(now) {
+                closes_at = None;
+                if let Some(event) = window.close() {
+                    forwarded.push((now, event)).unwrap();
+                }
+            }
+            if now % 10 == 0 && now < 100 {
+                let (opened, forward) = window.offer(now / 10);
+                if opened {
+                    closes_at.replace(now + 35);
+                }
+                if let Some(event) = forward {
+                    forwarded.push((now, event)).unwrap();
+                }
+            }
+        }
+        forwarded
+    }
+
+    #[test]
+    fn test_leading() {
+        assert_eq!(burst(Mode::Leading), [(0, 0), (40, 4), (80, 8)]);
+    }
+
+    #[test]
+    fn test_trailing() {
+        assert_eq!(burst(Mode::Trailing), [(35, 3), (75, 7), (115, 9)]);
+    }
+
+    #[test]
+    fn test_trailing_single() {
+        let mut window = Window::new(Mode::Trailing);
+        assert_eq!(window.offer('a'), (true, None));
+        assert_eq!(window.close(), Some('a'));
+
+        // a window without events forwards nothing
+        assert_eq!(window.close(), None);
+    }
+}
@@ -54,7 +54,7 @@ impl<T: Sized> Signal<T> {
                 }
                 State::Waiting(w) if w.will_wake(cx.waker()) => Poll::Pending,
                 State::Waiting(_) => {
-                    log::error!("waker overflow");
+                    error!("waker overflow");
                     Poll::Pending
                 }
                 State::Signaled(_) => match mem::replace(state, State::None) {
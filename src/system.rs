@@ -0,0 +1,135 @@
+//! Resetting the system from application code.
+//!
+//! An application detecting a state it cannot recover from may `reset()` the MCU, or
+//! notify a mounted `System` actor with `SystemCommand::Reset`. Before the core is
+//! reset, the flush hooks registered with `on_reset` run, in the order registered, so
+//! that critical state, such as a pending flash write, is not lost. After the reset,
+//! `hal::reset::reason()` reports `ResetReason::Software`.
+
+use crate::prelude::*;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// The most flush hooks `on_reset` holds.
+pub const MAX_HOOKS: usize = 4;
+
+static HOOKS: [AtomicPtr<()>; MAX_HOOKS] = [
+    AtomicPtr::new(core::ptr::null_mut()),
+    AtomicPtr::new(core::ptr::null_mut()),
+    AtomicPtr::new(core::ptr::null_mut()),
+    AtomicPtr::new(core::ptr::null_mut()),
+];
+
+/// Register `flush` to run before the system is reset, returning `false` if
+/// `MAX_HOOKS` hooks are already registered.
+///
+/// Hooks run with the reset already decided, so should only complete work in flight
+/// rather than start anything new, and must not wait on other actors.
+pub fn on_reset(flush: fn()) -> bool {
+    HOOKS.iter().any(|hook| {
+        hook.compare_exchange(
+            core::ptr::null_mut(),
+            flush as *mut (),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        )
+        .is_ok()
+    })
+}
+
+/// Trait for resetting the core.
+pub(crate) trait SystemReset {
+    fn reset(&mut self) -> !;
+}
+
+/// Resets through the System Control Block of the Cortex-M core.
+pub(crate) struct CoreReset;
+
+impl SystemReset for CoreReset {
+    fn reset(&mut self) -> ! {
+        cortex_m::peripheral::SCB::sys_reset()
+    }
+}
+
+/// Run the flush hooks registered with `on_reset`, then reset the MCU.
+///
+/// This function does not return.
+pub fn reset() -> ! {
+    reset_with(&mut CoreReset)
+}
+
+fn reset_with<R: SystemReset>(core: &mut R) -> ! {
+    for hook in HOOKS.iter() {
+        let flush = hook.load(Ordering::Acquire);
+        if !flush.is_null() {
+            // only ever set from a `fn()` in `on_reset`
+            let flush: fn() = unsafe { core::mem::transmute(flush) };
+            flush();
+        }
+    }
+    core.reset()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SystemCommand {
+    /// Reset the MCU, as with `reset()`.
+    Reset,
+}
+
+/// Carries out `SystemCommand`s sent from other actors.
+pub struct System;
+
+impl Actor for System {}
+
+impl NotifyHandler<SystemCommand> for System {
+    fn on_notify(self, command: SystemCommand) -> Completion<Self> {
+        match command {
+            SystemCommand::Reset => reset(),
+        }
+    }
+}
+
+impl Address<System> {
+    /// Reset the MCU once the system actor is next polled.
+    pub fn reset(&self) {
+        self.notify(SystemCommand::Reset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+    use std::panic::{self, AssertUnwindSafe};
+
+    /// Each step taken, in order, as digits of a decimal number.
+    static STEPS: AtomicUsize = AtomicUsize::new(0);
+
+    fn step(step: usize) {
+        STEPS.store(STEPS.load(Ordering::SeqCst) * 10 + step, Ordering::SeqCst);
+    }
+
+    struct MockReset;
+
+    impl SystemReset for MockReset {
+        fn reset(&mut self) -> ! {
+            step(3);
+            panic!("reset");
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        assert!(on_reset(|| step(1)));
+        assert!(on_reset(|| step(2)));
+        assert!(on_reset(|| {}));
+        assert!(on_reset(|| {}));
+        assert!(!on_reset(|| {}));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| reset_with(&mut MockReset)));
+        assert!(result.is_err());
+        // both flush hooks ran, in order, before the reset
+        assert_eq!(STEPS.load(Ordering::SeqCst), 123);
+    }
+}
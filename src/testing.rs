@@ -0,0 +1,38 @@
+//! Running futures in unit tests, on the host and without an executor.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::MockClock;
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll, Waker};
+
+/// Poll `future` once, with a waker that does nothing when woken.
+pub(crate) fn poll<F: Future + ?Sized>(future: Pin<&mut F>) -> Poll<F::Output> {
+    future.poll(&mut Context::from_waker(Waker::noop()))
+}
+
+/// Poll `future` until it completes, for futures needing nothing else to happen meanwhile.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = poll(future.as_mut()) {
+            return output;
+        }
+    }
+}
+
+/// Poll `future` until it completes, advancing `clock` a millisecond whenever it is
+/// pending.
+pub(crate) fn block_on_advancing<E, F>(clock: &MockClock<E>, future: F) -> F::Output
+where
+    E: Clone + 'static,
+    F: Future,
+{
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = poll(future.as_mut()) {
+            return output;
+        }
+        clock.advance(Milliseconds(1u32));
+    }
+}
@@ -0,0 +1,159 @@
+//! Tracing the messages sent between actors.
+//!
+//! With the `trace` feature enabled, every notification, request and stream sent
+//! through an address is reported to the sink registered with `set_trace_sink`, naming
+//! the type of the actor sending it, the actor receiving it and the message, along with
+//! the uptime given by the sink. The sink may buffer the events, or write them out,
+//! such as over RTT, to follow an interaction between actors after the fact.
+//!
+//! Without the feature, this module is left out, and sending a message does no more
+//! than before.
+
+use crate::actor::CURRENT;
+use crate::domain::time::uptime::Instant;
+
+/// How a message was sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// By `notify(...)`, without waiting for it to be handled.
+    Notify,
+    /// By `request(...)` or `request_ref(...)`, awaiting the response.
+    Request,
+    /// By `stream(...)`, for a stream of items.
+    Stream,
+}
+
+/// A message sent to an actor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SendEvent {
+    /// How the message was sent.
+    pub kind: Kind,
+    /// The type of the actor sending the message, or `None` if sent from outside of
+    /// any actor, such as while mounting or from an interrupt.
+    pub sender: Option<&'static str>,
+    /// The type of the actor the message was sent to.
+    pub receiver: &'static str,
+    /// The type of the message.
+    pub message: &'static str,
+    /// When the message was sent, by the uptime of the sink.
+    pub at: Instant,
+}
+
+/// Receives an event for each message sent between actors.
+///
+/// The sink is shared by every context sending messages, interrupts included, so must
+/// be `Sync`.
+pub trait TraceSink: Sync {
+    /// The current uptime, to stamp events with.
+    ///
+    /// Called as each message is sent, and so must not await, such as by reading a
+    /// free-running counter rather than asking a `TimerActor`.
+    fn uptime(&self) -> Instant;
+
+    /// Called with each message sent, from the context sending it, which may be an
+    /// interrupt.
+    fn on_send(&self, event: &SendEvent);
+}
+
+static mut SINK: Option<&'static dyn TraceSink> = None;
+
+/// Report every message sent from now on to `sink`, in place of any sink registered
+/// before.
+///
+/// Best called before the supervisor is started, as no message sent concurrently is
+/// guaranteed to reach either sink.
+pub fn set_trace_sink(sink: &'static dyn TraceSink) {
+    unsafe {
+        SINK = Some(sink);
+    }
+}
+
+/// Report the message of type `M` being sent to an actor of type `A`.
+pub(crate) fn send<A, M>(kind: Kind) {
+    if let Some(sink) = unsafe { SINK } {
+        sink.on_send(&SendEvent {
+            kind,
+            sender: CURRENT.type_name.get(),
+            receiver: core::any::type_name::<A>(),
+            message: core::any::type_name::<M>(),
+            at: sink.uptime(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::domain::time::duration::Milliseconds;
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use heapless::{consts::*, Vec};
+    use std::boxed::Box;
+    use std::sync::Mutex;
+    use std::thread::{self, ThreadId};
+
+    /// Captures the events sent from the thread creating it, stamping each a millisecond
+    /// after the last, and ignores those sent meanwhile by tests on other threads.
+    struct Capture {
+        thread: ThreadId,
+        now: AtomicU32,
+        events: Mutex<Vec<SendEvent, U8>>,
+    }
+
+    impl Capture {
+        fn new() -> Self {
+            Self {
+                thread: thread::current().id(),
+                now: AtomicU32::new(0),
+                events: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl TraceSink for Capture {
+        fn uptime(&self) -> Instant {
+            Instant::from_uptime(Milliseconds(self.now.fetch_add(1, Ordering::SeqCst) + 1))
+        }
+
+        fn on_send(&self, event: &SendEvent) {
+            if thread::current().id() == self.thread {
+                self.events.lock().unwrap().push(*event).ok();
+            }
+        }
+    }
+
+    struct Button;
+    struct Led;
+    struct Press;
+    struct On;
+
+    #[test]
+    fn test_send() {
+        let capture: &'static Capture = Box::leak(Box::new(Capture::new()));
+        set_trace_sink(capture);
+
+        send::<Button, Press>(Kind::Notify);
+        CURRENT.type_name.set(Some(core::any::type_name::<Button>()));
+        send::<Led, On>(Kind::Request);
+        send::<Led, On>(Kind::Stream);
+        CURRENT.type_name.set(None);
+
+        // in the order sent, from outside of any actor and then from the button
+        let events = capture.events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind, Kind::Notify);
+        assert_eq!(events[0].sender, None);
+        assert!(events[0].receiver.ends_with("::Button"));
+        assert!(events[0].message.ends_with("::Press"));
+        assert_eq!(events[1].kind, Kind::Request);
+        assert!(events[1].sender.unwrap().ends_with("::Button"));
+        assert!(events[1].receiver.ends_with("::Led"));
+        assert!(events[1].message.ends_with("::On"));
+        assert_eq!(events[2].kind, Kind::Stream);
+        assert!(events
+            .iter()
+            .zip(events.iter().skip(1))
+            .all(|(earlier, later)| earlier.at < later.at));
+    }
+}
@@ -0,0 +1,165 @@
+//! Base64 (RFC 4648, standard alphabet, padded) for carrying binary payloads over
+//! text protocols such as AT commands or MQTT topics with text payloads.
+//!
+//! Both directions write into a caller-provided buffer, returning the number of
+//! bytes written. `encoded_len` and `decoded_len` give the room needed.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const PAD: u8 = b'=';
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The output buffer cannot hold the result.
+    BufferTooSmall,
+    /// The input is not a whole number of 4-character groups.
+    InvalidLength,
+    /// The input holds a character outside the alphabet, at the given offset.
+    InvalidByte(usize),
+    /// Padding appears other than at the end of the last group, or leaves bits set
+    /// that no byte was encoded into.
+    InvalidPadding,
+}
+
+/// The length of the encoding of `len` bytes.
+pub const fn encoded_len(len: usize) -> usize {
+    len.div_ceil(3) * 4
+}
+
+/// The most bytes the decoding of an input of `len` characters may hold.
+pub const fn decoded_len(len: usize) -> usize {
+    len / 4 * 3
+}
+
+/// Encode `input` into `output`, returning the number of characters written.
+pub fn encode(input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+    let len = encoded_len(input.len());
+    let output = output.get_mut(..len).ok_or(Error::BufferTooSmall)?;
+    for (chunk, out) in input.chunks(3).zip(output.chunks_mut(4)) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let group = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for (i, c) in out.iter_mut().enumerate() {
+            *c = if i <= chunk.len() {
+                ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize]
+            } else {
+                PAD
+            };
+        }
+    }
+    Ok(len)
+}
+
+fn value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Decode `input` into `output`, returning the number of bytes written.
+pub fn decode(input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+    if !input.len().is_multiple_of(4) {
+        return Err(Error::InvalidLength);
+    }
+    let groups = input.len() / 4;
+    let mut len = 0;
+    for (index, group) in input.chunks(4).enumerate() {
+        let last = index + 1 == groups;
+        let padding = group.iter().rev().take_while(|c| **c == PAD).count();
+        if padding > 2 || (padding > 0 && !last) {
+            return Err(Error::InvalidPadding);
+        }
+
+        let mut bits = 0u32;
+        for (i, c) in group[..4 - padding].iter().enumerate() {
+            let v = value(*c).ok_or(Error::InvalidByte(index * 4 + i))?;
+            bits |= (v as u32) << (18 - 6 * i);
+        }
+        let bytes = 3 - padding;
+        if bits & (0xff_ffff >> (8 * bytes)) != 0 {
+            return Err(Error::InvalidPadding);
+        }
+
+        let out = output
+            .get_mut(len..len + bytes)
+            .ok_or(Error::BufferTooSmall)?;
+        for (i, b) in out.iter_mut().enumerate() {
+            *b = (bits >> (16 - 8 * i)) as u8;
+        }
+        len += bytes;
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The test vectors of RFC 4648.
+    const VECTORS: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn test_encode() {
+        let mut buf = [0; 16];
+        for (plain, encoded) in VECTORS.iter() {
+            let len = encode(plain.as_bytes(), &mut buf).unwrap();
+            assert_eq!(&buf[..len], encoded.as_bytes());
+            assert_eq!(len, encoded_len(plain.len()));
+        }
+
+        let len = encode(&[0xfb, 0xff, 0xbf], &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"+/+/");
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut buf = [0; 16];
+        for (plain, encoded) in VECTORS.iter() {
+            let len = decode(encoded.as_bytes(), &mut buf).unwrap();
+            assert_eq!(&buf[..len], plain.as_bytes());
+            assert!(len <= decoded_len(encoded.len()));
+        }
+
+        let len = decode(b"+/+/", &mut buf).unwrap();
+        assert_eq!(&buf[..len], [0xfb, 0xff, 0xbf]);
+    }
+
+    #[test]
+    fn test_buffer_too_small() {
+        let mut buf = [0; 7];
+        assert_eq!(encode(b"foobar", &mut buf), Err(Error::BufferTooSmall));
+
+        // padding makes the room needed smaller than decoded_len
+        let mut buf = [0; 4];
+        assert_eq!(decode(b"Zm9vYg==", &mut buf), Ok(4));
+        assert_eq!(decode(b"Zm9vYmE=", &mut buf), Err(Error::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut buf = [0; 16];
+        assert_eq!(decode(b"Zm9", &mut buf), Err(Error::InvalidLength));
+        assert_eq!(decode(b"Zm9v!mFy", &mut buf), Err(Error::InvalidByte(4)));
+        assert_eq!(decode(b"Zm-v", &mut buf), Err(Error::InvalidByte(2)));
+        assert_eq!(decode(b"Zg==Zm8=", &mut buf), Err(Error::InvalidPadding));
+        assert_eq!(decode(b"Z===", &mut buf), Err(Error::InvalidPadding));
+        assert_eq!(decode(b"Zm=v", &mut buf), Err(Error::InvalidByte(2)));
+        // the last character of "Zg==" may only encode 4 bits
+        assert_eq!(decode(b"Zh==", &mut buf), Err(Error::InvalidPadding));
+    }
+}
@@ -0,0 +1,7 @@
+//! Helpers shared by drivers, independent of the actor system.
+
+pub mod base64;
+pub mod morse;
+pub mod poll;
+pub mod retry;
+pub mod task;
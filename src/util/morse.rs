@@ -0,0 +1,143 @@
+//! Timing of messages in Morse code.
+//!
+//! `Morse` turns a message into the `Signal`s keying it, each lasting a number of
+//! units: a dot is one unit on and a dash three, with one unit off between the symbols
+//! of a letter, three between letters, and seven between words. Blinking an LED for
+//! the signals at, say, 200ms a unit sends the message.
+
+/// A dot, in units.
+pub const DOT: u32 = 1;
+/// A dash, in units.
+pub const DASH: u32 = 3;
+/// The gap between the symbols of a letter, in units.
+pub const SYMBOL_GAP: u32 = 1;
+/// The gap between letters, in units.
+pub const LETTER_GAP: u32 = 3;
+/// The gap between words, in units.
+pub const WORD_GAP: u32 = 7;
+
+/// Keying on or off for a number of units.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Signal {
+    On(u32),
+    Off(u32),
+}
+
+/// The signals keying a message, ignoring characters without a Morse code.
+///
+/// There is no gap before the first signal or after the last.
+#[derive(Clone, Debug)]
+pub struct Morse<'a> {
+    chars: core::str::Chars<'a>,
+    symbols: &'static [u8],
+    gap: u32,
+}
+
+impl<'a> Morse<'a> {
+    pub fn new(message: &'a str) -> Self {
+        Self {
+            chars: message.chars(),
+            symbols: &[],
+            gap: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Morse<'a> {
+    type Item = Signal;
+
+    fn next(&mut self) -> Option<Signal> {
+        loop {
+            if let Some((symbol, rest)) = self.symbols.split_first() {
+                if self.gap > 0 {
+                    let gap = self.gap;
+                    self.gap = 0;
+                    return Some(Signal::Off(gap));
+                }
+                self.symbols = rest;
+                self.gap = SYMBOL_GAP;
+                return Some(Signal::On(if *symbol == b'.' { DOT } else { DASH }));
+            }
+
+            let c = self.chars.next()?;
+            if c == ' ' {
+                if self.gap > 0 {
+                    self.gap = WORD_GAP;
+                }
+            } else if let Some(code) = code(c) {
+                if self.gap > 0 {
+                    self.gap = self.gap.max(LETTER_GAP);
+                }
+                self.symbols = code.as_bytes();
+            }
+        }
+    }
+}
+
+/// The dots and dashes of `c`.
+fn code(c: char) -> Option<&'static str> {
+    let code = match c.to_ascii_uppercase() {
+        'A' => ".-",
+        'B' => "-...",
+        'C' => "-.-.",
+        'D' => "-..",
+        'E' => ".",
+        'F' => "..-.",
+        'G' => "--.",
+        'H' => "....",
+        'I' => "..",
+        'J' => ".---",
+        'K' => "-.-",
+        'L' => ".-..",
+        'M' => "--",
+        'N' => "-.",
+        'O' => "---",
+        'P' => ".--.",
+        'Q' => "--.-",
+        'R' => ".-.",
+        'S' => "...",
+        'T' => "-",
+        'U' => "..-",
+        'V' => "...-",
+        'W' => ".--",
+        'X' => "-..-",
+        'Y' => "-.--",
+        'Z' => "--..",
+        '0' => "-----",
+        '1' => ".----",
+        '2' => "..---",
+        '3' => "...--",
+        '4' => "....-",
+        '5' => ".....",
+        '6' => "-....",
+        '7' => "--...",
+        '8' => "---..",
+        '9' => "----.",
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::{consts::*, Vec};
+
+    fn signals(message: &str) -> Vec<Signal, U64> {
+        Morse::new(message).collect()
+    }
+
+    #[test]
+    fn test_letters() {
+        use Signal::*;
+        assert_eq!(signals("et"), [On(DOT), Off(LETTER_GAP), On(DASH)]);
+        assert_eq!(signals("A"), [On(DOT), Off(SYMBOL_GAP), On(DASH)]);
+    }
+
+    #[test]
+    fn test_words() {
+        use Signal::*;
+        assert_eq!(signals(" e  e? "), [On(DOT), Off(WORD_GAP), On(DOT)]);
+        assert_eq!(signals("#"), []);
+    }
+}
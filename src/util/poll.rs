@@ -0,0 +1,140 @@
+//! Polling a status until it is set, waiting between polls rather than busy-polling.
+//!
+//! Many peripherals signal a result is ready by setting a bit in a status register.
+//! Reading it back to back keeps the bus busy for nothing: `poll_until` instead waits
+//! on a `Clock` between polls, and gives up with `DeviceError::Timeout` once a maximum
+//! wait has passed.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::Clock;
+use crate::error::DeviceError;
+use core::future::Future;
+
+/// Run `poll` until it returns `true`, waiting `interval` on `clock` between polls.
+///
+/// Gives up with `Timeout` rather than wait past `timeout` in total, so `poll` runs at
+/// most `timeout / interval + 1` times. A zero `interval` counts as a millisecond
+/// towards that cap, so it never polls without end. An error from `poll` is returned
+/// at once.
+pub async fn poll_until<C, F, Fut>(
+    clock: &C,
+    mut poll: F,
+    interval: Milliseconds,
+    timeout: Milliseconds,
+) -> Result<(), DeviceError>
+where
+    C: Clock,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<bool, DeviceError>>,
+{
+    let polls = (timeout.0 / interval.0.max(1)).saturating_add(1);
+    for polled in 1..=polls {
+        if poll().await? {
+            return Ok(());
+        }
+        if polled < polls {
+            clock.delay(interval).await;
+        }
+    }
+    Err(DeviceError::Timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::timer::MockClock;
+    use crate::testing::block_on_advancing;
+    use core::cell::Cell;
+    use embedded_hal::blocking::i2c::WriteRead;
+    use std::boxed::Box;
+
+    const STATUS: u8 = 0x27;
+
+    /// An I2C bus whose status register reads ready after a number of reads.
+    struct MockI2c {
+        reads: &'static Cell<u32>,
+        ready_after: u32,
+    }
+
+    impl WriteRead for MockI2c {
+        type Error = ();
+
+        fn write_read(&mut self, _: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            assert_eq!(bytes, [STATUS]);
+            self.reads.set(self.reads.get() + 1);
+            buffer[0] = if self.reads.get() > self.ready_after {
+                0b11
+            } else {
+                0b00
+            };
+            Ok(())
+        }
+    }
+
+    /// Poll the mock until ready, advancing the clock a millisecond at a time, and
+    /// return the result with the number of reads and the time taken.
+    fn run(ready_after: u32, interval: u32, timeout: u32) -> (Result<(), DeviceError>, u32, u32) {
+        let clock: &'static MockClock<()> = Box::leak(Box::new(MockClock::new()));
+        let reads: &'static Cell<u32> = Box::leak(Box::new(Cell::new(0)));
+        let mut i2c = MockI2c { reads, ready_after };
+        let poll = || {
+            let mut buf = [0; 1];
+            let result = i2c
+                .write_read(0x5F, &[STATUS], &mut buf)
+                .map(|_| buf[0] & 0b11 != 0)
+                .map_err(|_| DeviceError::BusError);
+            async move { result }
+        };
+
+        let polling = poll_until(
+            &clock,
+            poll,
+            Milliseconds(interval),
+            Milliseconds(timeout),
+        );
+        let result = block_on_advancing(clock, polling);
+        (result, reads.get(), clock.now().0)
+    }
+
+    #[test]
+    fn test_ready_after_polls() {
+        let (result, reads, elapsed) = run(3, 5, 100);
+        assert_eq!(result, Ok(()));
+        assert_eq!(reads, 4);
+        assert_eq!(elapsed, 15);
+
+        // ready at once, without waiting
+        let (result, reads, elapsed) = run(0, 5, 100);
+        assert_eq!(result, Ok(()));
+        assert_eq!(reads, 1);
+        assert_eq!(elapsed, 0);
+    }
+
+    #[test]
+    fn test_timeout() {
+        // never more than the budget of reads, nor more time than the timeout
+        let (result, reads, elapsed) = run(100, 5, 20);
+        assert_eq!(result, Err(DeviceError::Timeout));
+        assert_eq!(reads, 5);
+        assert_eq!(elapsed, 20);
+
+        let (result, reads, _) = run(100, 5, 0);
+        assert_eq!(result, Err(DeviceError::Timeout));
+        assert_eq!(reads, 1);
+    }
+
+    #[test]
+    fn test_zero_interval() {
+        // polls back to back, but no more often than once per millisecond of timeout
+        let (result, reads, elapsed) = run(100, 0, 10);
+        assert_eq!(result, Err(DeviceError::Timeout));
+        assert_eq!(reads, 11);
+        assert_eq!(elapsed, 0);
+
+        let (result, reads, _) = run(3, 0, 10);
+        assert_eq!(result, Ok(()));
+        assert_eq!(reads, 4);
+    }
+}
@@ -0,0 +1,225 @@
+//! Retrying fallible operations with a backoff between attempts.
+//!
+//! `retry` runs an async operation until it succeeds or runs out of attempts, waiting
+//! on a `Clock` between failures. The wait is described by a `Backoff`: a fixed delay,
+//! or one doubling with each failure up to a ceiling.
+//!
+//! # Jitter
+//!
+//! Devices failing together, such as after a shared gateway restarts, retry together
+//! with plain backoff. A `Jitter` spreads their retries out:
+//!
+//! * `Jitter::None` waits exactly the backoff delay, the default.
+//! * `Jitter::Full` waits a random time between zero and the backoff delay, spreading
+//!   retries the most, at the cost of some retrying almost at once.
+//! * `Jitter::Equal` waits half the backoff delay plus a random time up to the other
+//!   half, keeping a floor under each wait.
+//!
+//! Jitter draws from a small pseudo-random generator seeded by the caller; seed it with
+//! something differing between devices, such as a serial number.
+
+use crate::domain::time::duration::Milliseconds;
+use crate::driver::timer::Clock;
+use core::future::Future;
+
+/// How much of the backoff delay is randomized.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Jitter {
+    None,
+    Full,
+    Equal,
+}
+
+/// The delays between attempts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Backoff {
+    initial: Milliseconds,
+    max: Milliseconds,
+    exponential: bool,
+    jitter: Jitter,
+    seed: u32,
+}
+
+impl Backoff {
+    /// Wait `delay` after every failure.
+    pub fn fixed<DUR: Into<Milliseconds>>(delay: DUR) -> Self {
+        let delay = delay.into();
+        Self {
+            initial: delay,
+            max: delay,
+            exponential: false,
+            jitter: Jitter::None,
+            seed: 0,
+        }
+    }
+
+    /// Wait `initial` after the first failure, doubling the wait after each further
+    /// failure, up to `max`.
+    pub fn exponential<I: Into<Milliseconds>, M: Into<Milliseconds>>(initial: I, max: M) -> Self {
+        Self {
+            initial: initial.into(),
+            max: max.into(),
+            exponential: true,
+            jitter: Jitter::None,
+            seed: 0,
+        }
+    }
+
+    /// Randomize the delays by `jitter`, drawing from a generator seeded with `seed`.
+    pub fn with_jitter(mut self, jitter: Jitter, seed: u32) -> Self {
+        self.jitter = jitter;
+        // xorshift never leaves zero
+        self.seed = if seed == 0 { 0x9e37_79b9 } else { seed };
+        self
+    }
+
+    /// The delay after the `failures`th failure, counting from 1, before jitter.
+    fn delay(&self, failures: u32) -> Milliseconds {
+        let delay = if self.exponential {
+            let factor = 1u32.checked_shl(failures - 1).unwrap_or(u32::MAX);
+            self.initial.0.saturating_mul(factor)
+        } else {
+            self.initial.0
+        };
+        Milliseconds(delay.min(self.max.0))
+    }
+
+    /// The delay after the `failures`th failure, with jitter applied.
+    fn next(&mut self, failures: u32) -> Milliseconds {
+        let delay = self.delay(failures).0;
+        match self.jitter {
+            Jitter::None => Milliseconds(delay),
+            Jitter::Full => Milliseconds(self.random(delay)),
+            Jitter::Equal => Milliseconds(delay / 2 + self.random(delay - delay / 2)),
+        }
+    }
+
+    /// A pseudo-random number from 0 to `bound`, inclusive.
+    fn random(&mut self, bound: u32) -> u32 {
+        let mut x = self.seed;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.seed = x;
+        (x as u64 % (bound as u64 + 1)) as u32
+    }
+}
+
+/// Run `op` until it succeeds, for at most `attempts` attempts, waiting on `clock` for
+/// the `backoff` delay after each failure.
+///
+/// Returns the first success, or the error of the last attempt. A single attempt is
+/// made when `attempts` is 0.
+pub async fn retry<C, F, Fut, T, E>(
+    clock: &C,
+    mut op: F,
+    attempts: u32,
+    mut backoff: Backoff,
+) -> Result<T, E>
+where
+    C: Clock,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut failures = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                failures += 1;
+                if failures >= attempts {
+                    return Err(err);
+                }
+                clock.delay(backoff.next(failures)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::driver::timer::MockClock;
+    use crate::testing::block_on_advancing;
+    use core::cell::{Cell, RefCell};
+    use heapless::{consts::*, Vec};
+    use std::boxed::Box;
+
+    /// Retry an operation failing `failing` times, advancing the clock a millisecond at
+    /// a time, and return the result with the times of each attempt.
+    fn run(failing: u32, attempts: u32, backoff: Backoff) -> (Result<u32, ()>, Vec<u32, U16>) {
+        let clock: &'static MockClock<()> = Box::leak(Box::new(MockClock::new()));
+        let tries = Cell::new(0);
+        let times = RefCell::new(Vec::new());
+        let op = || {
+            let attempt = tries.get() + 1;
+            tries.set(attempt);
+            let _ = times.borrow_mut().push(clock.now().0);
+            async move {
+                if attempt > failing {
+                    Ok(attempt)
+                } else {
+                    Err(())
+                }
+            }
+        };
+
+        let result = block_on_advancing(clock, retry(&clock, op, attempts, backoff));
+        let times = times.borrow().clone();
+        (result, times)
+    }
+
+    #[test]
+    fn test_fixed() {
+        let (result, times) = run(2, 5, Backoff::fixed(Milliseconds(10u32)));
+        assert_eq!(result, Ok(3));
+        assert_eq!(times, [0, 10, 20]);
+
+        let (result, times) = run(0, 5, Backoff::fixed(Milliseconds(10u32)));
+        assert_eq!(result, Ok(1));
+        assert_eq!(times, [0]);
+    }
+
+    #[test]
+    fn test_exponential() {
+        let backoff = Backoff::exponential(Milliseconds(10u32), Milliseconds(50u32));
+        let (result, times) = run(4, 5, backoff);
+        assert_eq!(result, Ok(5));
+        // waiting 10, 20, 40, then 50 rather than 80
+        assert_eq!(times, [0, 10, 30, 70, 120]);
+    }
+
+    #[test]
+    fn test_exhausted() {
+        let (result, times) = run(10, 3, Backoff::fixed(Milliseconds(5u32)));
+        assert_eq!(result, Err(()));
+        assert_eq!(times, [0, 5, 10]);
+
+        let (result, times) = run(10, 0, Backoff::fixed(Milliseconds(5u32)));
+        assert_eq!(result, Err(()));
+        assert_eq!(times, [0]);
+    }
+
+    #[test]
+    fn test_jitter() {
+        let mut full = Backoff::fixed(Milliseconds(100u32)).with_jitter(Jitter::Full, 7);
+        let mut equal = Backoff::fixed(Milliseconds(100u32)).with_jitter(Jitter::Equal, 7);
+        let mut spread = false;
+        for failures in 1..20 {
+            let delay = full.next(failures);
+            assert!(delay <= Milliseconds(100u32));
+            spread |= delay != Milliseconds(100u32);
+
+            let delay = equal.next(failures);
+            assert!(delay >= Milliseconds(50u32) && delay <= Milliseconds(100u32));
+        }
+        assert!(spread);
+
+        // the same seed gives the same delays
+        let mut a = Backoff::fixed(Milliseconds(100u32)).with_jitter(Jitter::Full, 42);
+        let mut b = a;
+        assert_eq!(a.next(1), b.next(1));
+    }
+}
@@ -0,0 +1,34 @@
+//! Cooperating with the other actors while waiting on a peripheral that can only be polled.
+
+use core::future::poll_fn;
+use core::task::Poll;
+
+/// Let the executor run the other actors before carrying on, such as between polls of
+/// a peripheral which signals no interrupt.
+pub async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::poll;
+    use core::pin::pin;
+
+    #[test]
+    fn test_yield_now() {
+        let mut yielding = pin!(yield_now());
+        assert_eq!(poll(yielding.as_mut()), Poll::Pending);
+        assert_eq!(poll(yielding.as_mut()), Poll::Ready(()));
+    }
+}
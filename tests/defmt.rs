@@ -0,0 +1,14 @@
+//! Ensures the crate builds with `defmt` logging in place of `log`:
+//!
+//! ```text
+//! cargo test --no-default-features --features defmt --test defmt
+//! ```
+#![cfg(all(feature = "defmt", not(feature = "log")))]
+
+use drogue_device::domain::temperature::{Celsius, Temperature};
+
+#[test]
+fn builds_with_defmt() {
+    let temperature = Temperature::<Celsius>::new(21.5);
+    assert_eq!(temperature.value(), 21.5);
+}